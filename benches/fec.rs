@@ -0,0 +1,47 @@
+//! Encode and decode throughput of `protocol::fec`, run with `cargo bench --features fec --bench fec`
+//!
+//! A plain `main` timing with `Instant`, so no benchmark framework is pulled into the dependency tree.
+//! Decode is measured clean, the common case, and with the most errors each block can repair.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use Mesh::protocol::fec::{Fec, BLOCK_LEN};
+use Mesh::protocol::mesh::MAX_FRAME_LEN;
+
+const ITERATIONS: u32 = 2_000;
+
+fn time(name: &str, parity: usize, mut run: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let per_frame = start.elapsed() / ITERATIONS;
+    println!("{name:<18} parity {parity:>2}: {:>8.1} us/frame", per_frame.as_secs_f64() * 1e6);
+}
+
+fn main() {
+    let frame: [u8; MAX_FRAME_LEN] = core::array::from_fn(|i| (i * 31 + 7) as u8);
+    for parity in [16, 32, 64] {
+        let fec = Fec::new(parity).expect("supported parity");
+        let mut encoded = [0u8; 2 * BLOCK_LEN];
+        let len = fec.encode(&frame, &mut encoded).expect("fits two blocks");
+        time("encode", parity, || {
+            black_box(fec.encode(black_box(&frame), &mut encoded).ok());
+        });
+
+        let clean = encoded;
+        let mut corrupted = encoded;
+        for start in (0..len).step_by(BLOCK_LEN) {
+            for byte in &mut corrupted[start..start + parity / 2] {
+                *byte ^= 0x5A;
+            }
+        }
+        for (name, input) in [("decode clean", clean), ("decode worst case", corrupted)] {
+            time(name, parity, || {
+                let mut buf = input;
+                black_box(fec.decode(black_box(&mut buf[..len])).ok());
+            });
+        }
+    }
+}
@@ -0,0 +1,13 @@
+#!/bin/sh
+# Checks the public API against a released version with cargo-semver-checks
+#
+# Usage: scripts/semver-check.sh [baseline-rev]
+#
+# The baseline defaults to the most recent tag. Every feature is enabled so feature-gated items are
+# checked too. Install the tool with `cargo install cargo-semver-checks`.
+set -eu
+
+cd "$(dirname "$0")/.."
+baseline="${1:-$(git describe --tags --abbrev=0)}"
+echo "checking the API against $baseline"
+cargo semver-checks check-release --baseline-rev "$baseline" --all-features
@@ -0,0 +1,182 @@
+use core::fmt::Write;
+
+use heapless::String;
+
+use super::{Alert, AlertKind, Notifier, Severity};
+use crate::protocol::emergency::EmergencyKind;
+use crate::protocol::events::FlightEvent;
+
+/// Maximum length of a single spoken callout
+pub const MAX_CALLOUT_LEN: usize = 128;
+
+const METERS_TO_FEET: f32 = 3.280_84;
+
+/// Text-to-speech engine implemented by the application
+pub trait Speaker {
+    fn speak(&mut self, text: &str);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Units {
+    #[default]
+    Feet,
+    Meters,
+}
+
+/// Callouts turns flight events and alerts into spoken phrases, like a commercial altimeter would
+pub struct Callouts<S: Speaker> {
+    pub speaker: S,
+    pub units: Units,
+    /// Altitudes are rounded to this step before being spoken
+    pub altitude_step: u32,
+}
+
+impl<S: Speaker> Callouts<S> {
+    pub fn new(speaker: S) -> Self {
+        Self { speaker, units: Units::Feet, altitude_step: 10 }
+    }
+
+    pub fn on_event(&mut self, event: &FlightEvent) {
+        let mut text: String<MAX_CALLOUT_LEN> = String::new();
+        let _ = match event {
+            FlightEvent::Launch => text.push_str("Liftoff"),
+            FlightEvent::Burnout => text.push_str("Burnout"),
+            FlightEvent::Apogee { altitude_m } => {
+                let _ = text.push_str("Apogee ");
+                self.push_altitude(&mut text, *altitude_m);
+                Ok(())
+            }
+            FlightEvent::DrogueDeployed => text.push_str("Drogue deployed"),
+            FlightEvent::MainDeployed => text.push_str("Main deployed"),
+            FlightEvent::Landed => text.push_str("Landed"),
+        };
+        self.speaker.speak(&text);
+    }
+
+    fn push_altitude(&self, text: &mut String<MAX_CALLOUT_LEN>, altitude_m: f32) {
+        let (value, unit) = match self.units {
+            Units::Feet => (altitude_m * METERS_TO_FEET, "feet"),
+            Units::Meters => (altitude_m, "meters"),
+        };
+        let step = self.altitude_step.max(1);
+        let rounded = ((value.max(0.0) as u32 + step / 2) / step) * step;
+        push_number(text, rounded);
+        let _ = write!(text, " {}", unit);
+    }
+}
+
+impl<S: Speaker> Notifier for Callouts<S> {
+    fn notify(&mut self, alert: &Alert) {
+        let phrase = match alert.kind {
+            AlertKind::LostLink => "Warning, telemetry lost",
+            AlertKind::DescentRate => "Warning, fast descent",
+            AlertKind::BatteryLow => "Battery low",
+            AlertKind::GeofenceBreach => "Warning, outside geofence",
+            AlertKind::RadioDerated => "Radio overheating, power reduced",
+            AlertKind::LinkMargin => "Warning, link margin low at apogee",
+            AlertKind::Emergency => match EmergencyKind::from_code(alert.value as u8) {
+                Some(EmergencyKind::RangeHold) => "Range hold, range hold",
+                Some(EmergencyKind::MisfireHold) => "Misfire, hold, misfire, hold",
+                Some(EmergencyKind::HeadsUp) => "Heads up, heads up",
+                Some(EmergencyKind::AllClear) => "All clear",
+                None => "Emergency",
+            },
+            AlertKind::Silence => match alert.severity {
+                Severity::Warning => "Telemetry silent",
+                Severity::Critical => "Warning, no telemetry from the rocket",
+            },
+        };
+        self.speaker.speak(phrase);
+    }
+}
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+const TENS: [&str; 10] = ["", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+
+/// Appends a number in words, e.g. 10200 -> "ten thousand two hundred"
+pub fn push_number<const N: usize>(text: &mut String<N>, value: u32) {
+    if value == 0 {
+        let _ = text.push_str(ONES[0]);
+        return;
+    }
+    let mut first = true;
+    for (scale, name) in [(1_000_000_000, "billion"), (1_000_000, "million"), (1_000, "thousand"), (1, "")] {
+        let group = (value / scale) % 1000;
+        if group == 0 {
+            continue;
+        }
+        if !first {
+            let _ = text.push(' ');
+        }
+        first = false;
+        push_hundreds(text, group);
+        if !name.is_empty() {
+            let _ = write!(text, " {}", name);
+        }
+    }
+}
+
+fn push_hundreds<const N: usize>(text: &mut String<N>, value: u32) {
+    let (hundreds, rest) = (value / 100, value % 100);
+    if hundreds > 0 {
+        let _ = write!(text, "{} hundred", ONES[hundreds as usize]);
+        if rest > 0 {
+            let _ = text.push(' ');
+        }
+    }
+    match rest {
+        0 => {}
+        1..=19 => {
+            let _ = text.push_str(ONES[rest as usize]);
+        }
+        _ => {
+            let _ = text.push_str(TENS[(rest / 10) as usize]);
+            if rest % 10 > 0 {
+                let _ = write!(text, " {}", ONES[(rest % 10) as usize]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Transcript {
+        lines: Vec<std::string::String>,
+    }
+
+    impl Speaker for &mut Transcript {
+        fn speak(&mut self, text: &str) {
+            self.lines.push(text.into());
+        }
+    }
+
+    fn words(value: u32) -> String<64> {
+        let mut text = String::new();
+        push_number(&mut text, value);
+        text
+    }
+
+    #[test]
+    fn test_number_words() {
+        assert_eq!(words(0).as_str(), "zero");
+        assert_eq!(words(15).as_str(), "fifteen");
+        assert_eq!(words(342).as_str(), "three hundred forty two");
+        assert_eq!(words(10_200).as_str(), "ten thousand two hundred");
+        assert_eq!(words(1_000_005).as_str(), "one million five");
+    }
+
+    #[test]
+    fn test_apogee_callout_in_feet() {
+        let mut transcript = Transcript::default();
+        let mut callouts = Callouts::new(&mut transcript);
+        callouts.on_event(&FlightEvent::Apogee { altitude_m: 3_108.96 });
+        callouts.on_event(&FlightEvent::MainDeployed);
+        assert_eq!(transcript.lines, ["Apogee ten thousand two hundred feet", "Main deployed"]);
+    }
+}
@@ -0,0 +1,346 @@
+pub mod callout;
+#[cfg(feature = "std")]
+pub mod notifiers;
+
+use core::fmt;
+
+use crate::geo;
+use crate::protocol::emergency::{Emergency, EmergencyKind};
+use crate::protocol::thermal::RadioThermal;
+use crate::protocol::AllSensorData;
+use crate::radio::link_budget::LinkBudget;
+use crate::radio::LoraParams;
+use crate::tracker::{look_angles, DeadReckoning, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    LostLink = 0,
+    DescentRate = 1,
+    BatteryLow = 2,
+    GeofenceBreach = 3,
+    RadioDerated = 4,
+    LinkMargin = 5,
+    /// A range-safety Emergency was broadcast, the value is its `EmergencyKind` code
+    Emergency = 6,
+    /// The ground watchdog heard nothing from the node for longer than its flight phase allows, in seconds
+    Silence = 7,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning = 0,
+    Critical = 1,
+}
+
+/// Alert raised by an alert rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub severity: Severity,
+    pub uid: u8,
+    /// Value that triggered the rule (seconds, m/s, volts, meters, Celsius or dB depending on kind)
+    pub value: f32,
+}
+
+impl Alert {
+    /// The alert for a received Emergency, valued by its `EmergencyKind` code
+    pub fn emergency(emergency: &Emergency) -> Alert {
+        let kind = AlertKind::Emergency;
+        Alert { kind, severity: severity(kind), uid: emergency.uid, value: emergency.kind as u8 as f32 }
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        };
+        match self.kind {
+            AlertKind::LostLink => write!(f, "{} node {}: no telemetry for {:.1} s", severity, self.uid, self.value),
+            AlertKind::DescentRate => write!(f, "{} node {}: descending at {:.1} m/s", severity, self.uid, self.value),
+            AlertKind::BatteryLow => write!(f, "{} node {}: battery at {:.2} V", severity, self.uid, self.value),
+            AlertKind::GeofenceBreach => write!(f, "{} node {}: {:.0} m outside geofence", severity, self.uid, self.value),
+            AlertKind::RadioDerated => write!(f, "{} node {}: radio derated, PA {:.0} C", severity, self.uid, self.value),
+            AlertKind::LinkMargin => {
+                write!(f, "{} node {}: link margin at apogee {:.1} dB", severity, self.uid, self.value)
+            }
+            AlertKind::Emergency => {
+                let kind = EmergencyKind::from_code(self.value as u8).map_or("unknown", EmergencyKind::describe);
+                write!(f, "{} node {}: emergency, {}", severity, self.uid, kind)
+            }
+            AlertKind::Silence => write!(f, "{} node {}: silent for {:.1} s", severity, self.uid, self.value),
+        }
+    }
+}
+
+/// Notifier delivers alerts to the operator
+pub trait Notifier {
+    fn notify(&mut self, alert: &Alert);
+}
+
+/// Circular geofence around the launch site
+#[derive(Debug, Clone, Copy)]
+pub struct Geofence {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_m: f64,
+}
+
+/// Link to check against the predicted trajectory
+#[derive(Debug, Clone, Copy)]
+pub struct LinkMarginCheck {
+    /// Ground station antenna
+    pub station: Position,
+    pub budget: LinkBudget,
+    /// Modulation the vehicle is configured for
+    pub params: LoraParams,
+    /// Warn when the predicted margin drops below this, in dB
+    pub min_margin_db: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AlertConfig {
+    /// Raise LostLink when no frame is received for this long
+    pub lost_link_ms: u64,
+    /// Maximum allowed descent rate in m/s, positive downwards
+    pub max_descent_rate: Option<f32>,
+    pub min_battery_voltage: Option<f32>,
+    pub geofence: Option<Geofence>,
+    pub link_margin: Option<LinkMarginCheck>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            lost_link_ms: 10_000,
+            max_descent_rate: Some(30.0),
+            min_battery_voltage: Some(7.0),
+            geofence: None,
+            link_margin: None,
+        }
+    }
+}
+
+/// AlertMonitor evaluates the alert rules for one node and notifies when a rule becomes active
+///
+/// Alerts are edge-triggered: a rule notifies once when it trips and re-arms when the condition clears.
+#[derive(Debug, Clone, Copy)]
+pub struct AlertMonitor {
+    uid: u8,
+    config: AlertConfig,
+    last_frame_ms: Option<u64>,
+    last_altitude: Option<(f32, u64)>,
+    active: u8,
+}
+
+impl AlertMonitor {
+    pub fn new(uid: u8, config: AlertConfig) -> Self {
+        Self { uid, config, last_frame_ms: None, last_altitude: None, active: 0 }
+    }
+
+    pub fn is_active(&self, kind: AlertKind) -> bool {
+        self.active & (1 << kind as u8) != 0
+    }
+
+    /// Evaluates the telemetry rules against a newly received frame
+    pub fn on_frame(
+        &mut self,
+        now_ms: u64,
+        sensors: &AllSensorData,
+        battery_voltage: Option<f32>,
+        notifiers: &mut [&mut dyn Notifier],
+    ) {
+        self.last_frame_ms = Some(now_ms);
+        self.set(AlertKind::LostLink, None, notifiers);
+
+        if let (Some(limit), Some(bmp)) = (self.config.max_descent_rate, sensors.bmp390) {
+            if let Some((last_alt, last_ms)) = self.last_altitude {
+                let dt = now_ms.saturating_sub(last_ms) as f32 / 1000.0;
+                if dt > 0.0 {
+                    let descent_rate = (last_alt - bmp.altitude) / dt;
+                    let tripped = descent_rate > limit;
+                    self.set(AlertKind::DescentRate, tripped.then_some(descent_rate), notifiers);
+                }
+            }
+            self.last_altitude = Some((bmp.altitude, now_ms));
+        }
+
+        if let (Some(min), Some(voltage)) = (self.config.min_battery_voltage, battery_voltage) {
+            self.set(AlertKind::BatteryLow, (voltage < min).then_some(voltage), notifiers);
+        }
+
+        if let (Some(fence), Some(gps)) = (self.config.geofence, sensors.gps) {
+            let distance = geo::distance_m(fence.latitude, fence.longitude, gps.latitude, gps.longitude);
+            let outside = distance - fence.radius_m;
+            self.set(AlertKind::GeofenceBreach, (outside > 0.0).then_some(outside as f32), notifiers);
+        }
+    }
+
+    /// Raises RadioDerated while the node reports reduced TX power
+    pub fn on_radio_thermal(&mut self, thermal: &RadioThermal, notifiers: &mut [&mut dyn Notifier]) {
+        self.set(AlertKind::RadioDerated, thermal.derated.then_some(thermal.pa_temperature_c), notifiers);
+    }
+
+    /// Raises LinkMargin while climbing if the link is predicted not to close with margin at apogee
+    ///
+    /// The range checked is the larger of the current range and the range to the drag-free apogee, so the
+    /// warning comes while there is still time to command a slower data rate or more power. Past apogee the
+    /// rule clears.
+    pub fn on_trajectory(&mut self, trajectory: &DeadReckoning, now_ms: u64, notifiers: &mut [&mut dyn Notifier]) {
+        let Some(check) = self.config.link_margin else {
+            return;
+        };
+        let (Some(apogee), Some(current)) = (trajectory.predicted_apogee(), trajectory.estimate(now_ms)) else {
+            self.set(AlertKind::LinkMargin, None, notifiers);
+            return;
+        };
+        let (_, _, apogee_range) = look_angles(&check.station, &apogee);
+        let (_, _, current_range) = look_angles(&check.station, &current);
+        let margin = check.budget.margin_db(&check.params, apogee_range.max(current_range) as f32);
+        self.set(AlertKind::LinkMargin, (margin < check.min_margin_db).then_some(margin), notifiers);
+    }
+
+    /// Notifies every Emergency received, each broadcast once since the mesh suppresses duplicates
+    ///
+    /// Unlike the telemetry rules this is not edge-triggered: a repeated hold is announced again.
+    pub fn on_emergency(&mut self, emergency: &Emergency, notifiers: &mut [&mut dyn Notifier]) {
+        let alert = Alert::emergency(emergency);
+        for notifier in notifiers.iter_mut() {
+            notifier.notify(&alert);
+        }
+    }
+
+    /// Checks time-based rules, call periodically even when no frames arrive
+    pub fn poll(&mut self, now_ms: u64, notifiers: &mut [&mut dyn Notifier]) {
+        let Some(last) = self.last_frame_ms else {
+            return;
+        };
+        let silence = now_ms.saturating_sub(last);
+        let tripped = silence > self.config.lost_link_ms;
+        self.set(AlertKind::LostLink, tripped.then_some(silence as f32 / 1000.0), notifiers);
+    }
+
+    fn set(&mut self, kind: AlertKind, value: Option<f32>, notifiers: &mut [&mut dyn Notifier]) {
+        let bit = 1 << kind as u8;
+        match value {
+            Some(value) if self.active & bit == 0 => {
+                self.active |= bit;
+                let alert = Alert { kind, severity: severity(kind), uid: self.uid, value };
+                #[cfg(feature = "tracing")]
+                let _span = tracing::info_span!("sinks", ?kind, uid = self.uid, sinks = notifiers.len()).entered();
+                for notifier in notifiers.iter_mut() {
+                    notifier.notify(&alert);
+                }
+            }
+            Some(_) => {}
+            None => self.active &= !bit,
+        }
+    }
+}
+
+fn severity(kind: AlertKind) -> Severity {
+    match kind {
+        AlertKind::BatteryLow | AlertKind::RadioDerated | AlertKind::LinkMargin => Severity::Warning,
+        AlertKind::LostLink
+        | AlertKind::DescentRate
+        | AlertKind::GeofenceBreach
+        | AlertKind::Emergency
+        | AlertKind::Silence => Severity::Critical,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::BMP390;
+
+    #[derive(Default)]
+    struct Recorder {
+        alerts: Vec<Alert>,
+    }
+
+    impl Notifier for Recorder {
+        fn notify(&mut self, alert: &Alert) {
+            self.alerts.push(*alert);
+        }
+    }
+
+    fn frame(altitude: f32) -> AllSensorData {
+        AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: Some(BMP390 { pressure: 90_000.0, temperature: 10.0, altitude }),
+            gps: None,
+            adxl375: None,
+            ism330dhcx2: None,
+        }
+    }
+
+    #[test]
+    fn test_lost_link_is_edge_triggered() {
+        let mut recorder = Recorder::default();
+        let mut monitor = AlertMonitor::new(3, AlertConfig::default());
+        monitor.on_frame(0, &frame(100.0), None, &mut [&mut recorder]);
+        monitor.poll(10_000, &mut [&mut recorder]);
+        assert!(recorder.alerts.is_empty());
+        monitor.poll(12_000, &mut [&mut recorder]);
+        monitor.poll(13_000, &mut [&mut recorder]);
+        assert_eq!(recorder.alerts.len(), 1);
+        assert_eq!(recorder.alerts[0].kind, AlertKind::LostLink);
+        assert_eq!(recorder.alerts[0].value, 12.0);
+
+        monitor.on_frame(14_000, &frame(100.0), None, &mut [&mut recorder]);
+        assert!(!monitor.is_active(AlertKind::LostLink));
+    }
+
+    #[test]
+    fn test_every_emergency_is_announced() {
+        let mut recorder = Recorder::default();
+        let mut monitor = AlertMonitor::new(3, AlertConfig::default());
+        let hold = Emergency { uid: 1, kind: EmergencyKind::MisfireHold, text: "pad 3".try_into().unwrap() };
+        monitor.on_emergency(&hold, &mut [&mut recorder]);
+        monitor.on_emergency(&hold, &mut [&mut recorder]);
+        assert_eq!(recorder.alerts.len(), 2);
+        let alert = recorder.alerts[0];
+        assert_eq!((alert.kind, alert.severity, alert.uid), (AlertKind::Emergency, Severity::Critical, 1));
+        assert_eq!(alert.value, EmergencyKind::MisfireHold as u8 as f32);
+        assert_eq!(std::format!("{}", alert), "CRITICAL node 1: emergency, misfire hold");
+    }
+
+    #[test]
+    fn test_descent_rate_and_battery() {
+        let mut recorder = Recorder::default();
+        let mut monitor = AlertMonitor::new(3, AlertConfig::default());
+        monitor.on_frame(0, &frame(1_000.0), Some(8.0), &mut [&mut recorder]);
+        monitor.on_frame(1_000, &frame(950.0), Some(6.5), &mut [&mut recorder]);
+        let kinds: Vec<_> = recorder.alerts.iter().map(|a| a.kind).collect();
+        assert_eq!(kinds, [AlertKind::DescentRate, AlertKind::BatteryLow]);
+        assert_eq!(recorder.alerts[0].value, 50.0);
+    }
+
+    #[test]
+    fn test_link_margin_warns_before_apogee() {
+        let station = Position { latitude: 37.0, longitude: -80.0, altitude: 600.0 };
+        let budget = LinkBudget { tx_power_dbm: 2.0, frequency_hz: 915_000_000, antenna_gain_db: 0.0, losses_db: 6.0 };
+        let params = LoraParams { spreading_factor: 7, bandwidth_hz: 500_000, ..LoraParams::default() };
+        let check = LinkMarginCheck { station, budget, params, min_margin_db: 10.0 };
+        let config = AlertConfig { link_margin: Some(check), ..AlertConfig::default() };
+        let mut recorder = Recorder::default();
+        let mut monitor = AlertMonitor::new(3, config);
+
+        // Climbing at 300 m/s, 1 km up: apogee is predicted near 5.6 km, where SF7/500 kHz at 2 dBm has about 8 dB left
+        let mut trajectory = DeadReckoning::default();
+        trajectory.update(Position { altitude: 1_300.0, ..station }, 0);
+        trajectory.update(Position { altitude: 1_600.0, ..station }, 1_000);
+        monitor.on_trajectory(&trajectory, 1_000, &mut [&mut recorder]);
+        assert_eq!(recorder.alerts.len(), 1);
+        assert_eq!((recorder.alerts[0].kind, recorder.alerts[0].severity), (AlertKind::LinkMargin, Severity::Warning));
+        assert!((recorder.alerts[0].value - 7.9).abs() < 0.1);
+
+        // Past apogee the rule re-arms
+        trajectory.update(Position { altitude: 1_500.0, ..station }, 2_000);
+        monitor.on_trajectory(&trajectory, 2_000, &mut [&mut recorder]);
+        assert!(!monitor.is_active(AlertKind::LinkMargin));
+    }
+}
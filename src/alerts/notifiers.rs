@@ -0,0 +1,161 @@
+//! Notifier implementations for the ground station (requires the `std` feature)
+
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use super::{Alert, Notifier, Severity};
+
+/// Prints alerts to stderr
+#[derive(Debug, Default)]
+pub struct ConsoleNotifier;
+
+impl Notifier for ConsoleNotifier {
+    fn notify(&mut self, alert: &Alert) {
+        eprintln!("[ALERT] {}", alert);
+    }
+}
+
+/// Sounds the terminal bell, three times for critical alerts
+#[derive(Debug, Default)]
+pub struct BeepNotifier;
+
+impl Notifier for BeepNotifier {
+    fn notify(&mut self, alert: &Alert) {
+        let beeps = match alert.severity {
+            Severity::Warning => "\x07",
+            Severity::Critical => "\x07\x07\x07",
+        };
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(beeps.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// Plain HTTP endpoint (no TLS), e.g. a local relay or chat webhook bridge on the ground network
+#[derive(Debug, Clone)]
+pub struct HttpEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+/// POSTs every alert as a JSON object to a webhook
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    pub endpoint: HttpEndpoint,
+    /// Error from the most recent delivery attempt, if it failed
+    pub last_error: Option<io::Error>,
+}
+
+impl WebhookNotifier {
+    pub fn new(endpoint: HttpEndpoint) -> Self {
+        Self { endpoint, last_error: None }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&mut self, alert: &Alert) {
+        // JSON has no NaN or infinity
+        let value = if alert.value.is_finite() { alert.value.to_string() } else { "null".into() };
+        let body = format!(
+            "{{\"kind\":\"{:?}\",\"severity\":\"{:?}\",\"uid\":{},\"value\":{},\"text\":\"{}\"}}",
+            alert.kind, alert.severity, alert.uid, value, alert
+        );
+        self.last_error = post(&self.endpoint, "application/json", &body).err();
+    }
+}
+
+/// Sends alerts as text messages through an HTTP SMS gateway
+///
+/// The gateway receives a form-encoded POST with `to`, `key`, and `message` fields.
+#[derive(Debug)]
+pub struct SmsGatewayNotifier {
+    pub endpoint: HttpEndpoint,
+    pub api_key: String,
+    pub recipients: Vec<String>,
+    /// Only alerts at or above this severity are texted
+    pub min_severity: Severity,
+    pub last_error: Option<io::Error>,
+}
+
+impl SmsGatewayNotifier {
+    pub fn new(endpoint: HttpEndpoint, api_key: String, recipients: Vec<String>) -> Self {
+        Self { endpoint, api_key, recipients, min_severity: Severity::Critical, last_error: None }
+    }
+}
+
+impl Notifier for SmsGatewayNotifier {
+    fn notify(&mut self, alert: &Alert) {
+        if alert.severity < self.min_severity {
+            return;
+        }
+        let message = alert.to_string();
+        self.last_error = None;
+        for to in &self.recipients {
+            let body = format!(
+                "to={}&key={}&message={}",
+                form_encode(to),
+                form_encode(&self.api_key),
+                form_encode(&message)
+            );
+            if let Err(e) = post(&self.endpoint, "application/x-www-form-urlencoded", &body) {
+                self.last_error = Some(e);
+            }
+        }
+    }
+}
+
+fn post(endpoint: &HttpEndpoint, content_type: &str, body: &str) -> io::Result<()> {
+    let mut stream = TcpStream::connect((endpoint.host.as_str(), endpoint.port))?;
+    stream.set_write_timeout(Some(Duration::from_secs(2)))?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        endpoint.path,
+        endpoint.host,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes())
+}
+
+fn form_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertKind;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_webhook_posts_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut notifier = WebhookNotifier::new(HttpEndpoint { host: "127.0.0.1".into(), port, path: "/hook".into() });
+        notifier.notify(&Alert { kind: AlertKind::LostLink, severity: Severity::Critical, uid: 4, value: 11.0 });
+        assert!(notifier.last_error.is_none());
+
+        let (mut conn, _) = listener.accept().unwrap();
+        let mut request = String::new();
+        conn.read_to_string(&mut request).unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"));
+        assert!(request.contains("\"kind\":\"LostLink\""));
+    }
+
+    #[test]
+    fn test_form_encode() {
+        assert_eq!(form_encode("a b&c"), "a+b%26c");
+    }
+}
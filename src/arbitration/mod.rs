@@ -0,0 +1,128 @@
+use crate::protocol::arbitration::{Arbitration, ComputerRole};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ArbitrationConfig {
+    pub heartbeat_interval_ms: u64,
+    /// The backup takes over after this many heartbeats from the primary are missed
+    pub missed_beats: u8,
+}
+
+impl Default for ArbitrationConfig {
+    fn default() -> Self {
+        Self { heartbeat_interval_ms: 100, missed_beats: 3 }
+    }
+}
+
+/// Arbiter elects a primary between two flight computers running the same stack
+///
+/// Both start as backup; whichever first misses the other's heartbeats takes over with a new term.
+/// If both claim the same term, the lower UID stays primary. Frames from the backup are tagged by
+/// setting `NodeConfig::backup` from `is_backup`.
+#[derive(Debug, Clone, Copy)]
+pub struct Arbiter {
+    uid: u8,
+    config: ArbitrationConfig,
+    role: ComputerRole,
+    term: u16,
+    last_peer_ms: Option<u64>,
+    next_heartbeat_ms: u64,
+    takeover: bool,
+}
+
+impl Arbiter {
+    pub fn new(uid: u8, config: ArbitrationConfig) -> Self {
+        Self {
+            uid,
+            config,
+            role: ComputerRole::Backup,
+            term: 0,
+            last_peer_ms: None,
+            next_heartbeat_ms: 0,
+            takeover: false,
+        }
+    }
+
+    pub fn role(&self) -> ComputerRole {
+        self.role
+    }
+
+    pub fn is_backup(&self) -> bool {
+        self.role == ComputerRole::Backup
+    }
+
+    pub fn term(&self) -> u16 {
+        self.term
+    }
+
+    /// Handles the peer's heartbeat, stepping down if the peer holds a newer or winning claim
+    pub fn on_heartbeat(&mut self, peer: &Arbitration, now_ms: u64) {
+        if peer.uid == self.uid {
+            return;
+        }
+        self.last_peer_ms = Some(now_ms);
+        let peer_wins = peer.term > self.term || (peer.term == self.term && peer.uid < self.uid);
+        if peer.role == ComputerRole::Primary && self.role == ComputerRole::Primary && peer_wins {
+            self.role = ComputerRole::Backup;
+        }
+        self.term = self.term.max(peer.term);
+    }
+
+    /// Takes over if the peer has gone silent and returns a heartbeat when one is due
+    pub fn poll(&mut self, now_ms: u64) -> Option<Arbitration> {
+        let last_peer = *self.last_peer_ms.get_or_insert(now_ms);
+        let timeout = self.config.heartbeat_interval_ms * self.config.missed_beats as u64;
+        if self.role == ComputerRole::Backup && now_ms.saturating_sub(last_peer) > timeout {
+            self.role = ComputerRole::Primary;
+            self.term = self.term.wrapping_add(1);
+            self.takeover = true;
+            self.next_heartbeat_ms = now_ms;
+        }
+        if now_ms < self.next_heartbeat_ms {
+            return None;
+        }
+        self.next_heartbeat_ms = now_ms + self.config.heartbeat_interval_ms;
+        let heartbeat = Arbitration { uid: self.uid, role: self.role, term: self.term, takeover: self.takeover };
+        self.takeover = false;
+        Some(heartbeat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(a: &mut Arbiter, b: &mut Arbiter, now: u64) {
+        if let Some(beat) = a.poll(now) {
+            b.on_heartbeat(&beat, now);
+        }
+        if let Some(beat) = b.poll(now) {
+            a.on_heartbeat(&beat, now);
+        }
+    }
+
+    #[test]
+    fn test_election_takeover_and_step_down() {
+        let mut a = Arbiter::new(1, ArbitrationConfig::default());
+        let mut b = Arbiter::new(2, ArbitrationConfig::default());
+        // Heartbeats sent at boot are lost; A times out first and B hears its claim before its own timeout
+        a.poll(0);
+        b.poll(0);
+        for now in (400..1_000).step_by(100) {
+            exchange(&mut a, &mut b, now);
+        }
+        assert_eq!((a.role(), b.role()), (ComputerRole::Primary, ComputerRole::Backup));
+
+        // A goes silent and B takes over with a new term
+        let mut takeover = None;
+        for now in (1_000..1_500).step_by(100) {
+            takeover = takeover.or(b.poll(now).filter(|beat| beat.takeover));
+        }
+        let takeover = takeover.unwrap();
+        assert_eq!((takeover.role, takeover.term), (ComputerRole::Primary, a.term() + 1));
+
+        // A comes back believing it is primary and steps down on hearing the newer term
+        a.on_heartbeat(&takeover, 1_500);
+        assert!(a.is_backup());
+        assert!(!b.is_backup());
+    }
+}
@@ -0,0 +1,112 @@
+//! Command-line tools for flight logs
+//!
+//! ```text
+//! mesh-cli log trim --from <ms> --to <ms> <input> [-o <output>]
+//! mesh-cli log merge <a.mshlog> <b.mshlog> [-o <output>]
+//! mesh-cli log anonymize <input> [-o <output>]
+//! ```
+//!
+//! Output goes to standard output unless `-o` is given.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+
+use Mesh::flight::log::FlightLog;
+
+const USAGE: &str = "usage:
+  mesh-cli log trim --from <ms> --to <ms> <input> [-o <output>]
+  mesh-cli log merge <a.mshlog> <b.mshlog> [-o <output>]
+  mesh-cli log anonymize <input> [-o <output>]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("mesh-cli: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Options and positional arguments after the subcommand
+#[derive(Default)]
+struct Args<'a> {
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+    output: Option<&'a str>,
+    inputs: Vec<&'a str>,
+}
+
+fn parse(args: &[String]) -> Result<Args<'_>, String> {
+    let mut parsed = Args::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().map(String::as_str).ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--from" => parsed.from_ms = Some(milliseconds(value()?)?),
+            "--to" => parsed.to_ms = Some(milliseconds(value()?)?),
+            "-o" | "--output" => parsed.output = Some(value()?),
+            flag if flag.starts_with('-') => return Err(format!("unknown option {flag}\n{USAGE}")),
+            input => parsed.inputs.push(input),
+        }
+    }
+    Ok(parsed)
+}
+
+fn milliseconds(value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("{value} is not a time in milliseconds"))
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (Some("log"), Some(command)) = (args.first().map(String::as_str), args.get(1)) else {
+        return Err(USAGE.into());
+    };
+    let options = parse(&args[2..])?;
+    let log = match (command.as_str(), options.inputs.as_slice()) {
+        ("trim", [input]) => {
+            let (Some(from_ms), Some(to_ms)) = (options.from_ms, options.to_ms) else {
+                return Err(format!("trim needs --from and --to\n{USAGE}"));
+            };
+            let mut log = read(input)?;
+            log.trim(from_ms, to_ms);
+            log
+        }
+        ("merge", [a, b]) => {
+            let mut log = read(a)?;
+            log.merge(read(b)?);
+            log
+        }
+        ("anonymize", [input]) => {
+            let mut log = read(input)?;
+            log.anonymize();
+            log
+        }
+        _ => return Err(USAGE.into()),
+    };
+    if log.skipped > 0 {
+        eprintln!("mesh-cli: skipped {} undecodable frames", log.skipped);
+    }
+    write(&log, options.output)
+}
+
+fn read(path: &str) -> Result<FlightLog, String> {
+    let file = File::open(path).map_err(|e| format!("{path}: {e}"))?;
+    FlightLog::read(BufReader::new(file)).map_err(|e| format!("{path}: {e}"))
+}
+
+fn write(log: &FlightLog, path: Option<&str>) -> Result<(), String> {
+    let result = match path {
+        Some(path) => File::create(path).and_then(|file| {
+            let mut output = BufWriter::new(file);
+            log.write(&mut output)?;
+            output.flush()
+        }),
+        None => {
+            let mut output = io::stdout().lock();
+            log.write(&mut output).and_then(|()| output.flush())
+        }
+    };
+    result.map_err(|e| format!("{}: {e}", path.unwrap_or("stdout")))
+}
@@ -0,0 +1,134 @@
+use crate::protocol::bootloader::{BootStatus, BootloaderMessage, RefuseReason};
+use crate::protocol::health::ArmingState;
+
+/// Device side: decides whether an Enter request may be honored
+///
+/// Returns the Ack to send; the device should only jump to the bootloader after sending a Ready ack.
+pub fn handle_enter(uid: u8, arming: ArmingState, image_size: u32, max_image_size: u32) -> BootloaderMessage {
+    let status = if arming != ArmingState::Safe {
+        BootStatus::Refused(RefuseReason::Armed)
+    } else if image_size > max_image_size {
+        BootStatus::Refused(RefuseReason::ImageTooLarge)
+    } else {
+        BootStatus::Ready
+    };
+    BootloaderMessage::Ack { uid, status }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Idle,
+    AwaitingAck,
+    Flashing { bytes_written: u32, total: u32 },
+    Done,
+    Failed(SessionError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionError {
+    Refused(RefuseReason),
+    /// No ack or progress report within the timeout
+    Timeout,
+    /// Device reported the written image failed verification
+    VerifyFailed,
+}
+
+/// Host side of the re-flash handshake, driven by the ground-station CLI
+#[derive(Debug, Clone, Copy)]
+pub struct FlashSession {
+    target_uid: u8,
+    timeout_ms: u64,
+    last_heard_ms: u64,
+    state: SessionState,
+}
+
+impl FlashSession {
+    pub fn new(target_uid: u8, timeout_ms: u64) -> Self {
+        Self { target_uid, timeout_ms, last_heard_ms: 0, state: SessionState::Idle }
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Begins the handshake, returning the Enter message to frame and send
+    pub fn start(&mut self, image_size: u32, image_crc: u32, now_ms: u64) -> BootloaderMessage {
+        self.state = SessionState::AwaitingAck;
+        self.last_heard_ms = now_ms;
+        BootloaderMessage::Enter { target_uid: self.target_uid, image_size, image_crc }
+    }
+
+    /// Feeds a message received from the device
+    pub fn on_message(&mut self, message: &BootloaderMessage, now_ms: u64) -> SessionState {
+        let next = match (*message, self.state) {
+            (BootloaderMessage::Ack { uid, status }, SessionState::AwaitingAck) if uid == self.target_uid => {
+                match status {
+                    BootStatus::Ready => SessionState::Flashing { bytes_written: 0, total: 0 },
+                    BootStatus::Refused(reason) => SessionState::Failed(SessionError::Refused(reason)),
+                }
+            }
+            (BootloaderMessage::Progress { uid, bytes_written, total }, SessionState::Flashing { .. })
+                if uid == self.target_uid =>
+            {
+                SessionState::Flashing { bytes_written, total }
+            }
+            (BootloaderMessage::Complete { uid, success }, SessionState::Flashing { .. }) if uid == self.target_uid => {
+                if success {
+                    SessionState::Done
+                } else {
+                    SessionState::Failed(SessionError::VerifyFailed)
+                }
+            }
+            // Messages from other devices or out of sequence are ignored
+            _ => return self.state,
+        };
+        self.state = next;
+        self.last_heard_ms = now_ms;
+        next
+    }
+
+    /// Fails the session if the device has gone quiet, call periodically
+    pub fn poll(&mut self, now_ms: u64) -> SessionState {
+        let waiting = matches!(self.state, SessionState::AwaitingAck | SessionState::Flashing { .. });
+        if waiting && now_ms.saturating_sub(self.last_heard_ms) > self.timeout_ms {
+            self.state = SessionState::Failed(SessionError::Timeout);
+        }
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::serial::{decode_frame, encode_frame};
+
+    #[test]
+    fn test_handshake_over_serial_framing() {
+        let mut session = FlashSession::new(4, 2_000);
+        let mut buf = [0u8; 32];
+        let frame = encode_frame(&session.start(40_000, 0xDEAD_BEEF, 0), &mut buf).unwrap();
+        let enter: BootloaderMessage = decode_frame(frame).unwrap();
+
+        let BootloaderMessage::Enter { image_size, .. } = enter else {
+            panic!("expected Enter");
+        };
+        let ack = handle_enter(4, ArmingState::Safe, image_size, 128 * 1024);
+        assert_eq!(session.on_message(&ack, 100), SessionState::Flashing { bytes_written: 0, total: 0 });
+
+        let progress = BootloaderMessage::Progress { uid: 4, bytes_written: 20_000, total: 40_000 };
+        assert_eq!(session.on_message(&progress, 500), SessionState::Flashing { bytes_written: 20_000, total: 40_000 });
+        assert_eq!(session.on_message(&BootloaderMessage::Complete { uid: 4, success: true }, 900), SessionState::Done);
+    }
+
+    #[test]
+    fn test_refused_when_armed_and_timeout() {
+        let mut session = FlashSession::new(4, 2_000);
+        session.start(1_000, 0, 0);
+        let ack = handle_enter(4, ArmingState::ArmedPad, 1_000, 128 * 1024);
+        assert_eq!(session.on_message(&ack, 10), SessionState::Failed(SessionError::Refused(RefuseReason::Armed)));
+
+        let mut session = FlashSession::new(4, 2_000);
+        session.start(1_000, 0, 0);
+        assert_eq!(session.poll(2_001), SessionState::Failed(SessionError::Timeout));
+    }
+}
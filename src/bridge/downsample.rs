@@ -0,0 +1,120 @@
+use heapless::FnvIndexMap;
+
+use crate::ground::{GroundEvent, Sink};
+use crate::protocol::packet::PacketKind;
+
+/// Number of `(node, kind)` streams tracked per downsampled sink
+pub const MAX_STREAMS: usize = 32;
+
+/// Packet kinds that are periodic telemetry and may be thinned out
+///
+/// Everything else (events, commands, acks) is always passed through.
+pub const TELEMETRY_KINDS: [PacketKind; 3] = [PacketKind::Sensors, PacketKind::Health, PacketKind::TrackerStatus];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownsampleConfig {
+    /// Minimum spacing between forwarded telemetry packets of the same kind from the same node
+    pub interval_ms: u64,
+}
+
+impl DownsampleConfig {
+    pub fn hz(rate: u32) -> Self {
+        Self { interval_ms: 1_000 / rate.max(1) as u64 }
+    }
+}
+
+/// Downsample wraps a sink so it receives telemetry at a reduced rate
+///
+/// Each subscriber gets its own wrapper, e.g. 1 Hz for the public display and the raw sink for the LCO.
+pub struct Downsample<S: Sink> {
+    pub config: DownsampleConfig,
+    inner: S,
+    last: FnvIndexMap<(u8, u8), u64, MAX_STREAMS>,
+    dropped: u32,
+}
+
+impl<S: Sink> Downsample<S> {
+    pub fn new(inner: S, config: DownsampleConfig) -> Self {
+        Self { config, inner, last: FnvIndexMap::new(), dropped: 0 }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Telemetry packets withheld from the inner sink
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    fn admit(&mut self, event: &GroundEvent) -> bool {
+        let kind = event.packet.kind();
+        if !TELEMETRY_KINDS.contains(&kind) {
+            return true;
+        }
+        let key = (event.header.source_uid, kind as u8);
+        match self.last.get(&key) {
+            Some(&last) if event.at_ms.saturating_sub(last) < self.config.interval_ms => false,
+            _ => {
+                // A full table passes untracked streams at full rate rather than silencing them
+                let _ = self.last.insert(key, event.at_ms);
+                true
+            }
+        }
+    }
+}
+
+impl<S: Sink> Sink for Downsample<S> {
+    fn deliver(&mut self, event: &GroundEvent) {
+        if self.admit(event) {
+            self.inner.deliver(event);
+        } else {
+            self.dropped += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::health::Health;
+    use crate::protocol::mesh::MeshHeader;
+    use crate::protocol::packet::Packet;
+
+    #[derive(Default)]
+    struct Count(u32);
+
+    impl Sink for Count {
+        fn deliver(&mut self, _event: &GroundEvent) {
+            self.0 += 1;
+        }
+    }
+
+    fn event(source_uid: u8, at_ms: u64, packet: Packet) -> GroundEvent {
+        let header = MeshHeader {
+            source_uid,
+            destination_uid: 0,
+            sequence: 0,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        GroundEvent { receiver: 0, at_ms, header, packet, quality: Default::default(), sanitized: false }
+    }
+
+    #[test]
+    fn test_telemetry_thinned_events_kept() {
+        let mut public = Downsample::new(Count::default(), DownsampleConfig::hz(1));
+        for i in 0..20 {
+            public.deliver(&event(3, i * 100, Packet::Health(Health::default())));
+            public.deliver(&event(4, i * 100, Packet::Health(Health::default())));
+        }
+        public.deliver(&event(3, 1_950, Packet::Event(FlightEvent::Launch)));
+        // Two per node at 0 and 1000 ms, plus the event
+        assert_eq!(public.inner().0, 5);
+        assert_eq!(public.dropped(), 36);
+    }
+}
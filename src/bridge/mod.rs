@@ -0,0 +1,4 @@
+//! Layers between the ground runtime and its consumers
+
+pub mod downsample;
+pub mod spectator;
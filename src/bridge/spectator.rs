@@ -0,0 +1,129 @@
+use crate::ground::{GroundEvent, Sink};
+use crate::protocol::countdown::CountdownState;
+use crate::protocol::packet::Packet;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectatorConfig {
+    /// GPS positions are snapped to a grid of this many degrees (0.01 deg is roughly 1 km)
+    pub position_grid_deg: f64,
+}
+
+impl Default for SpectatorConfig {
+    fn default() -> Self {
+        Self { position_grid_deg: 0.01 }
+    }
+}
+
+/// Returns the copy of `event` that may be shown to the public, or `None` if it must not be shown at all
+///
+/// Only flight telemetry, flight events and the countdown pass. Node UIDs are zeroed and positions are
+/// coarsened so the pad location cannot be recovered. Because this is an allow-list, packet types added
+/// later (including anything carrying callsigns) stay private until explicitly allowed here.
+pub fn sanitize(event: &GroundEvent, config: &SpectatorConfig) -> Option<GroundEvent> {
+    let packet = match &event.packet {
+        Packet::Sensors(sensors) => {
+            let mut sensors = *sensors;
+            if let Some(gps) = sensors.gps.as_mut() {
+                gps.latitude = snap(gps.latitude, config.position_grid_deg);
+                gps.longitude = snap(gps.longitude, config.position_grid_deg);
+                gps.sats_data = Default::default();
+            }
+            Packet::Sensors(sensors)
+        }
+        Packet::Event(event) => Packet::Event(*event),
+        Packet::Countdown(countdown) => Packet::Countdown(CountdownState { origin_uid: 0, ..*countdown }),
+        _ => return None,
+    };
+    let mut header = event.header;
+    header.source_uid = 0;
+    header.destination_uid = 0;
+    let sanitized = event.sanitized;
+    Some(GroundEvent { receiver: 0, at_ms: event.at_ms, header, packet, quality: Default::default(), sanitized })
+}
+
+fn snap(value: f64, grid: f64) -> f64 {
+    if grid <= 0.0 {
+        return value;
+    }
+    libm::round(value / grid) * grid
+}
+
+/// SpectatorSink enforces the public feed policy in front of the livestream overlay sink
+///
+/// The overlay only ever sees sanitized events, so it does not need to be trusted with raw telemetry.
+pub struct SpectatorSink<S: Sink> {
+    pub config: SpectatorConfig,
+    inner: S,
+}
+
+impl<S: Sink> SpectatorSink<S> {
+    pub fn new(inner: S, config: SpectatorConfig) -> Self {
+        Self { config, inner }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: Sink> Sink for SpectatorSink<S> {
+    fn deliver(&mut self, event: &GroundEvent) {
+        if let Some(public) = sanitize(event, &self.config) {
+            self.inner.deliver(&public);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::command::{Command, SignedCommand};
+    use crate::protocol::mesh::MeshHeader;
+    use crate::protocol::{AllSensorData, GpsFix, GPS};
+
+    fn event(packet: Packet) -> GroundEvent {
+        let header = MeshHeader {
+            source_uid: 7,
+            destination_uid: 1,
+            sequence: 5,
+            hops_left: 2,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        GroundEvent { receiver: 1, at_ms: 10, header, packet, quality: Default::default(), sanitized: false }
+    }
+
+    #[test]
+    fn test_commands_dropped_and_positions_coarsened() {
+        let command = SignedCommand { sender_uid: 1, target_uid: 7, sequence: 1, command: Command::ArmPad, tag: [0; 8] };
+        assert_eq!(sanitize(&event(Packet::Command(command)), &SpectatorConfig::default()), None);
+
+        let gps = GPS {
+            latitude: 37.228_31,
+            longitude: -80.422_17,
+            altitude: 600.0,
+            altitude_msl: 600.0,
+            num_sats: 12,
+            fix_type: GpsFix::Fix3D,
+            utc_time: Default::default(),
+            sats_data: Default::default(),
+        };
+        let sensors = AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: None,
+            gps: Some(gps),
+            adxl375: None,
+            ism330dhcx2: None,
+        };
+        let public = sanitize(&event(Packet::Sensors(sensors)), &SpectatorConfig::default()).unwrap();
+        assert_eq!(public.header.source_uid, 0);
+        let Packet::Sensors(AllSensorData { gps: Some(gps), .. }) = public.packet else {
+            panic!("expected sensors");
+        };
+        assert!((gps.latitude - 37.23).abs() < 1e-9);
+        assert!((gps.longitude + 80.42).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::protocol::config_hash::ConfigHash;
+use crate::protocol::gonogo::{Criterion, CriterionResult, GoNoGoReport, Verdict};
+use crate::protocol::selftest::SelfTestReport;
+use crate::protocol::{AllSensorData, GpsFix};
+
+/// Thresholds for the prelaunch Go/NoGo checklist
+///
+/// Criteria set to `None` (or a zero continuity mask) are not evaluated.
+#[derive(Debug, Clone, Copy)]
+pub struct ChecklistConfig {
+    /// Minimum satellites required together with a 3D fix
+    pub min_sats: Option<u8>,
+    /// Require every sensor in AllSensorData to be reporting
+    pub require_all_sensors: bool,
+    pub min_battery_voltage: Option<f32>,
+    /// Minimum margin above the demodulation floor, in dB
+    pub min_link_margin_db: Option<f32>,
+    /// Bitmask of pyro channels that must show continuity
+    pub required_continuity: u8,
+    /// Require a hardware self-test report with no failures
+    pub require_self_test: bool,
+    /// Configuration the vehicle must report before arming, `None` to skip the check
+    pub expected_config: Option<ConfigHash>,
+}
+
+impl Default for ChecklistConfig {
+    fn default() -> Self {
+        Self {
+            min_sats: Some(8),
+            require_all_sensors: true,
+            min_battery_voltage: Some(7.4),
+            min_link_margin_db: Some(6.0),
+            required_continuity: 0b11,
+            require_self_test: true,
+            expected_config: None,
+        }
+    }
+}
+
+/// Live telemetry the checklist is evaluated against
+///
+/// Values that have not been received yet are `None` and evaluate to `Verdict::Unknown`.
+#[derive(Debug, Clone, Copy)]
+pub struct ChecklistInputs<'a> {
+    pub sensors: Option<&'a AllSensorData>,
+    pub battery_voltage: Option<f32>,
+    pub link_margin_db: Option<f32>,
+    /// Bitmask of pyro channels currently showing continuity
+    pub continuity: Option<u8>,
+    pub self_test: Option<&'a SelfTestReport>,
+    pub config: Option<&'a ConfigHash>,
+}
+
+/// Evaluates every configured criterion and builds the report for the ground display
+pub fn evaluate(config: &ChecklistConfig, inputs: &ChecklistInputs, uid: u8) -> GoNoGoReport {
+    let mut report = GoNoGoReport {
+        uid,
+        overall: Verdict::Go,
+        results: heapless::Vec::new(),
+    };
+
+    if let Some(min_sats) = config.min_sats {
+        let gps = inputs.sensors.and_then(|s| s.gps);
+        let result = match gps {
+            Some(gps) => CriterionResult {
+                criterion: Criterion::GpsFix,
+                verdict: go_if(has_3d_fix(gps.fix_type) && gps.num_sats >= min_sats),
+                value: Some(gps.num_sats as f32),
+            },
+            None => unknown(Criterion::GpsFix),
+        };
+        push(&mut report, result);
+    }
+
+    if config.require_all_sensors {
+        let result = match inputs.sensors {
+            Some(sensors) => {
+                let reporting = sensors_reporting(sensors);
+                CriterionResult {
+                    criterion: Criterion::Sensors,
+                    verdict: go_if(reporting == SENSOR_COUNT),
+                    value: Some(reporting as f32),
+                }
+            }
+            None => unknown(Criterion::Sensors),
+        };
+        push(&mut report, result);
+    }
+
+    if let Some(min) = config.min_battery_voltage {
+        push(&mut report, threshold(Criterion::Battery, inputs.battery_voltage, min));
+    }
+
+    if let Some(min) = config.min_link_margin_db {
+        push(&mut report, threshold(Criterion::LinkMargin, inputs.link_margin_db, min));
+    }
+
+    if config.required_continuity != 0 {
+        let result = match inputs.continuity {
+            Some(mask) => CriterionResult {
+                criterion: Criterion::Continuity,
+                verdict: go_if(mask & config.required_continuity == config.required_continuity),
+                value: Some(mask as f32),
+            },
+            None => unknown(Criterion::Continuity),
+        };
+        push(&mut report, result);
+    }
+
+    if config.require_self_test {
+        let result = match inputs.self_test {
+            Some(self_test) => {
+                let failures = self_test.failures();
+                CriterionResult {
+                    criterion: Criterion::SelfTest,
+                    verdict: go_if(failures == 0),
+                    value: Some(failures as f32),
+                }
+            }
+            None => unknown(Criterion::SelfTest),
+        };
+        push(&mut report, result);
+    }
+
+    if let Some(expected) = &config.expected_config {
+        let result = match inputs.config {
+            Some(reported) => {
+                let mismatches = reported.mismatches(expected);
+                CriterionResult {
+                    criterion: Criterion::Config,
+                    verdict: go_if(mismatches == 0),
+                    value: Some(mismatches as f32),
+                }
+            }
+            None => unknown(Criterion::Config),
+        };
+        push(&mut report, result);
+    }
+
+    report
+}
+
+const SENSOR_COUNT: u8 = 6;
+
+fn sensors_reporting(sensors: &AllSensorData) -> u8 {
+    [
+        sensors.ism330dhcx.is_some(),
+        sensors.lsm6dso32.is_some(),
+        sensors.bmp390.is_some(),
+        sensors.gps.is_some(),
+        sensors.adxl375.is_some(),
+        sensors.ism330dhcx2.is_some(),
+    ]
+    .iter()
+    .filter(|present| **present)
+    .count() as u8
+}
+
+fn has_3d_fix(fix: GpsFix) -> bool {
+    matches!(fix, GpsFix::Fix3D | GpsFix::GPSPlusDeadReckoning)
+}
+
+fn go_if(condition: bool) -> Verdict {
+    if condition {
+        Verdict::Go
+    } else {
+        Verdict::NoGo
+    }
+}
+
+fn unknown(criterion: Criterion) -> CriterionResult {
+    CriterionResult { criterion, verdict: Verdict::Unknown, value: None }
+}
+
+fn threshold(criterion: Criterion, value: Option<f32>, min: f32) -> CriterionResult {
+    match value {
+        Some(value) => CriterionResult { criterion, verdict: go_if(value >= min), value: Some(value) },
+        None => unknown(criterion),
+    }
+}
+
+fn push(report: &mut GoNoGoReport, result: CriterionResult) {
+    // A single NoGo wins; otherwise any Unknown keeps the overall verdict from being Go
+    report.overall = match (report.overall, result.verdict) {
+        (Verdict::NoGo, _) | (_, Verdict::NoGo) => Verdict::NoGo,
+        (Verdict::Unknown, _) | (_, Verdict::Unknown) => Verdict::Unknown,
+        _ => Verdict::Go,
+    };
+    // Capacity covers every Criterion variant, so this cannot overflow
+    let _ = report.results.push(result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{NavSat, BMP390, GPS, UTC};
+
+    fn gps(fix_type: GpsFix, num_sats: u8) -> GPS {
+        GPS {
+            latitude: 37.2,
+            longitude: -80.4,
+            altitude: 600.0,
+            altitude_msl: 610.0,
+            num_sats,
+            fix_type,
+            utc_time: UTC::default(),
+            sats_data: NavSat::default(),
+        }
+    }
+
+    fn sensors(gps: Option<GPS>) -> AllSensorData {
+        AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: Some(BMP390 { pressure: 101_325.0, temperature: 20.0, altitude: 0.0 }),
+            gps,
+            adxl375: None,
+            ism330dhcx2: None,
+        }
+    }
+
+    #[test]
+    fn test_go_when_all_criteria_met() {
+        let config = ChecklistConfig { require_all_sensors: false, ..Default::default() };
+        let data = sensors(Some(gps(GpsFix::Fix3D, 10)));
+        let inputs = ChecklistInputs {
+            sensors: Some(&data),
+            battery_voltage: Some(8.1),
+            link_margin_db: Some(12.0),
+            continuity: Some(0b11),
+            self_test: Some(&SelfTestReport::default()),
+            config: None,
+        };
+        let report = evaluate(&config, &inputs, 7);
+        assert_eq!(report.overall, Verdict::Go);
+        assert_eq!(report.results.len(), 5);
+    }
+
+    #[test]
+    fn test_nogo_on_weak_fix_and_missing_sensors() {
+        let data = sensors(Some(gps(GpsFix::Fix2D, 10)));
+        let inputs = ChecklistInputs {
+            sensors: Some(&data),
+            battery_voltage: Some(8.1),
+            link_margin_db: None,
+            continuity: Some(0b01),
+            self_test: None,
+            config: Some(&ConfigHash { uid: 7, params: 1, keys: 2, channel_plan: 4 }),
+        };
+        let expected = ConfigHash { uid: 7, params: 9, keys: 2, channel_plan: 3 };
+        let config = ChecklistConfig { expected_config: Some(expected), ..Default::default() };
+        let report = evaluate(&config, &inputs, 7);
+        assert_eq!(report.overall, Verdict::NoGo);
+        assert_eq!(report.result(Criterion::GpsFix).unwrap().verdict, Verdict::NoGo);
+        assert_eq!(report.result(Criterion::Sensors).unwrap().value, Some(2.0));
+        assert_eq!(report.result(Criterion::LinkMargin).unwrap().verdict, Verdict::Unknown);
+        assert_eq!(report.result(Criterion::Continuity).unwrap().verdict, Verdict::NoGo);
+        assert_eq!(report.result(Criterion::SelfTest).unwrap().verdict, Verdict::Unknown);
+        assert_eq!(report.result(Criterion::Config).unwrap().value, Some(2.0));
+    }
+}
@@ -0,0 +1,139 @@
+use core::cell::Cell;
+
+/// Clock is the node's monotonic time source in milliseconds
+///
+/// Mesh timers (retransmission, dedup windows, staleness) read time through this trait so the same logic
+/// runs on hardware, on the desktop, and deterministically under test.
+pub trait Clock {
+    fn now_ms(&self) -> u64;
+
+    /// Blocks for at least `ms` milliseconds
+    fn delay_ms(&self, ms: u32) {
+        let until = self.now_ms() + ms as u64;
+        while self.now_ms() < until {
+            core::hint::spin_loop();
+        }
+    }
+
+    fn elapsed_ms(&self, since_ms: u64) -> u64 {
+        self.now_ms().saturating_sub(since_ms)
+    }
+}
+
+impl<T: Clock + ?Sized> Clock for &T {
+    fn now_ms(&self) -> u64 {
+        (**self).now_ms()
+    }
+
+    fn delay_ms(&self, ms: u32) {
+        (**self).delay_ms(ms)
+    }
+}
+
+/// MockClock only moves when told to, for tests and the simulator
+///
+/// Delays advance the clock instantly. Share one clock between several nodes by passing `&MockClock`.
+#[derive(Debug, Default)]
+pub struct MockClock {
+    now: Cell<u64>,
+}
+
+impl MockClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self { now: Cell::new(start_ms) }
+    }
+
+    pub fn set(&self, now_ms: u64) {
+        self.now.set(now_ms);
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now.set(self.now.get() + ms);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_ms(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn delay_ms(&self, ms: u32) {
+        self.advance(ms as u64);
+    }
+}
+
+/// TickClock extends a free-running hardware counter into a monotonic 64-bit millisecond clock
+///
+/// `read_ticks` returns the raw counter (SysTick, RTC or timer peripheral), which may wrap at 32 bits.
+/// The clock must be read at least once per counter wrap period.
+pub struct TickClock<F: Fn() -> u32> {
+    read_ticks: F,
+    ticks_per_ms: u32,
+    /// Last raw reading and the total ticks accumulated up to it
+    state: Cell<(u32, u64)>,
+}
+
+impl<F: Fn() -> u32> TickClock<F> {
+    pub fn new(read_ticks: F, ticks_per_ms: u32) -> Self {
+        let start = read_ticks();
+        Self { read_ticks, ticks_per_ms: ticks_per_ms.max(1), state: Cell::new((start, 0)) }
+    }
+}
+
+impl<F: Fn() -> u32> Clock for TickClock<F> {
+    fn now_ms(&self) -> u64 {
+        let (last, total) = self.state.get();
+        let ticks = (self.read_ticks)();
+        let total = total + ticks.wrapping_sub(last) as u64;
+        self.state.set((ticks, total));
+        total / self.ticks_per_ms as u64
+    }
+}
+
+/// StdClock measures time since it was created
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct StdClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self { start: std::time::Instant::now() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    fn delay_ms(&self, ms: u32) {
+        std::thread::sleep(std::time::Duration::from_millis(ms as u64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_clock_survives_counter_wrap() {
+        let ticks = Cell::new(u32::MAX - 999);
+        let clock = TickClock::new(|| ticks.get(), 1_000);
+        ticks.set(ticks.get().wrapping_add(1_500));
+        assert_eq!(clock.now_ms(), 1);
+        ticks.set(ticks.get().wrapping_add(2_500));
+        assert_eq!(clock.now_ms(), 4);
+    }
+
+    #[test]
+    fn test_mock_delay_advances() {
+        let clock = MockClock::new(100);
+        clock.delay_ms(50);
+        assert_eq!(clock.now_ms(), 150);
+        assert_eq!(clock.elapsed_ms(120), 30);
+    }
+}
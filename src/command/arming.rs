@@ -0,0 +1,109 @@
+use crate::protocol::command::Command;
+use crate::protocol::health::ArmingState;
+
+/// Timeouts after which an armed state automatically reverts to SAFE
+#[derive(Debug, Clone, Copy)]
+pub struct ArmingConfig {
+    /// Time allowed in ARMED_PAD before advancing to ARMED_FLIGHT
+    pub pad_timeout_ms: u64,
+    /// Time allowed in ARMED_FLIGHT before launch is detected
+    pub flight_timeout_ms: u64,
+}
+
+impl Default for ArmingConfig {
+    fn default() -> Self {
+        Self { pad_timeout_ms: 15 * 60_000, flight_timeout_ms: 5 * 60_000 }
+    }
+}
+
+/// Arming interlock state machine: SAFE -> ARMED_PAD -> ARMED_FLIGHT
+///
+/// Any state can be returned to SAFE by command, armed states revert to SAFE on timeout.
+#[derive(Debug, Clone, Copy)]
+pub struct Arming {
+    config: ArmingConfig,
+    state: ArmingState,
+    entered_at_ms: u64,
+    launched: bool,
+}
+
+impl Arming {
+    pub fn new(config: ArmingConfig) -> Self {
+        Self { config, state: ArmingState::Safe, entered_at_ms: 0, launched: false }
+    }
+
+    pub fn state(&self) -> ArmingState {
+        self.state
+    }
+
+    /// Applies an arming command, returning the new state or `None` if the transition is not allowed
+    pub fn command(&mut self, command: Command, now_ms: u64) -> Option<ArmingState> {
+        let next = match (self.state, command) {
+            (_, Command::Safe) => ArmingState::Safe,
+            (ArmingState::Safe, Command::ArmPad) => ArmingState::ArmedPad,
+            (ArmingState::ArmedPad, Command::ArmFlight) => ArmingState::ArmedFlight,
+            _ => return None,
+        };
+        self.enter(next, now_ms);
+        Some(next)
+    }
+
+    /// Stops the ARMED_FLIGHT timeout once the vehicle has left the pad
+    pub fn notify_launch(&mut self) {
+        if self.state == ArmingState::ArmedFlight {
+            self.launched = true;
+        }
+    }
+
+    /// Reverts to SAFE if the current armed state timed out, returning the state that expired
+    pub fn poll(&mut self, now_ms: u64) -> Option<ArmingState> {
+        let timeout = match self.state {
+            ArmingState::Safe => return None,
+            ArmingState::ArmedFlight if self.launched => return None,
+            ArmingState::ArmedPad => self.config.pad_timeout_ms,
+            ArmingState::ArmedFlight => self.config.flight_timeout_ms,
+        };
+        if now_ms.saturating_sub(self.entered_at_ms) <= timeout {
+            return None;
+        }
+        let expired = self.state;
+        self.enter(ArmingState::Safe, now_ms);
+        Some(expired)
+    }
+
+    fn enter(&mut self, state: ArmingState, now_ms: u64) {
+        self.state = state;
+        self.entered_at_ms = now_ms;
+        self.launched = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_must_follow_sequence() {
+        let mut arming = Arming::new(ArmingConfig::default());
+        assert_eq!(arming.command(Command::ArmFlight, 0), None);
+        assert_eq!(arming.command(Command::ArmPad, 0), Some(ArmingState::ArmedPad));
+        assert_eq!(arming.command(Command::ArmFlight, 10), Some(ArmingState::ArmedFlight));
+        assert_eq!(arming.command(Command::Safe, 20), Some(ArmingState::Safe));
+    }
+
+    #[test]
+    fn test_timeout_reverts_to_safe_unless_launched() {
+        let config = ArmingConfig { pad_timeout_ms: 1_000, flight_timeout_ms: 1_000 };
+        let mut arming = Arming::new(config);
+        arming.command(Command::ArmPad, 0);
+        assert_eq!(arming.poll(1_000), None);
+        assert_eq!(arming.poll(1_001), Some(ArmingState::ArmedPad));
+        assert_eq!(arming.state(), ArmingState::Safe);
+
+        arming.command(Command::ArmPad, 2_000);
+        arming.command(Command::ArmFlight, 2_500);
+        arming.notify_launch();
+        assert_eq!(arming.poll(10_000), None);
+        assert_eq!(arming.state(), ArmingState::ArmedFlight);
+    }
+}
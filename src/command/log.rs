@@ -0,0 +1,73 @@
+use heapless::Deque;
+
+use crate::protocol::command::Command;
+use crate::protocol::health::ArmingState;
+
+/// Why a command was refused by the command layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Authentication tag did not verify against the sender's key
+    BadTag,
+    /// Sequence number was not newer than the last accepted one from this sender
+    Replay,
+    /// Addressed to a different node
+    WrongTarget,
+    /// Authenticated fields could not be serialized for verification
+    Encoding,
+    /// The same UID tried to provide both confirmations
+    SameConfirmer,
+    /// Arming command not valid from the current arming state
+    InvalidTransition,
+    /// Sender's role does not allow this class of message
+    NotPermitted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandEvent {
+    Received { sender_uid: u8, command: Command },
+    Rejected { sender_uid: u8, reason: RejectReason },
+    AwaitingConfirmation { sender_uid: u8, command: Command },
+    ConfirmationExpired { sender_uid: u8, command: Command },
+    Executed { command: Command },
+    ArmingChanged { from: ArmingState, to: ArmingState },
+    /// An armed state timed out and the vehicle reverted to SAFE
+    ArmingTimeout { from: ArmingState },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoggedEvent {
+    pub at_ms: u64,
+    pub event: CommandEvent,
+}
+
+/// Fixed-size log of command events, oldest entries are dropped once full
+#[derive(Debug, Default)]
+pub struct EventLog<const N: usize> {
+    events: Deque<LoggedEvent, N>,
+}
+
+impl<const N: usize> EventLog<N> {
+    pub fn new() -> Self {
+        Self { events: Deque::new() }
+    }
+
+    pub fn record(&mut self, at_ms: u64, event: CommandEvent) {
+        if self.events.is_full() {
+            self.events.pop_front();
+        }
+        let _ = self.events.push_back(LoggedEvent { at_ms, event });
+    }
+
+    /// Events from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
@@ -0,0 +1,363 @@
+pub mod arming;
+pub mod log;
+pub mod roles;
+
+use heapless::FnvIndexMap;
+
+use crate::protocol::command::{Command, SignedCommand};
+use crate::protocol::health::{ArmingState, DegradationLevel, Health};
+use arming::{Arming, ArmingConfig};
+use log::{CommandEvent, EventLog, RejectReason};
+use roles::{MessageClass, RoleTable};
+
+/// Number of command events kept for post-flight review
+pub const EVENT_LOG_LEN: usize = 32;
+/// Number of distinct senders tracked for replay protection, enough for every UID the role table holds
+pub const MAX_SENDERS: usize = roles::MAX_ROLES;
+
+/// Authenticator verifies command tags, implemented by the application with its key material
+pub trait Authenticator {
+    /// Returns true if `tag` is a valid tag over `message` for the given sender
+    fn verify(&self, sender_uid: u8, message: &[u8], tag: &[u8]) -> bool;
+}
+
+/// Action the node should carry out after a command has been accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Abort,
+    /// The arming state changed, by command or by timeout
+    Arming(ArmingState),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CommandConfig {
+    /// Window in which the second Abort confirmation must arrive
+    pub abort_window_ms: u64,
+    pub arming: ArmingConfig,
+}
+
+impl Default for CommandConfig {
+    fn default() -> Self {
+        Self { abort_window_ms: 10_000, arming: ArmingConfig::default() }
+    }
+}
+
+/// Two-man rule: a command is only acted on once confirmed by a second, distinct UID within a window
+#[derive(Debug, Clone, Copy)]
+pub struct TwoManRule {
+    pub window_ms: u64,
+    pending: Option<(u8, u64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// First confirmation recorded, waiting for a second UID
+    Pending,
+    Confirmed,
+    /// The same UID confirmed twice, the pending request is kept unchanged
+    SameConfirmer,
+}
+
+impl TwoManRule {
+    pub fn new(window_ms: u64) -> Self {
+        Self { window_ms, pending: None }
+    }
+
+    /// Removes a pending confirmation older than the window, returning the UID that issued it
+    pub fn expire(&mut self, now_ms: u64) -> Option<u8> {
+        match self.pending {
+            Some((uid, at_ms)) if now_ms.saturating_sub(at_ms) > self.window_ms => {
+                self.pending = None;
+                Some(uid)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn confirm(&mut self, uid: u8, now_ms: u64) -> Confirmation {
+        self.expire(now_ms);
+        match self.pending {
+            None => {
+                self.pending = Some((uid, now_ms));
+                Confirmation::Pending
+            }
+            Some((first, _)) if first == uid => Confirmation::SameConfirmer,
+            Some(_) => {
+                self.pending = None;
+                Confirmation::Confirmed
+            }
+        }
+    }
+}
+
+/// CommandProcessor authenticates uplinked commands and turns them into onboard actions
+///
+/// Every decision, including rejections, is recorded in the event log. Sequence numbers are only tracked
+/// for senders whose role permits commands, so other key holders can't crowd them out of replay protection.
+pub struct CommandProcessor<A: Authenticator> {
+    uid: u8,
+    authenticator: A,
+    last_sequence: FnvIndexMap<u8, u16, MAX_SENDERS>,
+    abort: TwoManRule,
+    arming: Arming,
+    roles: RoleTable,
+    log: EventLog<EVENT_LOG_LEN>,
+}
+
+impl<A: Authenticator> CommandProcessor<A> {
+    /// Only UIDs given the LCO role in `roles` are allowed to command the vehicle
+    pub fn new(uid: u8, authenticator: A, roles: RoleTable, config: CommandConfig) -> Self {
+        Self {
+            uid,
+            authenticator,
+            last_sequence: FnvIndexMap::new(),
+            abort: TwoManRule::new(config.abort_window_ms),
+            arming: Arming::new(config.arming),
+            roles,
+            log: EventLog::new(),
+        }
+    }
+
+    pub fn roles(&self) -> &RoleTable {
+        &self.roles
+    }
+
+    /// Checks that a sender's role allows a class of message, logging a rejection if not
+    pub fn authorize(&mut self, sender_uid: u8, class: MessageClass, now_ms: u64) -> bool {
+        let permitted = self.roles.permits(sender_uid, class);
+        if !permitted {
+            self.log.record(now_ms, CommandEvent::Rejected { sender_uid, reason: RejectReason::NotPermitted });
+        }
+        permitted
+    }
+
+    pub fn arming_state(&self) -> ArmingState {
+        self.arming.state()
+    }
+
+    /// Forwards launch detection to the arming state machine
+    pub fn notify_launch(&mut self) {
+        self.arming.notify_launch();
+    }
+
+    /// Builds a health packet carrying the current arming state
+    ///
+    /// Nodes running a `BatteryPolicy` fill in `degradation` from `BatteryPolicy::level`.
+    pub fn health(&self, uptime_ms: u32, battery_voltage: f32) -> Health {
+        Health {
+            uid: self.uid,
+            uptime_ms,
+            battery_voltage,
+            arming: self.arming.state(),
+            rebooted: false,
+            degradation: DegradationLevel::Normal,
+        }
+    }
+
+    pub fn log(&self) -> &EventLog<EVENT_LOG_LEN> {
+        &self.log
+    }
+
+    /// Expires stale confirmations and arming states, call periodically so timeouts are acted on promptly
+    pub fn poll(&mut self, now_ms: u64) -> Option<Action> {
+        if let Some(sender_uid) = self.abort.expire(now_ms) {
+            self.log.record(now_ms, CommandEvent::ConfirmationExpired { sender_uid, command: Command::Abort });
+        }
+        let from = self.arming.poll(now_ms)?;
+        self.log.record(now_ms, CommandEvent::ArmingTimeout { from });
+        Some(Action::Arming(ArmingState::Safe))
+    }
+
+    pub fn handle(&mut self, cmd: &SignedCommand, now_ms: u64) -> Option<Action> {
+        let timed_out = self.poll(now_ms);
+        if let Err(reason) = self.authenticate(cmd) {
+            self.log.record(now_ms, CommandEvent::Rejected { sender_uid: cmd.sender_uid, reason });
+            return timed_out;
+        }
+        if !self.authorize(cmd.sender_uid, MessageClass::Command, now_ms) {
+            return timed_out;
+        }
+        self.last_sequence
+            .insert(cmd.sender_uid, cmd.sequence)
+            .expect("only role holders are tracked, and the role table holds at most MAX_SENDERS");
+        self.log.record(now_ms, CommandEvent::Received { sender_uid: cmd.sender_uid, command: cmd.command });
+
+        match cmd.command {
+            Command::Abort => match self.abort.confirm(cmd.sender_uid, now_ms) {
+                Confirmation::Pending => {
+                    self.log.record(now_ms, CommandEvent::AwaitingConfirmation {
+                        sender_uid: cmd.sender_uid,
+                        command: cmd.command,
+                    });
+                    timed_out
+                }
+                Confirmation::SameConfirmer => {
+                    self.log.record(now_ms, CommandEvent::Rejected {
+                        sender_uid: cmd.sender_uid,
+                        reason: RejectReason::SameConfirmer,
+                    });
+                    timed_out
+                }
+                Confirmation::Confirmed => {
+                    self.log.record(now_ms, CommandEvent::Executed { command: cmd.command });
+                    // An abort always safes the vehicle
+                    if self.arming.state() != ArmingState::Safe {
+                        self.apply_arming(Command::Safe, cmd.sender_uid, now_ms);
+                    }
+                    Some(Action::Abort)
+                }
+            },
+            Command::ArmPad | Command::ArmFlight | Command::Safe => {
+                self.apply_arming(cmd.command, cmd.sender_uid, now_ms).or(timed_out)
+            }
+        }
+    }
+
+    fn apply_arming(&mut self, command: Command, sender_uid: u8, now_ms: u64) -> Option<Action> {
+        let from = self.arming.state();
+        match self.arming.command(command, now_ms) {
+            Some(to) => {
+                self.log.record(now_ms, CommandEvent::ArmingChanged { from, to });
+                Some(Action::Arming(to))
+            }
+            None => {
+                self.log.record(now_ms, CommandEvent::Rejected { sender_uid, reason: RejectReason::InvalidTransition });
+                None
+            }
+        }
+    }
+
+    fn authenticate(&self, cmd: &SignedCommand) -> Result<(), RejectReason> {
+        if cmd.target_uid != self.uid {
+            return Err(RejectReason::WrongTarget);
+        }
+        let mut buf = [0u8; 16];
+        let message = cmd.signed_bytes(&mut buf).map_err(|_| RejectReason::Encoding)?;
+        if !self.authenticator.verify(cmd.sender_uid, message, &cmd.tag) {
+            return Err(RejectReason::BadTag);
+        }
+        if let Some(&last) = self.last_sequence.get(&cmd.sender_uid) {
+            let diff = cmd.sequence.wrapping_sub(last);
+            if diff == 0 || diff >= 0x8000 {
+                return Err(RejectReason::Replay);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::command::TAG_LEN;
+    use crate::protocol::node_info::Role;
+
+    /// Test authenticator: the tag is the sender UID repeated
+    struct UidTag;
+
+    impl Authenticator for UidTag {
+        fn verify(&self, sender_uid: u8, _message: &[u8], tag: &[u8]) -> bool {
+            tag.iter().all(|b| *b == sender_uid)
+        }
+    }
+
+    fn lcos() -> RoleTable {
+        let mut roles = RoleTable::new();
+        roles.assign(1, Role::Lco);
+        roles.assign(2, Role::Lco);
+        roles.assign(3, Role::Recovery);
+        roles
+    }
+
+    fn config() -> CommandConfig {
+        CommandConfig { abort_window_ms: 5_000, arming: ArmingConfig { pad_timeout_ms: 60_000, flight_timeout_ms: 60_000 } }
+    }
+
+    fn signed(sender_uid: u8, sequence: u16, command: Command) -> SignedCommand {
+        SignedCommand { sender_uid, target_uid: 9, sequence, command, tag: [sender_uid; TAG_LEN] }
+    }
+
+    fn abort(sender_uid: u8, sequence: u16) -> SignedCommand {
+        signed(sender_uid, sequence, Command::Abort)
+    }
+
+    #[test]
+    fn test_abort_requires_two_distinct_confirmations() {
+        let mut processor = CommandProcessor::new(9, UidTag, lcos(), config());
+        assert_eq!(processor.handle(&abort(1, 1), 0), None);
+        assert_eq!(processor.handle(&abort(1, 2), 100), None);
+        assert_eq!(processor.handle(&abort(2, 1), 200), Some(Action::Abort));
+        assert!(processor
+            .log()
+            .iter()
+            .any(|e| e.event == CommandEvent::Rejected { sender_uid: 1, reason: RejectReason::SameConfirmer }));
+    }
+
+    #[test]
+    fn test_confirmation_window_expires() {
+        let mut processor = CommandProcessor::new(9, UidTag, lcos(), config());
+        assert_eq!(processor.handle(&abort(1, 1), 0), None);
+        assert_eq!(processor.handle(&abort(2, 1), 6_000), None);
+        assert!(processor
+            .log()
+            .iter()
+            .any(|e| e.event == CommandEvent::ConfirmationExpired { sender_uid: 1, command: Command::Abort }));
+        assert_eq!(processor.handle(&abort(1, 2), 7_000), Some(Action::Abort));
+    }
+
+    #[test]
+    fn test_rejects_bad_tag_and_replay() {
+        let mut processor = CommandProcessor::new(9, UidTag, lcos(), config());
+        let mut forged = abort(1, 1);
+        forged.tag = [0; TAG_LEN];
+        assert_eq!(processor.handle(&forged, 0), None);
+        assert_eq!(processor.handle(&abort(1, 1), 0), None);
+        assert_eq!(processor.handle(&abort(2, 5), 10), Some(Action::Abort));
+        assert_eq!(processor.handle(&abort(2, 5), 20), None);
+        assert_eq!(
+            processor.log().iter().last().unwrap().event,
+            CommandEvent::Rejected { sender_uid: 2, reason: RejectReason::Replay }
+        );
+
+        // Authentic commands from senders who may not command don't use up replay protection for the LCOs
+        let mut processor = CommandProcessor::new(9, UidTag, lcos(), config());
+        for sender_uid in 10..10 + MAX_SENDERS as u8 {
+            assert_eq!(processor.handle(&abort(sender_uid, 1), 0), None);
+        }
+        assert_eq!(processor.handle(&abort(1, 1), 10), None);
+        assert_eq!(processor.handle(&abort(2, 1), 20), Some(Action::Abort));
+        assert_eq!(processor.handle(&abort(2, 1), 30), None);
+        assert_eq!(
+            processor.log().iter().last().unwrap().event,
+            CommandEvent::Rejected { sender_uid: 2, reason: RejectReason::Replay }
+        );
+    }
+
+    #[test]
+    fn test_arming_commands_and_health_echo() {
+        let mut processor = CommandProcessor::new(9, UidTag, lcos(), config());
+        assert_eq!(processor.handle(&signed(1, 1, Command::ArmFlight), 0), None);
+        assert_eq!(processor.handle(&signed(1, 2, Command::ArmPad), 0), Some(Action::Arming(ArmingState::ArmedPad)));
+        assert_eq!(processor.health(1_000, 8.0).arming, ArmingState::ArmedPad);
+
+        assert_eq!(processor.poll(60_001), Some(Action::Arming(ArmingState::Safe)));
+        assert_eq!(processor.health(61_000, 8.0).arming, ArmingState::Safe);
+        assert_eq!(
+            processor.log().iter().last().unwrap().event,
+            CommandEvent::ArmingTimeout { from: ArmingState::ArmedPad }
+        );
+    }
+
+    #[test]
+    fn test_only_lco_may_command() {
+        let mut processor = CommandProcessor::new(9, UidTag, lcos(), config());
+        assert_eq!(processor.handle(&signed(3, 1, Command::ArmPad), 0), None);
+        assert_eq!(processor.handle(&signed(4, 1, Command::ArmPad), 0), None);
+        assert_eq!(
+            processor.log().iter().last().unwrap().event,
+            CommandEvent::Rejected { sender_uid: 4, reason: RejectReason::NotPermitted }
+        );
+        assert!(processor.authorize(3, MessageClass::Text, 0));
+        assert_eq!(processor.arming_state(), ArmingState::Safe);
+    }
+}
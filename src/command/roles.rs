@@ -0,0 +1,82 @@
+use heapless::FnvIndexMap;
+
+use crate::protocol::node_info::{NodeInfo, Role};
+
+/// Number of UIDs that can be assigned a role
+pub const MAX_ROLES: usize = 16;
+
+/// Class of message a ground node is trying to transmit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageClass {
+    Command,
+    Text,
+    Position,
+}
+
+/// Returns true if a node with the given role may transmit this class of message
+pub fn permits(role: Role, class: MessageClass) -> bool {
+    match role {
+        Role::Lco => true,
+        Role::Recovery => matches!(class, MessageClass::Text | MessageClass::Position),
+        Role::Observer => false,
+    }
+}
+
+/// RoleTable binds each UID (and therefore its signing key) to a role
+///
+/// UIDs that are not registered are treated as observers.
+#[derive(Debug, Clone, Default)]
+pub struct RoleTable {
+    roles: FnvIndexMap<u8, Role, MAX_ROLES>,
+}
+
+impl RoleTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assigns a role, returns false if the table is full
+    pub fn assign(&mut self, uid: u8, role: Role) -> bool {
+        self.roles.insert(uid, role).is_ok()
+    }
+
+    pub fn role(&self, uid: u8) -> Role {
+        self.roles.get(&uid).copied().unwrap_or_default()
+    }
+
+    pub fn permits(&self, uid: u8, class: MessageClass) -> bool {
+        permits(self.role(uid), class)
+    }
+
+    /// Checks that the role a node announces matches the one bound to its key
+    pub fn validate(&self, info: &NodeInfo) -> bool {
+        self.role(info.uid) == info.role
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        let mut table = RoleTable::new();
+        table.assign(1, Role::Lco);
+        table.assign(2, Role::Recovery);
+
+        assert!(table.permits(1, MessageClass::Command));
+        assert!(!table.permits(2, MessageClass::Command));
+        assert!(table.permits(2, MessageClass::Position));
+        assert!(!table.permits(3, MessageClass::Text));
+    }
+
+    #[test]
+    fn test_validate_announced_role() {
+        let mut table = RoleTable::new();
+        table.assign(2, Role::Recovery);
+        let mut info = NodeInfo { uid: 2, role: Role::Recovery, ..Default::default() };
+        assert!(table.validate(&info));
+        info.role = Role::Lco;
+        assert!(!table.validate(&info));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::protocol::countdown::{CountdownPhase, CountdownState};
+
+/// Countdown keeps the latest CountdownState received from the mesh and derives the current T-minus
+///
+/// Used the same way by pad crew handhelds, onboard logging, and the ground station itself.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Countdown {
+    state: Option<CountdownState>,
+    received_at_ms: u64,
+}
+
+impl Countdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a received state, returning true if it was new and should be logged and rebroadcast
+    pub fn apply(&mut self, state: CountdownState, now_ms: u64) -> bool {
+        if let Some(current) = self.state {
+            if current.origin_uid == state.origin_uid && !is_newer(state.sequence, current.sequence) {
+                return false;
+            }
+        }
+        self.state = Some(state);
+        self.received_at_ms = now_ms;
+        true
+    }
+
+    pub fn state(&self) -> Option<&CountdownState> {
+        self.state.as_ref()
+    }
+
+    pub fn phase(&self) -> CountdownPhase {
+        self.state.map(|s| s.phase).unwrap_or_default()
+    }
+
+    /// Current time to T-0 in milliseconds, negative after T-0
+    ///
+    /// Frozen while in a hold, `None` when idle or aborted.
+    pub fn t_minus_ms(&self, now_ms: u64) -> Option<i64> {
+        let state = self.state?;
+        match state.phase {
+            CountdownPhase::Counting => {
+                let elapsed = now_ms.saturating_sub(self.received_at_ms) as i64;
+                Some(state.t_minus_ms as i64 - elapsed)
+            }
+            CountdownPhase::Hold => Some(state.t_minus_ms as i64),
+            CountdownPhase::Idle | CountdownPhase::Aborted => None,
+        }
+    }
+}
+
+/// Sequence comparison tolerant of u16 wraparound
+fn is_newer(candidate: u16, current: u16) -> bool {
+    let diff = candidate.wrapping_sub(current);
+    diff != 0 && diff < 0x8000
+}
+
+/// Sequencer originates countdown states on the ground station
+#[derive(Debug, Clone, Copy)]
+pub struct Sequencer {
+    uid: u8,
+    sequence: u16,
+    local: Countdown,
+}
+
+impl Sequencer {
+    pub fn new(uid: u8) -> Self {
+        Self { uid, sequence: 0, local: Countdown::new() }
+    }
+
+    /// Local view of the countdown as issued by this sequencer
+    pub fn countdown(&self) -> &Countdown {
+        &self.local
+    }
+
+    /// Starts (or recycles) the count from the given time to T-0
+    pub fn start(&mut self, t_minus_ms: i32, now_ms: u64) -> CountdownState {
+        self.issue(CountdownPhase::Counting, t_minus_ms, now_ms)
+    }
+
+    pub fn hold(&mut self, now_ms: u64) -> CountdownState {
+        let remaining = self.remaining(now_ms);
+        self.issue(CountdownPhase::Hold, remaining, now_ms)
+    }
+
+    pub fn resume(&mut self, now_ms: u64) -> CountdownState {
+        let remaining = self.remaining(now_ms);
+        self.issue(CountdownPhase::Counting, remaining, now_ms)
+    }
+
+    pub fn abort(&mut self, now_ms: u64) -> CountdownState {
+        let remaining = self.remaining(now_ms);
+        self.issue(CountdownPhase::Aborted, remaining, now_ms)
+    }
+
+    /// Re-issues the current state unchanged, for periodic rebroadcast to late joiners
+    pub fn current(&self, now_ms: u64) -> CountdownState {
+        let mut state = self.local.state().copied().unwrap_or(CountdownState {
+            origin_uid: self.uid,
+            sequence: self.sequence,
+            ..Default::default()
+        });
+        if let Some(remaining) = self.local.t_minus_ms(now_ms) {
+            state.t_minus_ms = remaining as i32;
+        }
+        state
+    }
+
+    fn remaining(&self, now_ms: u64) -> i32 {
+        self.local
+            .t_minus_ms(now_ms)
+            .or_else(|| self.local.state().map(|s| s.t_minus_ms as i64))
+            .unwrap_or(0) as i32
+    }
+
+    fn issue(&mut self, phase: CountdownPhase, t_minus_ms: i32, now_ms: u64) -> CountdownState {
+        self.sequence = self.sequence.wrapping_add(1);
+        let state = CountdownState { origin_uid: self.uid, sequence: self.sequence, phase, t_minus_ms };
+        self.local.apply(state, now_ms);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hold_freezes_and_resume_continues() {
+        let mut sequencer = Sequencer::new(1);
+        let mut node = Countdown::new();
+
+        assert!(node.apply(sequencer.start(60_000, 0), 100));
+        assert_eq!(node.t_minus_ms(10_100), Some(50_000));
+
+        let hold = sequencer.hold(20_000);
+        assert_eq!(hold.t_minus_ms, 40_000);
+        assert!(node.apply(hold, 20_050));
+        assert_eq!(node.t_minus_ms(90_000), Some(40_000));
+
+        assert!(node.apply(sequencer.resume(100_000), 100_000));
+        assert_eq!(node.t_minus_ms(145_000), Some(-5_000));
+    }
+
+    #[test]
+    fn test_stale_and_repeated_states_are_not_forwarded() {
+        let mut sequencer = Sequencer::new(1);
+        let mut node = Countdown::new();
+        let first = sequencer.start(10_000, 0);
+        let second = sequencer.abort(1_000);
+
+        assert!(node.apply(second, 1_000));
+        assert!(!node.apply(second, 1_010));
+        assert!(!node.apply(first, 1_020));
+        assert_eq!(node.phase(), CountdownPhase::Aborted);
+        assert_eq!(node.t_minus_ms(2_000), None);
+    }
+
+    #[test]
+    fn test_sequence_wraparound() {
+        assert!(is_newer(0, u16::MAX));
+        assert!(!is_newer(u16::MAX, 0));
+    }
+}
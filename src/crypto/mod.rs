@@ -0,0 +1,260 @@
+//! Sealing custom command payloads so commands such as firing a pyro channel can't be spoofed or replayed
+//!
+//! Payloads are encrypted with ChaCha20-Poly1305 under a 256-bit key each team pre-shares between its
+//! ground station and vehicles. The 96-bit nonce is
+//!
+//! ```text
+//! sender uid (u8) | zero (3 bytes) | zero (4 bytes) | counter (u32, little endian)
+//! ```
+//!
+//! so it never repeats as long as each sender's counter doesn't, which `Sealer` persists in blocks like the
+//! frame counters and never wraps. The sender and target UIDs are authenticated as associated data. Only the
+//! sub-types a node registers with `CustomRegistry::register_sealed` need sealing, everything else stays plain.
+//!
+//! Telemetry is not encrypted, but `trailer` authenticates it against spoofing.
+
+pub mod trailer;
+
+use chacha20poly1305::aead::AeadInPlace;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce, Tag};
+use heapless::{FnvIndexMap, Vec};
+
+use crate::persistence::{self, keys, Persistence};
+use crate::protocol::custom::{CustomPayload, MAX_CUSTOM_DATA};
+use crate::protocol::mesh::BROADCAST_UID;
+use crate::protocol::sealed::{Sealed, SEAL_TAG_LEN};
+use crate::protocol::Error;
+
+/// Length of the pre-shared team key
+pub const KEY_LEN: usize = 32;
+/// Counters are reserved in blocks of this size so storage is written once per block, not once per message
+pub const RESERVE_BLOCK: u32 = 32;
+/// Maximum number of senders whose last counter an Opener remembers, a power of two
+pub const MAX_SENDERS: usize = 16;
+
+/// A pre-shared team key
+pub type Key = [u8; KEY_LEN];
+
+fn nonce(sender_uid: u8, counter: u32) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[0] = sender_uid;
+    nonce[8..].copy_from_slice(&counter.to_le_bytes());
+    nonce.into()
+}
+
+#[derive(Debug)]
+pub enum SealError<E> {
+    Storage(persistence::Error<E>),
+    /// Every counter value under this key has been used, sealing again would repeat a nonce
+    CounterExhausted,
+}
+
+impl<E> From<persistence::Error<E>> for SealError<E> {
+    fn from(error: persistence::Error<E>) -> Self {
+        SealError::Storage(error)
+    }
+}
+
+/// Sealer encrypts this node's outgoing custom payloads
+///
+/// Once the counter reaches `u32::MAX` it refuses to seal; the team key must be replaced before then.
+pub struct Sealer {
+    uid: u8,
+    cipher: ChaCha20Poly1305,
+    counter: u32,
+    /// Counter up to which the stored block is valid
+    reserved_until: u32,
+}
+
+impl Sealer {
+    /// Resumes from the stored counter, past every value that may have been used before a restart
+    pub fn resume<P: Persistence>(uid: u8, key: &Key, store: &mut P) -> Result<Self, persistence::Error<P::Error>> {
+        let mut buf = [0u8; 8];
+        let counter: Option<u32> = persistence::load(store, keys::CRYPTO_COUNTERS, &mut buf)?;
+        let counter = counter.unwrap_or(0);
+        let mut sealer = Self { uid, cipher: ChaCha20Poly1305::new(key.into()), counter, reserved_until: counter };
+        sealer.reserve(store)?;
+        Ok(sealer)
+    }
+
+    /// Encrypts `payload` for `target_uid`, or for every node holding the key with `BROADCAST_UID`
+    pub fn seal<P: Persistence>(
+        &mut self,
+        target_uid: u8,
+        payload: &CustomPayload,
+        store: &mut P,
+    ) -> Result<Sealed, SealError<P::Error>> {
+        if self.counter == self.reserved_until {
+            if self.reserved_until == u32::MAX {
+                return Err(SealError::CounterExhausted);
+            }
+            self.reserve(store)?;
+        }
+        // Below `reserved_until`, so the increment can't overflow
+        let counter = self.counter;
+        self.counter += 1;
+
+        let mut ciphertext = Vec::new();
+        // Both fit: the capacity is sized for the largest payload plus the tag
+        ciphertext.push(payload.sub_type).expect("sealed capacity");
+        ciphertext.extend_from_slice(&payload.data).expect("sealed capacity");
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(&nonce(self.uid, counter), &[self.uid, target_uid], &mut ciphertext)
+            .expect("payload within the cipher's length limit");
+        ciphertext.extend_from_slice(&tag).expect("sealed capacity");
+        Ok(Sealed { sender_uid: self.uid, target_uid, counter, ciphertext })
+    }
+
+    fn reserve<P: Persistence>(&mut self, store: &mut P) -> Result<(), persistence::Error<P::Error>> {
+        self.reserved_until = self.counter.saturating_add(RESERVE_BLOCK);
+        let mut buf = [0u8; 8];
+        persistence::save(store, keys::CRYPTO_COUNTERS, &self.reserved_until, &mut buf)
+    }
+}
+
+/// Opener authenticates and decrypts sealed payloads addressed to this node
+///
+/// It keeps the last counter accepted from each sender and refuses anything not newer. That state is in
+/// RAM; call `save` after acting on a command and `resume` at boot, or a restart lets old commands be
+/// replayed once more. New senders beyond `MAX_SENDERS` are refused with `TooManySenders`.
+pub struct Opener {
+    uid: u8,
+    cipher: ChaCha20Poly1305,
+    accepted: FnvIndexMap<u8, u32, MAX_SENDERS>,
+}
+
+impl Opener {
+    pub fn new(uid: u8, key: &Key) -> Self {
+        Self { uid, cipher: ChaCha20Poly1305::new(key.into()), accepted: FnvIndexMap::new() }
+    }
+
+    /// Restores the counters accepted before a restart
+    pub fn resume<P: Persistence>(uid: u8, key: &Key, store: &mut P) -> Result<Self, persistence::Error<P::Error>> {
+        let mut opener = Self::new(uid, key);
+        let mut buf = [0u8; 128];
+        if let Some(accepted) = persistence::load(store, keys::CRYPTO_REPLAY, &mut buf)? {
+            opener.accepted = accepted;
+        }
+        Ok(opener)
+    }
+
+    pub fn save<P: Persistence>(&self, store: &mut P) -> Result<(), persistence::Error<P::Error>> {
+        let mut buf = [0u8; 128];
+        persistence::save(store, keys::CRYPTO_REPLAY, &self.accepted, &mut buf)
+    }
+
+    /// Returns the payload for `CustomRegistry::dispatch_opened`
+    ///
+    /// A message for another node, with a bad tag or under another key is `Unauthenticated`; an authentic one
+    /// whose counter isn't above the last accepted from its sender is `Replayed`, and one from a new sender
+    /// once `MAX_SENDERS` are tracked is `TooManySenders`.
+    pub fn open(&mut self, sealed: &Sealed) -> Result<CustomPayload, Error> {
+        if sealed.target_uid != self.uid && sealed.target_uid != BROADCAST_UID {
+            return Err(Error::Unauthenticated);
+        }
+        let split = sealed.ciphertext.len().checked_sub(SEAL_TAG_LEN).ok_or(Error::Truncated)?;
+        let (ciphertext, tag) = sealed.ciphertext.split_at(split);
+        let mut plaintext: Vec<u8, { 1 + MAX_CUSTOM_DATA }> = Vec::from_slice(ciphertext).map_err(|_| Error::TooLong)?;
+        self.cipher
+            .decrypt_in_place_detached(
+                &nonce(sealed.sender_uid, sealed.counter),
+                &[sealed.sender_uid, sealed.target_uid],
+                &mut plaintext,
+                Tag::from_slice(tag),
+            )
+            .map_err(|_| Error::Unauthenticated)?;
+
+        if self.accepted.get(&sealed.sender_uid).is_some_and(|&last| sealed.counter <= last) {
+            return Err(Error::Replayed);
+        }
+        self.accepted.insert(sealed.sender_uid, sealed.counter).map_err(|_| Error::TooManySenders)?;
+        let (&sub_type, data) = plaintext.split_first().ok_or(Error::Truncated)?;
+        CustomPayload::new(sub_type, data).ok_or(Error::TooLong)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryStore;
+    use crate::protocol::custom::{CustomHandler, CustomRegistry};
+    use crate::protocol::Comment;
+
+    struct Fire(u8);
+
+    impl CustomHandler for Fire {
+        fn handle(&mut self, _source_uid: u8, data: &[u8]) {
+            self.0 = data[0];
+        }
+    }
+
+    #[test]
+    fn test_seal_open_rejects_tamper_replay_and_plain() {
+        let key = [7u8; KEY_LEN];
+        let mut store: MemoryStore<4, 128> = MemoryStore::new();
+        let mut sealer = Sealer::resume(0, &key, &mut store).unwrap();
+        let mut opener = Opener::new(3, &key);
+        let fire = CustomPayload::new(0x50, &[2]).unwrap();
+
+        let sealed = sealer.seal(3, &fire, &mut store).unwrap();
+        assert_eq!(sealed.ciphertext.len(), 2 + SEAL_TAG_LEN);
+        let opened = opener.open(&sealed).unwrap();
+        assert_eq!(opened, fire);
+        assert_eq!(opener.open(&sealed), Err(Error::Replayed));
+
+        let mut tampered = sealer.seal(3, &fire, &mut store).unwrap();
+        tampered.ciphertext[1] ^= 1;
+        assert_eq!(opener.open(&tampered), Err(Error::Unauthenticated));
+        let mut retargeted = sealer.seal(4, &fire, &mut store).unwrap();
+        assert_eq!(opener.open(&retargeted), Err(Error::Unauthenticated));
+        retargeted.target_uid = 3;
+        assert_eq!(opener.open(&retargeted), Err(Error::Unauthenticated));
+        let other_team = sealer.seal(3, &fire, &mut store).unwrap();
+        assert_eq!(Opener::new(3, &[8u8; KEY_LEN]).open(&other_team), Err(Error::Unauthenticated));
+
+        // Counters survive a restart on both ends
+        opener.save(&mut store).unwrap();
+        let mut sealer = Sealer::resume(0, &key, &mut store).unwrap();
+        let mut opener = Opener::resume(3, &key, &mut store).unwrap();
+        assert_eq!(opener.open(&sealed), Err(Error::Replayed));
+        let after = sealer.seal(BROADCAST_UID, &fire, &mut store).unwrap();
+        assert!(after.counter > sealed.counter);
+
+        // A sealed-only sub-type is refused in plain and delivered once opened
+        let mut handler = Fire(0);
+        let mut registry = CustomRegistry::new();
+        registry.register_sealed(0x50, &mut handler).unwrap();
+        let mut comment = Comment::default();
+        fire.write_to(&mut comment);
+        assert_eq!(registry.dispatch(&comment), Err(Error::Unauthenticated));
+        let opened = opener.open(&after).unwrap();
+        assert_eq!(registry.dispatch_opened(0, &opened), Ok(0x50));
+        drop(registry);
+        assert_eq!(handler.0, 2);
+    }
+
+    #[test]
+    fn test_full_replay_table_and_exhausted_counter() {
+        let key = [7u8; KEY_LEN];
+        let mut store: MemoryStore<4, 128> = MemoryStore::new();
+        let mut opener = Opener::new(3, &key);
+        let fire = CustomPayload::new(0x50, &[2]).unwrap();
+        for uid in 0..MAX_SENDERS as u8 {
+            let sealed = Sealer::resume(uid, &key, &mut store).unwrap().seal(3, &fire, &mut store).unwrap();
+            assert_eq!(opener.open(&sealed), Ok(fire.clone()));
+        }
+        let mut late = Sealer::resume(MAX_SENDERS as u8, &key, &mut store).unwrap();
+        assert_eq!(opener.open(&late.seal(3, &fire, &mut store).unwrap()), Err(Error::TooManySenders));
+
+        // The last counters are used, then sealing stops instead of wrapping to a used nonce
+        let mut buf = [0u8; 8];
+        persistence::save(&mut store, keys::CRYPTO_COUNTERS, &(u32::MAX - 2), &mut buf).unwrap();
+        let mut sealer = Sealer::resume(0, &key, &mut store).unwrap();
+        assert_eq!(sealer.seal(0, &fire, &mut store).unwrap().counter, u32::MAX - 2);
+        assert_eq!(sealer.seal(0, &fire, &mut store).unwrap().counter, u32::MAX - 1);
+        assert!(matches!(sealer.seal(0, &fire, &mut store), Err(SealError::CounterExhausted)));
+        let mut sealer = Sealer::resume(0, &key, &mut store).unwrap();
+        assert!(matches!(sealer.seal(0, &fire, &mut store), Err(SealError::CounterExhausted)));
+    }
+}
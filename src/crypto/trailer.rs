@@ -0,0 +1,186 @@
+//! Truncated HMAC-SHA256 trailers that let the ground station reject spoofed telemetry
+//!
+//! Telemetry stays in the clear. For the packet kinds a team chooses, the sender appends the first
+//! `tag_len` bytes of an HMAC over
+//!
+//! ```text
+//! serialized header with hops_left = 0 and route = None | packet bytes
+//! ```
+//!
+//! after the packet, inside the CRC. The tag covers every header field but the two relays rewrite: each
+//! lowers the hop count, and a source route advances at each relay and is dropped when the next relay is out
+//! of earshot. Changing those only alters how a frame travels, not who sent it or what it says, so the tag
+//! survives any number of hops. Receivers without the key ignore the trailer like any other bytes after the
+//! packet.
+//!
+//! A captured frame replayed within the ground's dedup window is dropped as a duplicate; later it shows
+//! up in `LossTracker` as a frame arriving far out of order.
+
+use heapless::Vec;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::protocol::mesh::{MeshFrame, MeshHeader, MAX_FRAME_LEN};
+use crate::protocol::packet::PacketKind;
+use crate::protocol::Error;
+
+/// Shortest and longest trailer, 4 bytes already leave a forger one chance in four billion per frame
+pub const MIN_TAG_LEN: usize = 4;
+pub const MAX_TAG_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrailerConfig {
+    /// Bytes of the HMAC sent, clamped to `MIN_TAG_LEN..=MAX_TAG_LEN`
+    pub tag_len: usize,
+    /// Whether each packet kind carries a trailer, indexed by `PacketKind as usize`
+    pub signed: [bool; PacketKind::COUNT],
+}
+
+impl TrailerConfig {
+    /// Trailers of `tag_len` bytes on `kinds` only
+    pub fn new(tag_len: usize, kinds: &[PacketKind]) -> Self {
+        let mut signed = [false; PacketKind::COUNT];
+        for kind in kinds {
+            signed[*kind as usize] = true;
+        }
+        Self { tag_len, signed }
+    }
+}
+
+/// Trailer signs outgoing frames on a node and checks incoming ones on the ground, under one shared key
+///
+/// Both ends must use the same config: a kind signed on one end and not the other is rejected or
+/// accepted unchecked.
+#[derive(Clone)]
+pub struct Trailer {
+    mac: Hmac<Sha256>,
+    config: TrailerConfig,
+}
+
+impl Trailer {
+    pub fn new(key: &[u8], config: TrailerConfig) -> Self {
+        let mac = Hmac::new_from_slice(key).expect("HMAC takes keys of any length");
+        let tag_len = config.tag_len.clamp(MIN_TAG_LEN, MAX_TAG_LEN);
+        Self { mac, config: TrailerConfig { tag_len, ..config } }
+    }
+
+    pub fn signs(&self, kind: PacketKind) -> bool {
+        self.config.signed[kind as usize]
+    }
+
+    /// The trailer to send `frame` with, empty for kinds that aren't signed
+    pub fn tag(&self, frame: &MeshFrame) -> postcard::Result<Vec<u8, MAX_TAG_LEN>> {
+        if !self.signs(frame.packet.kind()) {
+            return Ok(Vec::new());
+        }
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let packet = postcard::to_slice(&frame.packet, &mut buf)?;
+        let mac = self.mac(&frame.header, packet).finalize().into_bytes();
+        Ok(Vec::from_slice(&mac[..self.config.tag_len]).expect("tag_len is at most MAX_TAG_LEN"))
+    }
+
+    /// Decodes the message `checksum::verify_and_strip` returned, checking the trailer if its kind is signed
+    ///
+    /// A signed kind with a missing or wrong trailer is `Unauthenticated`; other kinds decode as with
+    /// `MeshFrame::decode`.
+    pub fn verify(&self, message: &[u8]) -> Result<MeshFrame, Error> {
+        let (frame, trailing) = MeshFrame::decode_with_trailer(message)?;
+        if !self.signs(frame.packet.kind()) {
+            return Ok(frame);
+        }
+        let tag_len = self.config.tag_len;
+        if trailing.len() < tag_len {
+            return Err(Error::Unauthenticated);
+        }
+        // The tag is the last bytes, anything between the packet and it is fields this build doesn't know
+        let (_, rest) = postcard::take_from_bytes::<MeshHeader>(message)?;
+        let (packet, tag) = rest.split_at(rest.len() - tag_len);
+        self.mac(&frame.header, packet).verify_truncated_left(tag).map_err(|_| Error::Unauthenticated)?;
+        Ok(frame)
+    }
+
+    fn mac(&self, header: &MeshHeader, packet: &[u8]) -> Hmac<Sha256> {
+        let header = MeshHeader { hops_left: 0, route: None, ..*header };
+        let mut buf = [0u8; 16];
+        let header = postcard::to_slice(&header, &mut buf).expect("a header without a route fits 16 bytes");
+        let mut mac = self.mac.clone();
+        mac.update(header);
+        mac.update(packet);
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::checksum;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::hello::Hello;
+    use crate::protocol::mesh::SourceRoute;
+    use crate::protocol::packet::Packet;
+
+    fn frame(packet: Packet) -> MeshFrame {
+        let header = MeshHeader {
+            source_uid: 3,
+            destination_uid: 0,
+            sequence: 41,
+            hops_left: 2,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        MeshFrame { header, packet }
+    }
+
+    fn message(frame: &MeshFrame, trailer: &[u8]) -> std::vec::Vec<u8> {
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let len = frame.encode_with_trailer(trailer, &mut buf).unwrap();
+        checksum::verify_and_strip(&buf[..len]).unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_signed_kinds_need_a_valid_trailer() {
+        let config = TrailerConfig::new(6, &[PacketKind::Event]);
+        let trailer = Trailer::new(b"team key", config);
+        let landed = frame(Packet::Event(FlightEvent::Landed));
+        let tag = trailer.tag(&landed).unwrap();
+        assert_eq!(tag.len(), 6);
+        assert_eq!(trailer.verify(&message(&landed, &tag)), Ok(landed.clone()));
+
+        // A relay lowering the hop count or advancing and dropping the route keeps the tag valid
+        let mut routed = landed.clone();
+        routed.header.route = SourceRoute::new(&[5, 6]);
+        let routed_tag = trailer.tag(&routed).unwrap();
+        let mut relayed = routed.clone();
+        relayed.header.hops_left = 1;
+        relayed.header.route = relayed.header.route.map(SourceRoute::advance);
+        assert_eq!(trailer.verify(&message(&relayed, &routed_tag)), Ok(relayed.clone()));
+        relayed.header.route = None;
+        assert_eq!(trailer.verify(&message(&relayed, &routed_tag)), Ok(relayed));
+
+        // Any other header field is covered
+        let forgeries: [fn(&mut MeshHeader); 6] = [
+            |header| header.source_uid = 4,
+            |header| header.destination_uid = 1,
+            |header| header.sequence = 42,
+            |header| header.ack_requested = true,
+            |header| header.rebooted = true,
+            |header| header.backup = true,
+        ];
+        for forge in forgeries {
+            let mut forged = landed.clone();
+            forge(&mut forged.header);
+            assert_eq!(trailer.verify(&message(&forged, &tag)), Err(Error::Unauthenticated));
+        }
+        assert_eq!(trailer.verify(&message(&landed, &[])), Err(Error::Unauthenticated));
+        let other_key = Trailer::new(b"guessed key", config);
+        assert_eq!(other_key.verify(&message(&landed, &tag)), Err(Error::Unauthenticated));
+
+        // Unsigned kinds pass without one, and receivers without the key ignore the trailer
+        let hello = frame(Packet::Hello(Hello { count: 1, interval_ms: 1_000 }));
+        assert!(trailer.tag(&hello).unwrap().is_empty());
+        assert_eq!(trailer.verify(&message(&hello, &[])), Ok(hello));
+        assert_eq!(MeshFrame::decode(&message(&landed, &tag)), Ok(landed));
+    }
+}
@@ -0,0 +1,41 @@
+use crate::protocol::BMP390;
+
+/// Specific gas constant of dry air, J/(kg K)
+const R_AIR: f32 = 287.05;
+/// Ratio of specific heats of air
+const GAMMA: f32 = 1.4;
+
+/// Aerodynamic state derived from fused velocity and the barometer's static pressure and temperature
+///
+/// Only the vertical velocity is fused, so both values assume near-vertical flight.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Aero {
+    pub dynamic_pressure_pa: f32,
+    pub mach: f32,
+}
+
+impl Aero {
+    pub fn compute(velocity_mps: f32, baro: &BMP390) -> Self {
+        let kelvin = (baro.temperature + 273.15).max(1.0);
+        let density = baro.pressure / (R_AIR * kelvin);
+        let speed_of_sound = libm::sqrtf(GAMMA * R_AIR * kelvin);
+        Self {
+            dynamic_pressure_pa: 0.5 * density * velocity_mps * velocity_mps,
+            mach: velocity_mps.abs() / speed_of_sound,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sea_level_standard_atmosphere() {
+        let baro = BMP390 { pressure: 101_325.0, temperature: 15.0, altitude: 0.0 };
+        let aero = Aero::compute(-340.3, &baro);
+        assert!((aero.mach - 1.0).abs() < 0.01);
+        // rho = 1.225 kg/m^3
+        assert!((aero.dynamic_pressure_pa - 0.5 * 1.225 * 340.3 * 340.3).abs() < 100.0);
+    }
+}
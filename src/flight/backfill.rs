@@ -0,0 +1,197 @@
+//! Post-processing of raw flight logs with the current fusion filter and state machine
+//!
+//! Logs recorded before derived state existed can be replayed to produce the same derived records a
+//! current node would have emitted, so flights from different seasons compare like for like.
+
+use serde::{Deserialize, Serialize};
+
+use super::fusion::{AltitudeFilter, FilterConfig};
+use super::state::{FlightPhase, FlightStateMachine, StateMachineConfig};
+use crate::protocol::events::FlightEvent;
+#[cfg(feature = "std")]
+use crate::protocol::vehicle::{LogHeader, VehicleConfig};
+use crate::protocol::AllSensorData;
+
+/// A raw telemetry frame as stored in a flight log
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RawRecord {
+    pub at_ms: u64,
+    pub data: AllSensorData,
+}
+
+/// Derived state computed for one raw record
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct DerivedRecord {
+    pub at_ms: u64,
+    pub altitude_m: f32,
+    pub vertical_velocity_mps: f32,
+    pub dynamic_pressure_pa: f32,
+    pub mach: f32,
+    pub phase: FlightPhase,
+    pub event: Option<FlightEvent>,
+}
+
+/// Replays raw records through a fresh filter and state machine
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Backfill {
+    filter: AltitudeFilter,
+    state_machine: FlightStateMachine,
+}
+
+impl Backfill {
+    pub fn new(filter: FilterConfig, state_machine: StateMachineConfig) -> Self {
+        Self { filter: AltitudeFilter::new(filter), state_machine: FlightStateMachine::new(state_machine) }
+    }
+
+    /// Processes the next record in log order, records without a barometer reading produce nothing
+    pub fn process(&mut self, record: &RawRecord) -> Option<DerivedRecord> {
+        let state = self.filter.update_frame(record.at_ms, &record.data)?;
+        let event = self.state_machine.update(&state, record.at_ms);
+        Some(DerivedRecord {
+            at_ms: record.at_ms,
+            altitude_m: state.altitude_m,
+            vertical_velocity_mps: state.vertical_velocity_mps,
+            dynamic_pressure_pa: self.filter.aero().dynamic_pressure_pa,
+            mach: self.filter.aero().mach,
+            phase: self.state_machine.phase(),
+            event,
+        })
+    }
+}
+
+/// Scratch space for one serialized RawRecord, sized for a full NavSat satellite table
+#[cfg(feature = "std")]
+pub const RECORD_FRAME_LEN: usize = 2048;
+
+/// Counts from a log backfill run
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BackfillSummary {
+    pub records: usize,
+    pub derived: usize,
+    /// Frames that could not be decoded and were skipped
+    pub skipped: usize,
+    /// Vehicle from the log's header, `None` for logs recorded without one
+    pub vehicle: Option<VehicleConfig>,
+}
+
+/// Writes the LogHeader that starts a flight log
+#[cfg(feature = "std")]
+pub fn write_log_header<W: std::io::Write>(mut output: W, vehicle: &VehicleConfig) -> std::io::Result<()> {
+    let mut buf = [0u8; 128];
+    let frame = crate::protocol::serial::encode_frame(&LogHeader::new(vehicle.clone()), &mut buf)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    output.write_all(frame)
+}
+
+/// Reads a log of COBS-framed RawRecords and writes COBS-framed DerivedRecords
+///
+/// A LogHeader at the head of the input is reported in the summary and copied to the head of the output.
+#[cfg(feature = "std")]
+pub fn backfill_log<R: std::io::Read, W: std::io::Write>(
+    mut input: R,
+    mut output: W,
+    backfill: &mut Backfill,
+) -> std::io::Result<BackfillSummary> {
+    use postcard::accumulator::FeedResult;
+
+    use crate::protocol::serial::{encode_frame, FrameAccumulator};
+
+    let mut summary = BackfillSummary::default();
+    let mut accumulator: FrameAccumulator<RECORD_FRAME_LEN> = FrameAccumulator::new();
+    let mut chunk = [0u8; 256];
+    let mut out = [0u8; 64];
+    let mut at_head = true;
+    loop {
+        let n = input.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(summary);
+        }
+        let mut window = &chunk[..n];
+        if core::mem::take(&mut at_head) {
+            if let Some(end) = window.iter().position(|b| *b == 0) {
+                let mut frame = [0u8; 256];
+                frame[..=end].copy_from_slice(&window[..=end]);
+                if let Ok(header) = LogHeader::decode(&mut frame[..=end]) {
+                    write_log_header(&mut output, &header.vehicle)?;
+                    summary.vehicle = Some(header.vehicle);
+                    window = &window[end + 1..];
+                }
+            }
+        }
+        while !window.is_empty() {
+            window = match accumulator.feed::<RawRecord>(window) {
+                FeedResult::Consumed => break,
+                FeedResult::OverFull(rest) | FeedResult::DeserError(rest) => {
+                    summary.skipped += 1;
+                    rest
+                }
+                FeedResult::Success { data, remaining } => {
+                    summary.records += 1;
+                    if let Some(derived) = backfill.process(&data) {
+                        let frame = encode_frame(&derived, &mut out)
+                            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                        output.write_all(frame)?;
+                        summary.derived += 1;
+                    }
+                    remaining
+                }
+            };
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::flight::GRAVITY;
+    use crate::protocol::serial::decode_frame;
+    use crate::protocol::{BMP390, LSM6DSO32};
+
+    fn record(at_ms: u64, altitude: f32, accel_up: f64) -> RawRecord {
+        let imu = LSM6DSO32 { accel_x: 0.0, accel_y: 0.0, accel_z: accel_up, gyro_x: 0.0, gyro_y: 0.0, gyro_z: 0.0 };
+        RawRecord {
+            at_ms,
+            data: AllSensorData {
+                ism330dhcx: None,
+                lsm6dso32: Some(imu),
+                bmp390: Some(BMP390 { pressure: 90_000.0, temperature: 10.0, altitude }),
+                gps: None,
+                adxl375: None,
+                ism330dhcx2: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_backfill_log_emits_launch() {
+        let mut log = std::vec::Vec::new();
+        let motor = "M1850".try_into().unwrap();
+        let vehicle = VehicleConfig { uid: 4, dry_mass_kg: 18.5, motor, ..Default::default() };
+        write_log_header(&mut log, &vehicle).unwrap();
+        let mut buf = [0u8; RECORD_FRAME_LEN];
+        let accel = GRAVITY as f64;
+        let samples = [(600.0, accel), (600.0, accel), (601.0, accel + 60.0), (610.0, accel + 60.0)];
+        for (i, (altitude, accel_up)) in samples.into_iter().enumerate() {
+            log.extend_from_slice(encode(&record(i as u64 * 100, altitude, accel_up), &mut buf));
+        }
+        log.extend_from_slice(&[1, 2, 3, 0]);
+
+        let mut out = std::vec::Vec::new();
+        let summary = backfill_log(&log[..], &mut out, &mut Backfill::default()).unwrap();
+        assert_eq!(summary, BackfillSummary { records: 4, derived: 4, skipped: 1, vehicle: Some(vehicle.clone()) });
+
+        let mut frames = out.split_mut(|b| *b == 0).filter(|f| !f.is_empty());
+        assert_eq!(LogHeader::decode(frames.next().unwrap()).unwrap().vehicle, vehicle);
+        let mut events = std::vec::Vec::new();
+        for frame in frames {
+            let derived: DerivedRecord = decode_frame(frame).unwrap();
+            events.extend(derived.event);
+        }
+        assert_eq!(events, [FlightEvent::Launch]);
+    }
+
+    fn encode<'a>(record: &RawRecord, buf: &'a mut [u8]) -> &'a [u8] {
+        crate::protocol::serial::encode_frame(record, buf).unwrap()
+    }
+}
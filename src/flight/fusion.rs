@@ -0,0 +1,150 @@
+use crate::protocol::AllSensorData;
+
+use super::aero::Aero;
+use super::GRAVITY;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FilterConfig {
+    /// Weight given to the barometric altitude residual
+    pub alpha: f32,
+    /// Weight given to the residual when correcting velocity
+    pub beta: f32,
+    /// Above this Mach number barometric corrections are ignored, shock waves make static pressure unreliable
+    pub baro_lockout_mach: Option<f32>,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self { alpha: 0.3, beta: 0.05, baro_lockout_mach: Some(0.8) }
+    }
+}
+
+/// Filtered vertical state relative to the pad
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FilterState {
+    pub altitude_m: f32,
+    pub vertical_velocity_mps: f32,
+    /// Vertical acceleration with gravity removed, when an accelerometer was available
+    pub vertical_accel_mps2: Option<f32>,
+}
+
+/// Alpha-beta filter fusing barometric altitude with vertical acceleration
+///
+/// The accelerometer drives the prediction step and the barometer corrects it, so the velocity estimate
+/// stays smooth through baro noise and transonic pressure spikes. The first barometric sample is taken as
+/// pad altitude.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AltitudeFilter {
+    pub config: FilterConfig,
+    pad_altitude: Option<f32>,
+    state: FilterState,
+    last_ms: Option<u64>,
+    aero: Aero,
+}
+
+impl AltitudeFilter {
+    pub fn new(config: FilterConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    pub fn state(&self) -> FilterState {
+        self.state
+    }
+
+    /// Dynamic pressure and Mach number from the last `update_frame`
+    pub fn aero(&self) -> Aero {
+        self.aero
+    }
+
+    pub fn baro_locked_out(&self) -> bool {
+        self.config.baro_lockout_mach.is_some_and(|limit| self.aero.mach > limit)
+    }
+
+    /// Runs one filter step, `accel_up` is the specific force along the vehicle's up axis in m/s^2
+    pub fn update(&mut self, now_ms: u64, baro_altitude: f32, accel_up: Option<f32>) -> FilterState {
+        self.step(now_ms, Some(baro_altitude), accel_up)
+    }
+
+    fn step(&mut self, now_ms: u64, baro_altitude: Option<f32>, accel_up: Option<f32>) -> FilterState {
+        let pad = *self.pad_altitude.get_or_insert(baro_altitude.unwrap_or(0.0));
+        let measured = baro_altitude.map(|altitude| altitude - pad);
+        let accel = accel_up.map(|a| a - GRAVITY);
+        let dt = self.last_ms.map_or(0.0, |last| now_ms.saturating_sub(last) as f32 / 1000.0);
+        self.last_ms = Some(now_ms);
+
+        let state = &mut self.state;
+        state.vertical_accel_mps2 = accel;
+        if dt <= 0.0 {
+            state.altitude_m = measured.unwrap_or(state.altitude_m);
+            return *state;
+        }
+        let a = accel.unwrap_or(0.0);
+        state.altitude_m += state.vertical_velocity_mps * dt + 0.5 * a * dt * dt;
+        state.vertical_velocity_mps += a * dt;
+
+        let Some(measured) = measured else {
+            return *state;
+        };
+        let residual = measured - state.altitude_m;
+        state.altitude_m += self.config.alpha * residual;
+        state.vertical_velocity_mps += self.config.beta * residual / dt;
+        *state
+    }
+
+    /// Runs one step from a telemetry frame, returns `None` if it has no barometer reading
+    ///
+    /// While the vehicle is above the lockout Mach number only the accelerometer propagates the state.
+    pub fn update_frame(&mut self, now_ms: u64, frame: &AllSensorData) -> Option<FilterState> {
+        let baro = frame.bmp390?;
+        let accel = frame.ism330dhcx.map(|imu| imu.accel_z as f32).or(frame.lsm6dso32.map(|imu| imu.accel_z as f32));
+        let altitude = (!self.baro_locked_out() || accel.is_none()).then_some(baro.altitude);
+        let state = self.step(now_ms, altitude, accel);
+        self.aero = Aero::compute(state.vertical_velocity_mps, &baro);
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_constant_climb() {
+        let mut filter = AltitudeFilter::new(FilterConfig::default());
+        filter.update(0, 600.0, Some(GRAVITY));
+        let mut state = FilterState::default();
+        for step in 1..=200 {
+            let t = step as f32 * 0.05;
+            state = filter.update(step * 50, 600.0 + 50.0 * t, Some(GRAVITY));
+        }
+        assert!((state.altitude_m - 500.0).abs() < 5.0);
+        assert!((state.vertical_velocity_mps - 50.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_baro_ignored_above_lockout_mach() {
+        use crate::protocol::{BMP390, LSM6DSO32};
+
+        let frame = |altitude: f32, accel_z: f64| AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: Some(LSM6DSO32 { accel_x: 0.0, accel_y: 0.0, accel_z, gyro_x: 0.0, gyro_y: 0.0, gyro_z: 0.0 }),
+            bmp390: Some(BMP390 { pressure: 90_000.0, temperature: 15.0, altitude }),
+            gps: None,
+            adxl375: None,
+            ism330dhcx2: None,
+        };
+        let mut filter = AltitudeFilter::new(FilterConfig::default());
+        filter.update_frame(0, &frame(0.0, GRAVITY as f64));
+        // Hard boost to supersonic speed, tracked by the barometer
+        for step in 1..=20 {
+            let t = step as f32 * 0.1;
+            filter.update_frame(step * 100, &frame(100.0 * t * t, (GRAVITY + 200.0) as f64));
+        }
+        assert!(filter.baro_locked_out());
+        let before = filter.state().altitude_m;
+        // A transonic pressure spike reads as a 500 m drop and is ignored
+        let state = filter.update_frame(2_100, &frame(-500.0, GRAVITY as f64)).unwrap();
+        assert!(state.altitude_m > before);
+        assert!(filter.aero().dynamic_pressure_pa > 50_000.0);
+    }
+}
@@ -0,0 +1,134 @@
+//! Whole-log edits for sharing flight data: trimming to a time window, merging and anonymizing
+//!
+//! Logs are the COBS-framed `.mshlog` files written on board, an optional LogHeader followed by RawRecords,
+//! see `backfill`. They are small enough to edit in memory.
+
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use postcard::accumulator::FeedResult;
+
+use super::backfill::{write_log_header, RawRecord, RECORD_FRAME_LEN};
+use crate::protocol::serial::{encode_frame, FrameAccumulator};
+use crate::protocol::vehicle::{LogHeader, VehicleConfig};
+
+/// A flight log read into memory
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FlightLog {
+    pub vehicle: Option<VehicleConfig>,
+    pub records: Vec<RawRecord>,
+    /// Frames that could not be decoded when the log was read, they are not written back
+    pub skipped: usize,
+}
+
+impl FlightLog {
+    pub fn read<R: Read>(mut input: R) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let mut log = FlightLog::default();
+        let mut window = &bytes[..];
+        if let Some(end) = window.iter().position(|b| *b == 0) {
+            let mut frame = window[..=end].to_vec();
+            if let Ok(header) = LogHeader::decode(&mut frame) {
+                log.vehicle = Some(header.vehicle);
+                window = &window[end + 1..];
+            }
+        }
+        let mut accumulator: FrameAccumulator<RECORD_FRAME_LEN> = FrameAccumulator::new();
+        while !window.is_empty() {
+            window = match accumulator.feed::<RawRecord>(window) {
+                FeedResult::Consumed => break,
+                FeedResult::OverFull(rest) | FeedResult::DeserError(rest) => {
+                    log.skipped += 1;
+                    rest
+                }
+                FeedResult::Success { data, remaining } => {
+                    log.records.push(data);
+                    remaining
+                }
+            };
+        }
+        Ok(log)
+    }
+
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        if let Some(vehicle) = &self.vehicle {
+            write_log_header(&mut output, vehicle)?;
+        }
+        let mut buf = std::vec![0u8; RECORD_FRAME_LEN];
+        for record in &self.records {
+            let frame = encode_frame(record, &mut buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            output.write_all(frame)?;
+        }
+        Ok(())
+    }
+
+    /// Keeps the records from `from_ms` up to and including `to_ms`
+    pub fn trim(&mut self, from_ms: u64, to_ms: u64) {
+        self.records.retain(|record| (from_ms..=to_ms).contains(&record.at_ms));
+    }
+
+    /// Interleaves `other`'s records by time, e.g. the logs of two receivers of the same flight
+    ///
+    /// A record present in both is kept once. This log's header wins, `other`'s is used if it has none.
+    pub fn merge(&mut self, other: FlightLog) {
+        if self.vehicle.is_none() {
+            self.vehicle = other.vehicle;
+        }
+        self.skipped += other.skipped;
+        self.records.extend(other.records);
+        // Stable, so records sharing a timestamp keep their order within each log
+        self.records.sort_by_key(|record| record.at_ms);
+        self.records.dedup();
+    }
+
+    /// Removes what identifies the team from the header: the node's UID and the airframe name
+    ///
+    /// Records hold only sensor readings and times; logs carry no callsigns or command keys to strip.
+    pub fn anonymize(&mut self) {
+        if let Some(vehicle) = self.vehicle.as_mut() {
+            vehicle.uid = 0;
+            vehicle.airframe.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{AllSensorData, BMP390};
+
+    fn record(at_ms: u64) -> RawRecord {
+        let bmp390 = Some(BMP390 { pressure: 90_000.0, temperature: 10.0, altitude: at_ms as f32 });
+        let data =
+            AllSensorData { ism330dhcx: None, lsm6dso32: None, bmp390, gps: None, adxl375: None, ism330dhcx2: None };
+        RawRecord { at_ms, data }
+    }
+
+    fn log(vehicle: Option<VehicleConfig>, times: &[u64]) -> FlightLog {
+        FlightLog { vehicle, records: times.iter().map(|&at_ms| record(at_ms)).collect(), skipped: 0 }
+    }
+
+    #[test]
+    fn test_trim_merge_and_anonymize_round_trip() {
+        let vehicle = VehicleConfig { uid: 4, airframe: "Tiamat".try_into().unwrap(), ..Default::default() };
+        let mut bytes = Vec::new();
+        log(Some(vehicle.clone()), &[0, 100, 200, 300]).write(&mut bytes).unwrap();
+        bytes.extend_from_slice(&[1, 2, 3, 0]);
+
+        let mut merged = FlightLog::read(&bytes[..]).unwrap();
+        assert_eq!((merged.vehicle.as_ref(), merged.records.len(), merged.skipped), (Some(&vehicle), 4, 1));
+        merged.merge(log(None, &[50, 200, 250]));
+        merged.trim(100, 250);
+        merged.anonymize();
+        let times: Vec<u64> = merged.records.iter().map(|record| record.at_ms).collect();
+        assert_eq!(times, [100, 200, 250]);
+
+        let mut out = Vec::new();
+        merged.write(&mut out).unwrap();
+        let reread = FlightLog::read(&out[..]).unwrap();
+        let anonymous = reread.vehicle.as_ref().unwrap();
+        assert_eq!((anonymous.uid, anonymous.airframe.as_str()), (0, ""));
+        assert_eq!((reread.records, reread.skipped), (merged.records, 0));
+    }
+}
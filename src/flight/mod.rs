@@ -0,0 +1,12 @@
+//! Onboard flight estimation: sensor fusion, the flight phase state machine and estimate sharing
+
+pub mod aero;
+pub mod backfill;
+pub mod fusion;
+#[cfg(feature = "std")]
+pub mod log;
+pub mod share;
+pub mod state;
+
+/// Standard gravity in m/s^2
+pub const GRAVITY: f32 = 9.806_65;
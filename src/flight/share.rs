@@ -0,0 +1,152 @@
+use heapless::Vec;
+
+use super::fusion::FilterState;
+use crate::protocol::estimate::{EstimateSubscribe, StateEstimate};
+
+/// Maximum number of nodes a publisher streams estimates to
+pub const MAX_SUBSCRIBERS: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Subscriber {
+    uid: u8,
+    interval_ms: u64,
+    next_at_ms: u64,
+    expires_at_ms: u64,
+}
+
+/// EstimatePublisher streams this node's fused state to subscribed nodes, e.g. the airbrake controller
+///
+/// Send each estimate with `MeshNode::send_urgent` so it goes ahead of queued telemetry.
+#[derive(Debug, Clone, Default)]
+pub struct EstimatePublisher {
+    uid: u8,
+    subscribers: Vec<Subscriber, MAX_SUBSCRIBERS>,
+}
+
+/// Error returned when every subscriber slot is taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscribersFull;
+
+impl EstimatePublisher {
+    pub fn new(uid: u8) -> Self {
+        Self { uid, subscribers: Vec::new() }
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// Adds, renews or cancels a subscription from `subscriber_uid`
+    pub fn on_subscribe(
+        &mut self,
+        subscriber_uid: u8,
+        request: &EstimateSubscribe,
+        now_ms: u64,
+    ) -> Result<(), SubscribersFull> {
+        self.subscribers.retain(|s| s.uid != subscriber_uid);
+        if request.interval_ms == 0 {
+            return Ok(());
+        }
+        let subscriber = Subscriber {
+            uid: subscriber_uid,
+            interval_ms: request.interval_ms as u64,
+            next_at_ms: now_ms,
+            expires_at_ms: now_ms + request.lease_ms as u64,
+        };
+        self.subscribers.push(subscriber).map_err(|_| SubscribersFull)
+    }
+
+    /// Drops expired subscriptions and returns the estimate with every subscriber it is due for
+    pub fn poll(&mut self, now_ms: u64, state: &FilterState) -> Option<(StateEstimate, Vec<u8, MAX_SUBSCRIBERS>)> {
+        self.subscribers.retain(|s| now_ms < s.expires_at_ms);
+        let mut due = Vec::new();
+        for subscriber in self.subscribers.iter_mut().filter(|s| now_ms >= s.next_at_ms) {
+            subscriber.next_at_ms = now_ms + subscriber.interval_ms;
+            let _ = due.push(subscriber.uid);
+        }
+        if due.is_empty() {
+            return None;
+        }
+        let estimate = StateEstimate {
+            uid: self.uid,
+            at_ms: now_ms as u32,
+            altitude_m: state.altitude_m,
+            vertical_velocity_mps: state.vertical_velocity_mps,
+            vertical_accel_mps2: state.vertical_accel_mps2,
+        };
+        Some((estimate, due))
+    }
+}
+
+/// EstimateSubscriber keeps a subscription to a remote node alive and holds its latest estimate
+#[derive(Debug, Clone, Copy)]
+pub struct EstimateSubscriber {
+    source_uid: u8,
+    request: EstimateSubscribe,
+    renew_at_ms: u64,
+    latest: Option<(StateEstimate, u64)>,
+}
+
+impl EstimateSubscriber {
+    pub fn new(source_uid: u8, interval_ms: u16, lease_ms: u16) -> Self {
+        Self { source_uid, request: EstimateSubscribe { interval_ms, lease_ms }, renew_at_ms: 0, latest: None }
+    }
+
+    pub fn source_uid(&self) -> u8 {
+        self.source_uid
+    }
+
+    /// Returns the request to send to the source when the lease is due for renewal
+    pub fn poll(&mut self, now_ms: u64) -> Option<EstimateSubscribe> {
+        if now_ms < self.renew_at_ms {
+            return None;
+        }
+        // Renew at half the lease so a single lost request doesn't end the stream
+        self.renew_at_ms = now_ms + (self.request.lease_ms / 2).max(1) as u64;
+        Some(self.request)
+    }
+
+    pub fn on_estimate(&mut self, estimate: &StateEstimate, now_ms: u64) {
+        if estimate.uid != self.source_uid {
+            return;
+        }
+        // Out-of-order frames must not replace a newer estimate
+        if self.latest.is_some_and(|(latest, _)| (estimate.at_ms.wrapping_sub(latest.at_ms) as i32) < 0) {
+            return;
+        }
+        self.latest = Some((*estimate, now_ms));
+    }
+
+    /// The latest estimate if it was received within `max_age_ms`, otherwise the caller falls back to its own
+    pub fn latest(&self, now_ms: u64, max_age_ms: u64) -> Option<StateEstimate> {
+        self.latest.filter(|(_, at)| now_ms.saturating_sub(*at) <= max_age_ms).map(|(estimate, _)| estimate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_streams_until_lease_expires() {
+        let mut publisher = EstimatePublisher::new(5);
+        let mut subscriber = EstimateSubscriber::new(5, 20, 1_000);
+        let state = FilterState { altitude_m: 812.0, vertical_velocity_mps: 140.0, vertical_accel_mps2: Some(-12.0) };
+
+        let request = subscriber.poll(0).unwrap();
+        publisher.on_subscribe(9, &request, 0).unwrap();
+        let (estimate, due) = publisher.poll(0, &state).unwrap();
+        assert_eq!(due.as_slice(), [9]);
+        assert!(publisher.poll(10, &state).is_none());
+        subscriber.on_estimate(&estimate, 5);
+        assert_eq!(subscriber.latest(30, 50).unwrap().vertical_velocity_mps, 140.0);
+        assert!(subscriber.latest(100, 50).is_none());
+
+        assert!(subscriber.poll(400).is_none());
+        assert!(subscriber.poll(500).is_some());
+        // Without renewal the publisher stops once the lease runs out
+        assert!(publisher.poll(999, &state).is_some());
+        assert!(publisher.poll(1_000, &state).is_none());
+        assert_eq!(publisher.subscribers(), 0);
+    }
+}
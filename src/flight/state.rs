@@ -0,0 +1,124 @@
+use crate::protocol::events::FlightEvent;
+
+use super::fusion::FilterState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum FlightPhase {
+    #[default]
+    Pad,
+    Boost,
+    Coast,
+    Descent,
+    Landed,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StateMachineConfig {
+    /// Net upward acceleration that signals launch
+    pub launch_accel_mps2: f32,
+    /// Altitude and velocity that signal launch when no accelerometer is available
+    pub launch_altitude_m: f32,
+    pub launch_velocity_mps: f32,
+    /// Vertical speed below which the vehicle is considered at rest
+    pub landed_velocity_mps: f32,
+    /// How long the vehicle must be at rest to declare landing
+    pub landed_hold_ms: u64,
+}
+
+impl Default for StateMachineConfig {
+    fn default() -> Self {
+        Self {
+            launch_accel_mps2: 30.0,
+            launch_altitude_m: 30.0,
+            launch_velocity_mps: 20.0,
+            landed_velocity_mps: 2.0,
+            landed_hold_ms: 5_000,
+        }
+    }
+}
+
+/// FlightStateMachine turns filtered state into flight phases and the events marking each transition
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlightStateMachine {
+    pub config: StateMachineConfig,
+    phase: FlightPhase,
+    max_altitude_m: f32,
+    at_rest_since: Option<u64>,
+}
+
+impl FlightStateMachine {
+    pub fn new(config: StateMachineConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    pub fn phase(&self) -> FlightPhase {
+        self.phase
+    }
+
+    /// Highest filtered altitude since launch
+    pub fn max_altitude_m(&self) -> f32 {
+        self.max_altitude_m
+    }
+
+    pub fn update(&mut self, state: &FilterState, now_ms: u64) -> Option<FlightEvent> {
+        self.max_altitude_m = self.max_altitude_m.max(state.altitude_m);
+        let (next, event) = match self.phase {
+            FlightPhase::Pad => {
+                let launched = match state.vertical_accel_mps2 {
+                    Some(accel) => accel > self.config.launch_accel_mps2,
+                    None => {
+                        state.altitude_m > self.config.launch_altitude_m
+                            && state.vertical_velocity_mps > self.config.launch_velocity_mps
+                    }
+                };
+                if !launched {
+                    return None;
+                }
+                self.max_altitude_m = state.altitude_m;
+                (FlightPhase::Boost, FlightEvent::Launch)
+            }
+            FlightPhase::Boost if state.vertical_accel_mps2.is_some_and(|a| a < 0.0) => {
+                (FlightPhase::Coast, FlightEvent::Burnout)
+            }
+            FlightPhase::Boost | FlightPhase::Coast if state.vertical_velocity_mps < 0.0 => {
+                (FlightPhase::Descent, FlightEvent::Apogee { altitude_m: self.max_altitude_m })
+            }
+            FlightPhase::Descent => {
+                if state.vertical_velocity_mps.abs() > self.config.landed_velocity_mps {
+                    self.at_rest_since = None;
+                    return None;
+                }
+                let since = *self.at_rest_since.get_or_insert(now_ms);
+                if now_ms.saturating_sub(since) < self.config.landed_hold_ms {
+                    return None;
+                }
+                (FlightPhase::Landed, FlightEvent::Landed)
+            }
+            _ => return None,
+        };
+        self.phase = next;
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(altitude_m: f32, vertical_velocity_mps: f32, accel: f32) -> FilterState {
+        FilterState { altitude_m, vertical_velocity_mps, vertical_accel_mps2: Some(accel) }
+    }
+
+    #[test]
+    fn test_full_flight_sequence() {
+        let mut sm = FlightStateMachine::new(StateMachineConfig::default());
+        assert_eq!(sm.update(&state(0.0, 0.0, 0.0), 0), None);
+        assert_eq!(sm.update(&state(1.0, 5.0, 80.0), 100), Some(FlightEvent::Launch));
+        assert_eq!(sm.update(&state(300.0, 150.0, -15.0), 2_000), Some(FlightEvent::Burnout));
+        assert_eq!(sm.update(&state(1_500.0, 1.0, -9.8), 12_000), None);
+        assert_eq!(sm.update(&state(1_499.0, -1.0, -9.8), 12_100), Some(FlightEvent::Apogee { altitude_m: 1_500.0 }));
+        assert_eq!(sm.update(&state(0.0, 0.5, 0.0), 100_000), None);
+        assert_eq!(sm.update(&state(0.0, 0.1, 0.0), 105_000), Some(FlightEvent::Landed));
+        assert_eq!(sm.phase(), FlightPhase::Landed);
+    }
+}
@@ -0,0 +1,36 @@
+use libm::{asin, atan2, cos, sin, sqrt};
+
+/// Mean Earth radius in meters
+pub const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Great-circle distance between two points in meters (haversine)
+pub fn distance_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lon2 - lon1).to_radians();
+    let a = sin(d_phi / 2.0) * sin(d_phi / 2.0) + cos(phi1) * cos(phi2) * sin(d_lambda / 2.0) * sin(d_lambda / 2.0);
+    2.0 * EARTH_RADIUS_M * asin(sqrt(a).min(1.0))
+}
+
+/// Initial bearing from the first point to the second in degrees, 0..360 clockwise from north
+pub fn bearing_deg(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lambda = (lon2 - lon1).to_radians();
+    let y = sin(d_lambda) * cos(phi2);
+    let x = cos(phi1) * sin(phi2) - sin(phi1) * cos(phi2) * cos(d_lambda);
+    (atan2(y, x).to_degrees() + 360.0) % 360.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_and_bearing() {
+        // One degree of latitude is ~111.2 km
+        let d = distance_m(37.0, -80.0, 38.0, -80.0);
+        assert!((d - 111_195.0).abs() < 10.0);
+        assert!((bearing_deg(37.0, -80.0, 38.0, -80.0) - 0.0).abs() < 1e-6);
+        assert!((bearing_deg(37.0, -80.0, 37.0, -79.0) - 90.0).abs() < 0.5);
+    }
+}
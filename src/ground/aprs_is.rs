@@ -0,0 +1,414 @@
+//! APRS-IS uplink so flights show up on aprs.fi and other APRS-IS consumers
+
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+use super::{GroundEvent, Sink};
+use crate::clock::Clock;
+use crate::protocol::aprs::TextComment;
+use crate::protocol::packet::Packet;
+use crate::protocol::{AprsCompressedPositionReport, GpsFix};
+
+/// Destination used as the APRS software identifier; APZ is the experimental range
+pub const TOCALL: &str = "APZRVT";
+
+/// Connection to an APRS-IS server
+pub trait AprsIsTransport {
+    type Error;
+
+    /// Connects to `server`, given as `host:port`
+    fn connect(&mut self, server: &str) -> Result<(), Self::Error>;
+    fn close(&mut self);
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+    /// Reads whatever the server has sent, returning 0 when nothing is available
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+/// Non-blocking TCP transport
+///
+/// There is no async runtime in the crate, so the client is polled from the ground loop instead of running
+/// as a task; connecting blocks for at most `connect_timeout`.
+#[derive(Debug)]
+pub struct TcpTransport {
+    pub connect_timeout: std::time::Duration,
+    stream: Option<std::net::TcpStream>,
+}
+
+impl Default for TcpTransport {
+    fn default() -> Self {
+        Self { connect_timeout: std::time::Duration::from_secs(5), stream: None }
+    }
+}
+
+impl AprsIsTransport for TcpTransport {
+    type Error = std::io::Error;
+
+    fn connect(&mut self, server: &str) -> std::io::Result<()> {
+        use std::net::ToSocketAddrs;
+        let mut last = std::io::Error::new(std::io::ErrorKind::NotFound, "server did not resolve");
+        for address in server.to_socket_addrs()? {
+            match std::net::TcpStream::connect_timeout(&address, self.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_nonblocking(true)?;
+                    self.stream = Some(stream);
+                    return Ok(());
+                }
+                Err(error) => last = error,
+            }
+        }
+        Err(last)
+    }
+
+    fn close(&mut self) {
+        self.stream = None;
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let stream = self.stream.as_mut().ok_or(std::io::ErrorKind::NotConnected)?;
+        stream.set_nonblocking(false)?;
+        let result = std::io::Write::write_all(stream, bytes);
+        stream.set_nonblocking(true)?;
+        result
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let stream = self.stream.as_mut().ok_or(std::io::ErrorKind::NotConnected)?;
+        match std::io::Read::read(stream, buf) {
+            Ok(0) => Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
+            result => result,
+        }
+    }
+}
+
+/// APRS-IS passcode for `callsign`; the SSID is ignored
+pub fn passcode(callsign: &str) -> u16 {
+    let base = callsign.split('-').next().unwrap_or_default();
+    let mut hash = 0x73E2u16;
+    for pair in base.as_bytes().chunks(2) {
+        hash ^= (pair[0].to_ascii_uppercase() as u16) << 8;
+        if let Some(low) = pair.get(1) {
+            hash ^= low.to_ascii_uppercase() as u16;
+        }
+    }
+    hash & 0x7FFF
+}
+
+#[derive(Debug, Clone)]
+pub struct AprsIsConfig {
+    /// Licensed callsign the ground station logs in as, such as `KJ4ABC-10`
+    pub login: String,
+    /// Passcode for `login`, computed from it when `None`
+    pub passcode: Option<u16>,
+    /// Servers as `host:port`, tried in turn whenever the current one fails
+    pub servers: Vec<String>,
+    /// Callsign the vehicle is reported under, such as `KJ4ABC-11`
+    pub source: String,
+    /// A keep-alive comment is sent after this long without sending anything
+    pub keepalive_ms: u64,
+    /// The connection is dropped after this long without hearing from the server, which sends its own
+    /// keep-alive about every 20 s
+    pub timeout_ms: u64,
+    /// Wait before connecting to the next server after a failure
+    pub retry_ms: u64,
+    /// Minimum time between positions, to stay within APRS-IS rate expectations
+    pub min_interval_ms: u64,
+}
+
+impl AprsIsConfig {
+    pub fn new(login: &str, source: &str) -> Self {
+        Self {
+            login: login.into(),
+            passcode: None,
+            servers: std::vec!["rotate.aprs2.net:14580".into()],
+            source: source.into(),
+            keepalive_ms: 60_000,
+            timeout_ms: 120_000,
+            retry_ms: 5_000,
+            min_interval_ms: 5_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AprsIsError {
+    NotConnected,
+    /// A position was forwarded less than `min_interval_ms` ago
+    RateLimited,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Disconnected { next_attempt_ms: u64 },
+    Connected { last_rx_ms: u64, last_tx_ms: u64 },
+}
+
+/// AprsIsClient logs in to APRS-IS and uploads the vehicle's position
+///
+/// Call `poll` from the ground loop to connect, rotate servers and keep the connection alive. As a sink it
+/// forwards every GPS fix in sensor telemetry; already-decoded reports can be sent with `forward`.
+pub struct AprsIsClient<T: AprsIsTransport, C: Clock> {
+    transport: T,
+    clock: C,
+    config: AprsIsConfig,
+    state: State,
+    server: usize,
+    verified: bool,
+    line: Vec<u8>,
+    last_position_ms: Option<u64>,
+    forwarded: u32,
+}
+
+impl<T: AprsIsTransport, C: Clock> AprsIsClient<T, C> {
+    /// Connects on the first `poll`
+    pub fn new(transport: T, clock: C, config: AprsIsConfig) -> Self {
+        Self {
+            transport,
+            clock,
+            config,
+            state: State::Disconnected { next_attempt_ms: 0 },
+            server: 0,
+            verified: false,
+            line: Vec::new(),
+            last_position_ms: None,
+            forwarded: 0,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        matches!(self.state, State::Connected { .. })
+    }
+
+    /// Whether the server accepted the passcode; unverified connections can't post packets
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+
+    /// Server currently used or tried next
+    pub fn server(&self) -> &str {
+        self.config.servers.get(self.server).map_or("", |server| server.as_str())
+    }
+
+    /// Positions sent to the server
+    pub fn forwarded(&self) -> u32 {
+        self.forwarded
+    }
+
+    pub fn poll(&mut self) {
+        let now = self.clock.now_ms();
+        match self.state {
+            State::Disconnected { next_attempt_ms } if now >= next_attempt_ms => self.connect(now),
+            State::Disconnected { .. } => {}
+            State::Connected { last_rx_ms, last_tx_ms } => {
+                self.read(now);
+                if now.saturating_sub(last_rx_ms) >= self.config.timeout_ms {
+                    self.fail(now);
+                } else if now.saturating_sub(last_tx_ms) >= self.config.keepalive_ms {
+                    self.send_line("#keepalive", now);
+                }
+            }
+        }
+    }
+
+    /// Uploads a position report for the vehicle
+    ///
+    /// A packed binary comment is not valid in an APRS-IS line and is left out; a text comment is kept.
+    pub fn forward(&mut self, report: &AprsCompressedPositionReport) -> Result<(), AprsIsError> {
+        let now = self.clock.now_ms();
+        if !self.is_connected() {
+            return Err(AprsIsError::NotConnected);
+        }
+        if self.last_position_ms.is_some_and(|last| now.saturating_sub(last) < self.config.min_interval_ms) {
+            return Err(AprsIsError::RateLimited);
+        }
+        let mut report = report.clone();
+        if report.text_comment.is_none() {
+            report.text_comment = TextComment::new("").ok();
+        }
+        let info = report.encode_info();
+        let line = format!("{}>{},TCPIP*:{}", self.config.source, TOCALL, String::from_utf8_lossy(&info));
+        if !self.send_line(&line, now) {
+            return Err(AprsIsError::NotConnected);
+        }
+        self.last_position_ms = Some(now);
+        self.forwarded += 1;
+        Ok(())
+    }
+
+    fn connect(&mut self, now: u64) {
+        let Some(server) = self.config.servers.get(self.server) else {
+            return;
+        };
+        if self.transport.connect(server).is_err() {
+            self.fail(now);
+            return;
+        }
+        self.state = State::Connected { last_rx_ms: now, last_tx_ms: now };
+        self.verified = false;
+        self.line.clear();
+        let passcode = self.config.passcode.unwrap_or_else(|| passcode(&self.config.login));
+        let login = format!("user {} pass {} vers Mesh {}", self.config.login, passcode, env!("CARGO_PKG_VERSION"));
+        self.send_line(&login, now);
+    }
+
+    /// Drops the connection and moves on to the next server
+    fn fail(&mut self, now: u64) {
+        self.transport.close();
+        self.verified = false;
+        self.server = (self.server + 1) % self.config.servers.len().max(1);
+        self.state = State::Disconnected { next_attempt_ms: now + self.config.retry_ms };
+    }
+
+    fn send_line(&mut self, line: &str, now: u64) -> bool {
+        let sent = self.transport.write_all(line.as_bytes()).and_then(|_| self.transport.write_all(b"\r\n"));
+        match (sent, &mut self.state) {
+            (Ok(()), State::Connected { last_tx_ms, .. }) => {
+                *last_tx_ms = now;
+                true
+            }
+            (Ok(()), _) => true,
+            (Err(_), _) => {
+                self.fail(now);
+                false
+            }
+        }
+    }
+
+    fn read(&mut self, now: u64) {
+        let mut buf = [0u8; 256];
+        loop {
+            let n = match self.transport.read(&mut buf) {
+                Ok(0) => return,
+                Ok(n) => n,
+                Err(_) => return self.fail(now),
+            };
+            if let State::Connected { last_rx_ms, .. } = &mut self.state {
+                *last_rx_ms = now;
+            }
+            for byte in &buf[..n] {
+                if *byte != b'\n' {
+                    self.line.push(*byte);
+                    continue;
+                }
+                let line = String::from_utf8_lossy(&self.line);
+                // "# logresp CALL verified, server T2TEST"; the unverified response says "unverified"
+                if let Some(response) = line.strip_prefix("# logresp ") {
+                    self.verified = response.split([' ', ',']).nth(1) == Some("verified");
+                }
+                self.line.clear();
+            }
+        }
+    }
+}
+
+impl<T: AprsIsTransport, C: Clock> Sink for AprsIsClient<T, C> {
+    fn deliver(&mut self, event: &GroundEvent) {
+        let Packet::Sensors(sensors) = &event.packet else {
+            return;
+        };
+        let has_fix = |fix| matches!(fix, GpsFix::Fix2D | GpsFix::Fix3D | GpsFix::GPSPlusDeadReckoning);
+        let Some(gps) = sensors.gps.as_ref().filter(|gps| has_fix(gps.fix_type)) else {
+            return;
+        };
+        let report = AprsCompressedPositionReport {
+            // Rocket, in the alternate symbol table
+            symbol_table: '\\',
+            symbol_code: 'O',
+            ..AprsCompressedPositionReport::encode(gps.latitude, gps.longitude, gps.altitude_msl, None, 0.0)
+        };
+        let _ = self.forward(&report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::clock::MockClock;
+
+    /// Records what was sent; servers listed in `down` refuse connections
+    #[derive(Default, Clone)]
+    struct Fake {
+        down: Vec<&'static str>,
+        connects: Rc<RefCell<Vec<String>>>,
+        sent: Rc<RefCell<String>>,
+        incoming: Rc<RefCell<Vec<u8>>>,
+    }
+
+    impl AprsIsTransport for Fake {
+        type Error = ();
+
+        fn connect(&mut self, server: &str) -> Result<(), ()> {
+            self.connects.borrow_mut().push(server.into());
+            if self.down.contains(&server) {
+                Err(())
+            } else {
+                Ok(())
+            }
+        }
+
+        fn close(&mut self) {}
+
+        fn write_all(&mut self, bytes: &[u8]) -> Result<(), ()> {
+            self.sent.borrow_mut().push_str(std::str::from_utf8(bytes).unwrap());
+            Ok(())
+        }
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            let mut incoming = self.incoming.borrow_mut();
+            let len = incoming.len().min(buf.len());
+            buf[..len].copy_from_slice(&incoming.drain(..len).collect::<Vec<_>>());
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn test_passcode() {
+        assert_eq!(passcode("N0CALL"), 13023);
+        assert_eq!(passcode("n0call-9"), 13023);
+    }
+
+    #[test]
+    fn test_rotates_servers_logs_in_and_forwards() {
+        let clock = MockClock::new(0);
+        let fake = Fake { down: std::vec!["a:14580"], ..Default::default() };
+        let mut config = AprsIsConfig::new("N0CALL-10", "N0CALL-11");
+        config.servers = std::vec!["a:14580".into(), "b:14580".into()];
+        let mut client = AprsIsClient::new(fake.clone(), &clock, config);
+
+        client.poll();
+        assert!(!client.is_connected());
+        clock.set(5_000);
+        client.poll();
+        assert_eq!(*fake.connects.borrow(), ["a:14580", "b:14580"]);
+        assert!(fake.sent.borrow().starts_with("user N0CALL-10 pass 13023 vers Mesh "));
+
+        fake.incoming.borrow_mut().extend_from_slice(b"# logresp N0CALL-10 verified, server T2TEST\r\n");
+        client.poll();
+        assert!(client.is_verified());
+
+        let report = AprsCompressedPositionReport {
+            symbol_table: '/',
+            symbol_code: 'O',
+            ..AprsCompressedPositionReport::encode(37.2296, -80.4139, 1_500.0, None, 0.0)
+        };
+        fake.sent.borrow_mut().clear();
+        client.forward(&report).unwrap();
+        assert_eq!(client.forward(&report), Err(AprsIsError::RateLimited));
+        let sent = fake.sent.borrow().clone();
+        assert!(sent.starts_with("N0CALL-11>APZRVT,TCPIP*:!/") && sent.ends_with("\r\n"), "{sent}");
+
+        // Keep-alive after a minute of silence, then rotation once the server stops talking
+        clock.set(65_000);
+        fake.incoming.borrow_mut().extend_from_slice(b"# aprsc 2.1\r\n");
+        client.poll();
+        assert!(fake.sent.borrow().ends_with("#keepalive\r\n"));
+        clock.set(185_000);
+        client.poll();
+        assert!(!client.is_connected());
+        assert_eq!(client.server(), "a:14580");
+    }
+}
@@ -0,0 +1,252 @@
+//! Fault injection for exercising the ground pipeline against the worst a radio link and a misbehaving
+//! flight computer can produce
+
+use heapless::{Deque, Vec};
+
+use super::Receiver;
+use crate::protocol::checksum::{self, CRC_LEN, PREFIX_LEN};
+use crate::protocol::mesh::{MeshFrame, MAX_FRAME_LEN};
+use crate::protocol::packet::Packet;
+use crate::protocol::ping::LinkQuality;
+use crate::rng::RandomSource;
+
+/// Frames held back for reordering
+const HELD: usize = 4;
+
+/// Chance of each fault, in percent of received frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChaosConfig {
+    /// Flip random bits, so the frame fails its CRC
+    pub corrupt: u8,
+    /// Cut the frame short
+    pub truncate: u8,
+    /// Deliver the frame twice
+    pub duplicate: u8,
+    /// Hold the frame back and deliver it after later ones
+    pub reorder: u8,
+    /// Replace sensor readings with NaN, infinities and out-of-range values, with a valid CRC
+    pub absurd: u8,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self { corrupt: 10, truncate: 5, duplicate: 10, reorder: 10, absurd: 20 }
+    }
+}
+
+/// Faults injected so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChaosStats {
+    pub corrupted: u32,
+    pub truncated: u32,
+    pub duplicated: u32,
+    pub reordered: u32,
+    pub absurd: u32,
+}
+
+type Frame = (Vec<u8, MAX_FRAME_LEN>, LinkQuality);
+
+/// ChaosReceiver wraps a receiver and mangles what it hears according to `ChaosConfig`
+///
+/// Put it in front of a real or scripted receiver in tests and soak runs; sinks should keep running and
+/// MeshGround's counters should account for every fault.
+pub struct ChaosReceiver<R: Receiver, G: RandomSource> {
+    inner: R,
+    rng: G,
+    pub config: ChaosConfig,
+    held: Deque<Frame, HELD>,
+    repeat: Option<Frame>,
+    stats: ChaosStats,
+}
+
+impl<R: Receiver, G: RandomSource> ChaosReceiver<R, G> {
+    pub fn new(inner: R, rng: G, config: ChaosConfig) -> Self {
+        Self { inner, rng, config, held: Deque::new(), repeat: None, stats: ChaosStats::default() }
+    }
+
+    pub fn stats(&self) -> ChaosStats {
+        self.stats
+    }
+
+    fn roll(&mut self, percent: u8) -> bool {
+        self.rng.below(100) < percent as u32
+    }
+
+    fn mangle(&mut self, frame: &mut Vec<u8, MAX_FRAME_LEN>) {
+        if self.roll(self.config.absurd) && make_absurd(frame, &mut self.rng) {
+            self.stats.absurd += 1;
+        }
+        if !frame.is_empty() && self.roll(self.config.corrupt) {
+            let bit = self.rng.below(frame.len() as u32 * 8);
+            frame[bit as usize / 8] ^= 1 << (bit % 8);
+            self.stats.corrupted += 1;
+        }
+        if !frame.is_empty() && self.roll(self.config.truncate) {
+            frame.truncate(self.rng.below(frame.len() as u32) as usize);
+            self.stats.truncated += 1;
+        }
+    }
+}
+
+impl<R: Receiver, G: RandomSource> Receiver for ChaosReceiver<R, G> {
+    fn receive(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)> {
+        let (frame, quality) = loop {
+            if let Some(repeat) = self.repeat.take() {
+                break repeat;
+            }
+            let mut raw = [0u8; MAX_FRAME_LEN];
+            let Some((len, quality)) = self.inner.receive(&mut raw) else {
+                // Nothing new, release what was held back
+                break self.held.pop_front()?;
+            };
+            let mut frame = Vec::from_slice(&raw[..len.min(MAX_FRAME_LEN)]).unwrap();
+            self.mangle(&mut frame);
+            if self.roll(self.config.duplicate) {
+                self.repeat = Some((frame.clone(), quality));
+                self.stats.duplicated += 1;
+            }
+            if !self.held.is_full() && self.roll(self.config.reorder) {
+                let _ = self.held.push_back((frame, quality));
+                self.stats.reordered += 1;
+                continue;
+            }
+            break (frame, quality);
+        };
+        let len = frame.len().min(buf.len());
+        buf[..len].copy_from_slice(&frame[..len]);
+        Some((len, quality))
+    }
+
+    fn take_crc_errors(&mut self) -> u32 {
+        self.inner.take_crc_errors()
+    }
+
+    fn take_link_change(&mut self) -> Option<super::LinkState> {
+        self.inner.take_link_change()
+    }
+}
+
+/// Rewrites a sensor frame with absurd readings and a fresh checksum, returning whether it was one
+fn make_absurd(frame: &mut Vec<u8, MAX_FRAME_LEN>, rng: &mut impl RandomSource) -> bool {
+    const ABSURD: [f64; 5] = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, f64::MAX, -1.0e12];
+    let Ok(message) = checksum::verify_and_strip(frame) else {
+        return false;
+    };
+    let Ok(MeshFrame { header, packet: Packet::Sensors(mut sensors) }) = MeshFrame::decode(message) else {
+        return false;
+    };
+    let mut pick = || ABSURD[rng.below(ABSURD.len() as u32) as usize];
+    if let Some(bmp) = sensors.bmp390.as_mut() {
+        (bmp.altitude, bmp.pressure, bmp.temperature) = (pick() as f32, pick() as f32, pick() as f32);
+    }
+    if let Some(gps) = sensors.gps.as_mut() {
+        (gps.latitude, gps.longitude, gps.altitude_msl) = (pick(), pick(), pick());
+    }
+    for imu in [sensors.ism330dhcx.as_mut(), sensors.ism330dhcx2.as_mut()].into_iter().flatten() {
+        (imu.accel_x, imu.accel_z, imu.gyro_y) = (pick(), pick(), pick());
+    }
+    let mut buf = [0u8; MAX_FRAME_LEN];
+    let mangled = MeshFrame { header, packet: Packet::Sensors(sensors) };
+    let Ok(len) = postcard::to_slice(&mangled, &mut buf[PREFIX_LEN..MAX_FRAME_LEN - CRC_LEN]).map(|m| m.len())
+    else {
+        return false;
+    };
+    let Ok(len) = checksum::append(&mut buf, len) else {
+        return false;
+    };
+    *frame = Vec::from_slice(&buf[..len]).unwrap();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+    use crate::bridge::downsample::{Downsample, DownsampleConfig};
+    use crate::bridge::spectator::SpectatorSink;
+    use crate::clock::MockClock;
+    use crate::ground::smoothing::{SmoothingConfig, Smoother};
+    use crate::ground::{GroundEvent, MeshGround, Sink, Subscription};
+    use crate::protocol::mesh::MeshHeader;
+    use crate::protocol::{AllSensorData, BMP390};
+    use crate::rng::NodeRng;
+
+    struct Scripted(StdVec<StdVec<u8>>);
+
+    impl Receiver for Scripted {
+        fn receive(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)> {
+            let frame = (!self.0.is_empty()).then(|| self.0.remove(0))?;
+            buf[..frame.len()].copy_from_slice(&frame);
+            Some((frame.len(), LinkQuality::default()))
+        }
+    }
+
+    /// The live altitude readout
+    struct Altitude(Smoother, u32);
+
+    impl Sink for Altitude {
+        fn deliver(&mut self, event: &GroundEvent) {
+            if let Packet::Sensors(AllSensorData { bmp390: Some(bmp), .. }) = &event.packet {
+                // One bad reading must not poison the readout for the rest of the flight
+                let smoothed = self.0.update(bmp.altitude);
+                assert!(smoothed.value.is_finite() || !bmp.altitude.is_finite());
+                self.1 += 1;
+            }
+        }
+    }
+
+    fn sensor_frame(sequence: u16) -> StdVec<u8> {
+        let header = MeshHeader {
+            source_uid: 2,
+            destination_uid: 0,
+            sequence,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        let sensors = AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: Some(BMP390 { pressure: 95_000.0, temperature: 20.0, altitude: sequence as f32 }),
+            gps: None,
+            adxl375: None,
+            ism330dhcx2: None,
+        };
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let message = &mut buf[PREFIX_LEN..MAX_FRAME_LEN - CRC_LEN];
+        let len = postcard::to_slice(&MeshFrame { header, packet: Packet::Sensors(sensors) }, message).unwrap().len();
+        let len = checksum::append(&mut buf, len).unwrap();
+        buf[..len].to_vec()
+    }
+
+    #[test]
+    fn test_ground_survives_chaos() {
+        let frames = (1..=500).map(sensor_frame).collect();
+        let mut chaos = ChaosReceiver::new(Scripted(frames), NodeRng::seeded(7, 0), ChaosConfig::default());
+        let mut altitude = Altitude(Smoother::new(SmoothingConfig::default()), 0);
+        let spectator = SpectatorSink::new(Altitude(Smoother::new(SmoothingConfig::default()), 0), Default::default());
+        let mut public = Downsample::new(spectator, DownsampleConfig::hz(1));
+
+        let mut ground = MeshGround::new(MockClock::new(0), 60_000);
+        ground.add_receiver(&mut chaos).unwrap();
+        ground.subscribe(Subscription::ALL, &mut altitude).unwrap();
+        ground.subscribe(Subscription::ALL, &mut public).unwrap();
+        let mut delivered = 0;
+        while ground.poll().is_some() {
+            delivered += 1;
+        }
+        let stats = ground.stats();
+        drop(ground);
+
+        let faults = chaos.stats();
+        assert!(faults.corrupted > 0 && faults.truncated > 0 && faults.duplicated > 0);
+        assert!(faults.reordered > 0 && faults.absurd > 0);
+        assert_eq!(stats.frames, 500 + faults.duplicated);
+        assert_eq!(stats.frames, delivered + stats.duplicates + stats.decode_errors);
+        assert_eq!(altitude.1, delivered);
+        assert!(altitude.0.update(501.0).value.is_finite());
+    }
+}
@@ -0,0 +1,238 @@
+//! Finding receiver dongles by USB VID/PID and reading from all of them without configuring port paths
+
+use heapless::{String, Vec};
+
+use super::serial::{ReconnectConfig, SerialPort, SerialReceiver};
+use super::{LinkState, Receiver};
+use crate::clock::Clock;
+use crate::protocol::ping::LinkQuality;
+
+/// Most dongles read at once
+pub const MAX_DEVICES: usize = 4;
+/// Longest USB serial number kept
+pub const MAX_SERIAL_LEN: usize = 32;
+
+/// USB vendor and product ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsbId {
+    pub vid: u16,
+    pub pid: u16,
+}
+
+/// USB-serial bridges receiver dongles are built on: CP210x, FT232R, CH340 and RP2040 CDC
+pub const KNOWN_RECEIVERS: &[UsbId] = &[
+    UsbId { vid: 0x10C4, pid: 0xEA60 },
+    UsbId { vid: 0x0403, pid: 0x6001 },
+    UsbId { vid: 0x1A86, pid: 0x7523 },
+    UsbId { vid: 0x2E8A, pid: 0x000A },
+];
+
+/// A USB serial device seen during a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbDevice {
+    pub id: UsbId,
+    /// Stays the same across replugs and ports, unlike the device path
+    pub serial_number: String<MAX_SERIAL_LEN>,
+}
+
+/// Platform enumeration of USB serial devices
+pub trait PortEnumerator {
+    type Port: SerialPort;
+
+    /// Lists the USB serial devices currently attached
+    fn scan(&mut self, found: &mut Vec<UsbDevice, MAX_DEVICES>);
+    /// Port for `device`, opened by its serial number so it is found again wherever it is replugged
+    fn port(&mut self, device: &UsbDevice) -> Self::Port;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DiscoveryConfig {
+    /// Devices treated as receivers
+    pub known: &'static [UsbId],
+    /// Time between scans for newly plugged devices
+    pub scan_ms: u64,
+    pub reconnect: ReconnectConfig,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self { known: KNOWN_RECEIVERS, scan_ms: 2_000, reconnect: ReconnectConfig::default() }
+    }
+}
+
+/// AutoReceiver scans for known dongles and reads every one it finds through its own SerialReceiver
+///
+/// There is no async runtime to spawn reader tasks on, so the per-device readers are polled in turn from
+/// `receive`. A device that is unplugged keeps its reader, which reattaches when the same serial number
+/// comes back; devices beyond `MAX_DEVICES` are ignored. The link is up while any device is connected.
+pub struct AutoReceiver<E: PortEnumerator, C: Clock + Clone> {
+    enumerator: E,
+    clock: C,
+    config: DiscoveryConfig,
+    devices: Vec<(UsbDevice, SerialReceiver<E::Port, C>), MAX_DEVICES>,
+    next: usize,
+    next_scan_ms: u64,
+    up: bool,
+    change: Option<LinkState>,
+}
+
+impl<E: PortEnumerator, C: Clock + Clone> AutoReceiver<E, C> {
+    /// The first scan runs on the first `receive`
+    pub fn new(enumerator: E, clock: C, config: DiscoveryConfig) -> Self {
+        Self {
+            enumerator,
+            clock,
+            config,
+            devices: Vec::new(),
+            next: 0,
+            next_scan_ms: 0,
+            up: false,
+            change: None,
+        }
+    }
+
+    /// Devices found so far, with whether each is currently connected
+    pub fn devices(&self) -> impl Iterator<Item = (&UsbDevice, bool)> {
+        self.devices.iter().map(|(device, receiver)| (device, receiver.is_connected()))
+    }
+
+    /// Adds a reader for each known device not seen before
+    pub fn scan(&mut self) {
+        let mut found = Vec::new();
+        self.enumerator.scan(&mut found);
+        for device in found {
+            if !self.config.known.contains(&device.id) || self.devices.iter().any(|(known, _)| *known == device) {
+                continue;
+            }
+            let port = self.enumerator.port(&device);
+            let receiver = SerialReceiver::new(port, self.clock.clone(), self.config.reconnect);
+            if self.devices.push((device, receiver)).is_err() {
+                break;
+            }
+        }
+    }
+
+    fn update_link(&mut self) {
+        for (_, receiver) in self.devices.iter_mut() {
+            receiver.take_link_change();
+        }
+        let up = self.devices.iter().any(|(_, receiver)| receiver.is_connected());
+        if up != self.up {
+            self.up = up;
+            self.change = Some(if up { LinkState::Up } else { LinkState::Down });
+        }
+    }
+}
+
+impl<E: PortEnumerator, C: Clock + Clone> Receiver for AutoReceiver<E, C> {
+    fn receive(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)> {
+        let now = self.clock.now_ms();
+        if now >= self.next_scan_ms {
+            self.scan();
+            self.next_scan_ms = now + self.config.scan_ms;
+        }
+        let mut received = None;
+        for _ in 0..self.devices.len() {
+            let index = self.next % self.devices.len();
+            received = self.devices[index].1.receive(buf);
+            if received.is_some() {
+                break;
+            }
+            self.next = index + 1;
+        }
+        self.update_link();
+        received
+    }
+
+    fn take_crc_errors(&mut self) -> u32 {
+        self.devices.iter_mut().map(|(_, receiver)| receiver.take_crc_errors()).sum()
+    }
+
+    fn take_link_change(&mut self) -> Option<LinkState> {
+        self.change.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+    use std::vec::Vec as StdVec;
+
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::protocol::serial::{encode_frame, BridgedFrame, BRIDGED_FRAME_LEN};
+
+    /// Attached devices by serial number, with the bytes each has waiting
+    type Bus = Rc<RefCell<BTreeMap<&'static str, (UsbId, StdVec<u8>)>>>;
+
+    struct Enumerator(Bus);
+
+    struct Port(Bus, String<MAX_SERIAL_LEN>);
+
+    impl SerialPort for Port {
+        type Error = ();
+
+        fn open(&mut self) -> Result<(), ()> {
+            self.0.borrow().contains_key(self.1.as_str()).then_some(()).ok_or(())
+        }
+
+        fn close(&mut self) {}
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            let mut bus = self.0.borrow_mut();
+            let (_, bytes) = bus.get_mut(self.1.as_str()).ok_or(())?;
+            let len = bytes.len().min(buf.len());
+            buf[..len].copy_from_slice(&bytes.drain(..len).collect::<StdVec<_>>());
+            Ok(len)
+        }
+    }
+
+    impl PortEnumerator for Enumerator {
+        type Port = Port;
+
+        fn scan(&mut self, found: &mut Vec<UsbDevice, MAX_DEVICES>) {
+            for (serial, (id, _)) in self.0.borrow().iter() {
+                let _ = found.push(UsbDevice { id: *id, serial_number: String::try_from(*serial).unwrap() });
+            }
+        }
+
+        fn port(&mut self, device: &UsbDevice) -> Port {
+            Port(self.0.clone(), device.serial_number.clone())
+        }
+    }
+
+    fn bridged(byte: u8) -> StdVec<u8> {
+        let frame = BridgedFrame { quality: LinkQuality::default(), frame: Vec::from_slice(&[byte; 4]).unwrap() };
+        encode_frame(&frame, &mut [0u8; BRIDGED_FRAME_LEN]).unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_discovers_known_devices_and_reads_each() {
+        let clock = MockClock::new(0);
+        let bus = Bus::default();
+        bus.borrow_mut().insert("A", (KNOWN_RECEIVERS[0], bridged(1)));
+        bus.borrow_mut().insert("mouse", (UsbId { vid: 0x046D, pid: 0xC077 }, StdVec::new()));
+        let mut receiver = AutoReceiver::new(Enumerator(bus.clone()), &clock, DiscoveryConfig::default());
+        let mut buf = [0u8; 16];
+
+        assert_eq!(receiver.receive(&mut buf).map(|(len, _)| buf[..len].to_vec()), Some(std::vec![1; 4]));
+        assert_eq!(receiver.take_link_change(), Some(LinkState::Up));
+        assert_eq!(receiver.devices().count(), 1);
+
+        // A second dongle plugged in is picked up on the next scan
+        bus.borrow_mut().insert("B", (KNOWN_RECEIVERS[2], bridged(2)));
+        assert_eq!(receiver.receive(&mut buf), None);
+        clock.set(2_000);
+        assert_eq!(receiver.receive(&mut buf).map(|(len, _)| buf[..len].to_vec()), Some(std::vec![2; 4]));
+        assert_eq!(receiver.devices().filter(|(_, connected)| *connected).count(), 2);
+        assert_eq!(receiver.take_link_change(), None);
+
+        // Unplugging both takes the link down
+        bus.borrow_mut().clear();
+        assert_eq!(receiver.receive(&mut buf), None);
+        assert_eq!(receiver.take_link_change(), Some(LinkState::Down));
+    }
+}
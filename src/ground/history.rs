@@ -0,0 +1,271 @@
+//! Recent telemetry per node at 1 s, 10 s and 1 min resolution, so charts render without reading the log
+//!
+//! `History` is a Sink. Every sample feeds all three resolutions, each keeping min, max and mean per
+//! bucket, so a zoomed-out chart shows the true extremes rather than a decimated trace.
+
+use heapless::{Deque, Vec};
+
+use super::{GroundEvent, Sink};
+use crate::protocol::packet::Packet;
+
+/// Number of `(node, metric)` series kept, further series are ignored
+pub const MAX_SERIES: usize = 16;
+
+/// A charted telemetry value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Altitude from the node's state estimate, in meters
+    Altitude,
+    /// Vertical velocity from the node's state estimate, in m/s
+    VerticalVelocity,
+    /// Barometric altitude, in meters
+    BaroAltitude,
+    /// GPS height above mean sea level, in meters
+    GpsAltitude,
+    BatteryVoltage,
+    /// Fill line pressure from a GSE node, in kPa
+    FillPressure,
+    /// Tank load cell reading from a GSE node, in kilograms
+    TankMass,
+    /// Mean wind speed at the pad, in m/s
+    WindSpeed,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 8] = [
+        Metric::Altitude,
+        Metric::VerticalVelocity,
+        Metric::BaroAltitude,
+        Metric::GpsAltitude,
+        Metric::BatteryVoltage,
+        Metric::FillPressure,
+        Metric::TankMass,
+        Metric::WindSpeed,
+    ];
+
+    /// The metric's value in `packet`, if it carries one
+    pub fn extract(self, packet: &Packet) -> Option<f32> {
+        match (self, packet) {
+            (Metric::Altitude, Packet::StateEstimate(estimate)) => Some(estimate.altitude_m),
+            (Metric::VerticalVelocity, Packet::StateEstimate(estimate)) => Some(estimate.vertical_velocity_mps),
+            (Metric::BaroAltitude, Packet::Sensors(sensors)) => sensors.bmp390.map(|bmp| bmp.altitude),
+            (Metric::GpsAltitude, Packet::Sensors(sensors)) => sensors.gps.map(|gps| gps.altitude_msl as f32),
+            (Metric::BatteryVoltage, Packet::Health(health)) => Some(health.battery_voltage),
+            (Metric::FillPressure, Packet::FillStatus(fill)) => Some(fill.fill_pressure_kpa),
+            (Metric::TankMass, Packet::FillStatus(fill)) => Some(fill.tank_mass_kg),
+            (Metric::WindSpeed, Packet::PadWeather(weather)) => Some(weather.wind_speed_mps),
+            _ => None,
+        }
+    }
+}
+
+/// Bucket width of a downsampled series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Second,
+    TenSeconds,
+    Minute,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 3] = [Resolution::Second, Resolution::TenSeconds, Resolution::Minute];
+
+    pub fn period_ms(self) -> u64 {
+        match self {
+            Resolution::Second => 1_000,
+            Resolution::TenSeconds => 10_000,
+            Resolution::Minute => 60_000,
+        }
+    }
+}
+
+/// Samples aggregated over one bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub start_ms: u64,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub samples: u32,
+}
+
+impl Point {
+    fn new(start_ms: u64, value: f32) -> Self {
+        Self { start_ms, min: value, max: value, mean: value, samples: 1 }
+    }
+
+    fn add(&mut self, value: f32) {
+        self.samples += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.mean += (value - self.mean) / self.samples as f32;
+    }
+}
+
+/// Closed buckets of one resolution, oldest first, plus the bucket still filling
+#[derive(Debug, Clone)]
+struct Ring<const N: usize> {
+    closed: Deque<Point, N>,
+    open: Option<Point>,
+}
+
+impl<const N: usize> Ring<N> {
+    fn record(&mut self, period_ms: u64, at_ms: u64, value: f32) {
+        let start_ms = at_ms - at_ms % period_ms;
+        match self.open.as_mut() {
+            Some(open) if open.start_ms == start_ms => open.add(value),
+            // Late samples belong to a bucket already closed and are dropped
+            Some(open) if open.start_ms > start_ms => {}
+            _ => {
+                if let Some(closed) = self.open.replace(Point::new(start_ms, value)) {
+                    if self.closed.is_full() {
+                        self.closed.pop_front();
+                    }
+                    let _ = self.closed.push_back(closed);
+                }
+            }
+        }
+    }
+
+    fn points(&self) -> impl Iterator<Item = &Point> {
+        self.closed.iter().chain(self.open.as_ref())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Series<const N: usize> {
+    source_uid: u8,
+    metric: Metric,
+    rings: [Ring<N>; 3],
+}
+
+/// History keeps up to `N` buckets per resolution for each node and metric
+///
+/// With `N` of 600 that is the last 10 minutes at 1 s, 100 minutes at 10 s and 10 hours at 1 min. The
+/// buffers are inline, so box a large History on std rather than keeping it on the stack.
+#[derive(Debug, Clone)]
+pub struct History<const N: usize> {
+    series: Vec<Series<N>, MAX_SERIES>,
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> History<N> {
+    pub fn new() -> Self {
+        Self { series: Vec::new() }
+    }
+
+    /// Adds a sample of `metric` from `source_uid`; non-finite values are skipped
+    pub fn record(&mut self, source_uid: u8, metric: Metric, at_ms: u64, value: f32) {
+        if !value.is_finite() {
+            return;
+        }
+        let index = match self.series.iter().position(|s| s.source_uid == source_uid && s.metric == metric) {
+            Some(index) => index,
+            None => {
+                let ring = || Ring { closed: Deque::new(), open: None };
+                let series = Series { source_uid, metric, rings: [ring(), ring(), ring()] };
+                if self.series.push(series).is_err() {
+                    return;
+                }
+                self.series.len() - 1
+            }
+        };
+        for (ring, resolution) in self.series[index].rings.iter_mut().zip(Resolution::ALL) {
+            ring.record(resolution.period_ms(), at_ms, value);
+        }
+    }
+
+    /// Buckets of `metric` from `source_uid`, oldest first, ending with the one still filling
+    pub fn points(&self, source_uid: u8, metric: Metric, resolution: Resolution) -> impl Iterator<Item = &Point> {
+        let series = self.series.iter().find(|s| s.source_uid == source_uid && s.metric == metric);
+        let tier = Resolution::ALL.iter().position(|r| *r == resolution).expect("every resolution has a ring");
+        series.into_iter().flat_map(move |series| series.rings[tier].points())
+    }
+
+    /// The `(node, metric)` pairs with data
+    pub fn series(&self) -> impl Iterator<Item = (u8, Metric)> + '_ {
+        self.series.iter().map(|series| (series.source_uid, series.metric))
+    }
+}
+
+impl<const N: usize> Sink for History<N> {
+    fn deliver(&mut self, event: &GroundEvent) {
+        for metric in Metric::ALL {
+            if let Some(value) = metric.extract(&event.packet) {
+                self.record(event.header.source_uid, metric, event.at_ms, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::gse::{FillStatus, PadWeather};
+    use crate::protocol::mesh::MeshHeader;
+    use crate::protocol::ping::LinkQuality;
+
+    #[test]
+    fn test_downsampling_keeps_extremes() {
+        let mut history = History::<4>::new();
+        // Climbing at 10 m/s, sampled every 250 ms for 75 s, with a single spike at 30 s
+        for step in 0..300u64 {
+            let at_ms = step * 250;
+            let value = if at_ms == 30_000 { 5_000.0 } else { at_ms as f32 / 100.0 };
+            history.record(3, Metric::Altitude, at_ms, value);
+        }
+        history.record(3, Metric::Altitude, 74_000, f32::NAN);
+
+        let seconds: std::vec::Vec<_> = history.points(3, Metric::Altitude, Resolution::Second).collect();
+        assert_eq!(seconds.len(), 5);
+        assert_eq!((seconds[0].start_ms, seconds[4].start_ms), (70_000, 74_000));
+        assert_eq!((seconds[4].samples, seconds[4].min, seconds[4].max), (4, 740.0, 747.5));
+
+        let tens: std::vec::Vec<_> = history.points(3, Metric::Altitude, Resolution::TenSeconds).collect();
+        let spike = tens.iter().find(|point| point.start_ms == 30_000).unwrap();
+        assert_eq!((spike.max, spike.min, spike.samples), (5_000.0, 302.5, 40));
+        let minutes: std::vec::Vec<_> = history.points(3, Metric::Altitude, Resolution::Minute).collect();
+        assert_eq!(minutes.iter().map(|point| point.start_ms).collect::<std::vec::Vec<_>>(), [0, 60_000]);
+        assert!((minutes[0].mean - 298.75 - (5_000.0 - 300.0) / 240.0).abs() < 0.01);
+
+        assert_eq!(history.points(4, Metric::Altitude, Resolution::Second).count(), 0);
+        assert_eq!(history.series().collect::<std::vec::Vec<_>>(), [(3, Metric::Altitude)]);
+    }
+
+    #[test]
+    fn test_gse_packets_charted_beside_the_vehicle() {
+        let mut history = History::<8>::new();
+        let weather = PadWeather {
+            uid: 9,
+            temperature_c: 24.0,
+            pressure_hpa: 880.0,
+            humidity_pct: 20,
+            wind_speed_mps: 4.5,
+            wind_gust_mps: 7.0,
+            wind_direction_deg: 270,
+        };
+        let fill = FillStatus { uid: 9, fill_pressure_kpa: 5_200.0, tank_mass_kg: 11.2, filling: true };
+        for (at_ms, packet) in [(0, Packet::PadWeather(weather)), (500, Packet::FillStatus(fill))] {
+            let header = MeshHeader {
+                source_uid: 9,
+                destination_uid: 0,
+                sequence: at_ms as u16,
+                hops_left: 3,
+                ack_requested: false,
+                rebooted: false,
+                backup: false,
+                route: None,
+            };
+            let quality = LinkQuality { rssi_dbm: -60, snr_db: 10.0 };
+            history.deliver(&GroundEvent { receiver: 0, at_ms, header, packet, quality, sanitized: false });
+        }
+        let series: std::vec::Vec<_> = history.series().collect();
+        assert_eq!(series, [(9, Metric::WindSpeed), (9, Metric::FillPressure), (9, Metric::TankMass)]);
+        assert_eq!(history.points(9, Metric::TankMass, Resolution::Second).next().unwrap().max, 11.2);
+    }
+}
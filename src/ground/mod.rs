@@ -0,0 +1,393 @@
+//! Ground-station runtime: receivers in, deduplicated typed events out to subscribed sinks
+
+#[cfg(feature = "aprs-is")]
+pub mod aprs_is;
+pub mod chaos;
+pub mod discovery;
+pub mod history;
+pub mod sdr;
+pub mod serial;
+pub mod smoothing;
+pub mod state;
+pub mod watchdog;
+
+use heapless::Vec;
+
+use self::watchdog::SilenceWatchdog;
+use crate::alerts::Alert;
+use crate::clock::Clock;
+#[cfg(feature = "crypto")]
+use crate::crypto::trailer::Trailer;
+use crate::node::dedup::DedupCache;
+use crate::node::handlers::PacketFilter;
+use crate::protocol::checksum::{self, PREFIX_LEN};
+use crate::protocol::finite::Finite;
+use crate::protocol::mesh::{MeshFrame, MeshHeader, MAX_FRAME_LEN};
+use crate::protocol::packet::Packet;
+use crate::protocol::ping::LinkQuality;
+use crate::protocol::Error;
+use crate::stats::decode::{DecodeFailure, DecodeStats};
+use crate::stats::loss::LossTracker;
+
+/// Maximum number of receivers attached to a MeshGround
+pub const MAX_RECEIVERS: usize = 4;
+/// Maximum number of sink subscriptions
+pub const MAX_SINKS: usize = 8;
+
+/// Receiver is a source of raw mesh frames, such as a radio node bridged over serial
+///
+/// Receivers handle their own link errors; a disconnected receiver simply yields nothing.
+pub trait Receiver {
+    fn receive(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)>;
+
+    /// Frames the radio rejected on CRC since the last call, for receivers that report them
+    fn take_crc_errors(&mut self) -> u32 {
+        0
+    }
+
+    /// Whether the receiver's link went down or came back since the last call, for receivers that can
+    /// lose their connection such as `serial::SerialReceiver`
+    fn take_link_change(&mut self) -> Option<LinkState> {
+        None
+    }
+}
+
+/// Connection state of a receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    Up,
+    Down,
+}
+
+/// A packet received by the ground station
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroundEvent {
+    /// Index of the receiver that heard the frame first
+    pub receiver: usize,
+    pub at_ms: u64,
+    pub header: MeshHeader,
+    pub packet: Packet,
+    pub quality: LinkQuality,
+    /// NaN, infinite or sentinel values in the packet were replaced, see `protocol::finite`
+    pub sanitized: bool,
+}
+
+/// Sink consumes ground events: dashboards, loggers, alert monitors
+pub trait Sink {
+    fn deliver(&mut self, event: &GroundEvent);
+
+    /// Called for every sink when receiver `receiver` loses or regains its link
+    fn link_changed(&mut self, _receiver: usize, _state: LinkState, _at_ms: u64) {}
+
+    /// Called for every sink when the runtime raises an alert, such as the silence watchdog's or a received
+    /// Emergency's; loggers mark it in the log
+    fn alert(&mut self, _alert: &Alert, _at_ms: u64) {}
+}
+
+/// Which events a sink receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscription {
+    pub filter: PacketFilter,
+    /// Only events from this node, or from every node if `None`
+    pub source_uid: Option<u8>,
+}
+
+impl Subscription {
+    pub const ALL: Subscription = Subscription { filter: PacketFilter::Any, source_uid: None };
+
+    pub fn matches(&self, event: &GroundEvent) -> bool {
+        self.filter.matches(event.packet.kind()) && self.source_uid.is_none_or(|uid| uid == event.header.source_uid)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistryFull;
+
+/// Counters describing what the ground station has received
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GroundStats {
+    pub frames: u32,
+    /// Frames already heard through another receiver or an earlier hop
+    pub duplicates: u32,
+    pub decode_errors: u32,
+    /// Packets that arrived with NaN, infinite or sentinel values
+    pub non_finite: u32,
+}
+
+/// MeshGround owns the receivers, merges what they hear and fans events out to subscribed sinks
+pub struct MeshGround<'a, C: Clock> {
+    clock: C,
+    receivers: Vec<&'a mut dyn Receiver, MAX_RECEIVERS>,
+    sinks: Vec<(Subscription, &'a mut dyn Sink), MAX_SINKS>,
+    dedup: DedupCache,
+    next_receiver: usize,
+    stats: GroundStats,
+    decode: DecodeStats,
+    loss: LossTracker,
+    watchdog: Option<SilenceWatchdog>,
+    #[cfg(feature = "crypto")]
+    trailer: Option<Trailer>,
+}
+
+impl<'a, C: Clock> MeshGround<'a, C> {
+    pub fn new(clock: C, dedup_window_ms: u64) -> Self {
+        Self {
+            clock,
+            receivers: Vec::new(),
+            sinks: Vec::new(),
+            dedup: DedupCache::new(dedup_window_ms),
+            next_receiver: 0,
+            stats: GroundStats::default(),
+            decode: DecodeStats::new(),
+            loss: LossTracker::new(),
+            watchdog: None,
+            #[cfg(feature = "crypto")]
+            trailer: None,
+        }
+    }
+
+    pub fn add_receiver(&mut self, receiver: &'a mut dyn Receiver) -> Result<usize, RegistryFull> {
+        self.receivers.push(receiver).map_err(|_| RegistryFull)?;
+        Ok(self.receivers.len() - 1)
+    }
+
+    pub fn subscribe(&mut self, subscription: Subscription, sink: &'a mut dyn Sink) -> Result<(), RegistryFull> {
+        self.sinks.push((subscription, sink)).map_err(|_| RegistryFull)
+    }
+
+    pub fn stats(&self) -> GroundStats {
+        self.stats
+    }
+
+    /// Decode counters per sender, by packet type and failure cause
+    pub fn decode_stats(&self) -> &DecodeStats {
+        &self.decode
+    }
+
+    /// Frames lost and reordered per sender, from gaps in their sequence numbers
+    pub fn loss_stats(&self) -> &LossTracker {
+        &self.loss
+    }
+
+    /// Watches for telemetry silence from the rocket, replacing any previous watchdog
+    pub fn watch(&mut self, watchdog: SilenceWatchdog) {
+        self.watchdog = Some(watchdog);
+    }
+
+    pub fn watchdog(&self) -> Option<&SilenceWatchdog> {
+        self.watchdog.as_ref()
+    }
+
+    /// Drops frames of the kinds `trailer` signs unless their HMAC trailer verifies, counting them as
+    /// `DecodeFailure::Unauthenticated` against the sender they claim
+    #[cfg(feature = "crypto")]
+    pub fn verify_with(&mut self, trailer: Trailer) {
+        self.trailer = Some(trailer);
+    }
+
+    /// Reads receivers in turn until a new packet arrives, delivers it to matching sinks and returns it
+    ///
+    /// Returns `None` once every receiver is drained; call again from the main loop.
+    pub fn poll(&mut self) -> Option<GroundEvent> {
+        let now = self.clock.now_ms();
+        if let Some(alert) = self.watchdog.as_mut().and_then(|watchdog| watchdog.poll(now)) {
+            self.sinks.iter_mut().for_each(|(_, sink)| sink.alert(&alert, now));
+        }
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let mut idle = 0;
+        while idle < self.receivers.len() {
+            let index = self.next_receiver % self.receivers.len();
+            for _ in 0..self.receivers[index].take_crc_errors() {
+                self.decode.record_failure(None, DecodeFailure::Crc);
+            }
+            let received = self.receivers[index].receive(&mut buf);
+            if let Some(state) = self.receivers[index].take_link_change() {
+                self.sinks.iter_mut().for_each(|(_, sink)| sink.link_changed(index, state, now));
+            }
+            let Some((len, quality)) = received else {
+                self.next_receiver = index + 1;
+                idle += 1;
+                continue;
+            };
+            idle = 0;
+            self.stats.frames += 1;
+            let message = match checksum::verify_and_strip(&buf[..len]) {
+                Ok(message) => message,
+                Err(error) => {
+                    self.stats.decode_errors += 1;
+                    // A frame that failed its CRC can't be trusted to name its sender
+                    let (source_uid, failure) = match error {
+                        Error::Truncated => (buf[..len].get(PREFIX_LEN).copied(), DecodeFailure::Truncated),
+                        _ => (None, DecodeFailure::Crc),
+                    };
+                    self.decode.record_failure(source_uid, failure);
+                    continue;
+                }
+            };
+            #[cfg(feature = "crypto")]
+            let decoded = match &self.trailer {
+                Some(trailer) => trailer.verify(message),
+                None => MeshFrame::decode(message),
+            };
+            #[cfg(not(feature = "crypto"))]
+            let decoded = MeshFrame::decode(message);
+            let (header, mut packet) = match decoded {
+                Ok(MeshFrame { header, packet }) => (header, packet),
+                Err(error) => {
+                    self.stats.decode_errors += 1;
+                    self.decode.record_failure(message.first().copied(), error.into());
+                    continue;
+                }
+            };
+            self.decode.record_decoded(header.source_uid, packet.kind());
+            if self.dedup.check(header.source_uid, header.sequence, now) {
+                self.stats.duplicates += 1;
+                continue;
+            }
+            self.loss.record(&header);
+            let sanitized = packet.sanitize_decoded() > 0;
+            self.stats.non_finite += sanitized as u32;
+            let event = GroundEvent { receiver: index, at_ms: now, header, packet, quality, sanitized };
+            if let Some(watchdog) = self.watchdog.as_mut() {
+                watchdog.on_event(&event);
+            }
+            if let Packet::Emergency(emergency) = &event.packet {
+                // Raised to every sink whatever its subscription, like the watchdog's alerts
+                let alert = Alert::emergency(emergency);
+                self.sinks.iter_mut().for_each(|(_, sink)| sink.alert(&alert, now));
+            }
+            self.fan_out(&event);
+            return Some(event);
+        }
+        None
+    }
+
+    fn fan_out(&mut self, event: &GroundEvent) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "sinks",
+            kind = ?event.packet.kind(),
+            source_uid = event.header.source_uid,
+            sequence = event.header.sequence
+        )
+        .entered();
+        for (subscription, sink) in self.sinks.iter_mut() {
+            if subscription.matches(event) {
+                sink.deliver(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::protocol::checksum::CRC_LEN;
+    use crate::protocol::emergency::{Emergency, EmergencyKind};
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::packet::PacketKind;
+
+    #[derive(Default)]
+    struct Scripted(StdVec<StdVec<u8>>);
+
+    impl Receiver for Scripted {
+        fn receive(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)> {
+            if self.0.is_empty() {
+                return None;
+            }
+            let frame = self.0.remove(0);
+            buf[..frame.len()].copy_from_slice(&frame);
+            Some((frame.len(), LinkQuality::default()))
+        }
+    }
+
+    #[derive(Default)]
+    struct Collect(StdVec<GroundEvent>);
+
+    impl Sink for Collect {
+        fn deliver(&mut self, event: &GroundEvent) {
+            self.0.push(event.clone());
+        }
+    }
+
+    fn frame(source_uid: u8, sequence: u16, packet: Packet) -> StdVec<u8> {
+        let header = MeshHeader {
+            source_uid,
+            destination_uid: 0,
+            sequence,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let message = &mut buf[PREFIX_LEN..MAX_FRAME_LEN - CRC_LEN];
+        let len = postcard::to_slice(&MeshFrame { header, packet }, message).unwrap().len();
+        let len = checksum::append(&mut buf, len).unwrap();
+        buf[..len].to_vec()
+    }
+
+    #[test]
+    fn test_merges_receivers_and_filters_sinks() {
+        let launch = frame(3, 1, Packet::Event(FlightEvent::Launch));
+        let mut left = Scripted(std::vec![launch.clone(), std::vec![0xFF, 0xFF]]);
+        let mut right = Scripted(std::vec![launch, frame(4, 1, Packet::Event(FlightEvent::Landed))]);
+        let mut everything = Collect::default();
+        let mut node_four = Collect::default();
+
+        let mut ground = MeshGround::new(MockClock::new(0), 10_000);
+        ground.add_receiver(&mut left).unwrap();
+        ground.add_receiver(&mut right).unwrap();
+        ground.subscribe(Subscription::ALL, &mut everything).unwrap();
+        let only_four = Subscription { filter: PacketFilter::Kind(PacketKind::Event), source_uid: Some(4) };
+        ground.subscribe(only_four, &mut node_four).unwrap();
+
+        let mut events = StdVec::new();
+        while let Some(event) = ground.poll() {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 2);
+        assert_eq!(ground.stats(), GroundStats { frames: 4, duplicates: 1, decode_errors: 1, non_finite: 0 });
+        assert_eq!(ground.decode_stats().sender(0xFF).unwrap().failures(DecodeFailure::Truncated), 1);
+        assert_eq!(ground.loss_stats().sender(3).unwrap().received, 1);
+        drop(ground);
+        assert_eq!(everything.0, events);
+        assert_eq!(node_four.0.len(), 1);
+        assert_eq!(node_four.0[0].packet, Packet::Event(FlightEvent::Landed));
+    }
+
+    #[derive(Default)]
+    struct Alerts(StdVec<(Alert, u64)>);
+
+    impl Sink for Alerts {
+        fn deliver(&mut self, _event: &GroundEvent) {}
+
+        fn alert(&mut self, alert: &Alert, at_ms: u64) {
+            self.0.push((*alert, at_ms));
+        }
+    }
+
+    #[test]
+    fn test_emergency_raises_an_alert_for_every_sink() {
+        let hold = Emergency { uid: 1, kind: EmergencyKind::MisfireHold, text: "pad 3".try_into().unwrap() };
+        let emergency = frame(1, 1, Packet::Emergency(hold.clone()));
+        let launch = frame(3, 1, Packet::Event(FlightEvent::Launch));
+        let mut radio = Scripted(std::vec![emergency.clone(), emergency, launch]);
+        let mut alerts = Alerts::default();
+
+        let mut ground = MeshGround::new(MockClock::new(500), 10_000);
+        ground.add_receiver(&mut radio).unwrap();
+        // Subscribed to flight events only, yet still told about the emergency
+        let events_only = Subscription { filter: PacketFilter::Kind(PacketKind::Event), source_uid: None };
+        ground.subscribe(events_only, &mut alerts).unwrap();
+        assert_eq!(ground.poll().map(|event| event.packet), Some(Packet::Emergency(hold.clone())));
+        while ground.poll().is_some() {}
+        drop(ground);
+        // The duplicate copy raises nothing
+        assert_eq!(alerts.0, [(Alert::emergency(&hold), 500)]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Mesh frames demodulated by an SDR pipeline, such as GNU Radio with an RTL-SDR, fed in as a receiver
+//!
+//! The pipeline writes frames to a byte stream: stdin, a pipe, a socket bridged from ZeroMQ, or a file of
+//! frames demodulated from IQ recorded during a flight. Frames are then checked and decoded by MeshGround
+//! exactly as those from a radio node.
+
+use heapless::Vec;
+use postcard::accumulator::FeedResult;
+
+use super::Receiver;
+use crate::protocol::kiss::{KissCommand, KissDecoder};
+use crate::protocol::ping::LinkQuality;
+use crate::protocol::serial::{BridgedFrame, FrameAccumulator, BRIDGED_FRAME_LEN};
+
+const CHUNK_LEN: usize = 64;
+
+/// A stream of bytes from the demodulator; `read` returns 0 when nothing is available
+pub trait SdrSource {
+    type Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read> SdrSource for T {
+    type Error = std::io::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        match std::io::Read::read(self, buf) {
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
+            result => result,
+        }
+    }
+}
+
+/// How the pipeline frames its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SdrFormat {
+    /// KISS data frames on port 0, as emitted by common GNU Radio packet sinks; signal quality is unknown
+    Kiss,
+    /// COBS-framed BridgedFrames, the radio node's own serial format, carrying RSSI and SNR
+    Bridged,
+}
+
+enum Decoder {
+    Kiss(KissDecoder),
+    Bridged(FrameAccumulator<BRIDGED_FRAME_LEN>),
+}
+
+/// SdrReceiver reads demodulated mesh frames from an SDR pipeline
+///
+/// A read error is treated like the end of the stream. Malformed framing is dropped and counted.
+pub struct SdrReceiver<S: SdrSource> {
+    source: S,
+    decoder: Decoder,
+    pending: Vec<u8, CHUNK_LEN>,
+    dropped: u32,
+}
+
+impl<S: SdrSource> SdrReceiver<S> {
+    pub fn new(source: S, format: SdrFormat) -> Self {
+        let decoder = match format {
+            SdrFormat::Kiss => Decoder::Kiss(KissDecoder::new()),
+            SdrFormat::Bridged => Decoder::Bridged(FrameAccumulator::new()),
+        };
+        Self { source, decoder, pending: Vec::new(), dropped: 0 }
+    }
+
+    /// Frames discarded because their framing was malformed
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Decodes from the pending bytes, leaving whatever follows a complete frame
+    fn decode(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)> {
+        let mut copy = |frame: &[u8]| {
+            let len = frame.len().min(buf.len());
+            buf[..len].copy_from_slice(&frame[..len]);
+            len
+        };
+        match &mut self.decoder {
+            Decoder::Kiss(decoder) => {
+                while !self.pending.is_empty() {
+                    match decoder.feed(self.pending.remove(0)) {
+                        Some(Ok(frame)) if frame.port == 0 && frame.command == KissCommand::Data => {
+                            return Some((copy(&frame.data), LinkQuality::default()));
+                        }
+                        Some(Err(_)) => self.dropped += 1,
+                        _ => {}
+                    }
+                }
+                None
+            }
+            Decoder::Bridged(accumulator) => {
+                let (rest, frame) = match accumulator.feed::<BridgedFrame>(&self.pending) {
+                    FeedResult::Consumed => (&[][..], None),
+                    FeedResult::OverFull(rest) | FeedResult::DeserError(rest) => {
+                        self.dropped += 1;
+                        (rest, None)
+                    }
+                    FeedResult::Success { data, remaining } => (remaining, Some(data)),
+                };
+                // What is left is a suffix of `pending`, so it always fits
+                self.pending = Vec::from_slice(rest).unwrap_or_default();
+                frame.map(|BridgedFrame { quality, frame }| (copy(&frame), quality))
+            }
+        }
+    }
+}
+
+impl<S: SdrSource> Receiver for SdrReceiver<S> {
+    fn receive(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)> {
+        loop {
+            if let Some(received) = self.decode(buf) {
+                return Some(received);
+            }
+            if !self.pending.is_empty() {
+                continue;
+            }
+            let mut chunk = [0u8; CHUNK_LEN];
+            match self.source.read(&mut chunk) {
+                Ok(0) | Err(_) => return None,
+                Ok(n) => self.pending.extend_from_slice(&chunk[..n]).expect("chunk fits pending"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::ground::MeshGround;
+    use crate::protocol::checksum::{self, CRC_LEN, PREFIX_LEN};
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::kiss;
+    use crate::protocol::mesh::{MeshFrame, MeshHeader, MAX_FRAME_LEN};
+    use crate::protocol::packet::Packet;
+    use crate::protocol::serial::encode_frame;
+
+    /// A recording replayed in small reads
+    struct Recording(StdVec<u8>);
+
+    impl SdrSource for Recording {
+        type Error = ();
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            let len = self.0.len().min(buf.len()).min(7);
+            buf[..len].copy_from_slice(&self.0.drain(..len).collect::<StdVec<_>>());
+            Ok(len)
+        }
+    }
+
+    fn mesh_frame(sequence: u16) -> StdVec<u8> {
+        let header = MeshHeader {
+            source_uid: 5,
+            destination_uid: 0,
+            sequence,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let message = &mut buf[PREFIX_LEN..MAX_FRAME_LEN - CRC_LEN];
+        let packet = Packet::Event(FlightEvent::Launch);
+        let len = postcard::to_slice(&MeshFrame { header, packet }, message).unwrap().len();
+        let len = checksum::append(&mut buf, len).unwrap();
+        buf[..len].to_vec()
+    }
+
+    #[test]
+    fn test_kiss_and_bridged_streams_reach_ground() {
+        let mut stream = StdVec::new();
+        for sequence in [1, 2] {
+            let mut buf = [0u8; 2 * MAX_FRAME_LEN];
+            let len = kiss::encode(0, KissCommand::Data, &mesh_frame(sequence), &mut buf).unwrap();
+            stream.extend_from_slice(&buf[..len]);
+        }
+        stream.extend_from_slice(&[kiss::FEND, 0x00, kiss::FESC, 0x00, kiss::FEND]);
+        let mut kiss = SdrReceiver::new(Recording(stream), SdrFormat::Kiss);
+
+        let quality = LinkQuality { rssi_dbm: -110, snr_db: -12.5 };
+        let frame = BridgedFrame { quality, frame: Vec::from_slice(&mesh_frame(3)).unwrap() };
+        let bridged = encode_frame(&frame, &mut [0u8; BRIDGED_FRAME_LEN]).unwrap().to_vec();
+        let mut bridged = SdrReceiver::new(Recording(bridged), SdrFormat::Bridged);
+
+        let mut ground = MeshGround::new(MockClock::new(0), 10_000);
+        ground.add_receiver(&mut kiss).unwrap();
+        ground.add_receiver(&mut bridged).unwrap();
+        let mut events = StdVec::new();
+        while let Some(event) = ground.poll() {
+            events.push(event);
+        }
+        assert_eq!(events.iter().map(|event| event.header.sequence).collect::<StdVec<_>>(), [1, 2, 3]);
+        assert_eq!(events[2].quality, quality);
+        drop(ground);
+        assert_eq!(kiss.dropped(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! Receiving mesh frames from a radio node on a USB serial port that may be unplugged and replugged
+
+use heapless::Vec;
+use postcard::accumulator::FeedResult;
+
+use super::{LinkState, Receiver};
+use crate::clock::Clock;
+use crate::protocol::ping::LinkQuality;
+use crate::protocol::serial::{BridgedFrame, FrameAccumulator, BRIDGED_FRAME_LEN};
+
+const CHUNK_LEN: usize = 64;
+
+/// A serial device that can be reopened after it disappears
+pub trait SerialPort {
+    type Error;
+
+    /// Opens the device, usually by a stable identifier such as its USB serial number
+    fn open(&mut self) -> Result<(), Self::Error>;
+    fn close(&mut self);
+    /// Reads whatever bytes are available; an error means the device is gone
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectConfig {
+    /// Time between attempts to reopen a missing device
+    pub retry_ms: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self { retry_ms: 500 }
+    }
+}
+
+/// SerialReceiver reads BridgedFrames from a serial port and reattaches it when it comes back
+///
+/// A read error closes the port and reports the link down; the port is reopened every `retry_ms` until it
+/// succeeds. Frames the radio node queued while it was detached are read as usual afterwards, and anything
+/// heard twice is dropped by MeshGround's duplicate suppression, so no restart is needed.
+pub struct SerialReceiver<P: SerialPort, C: Clock> {
+    port: P,
+    clock: C,
+    config: ReconnectConfig,
+    accumulator: FrameAccumulator<BRIDGED_FRAME_LEN>,
+    pending: Vec<u8, CHUNK_LEN>,
+    connected: bool,
+    next_attempt_ms: u64,
+    change: Option<LinkState>,
+    reconnects: u32,
+}
+
+impl<P: SerialPort, C: Clock> SerialReceiver<P, C> {
+    /// The port is opened on the first `receive`
+    pub fn new(port: P, clock: C, config: ReconnectConfig) -> Self {
+        Self {
+            port,
+            clock,
+            config,
+            accumulator: FrameAccumulator::new(),
+            pending: Vec::new(),
+            connected: false,
+            next_attempt_ms: 0,
+            change: None,
+            reconnects: 0,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Times the port was reopened after being lost
+    pub fn reconnects(&self) -> u32 {
+        self.reconnects
+    }
+
+    fn connect(&mut self, now: u64) -> bool {
+        if now < self.next_attempt_ms {
+            return false;
+        }
+        if self.port.open().is_err() {
+            self.next_attempt_ms = now + self.config.retry_ms;
+            return false;
+        }
+        // A frame cut off by the unplug can't be completed, start from the next delimiter
+        self.accumulator = FrameAccumulator::new();
+        self.connected = true;
+        self.change = Some(LinkState::Up);
+        true
+    }
+
+    fn disconnect(&mut self, now: u64) {
+        self.port.close();
+        self.pending.clear();
+        self.connected = false;
+        self.reconnects += 1;
+        self.next_attempt_ms = now + self.config.retry_ms;
+        self.change = Some(LinkState::Down);
+    }
+}
+
+impl<P: SerialPort, C: Clock> Receiver for SerialReceiver<P, C> {
+    fn receive(&mut self, buf: &mut [u8]) -> Option<(usize, LinkQuality)> {
+        let now = self.clock.now_ms();
+        if !self.connected && !self.connect(now) {
+            return None;
+        }
+        loop {
+            if self.pending.is_empty() {
+                let mut chunk = [0u8; CHUNK_LEN];
+                match self.port.read(&mut chunk) {
+                    Ok(0) => return None,
+                    Ok(n) => self.pending.extend_from_slice(&chunk[..n]).expect("chunk fits pending"),
+                    Err(_) => {
+                        self.disconnect(now);
+                        return None;
+                    }
+                }
+            }
+            let (rest, frame) = match self.accumulator.feed::<BridgedFrame>(&self.pending) {
+                FeedResult::Consumed => (&[][..], None),
+                FeedResult::OverFull(rest) | FeedResult::DeserError(rest) => (rest, None),
+                FeedResult::Success { data, remaining } => (remaining, Some(data)),
+            };
+            // What is left is a suffix of `pending`, so it always fits
+            self.pending = Vec::from_slice(rest).unwrap_or_default();
+            if let Some(BridgedFrame { quality, frame }) = frame {
+                let len = frame.len().min(buf.len());
+                buf[..len].copy_from_slice(&frame[..len]);
+                return Some((len, quality));
+            }
+        }
+    }
+
+    fn take_link_change(&mut self) -> Option<LinkState> {
+        self.change.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::protocol::serial::encode_frame;
+
+    /// Scripted reads; `None` simulates the device vanishing, which also makes opens fail until `plugged`
+    #[derive(Default)]
+    struct Port {
+        reads: VecDeque<Option<StdVec<u8>>>,
+        plugged: bool,
+        opens: u32,
+    }
+
+    impl SerialPort for Port {
+        type Error = ();
+
+        fn open(&mut self) -> Result<(), ()> {
+            self.opens += 1;
+            if self.plugged {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+
+        fn close(&mut self) {}
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            match self.reads.pop_front() {
+                Some(Some(bytes)) => {
+                    buf[..bytes.len()].copy_from_slice(&bytes);
+                    Ok(bytes.len())
+                }
+                Some(None) => {
+                    self.plugged = false;
+                    Err(())
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    fn bridged(byte: u8) -> StdVec<u8> {
+        let frame = BridgedFrame { quality: LinkQuality::default(), frame: Vec::from_slice(&[byte; 4]).unwrap() };
+        encode_frame(&frame, &mut [0u8; BRIDGED_FRAME_LEN]).unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_reattaches_after_unplug() {
+        let clock = MockClock::new(0);
+        let first = bridged(1);
+        let (head, tail) = first.split_at(3);
+        let port = Port {
+            reads: [Some(head.to_vec()), Some(tail.to_vec()), None, Some(bridged(2))].into(),
+            plugged: true,
+            opens: 0,
+        };
+        let mut receiver = SerialReceiver::new(port, &clock, ReconnectConfig::default());
+        let mut buf = [0u8; 16];
+
+        assert_eq!(receiver.receive(&mut buf).map(|(len, _)| len), Some(4));
+        assert_eq!(receiver.take_link_change(), Some(LinkState::Up));
+        assert_eq!(receiver.receive(&mut buf), None);
+        assert_eq!(receiver.take_link_change(), Some(LinkState::Down));
+
+        // Still unplugged, and no retry before retry_ms
+        assert_eq!(receiver.receive(&mut buf), None);
+        clock.set(500);
+        assert_eq!(receiver.receive(&mut buf), None);
+        assert_eq!(receiver.port.opens, 2);
+        receiver.port.plugged = true;
+        clock.set(1_000);
+        assert_eq!(receiver.receive(&mut buf).map(|(len, _)| buf[..len].to_vec()), Some(std::vec![2; 4]));
+        assert_eq!(receiver.take_link_change(), Some(LinkState::Up));
+        assert_eq!(receiver.reconnects(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+use heapless::Deque;
+
+/// Largest median window supported
+pub const MAX_WINDOW: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    /// Pass samples through unchanged, apart from outlier rejection
+    None,
+    /// Exponential moving average, `alpha` is the weight of the newest sample (0..=1)
+    Ema { alpha: f32 },
+    /// Median of the last `window` accepted samples
+    Median { window: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothingConfig {
+    pub method: Method,
+    /// Samples further than this from the recent median are rejected as corrupt
+    pub outlier_threshold: Option<f32>,
+    /// After this many consecutive rejections the signal is assumed to have really moved and is accepted
+    pub max_rejections: u8,
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self { method: Method::Ema { alpha: 0.3 }, outlier_threshold: None, max_rejections: 3 }
+    }
+}
+
+/// One smoothed value, always flagged as derived so exports never confuse it with the raw reading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Smoothed {
+    pub value: f32,
+    pub raw: f32,
+    /// The raw sample was rejected as an outlier and did not affect `value`
+    pub rejected: bool,
+    pub derived: bool,
+}
+
+/// Smoother cleans up one telemetry field for display, e.g. the live altitude readout
+#[derive(Debug, Clone)]
+pub struct Smoother {
+    pub config: SmoothingConfig,
+    history: Deque<f32, MAX_WINDOW>,
+    value: Option<f32>,
+    rejections: u8,
+}
+
+impl Smoother {
+    pub fn new(config: SmoothingConfig) -> Self {
+        Self { config, history: Deque::new(), value: None, rejections: 0 }
+    }
+
+    pub fn update(&mut self, raw: f32) -> Smoothed {
+        if !raw.is_finite() || self.is_outlier(raw) {
+            self.rejections = self.rejections.saturating_add(1);
+            let value = self.value.unwrap_or(raw);
+            return Smoothed { value, raw, rejected: true, derived: true };
+        }
+        self.rejections = 0;
+        if self.history.is_full() {
+            self.history.pop_front();
+        }
+        let _ = self.history.push_back(raw);
+
+        let value = match self.config.method {
+            Method::None => raw,
+            Method::Ema { alpha } => match self.value {
+                Some(previous) => previous + alpha.clamp(0.0, 1.0) * (raw - previous),
+                None => raw,
+            },
+            Method::Median { window } => median(self.history.iter().rev().take(window.clamp(1, MAX_WINDOW))),
+        };
+        self.value = Some(value);
+        Smoothed { value, raw, rejected: false, derived: true }
+    }
+
+    fn is_outlier(&self, raw: f32) -> bool {
+        let Some(threshold) = self.config.outlier_threshold else {
+            return false;
+        };
+        if self.history.len() < 3 || self.rejections >= self.config.max_rejections {
+            return false;
+        }
+        (raw - median(self.history.iter())).abs() > threshold
+    }
+}
+
+fn median<'a>(values: impl Iterator<Item = &'a f32>) -> f32 {
+    let mut sorted: heapless::Vec<f32, MAX_WINDOW> = values.copied().collect();
+    sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_spike_rejected() {
+        let config = SmoothingConfig { method: Method::Median { window: 3 }, outlier_threshold: Some(50.0), max_rejections: 2 };
+        let mut smoother = Smoother::new(config);
+        for raw in [100.0, 102.0, 104.0] {
+            assert!(!smoother.update(raw).rejected);
+        }
+        let spike = smoother.update(9_000.0);
+        assert!(spike.rejected && spike.derived);
+        assert_eq!(spike.value, 102.0);
+        assert_eq!(smoother.update(106.0).value, 104.0);
+    }
+
+    #[test]
+    fn test_persistent_step_is_accepted() {
+        let config = SmoothingConfig { method: Method::None, outlier_threshold: Some(10.0), max_rejections: 2 };
+        let mut smoother = Smoother::new(config);
+        for raw in [0.0, 0.0, 0.0] {
+            smoother.update(raw);
+        }
+        assert!(smoother.update(500.0).rejected);
+        assert!(smoother.update(500.0).rejected);
+        assert_eq!(smoother.update(500.0), Smoothed { value: 500.0, raw: 500.0, rejected: false, derived: true });
+    }
+}
@@ -0,0 +1,237 @@
+//! Latest value of each packet type per node, so UIs read current state instead of replaying events
+//!
+//! `StateStore` is a Sink that keeps one entry per `(node, packet kind)` and a version counter bumped on
+//! every change. Without `std`, consumers poll `changed_since`; with `std`, `SharedStateStore` hands out
+//! `StateWatch` handles that block until something changes.
+
+use heapless::Vec;
+
+use super::{GroundEvent, Sink};
+use crate::protocol::packet::{Packet, PacketKind};
+
+/// Latest packet of one kind from one node
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateEntry {
+    pub source_uid: u8,
+    pub packet: Packet,
+    /// When the packet was last received, even if it matched the stored value
+    pub at_ms: u64,
+    /// Store version at which the value last changed
+    pub version: u32,
+}
+
+/// StateStore holds the latest value for up to `N` `(node, packet kind)` pairs
+///
+/// Once full, the pair updated longest ago is replaced.
+#[derive(Debug, Clone, Default)]
+pub struct StateStore<const N: usize> {
+    entries: Vec<StateEntry, N>,
+    version: u32,
+}
+
+impl<const N: usize> StateStore<N> {
+    pub fn new() -> Self {
+        Self { entries: Vec::new(), version: 0 }
+    }
+
+    /// Stores `packet` as the latest of its kind from `source_uid`, returning whether the value changed
+    pub fn update(&mut self, source_uid: u8, packet: &Packet, at_ms: u64) -> bool {
+        let kind = packet.kind();
+        let matches = |entry: &&mut StateEntry| entry.source_uid == source_uid && entry.packet.kind() == kind;
+        if let Some(entry) = self.entries.iter_mut().find(matches) {
+            entry.at_ms = at_ms;
+            if entry.packet == *packet {
+                return false;
+            }
+            self.version = self.version.wrapping_add(1);
+            entry.packet = packet.clone();
+            entry.version = self.version;
+            return true;
+        }
+        if self.entries.is_full() {
+            if let Some(oldest) = (0..self.entries.len()).min_by_key(|&index| self.entries[index].at_ms) {
+                self.entries.swap_remove(oldest);
+            }
+        }
+        self.version = self.version.wrapping_add(1);
+        let entry = StateEntry { source_uid, packet: packet.clone(), at_ms, version: self.version };
+        self.entries.push(entry).is_ok()
+    }
+
+    /// Bumped on every change, compare against a previous value to see whether anything changed
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn get(&self, source_uid: u8, kind: PacketKind) -> Option<&StateEntry> {
+        self.entries.iter().find(|entry| entry.source_uid == source_uid && entry.packet.kind() == kind)
+    }
+
+    /// Every stored packet from `source_uid`
+    pub fn node(&self, source_uid: u8) -> impl Iterator<Item = &StateEntry> {
+        self.entries.iter().filter(move |entry| entry.source_uid == source_uid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StateEntry> {
+        self.entries.iter()
+    }
+
+    /// Entries whose value changed after `version`, for polling consumers
+    pub fn changed_since(&self, version: u32) -> impl Iterator<Item = &StateEntry> {
+        // Wrapping distance, so a consumer that polls at least once per 2^31 changes never misses one
+        self.entries.iter().filter(move |entry| (entry.version.wrapping_sub(version) as i32) > 0)
+    }
+}
+
+impl<const N: usize> Sink for StateStore<N> {
+    fn deliver(&mut self, event: &GroundEvent) {
+        self.update(event.header.source_uid, &event.packet, event.at_ms);
+    }
+}
+
+#[cfg(feature = "std")]
+pub use watch::{SharedStateStore, StateWatch};
+
+#[cfg(feature = "std")]
+mod watch {
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::time::Duration;
+
+    use super::StateStore;
+    use crate::ground::{GroundEvent, Sink};
+
+    type Shared<const N: usize> = Arc<(Mutex<StateStore<N>>, Condvar)>;
+
+    /// A StateStore shared between the ground loop and UI threads
+    ///
+    /// Subscribe it as a sink, then hand `watch` handles to the UI threads.
+    #[derive(Debug, Default)]
+    pub struct SharedStateStore<const N: usize> {
+        shared: Shared<N>,
+    }
+
+    impl<const N: usize> SharedStateStore<N> {
+        pub fn new() -> Self {
+            Self { shared: Arc::new((Mutex::new(StateStore::new()), Condvar::new())) }
+        }
+
+        /// A handle that is notified of changes after this call
+        pub fn watch(&self) -> StateWatch<N> {
+            let seen = lock(&self.shared).version();
+            StateWatch { shared: self.shared.clone(), seen }
+        }
+    }
+
+    impl<const N: usize> Sink for SharedStateStore<N> {
+        fn deliver(&mut self, event: &GroundEvent) {
+            let changed = lock(&self.shared).update(event.header.source_uid, &event.packet, event.at_ms);
+            if changed {
+                self.shared.1.notify_all();
+            }
+        }
+    }
+
+    /// Receiving end of a SharedStateStore, like a watch channel over the whole store
+    #[derive(Debug)]
+    pub struct StateWatch<const N: usize> {
+        shared: Shared<N>,
+        seen: u32,
+    }
+
+    impl<const N: usize> StateWatch<N> {
+        /// Waits up to `timeout` for a change not yet seen through this handle, returning whether one came
+        ///
+        /// Marks the current state as seen either way.
+        pub fn changed(&mut self, timeout: Duration) -> bool {
+            let (store, condvar) = &*self.shared;
+            let guard = store.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let seen = self.seen;
+            let (guard, _) = condvar
+                .wait_timeout_while(guard, timeout, |store| store.version() == seen)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            self.seen = guard.version();
+            self.seen != seen
+        }
+
+        /// The current state, held locked until the guard is dropped
+        pub fn borrow(&self) -> MutexGuard<'_, StateStore<N>> {
+            lock(&self.shared)
+        }
+
+        /// Version last seen through `changed`, to pass to `StateStore::changed_since`
+        pub fn seen(&self) -> u32 {
+            self.seen
+        }
+    }
+
+    fn lock<const N: usize>(shared: &Shared<N>) -> MutexGuard<'_, StateStore<N>> {
+        // Entries hold no cross-field invariant, so a store poisoned by a panicking thread is still usable
+        shared.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::hello::Hello;
+
+    #[test]
+    fn test_latest_value_per_node_and_kind() {
+        let mut store = StateStore::<3>::new();
+        assert!(store.update(2, &Packet::Event(FlightEvent::Launch), 0));
+        assert!(store.update(3, &Packet::Event(FlightEvent::Launch), 10));
+        let seen = store.version();
+        assert!(!store.update(2, &Packet::Event(FlightEvent::Launch), 20));
+        assert!(store.update(2, &Packet::Event(FlightEvent::Landed), 30));
+        assert!(store.update(2, &Packet::Hello(Hello { count: 1, interval_ms: 1_000 }), 40));
+
+        let changed: std::vec::Vec<_> = store.changed_since(seen).map(|entry| entry.packet.kind()).collect();
+        assert_eq!(changed, [PacketKind::Event, PacketKind::Hello]);
+        assert_eq!(store.get(2, PacketKind::Event).unwrap().packet, Packet::Event(FlightEvent::Landed));
+        assert_eq!(store.node(2).count(), 2);
+
+        // Full, so node 3's entry, updated longest ago, makes room
+        assert!(store.update(4, &Packet::Event(FlightEvent::Apogee { altitude_m: 3_000.0 }), 50));
+        assert!(store.get(3, PacketKind::Event).is_none());
+        assert_eq!(store.iter().count(), 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_watch_wakes_on_change() {
+        use std::time::Duration;
+
+        use crate::protocol::mesh::MeshHeader;
+
+        let mut shared = SharedStateStore::<4>::new();
+        let mut watch = shared.watch();
+        assert!(!watch.changed(Duration::from_millis(1)));
+
+        let header = MeshHeader {
+            source_uid: 7,
+            destination_uid: 0,
+            sequence: 1,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        let event = GroundEvent {
+            receiver: 0,
+            at_ms: 5,
+            header,
+            packet: Packet::Event(FlightEvent::Launch),
+            quality: Default::default(),
+            sanitized: false,
+        };
+        let waiter = std::thread::spawn(move || {
+            let woke = watch.changed(Duration::from_secs(5));
+            let packet = watch.borrow().get(7, PacketKind::Event).map(|entry| entry.packet.clone());
+            (woke, packet)
+        });
+        shared.deliver(&event);
+        assert_eq!(waiter.join().unwrap(), (true, Some(Packet::Event(FlightEvent::Launch))));
+    }
+}
@@ -0,0 +1,164 @@
+//! Dead-man watchdog on the rocket's telemetry, so a link lost in flight is noticed within seconds
+//!
+//! `AlertMonitor`'s LostLink waits one fixed window, long enough not to trip on the pad where nodes only
+//! send Hellos. In flight the rocket sends many frames a second and a few seconds of silence already
+//! matter, so the watchdog follows the flight phase from the rocket's FlightEvents and uses a window per
+//! phase, escalating from a warning to repeated critical alerts.
+
+use super::GroundEvent;
+use crate::alerts::{Alert, AlertKind, Severity};
+use crate::flight::state::FlightPhase;
+use crate::protocol::events::FlightEvent;
+use crate::protocol::packet::Packet;
+
+/// Silence tolerated in one flight phase before each alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SilenceWindows {
+    pub warning_ms: u64,
+    pub critical_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchdogConfig {
+    /// Windows for Pad, Boost, Coast, Descent and Landed, in that order
+    pub windows: [SilenceWindows; 5],
+    /// The critical alert is raised again this often while the silence lasts, `None` raises it once
+    pub repeat_ms: Option<u64>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        let windows = |warning_ms, critical_ms| SilenceWindows { warning_ms, critical_ms };
+        Self {
+            windows: [
+                windows(30_000, 60_000),
+                windows(2_000, 5_000),
+                windows(3_000, 6_000),
+                windows(3_000, 8_000),
+                // Landed nodes fall back to recovery reports every 15 s
+                windows(40_000, 120_000),
+            ],
+            repeat_ms: Some(10_000),
+        }
+    }
+}
+
+/// SilenceWatchdog raises alerts while no frames arrive from one node
+///
+/// `MeshGround::watch` runs one in the ground runtime, which hands its alerts to every sink. Nothing is
+/// raised before the first frame, and a frame from the node re-arms it.
+#[derive(Debug, Clone, Copy)]
+pub struct SilenceWatchdog {
+    uid: u8,
+    config: WatchdogConfig,
+    phase: FlightPhase,
+    last_frame_ms: Option<u64>,
+    /// Severity of the last alert raised in the current silence, and when
+    raised: Option<(Severity, u64)>,
+}
+
+impl SilenceWatchdog {
+    pub fn new(uid: u8, config: WatchdogConfig) -> Self {
+        Self { uid, config, phase: FlightPhase::Pad, last_frame_ms: None, raised: None }
+    }
+
+    pub fn uid(&self) -> u8 {
+        self.uid
+    }
+
+    /// Flight phase the node last reported, which picks the windows
+    pub fn phase(&self) -> FlightPhase {
+        self.phase
+    }
+
+    /// Records a received event, ignoring other nodes
+    pub fn on_event(&mut self, event: &GroundEvent) {
+        if event.header.source_uid != self.uid {
+            return;
+        }
+        self.last_frame_ms = Some(event.at_ms);
+        self.raised = None;
+        if let Packet::Event(flight_event) = &event.packet {
+            self.phase = match flight_event {
+                FlightEvent::Launch => FlightPhase::Boost,
+                FlightEvent::Burnout => FlightPhase::Coast,
+                FlightEvent::Apogee { .. } | FlightEvent::DrogueDeployed | FlightEvent::MainDeployed => {
+                    FlightPhase::Descent
+                }
+                FlightEvent::Landed => FlightPhase::Landed,
+            };
+        }
+    }
+
+    /// Returns an alert when the silence crosses the next window or a critical alert is due again
+    pub fn poll(&mut self, now_ms: u64) -> Option<Alert> {
+        let silence = now_ms.saturating_sub(self.last_frame_ms?);
+        let windows = self.config.windows[self.phase as usize];
+        let severity = if silence > windows.critical_ms {
+            Severity::Critical
+        } else if silence > windows.warning_ms {
+            Severity::Warning
+        } else {
+            return None;
+        };
+        let due = match self.raised {
+            None => true,
+            Some((raised, _)) if raised < severity => true,
+            Some((Severity::Critical, at_ms)) => {
+                self.config.repeat_ms.is_some_and(|repeat_ms| now_ms.saturating_sub(at_ms) >= repeat_ms)
+            }
+            Some(_) => false,
+        };
+        if !due {
+            return None;
+        }
+        self.raised = Some((severity, now_ms));
+        #[cfg(feature = "tracing")]
+        tracing::warn!(uid = self.uid, silence_ms = silence, phase = ?self.phase, ?severity, "telemetry silence");
+        Some(Alert { kind: AlertKind::Silence, severity, uid: self.uid, value: silence as f32 / 1000.0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::mesh::MeshHeader;
+    use crate::protocol::ping::LinkQuality;
+
+    fn event(source_uid: u8, at_ms: u64, packet: Packet) -> GroundEvent {
+        let header = MeshHeader {
+            source_uid,
+            destination_uid: 0,
+            sequence: 0,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        GroundEvent { receiver: 0, at_ms, header, packet, quality: LinkQuality::default(), sanitized: false }
+    }
+
+    #[test]
+    fn test_escalates_per_phase_and_rearms() {
+        let mut watchdog = SilenceWatchdog::new(3, WatchdogConfig::default());
+        assert_eq!(watchdog.poll(100_000), None);
+        watchdog.on_event(&event(3, 0, Packet::Event(FlightEvent::Launch)));
+        watchdog.on_event(&event(3, 1_000, Packet::Event(FlightEvent::Apogee { altitude_m: 3_000.0 })));
+        assert_eq!(watchdog.phase(), FlightPhase::Descent);
+
+        // Another node's frames don't feed the watchdog
+        watchdog.on_event(&event(4, 3_500, Packet::Event(FlightEvent::Landed)));
+        assert_eq!(watchdog.poll(4_000), None);
+        let warning = watchdog.poll(4_500).unwrap();
+        assert_eq!((warning.kind, warning.severity, warning.value), (AlertKind::Silence, Severity::Warning, 3.5));
+        assert_eq!(watchdog.poll(8_000), None);
+        assert_eq!(watchdog.poll(9_500).map(|alert| alert.severity), Some(Severity::Critical));
+        assert_eq!(watchdog.poll(15_000), None);
+        assert_eq!(watchdog.poll(19_500).map(|alert| alert.value), Some(18.5));
+
+        // Landed allows far longer silences
+        watchdog.on_event(&event(3, 20_000, Packet::Event(FlightEvent::Landed)));
+        assert_eq!(watchdog.poll(50_000), None);
+    }
+}
@@ -0,0 +1,3 @@
+//! Node-side pieces for ground support equipment at the pad, see `DeviceType::Gse`
+
+pub mod weather;
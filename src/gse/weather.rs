@@ -0,0 +1,218 @@
+//! Ingestion of NMEA 0183 weather stations, the serial format most pad stations and marine
+//! ultrasonic anemometers speak
+//!
+//! `$--MDA` carries everything at once. `$--MWV` (true wind only) and `$--XDR` temperature, pressure and
+//! humidity transducers fill in for stations that send those instead. Talker IDs are ignored and sentences
+//! with a bad checksum are counted and dropped.
+
+use heapless::Vec;
+
+use crate::protocol::gse::PadWeather;
+
+/// Longest NMEA sentence, including the `$` and the checksum
+pub const MAX_SENTENCE_LEN: usize = 82;
+
+const KNOTS_TO_MPS: f32 = 0.514_444;
+const KMH_TO_MPS: f32 = 1.0 / 3.6;
+const MPH_TO_MPS: f32 = 0.447_04;
+
+/// WeatherStation assembles PadWeather reports from a station's serial output
+#[derive(Debug, Clone)]
+pub struct WeatherStation {
+    uid: u8,
+    line: Vec<u8, MAX_SENTENCE_LEN>,
+    temperature_c: Option<f32>,
+    pressure_hpa: Option<f32>,
+    humidity_pct: Option<u8>,
+    /// Latest wind speed in m/s and the direction it blows from
+    wind: Option<(f32, u16)>,
+    /// Highest wind speed since the last report
+    gust_mps: f32,
+    rejected: u32,
+}
+
+impl WeatherStation {
+    /// `uid` is the reporting GSE node
+    pub fn new(uid: u8) -> Self {
+        Self {
+            uid,
+            line: Vec::new(),
+            temperature_c: None,
+            pressure_hpa: None,
+            humidity_pct: None,
+            wind: None,
+            gust_mps: 0.0,
+            rejected: 0,
+        }
+    }
+
+    /// Feeds bytes as they arrive from the serial port
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match byte {
+                b'$' => {
+                    self.line.clear();
+                    let _ = self.line.push(byte);
+                }
+                b'\r' | b'\n' => {
+                    if !self.line.is_empty() {
+                        let line = core::mem::take(&mut self.line);
+                        self.sentence(&line);
+                    }
+                }
+                // Overlong lines are not NMEA, drop them until the next `$`
+                _ => {
+                    if self.line.push(byte).is_err() {
+                        self.line.clear();
+                        self.rejected += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sentences dropped for a bad checksum or malformed fields
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    /// Current conditions once temperature, pressure and wind have all been heard, starting a new gust period
+    pub fn report(&mut self) -> Option<PadWeather> {
+        let (wind_speed_mps, wind_direction_deg) = self.wind?;
+        let report = PadWeather {
+            uid: self.uid,
+            temperature_c: self.temperature_c?,
+            pressure_hpa: self.pressure_hpa?,
+            humidity_pct: self.humidity_pct.unwrap_or(0),
+            wind_speed_mps,
+            wind_gust_mps: self.gust_mps.max(wind_speed_mps),
+            wind_direction_deg,
+        };
+        self.gust_mps = 0.0;
+        Some(report)
+    }
+
+    fn sentence(&mut self, line: &[u8]) {
+        let Some(body) = verify(line) else {
+            self.rejected += 1;
+            return;
+        };
+        let mut fields = body.split(',');
+        let Some(address) = fields.next().filter(|address| address.len() == 5 && address.is_ascii()) else {
+            self.rejected += 1;
+            return;
+        };
+        let parsed = match &address[2..] {
+            "MDA" => self.mda(fields),
+            "MWV" => self.mwv(fields),
+            "XDR" => self.xdr(fields),
+            _ => Some(()),
+        };
+        if parsed.is_none() {
+            self.rejected += 1;
+        }
+    }
+
+    /// Meteorological composite: pressure, air temperature, humidity and true wind
+    fn mda<'a>(&mut self, fields: impl Iterator<Item = &'a str>) -> Option<()> {
+        let fields: Vec<&str, 20> = fields.take(20).collect();
+        let field = |index: usize| fields.get(index).copied().filter(|field| !field.is_empty());
+        if let Some(bars) = field(2).and_then(|value| value.parse::<f32>().ok()) {
+            self.pressure_hpa = Some(bars * 1_000.0);
+        }
+        if let Some(celsius) = field(4).and_then(|value| value.parse().ok()) {
+            self.temperature_c = Some(celsius);
+        }
+        if let Some(humidity) = field(8).and_then(|value| value.parse::<f32>().ok()) {
+            self.humidity_pct = Some(humidity.clamp(0.0, 100.0) as u8);
+        }
+        let direction = field(12).and_then(|value| value.parse::<f32>().ok());
+        let speed = field(18).and_then(|value| value.parse::<f32>().ok());
+        if let (Some(direction), Some(speed)) = (direction, speed) {
+            self.wind(speed, direction);
+        }
+        Some(())
+    }
+
+    /// Wind speed and angle; relative wind needs a heading, which a fixed station doesn't report
+    fn mwv<'a>(&mut self, mut fields: impl Iterator<Item = &'a str>) -> Option<()> {
+        let angle: f32 = fields.next()?.parse().ok()?;
+        let reference = fields.next()?;
+        let speed: f32 = fields.next()?.parse().ok()?;
+        let scale = match fields.next()? {
+            "M" => 1.0,
+            "N" => KNOTS_TO_MPS,
+            "K" => KMH_TO_MPS,
+            "S" => MPH_TO_MPS,
+            _ => return None,
+        };
+        if reference == "T" && fields.next()? == "A" {
+            self.wind(speed * scale, angle);
+        }
+        Some(())
+    }
+
+    /// Transducer readings as (type, value, unit, name) quadruples
+    fn xdr<'a>(&mut self, mut fields: impl Iterator<Item = &'a str>) -> Option<()> {
+        while let Some(kind) = fields.next() {
+            let value: Option<f32> = fields.next()?.parse().ok();
+            let unit = fields.next()?;
+            let _name = fields.next();
+            match (kind, unit, value) {
+                ("C", "C", Some(celsius)) => self.temperature_c = Some(celsius),
+                ("P", "B", Some(bars)) => self.pressure_hpa = Some(bars * 1_000.0),
+                ("P", "P", Some(pascals)) => self.pressure_hpa = Some(pascals / 100.0),
+                ("H", "P", Some(humidity)) => self.humidity_pct = Some(humidity.clamp(0.0, 100.0) as u8),
+                _ => {}
+            }
+        }
+        Some(())
+    }
+
+    fn wind(&mut self, speed_mps: f32, from_deg: f32) {
+        let direction = ((from_deg + 0.5) as i32).rem_euclid(360) as u16;
+        self.wind = Some((speed_mps, direction));
+        self.gust_mps = self.gust_mps.max(speed_mps);
+    }
+}
+
+/// The sentence between `$` and `*` if its checksum matches
+fn verify(line: &[u8]) -> Option<&str> {
+    let line = core::str::from_utf8(line).ok()?.strip_prefix('$')?;
+    let (body, checksum) = line.split_once('*')?;
+    let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+    (body.bytes().fold(0, |sum, byte| sum ^ byte) == expected).then_some(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::gse::{WindLayer, WindsAloft};
+
+    #[test]
+    fn test_mda_and_mwv_sentences() {
+        let mut station = WeatherStation::new(9);
+        station.feed(b"$WIMWV,045.0,T,6.5,M,A*24\r\n");
+        assert_eq!(station.report(), None);
+        station.feed(b"$WIMDA,30.0112,I,1.0163,B,22.5,C,,,45.0,,8.2,C,270.0,T,268.5,M,9.7,N,5.0,M*54\r\n$WIMW");
+        station.feed(b"V,050.0,R,20.0,K,A*11\r\n$WIMWV,100.0,T,9.0,M,V*39\r\n$WIMWV,045.0,T,6.5,M,A*25\r\n");
+        let report = station.report().unwrap();
+        assert_eq!((report.pressure_hpa, report.temperature_c, report.humidity_pct), (1_016.3, 22.5, 45));
+        // Relative and invalid wind is ignored, the gust is the strongest true wind since the last report
+        assert_eq!((report.wind_speed_mps, report.wind_direction_deg, report.wind_gust_mps), (5.0, 270, 6.5));
+        assert_eq!(station.rejected(), 1);
+        assert_eq!(station.report().unwrap().wind_gust_mps, 5.0);
+        // A valid checksum over a talker ID that isn't ASCII
+        station.feed("$AéMD,1*3F\r\n".as_bytes());
+        assert_eq!(station.rejected(), 2);
+
+        // A westerly pushes the vehicle east, strengthening and veering to a northerly aloft
+        let mut winds = WindsAloft { uid: 9, layers: Vec::new() };
+        winds.layers.push(WindLayer { altitude_m: 0, speed_mps: 5.0, direction_deg: 270 }).unwrap();
+        winds.layers.push(WindLayer { altitude_m: 1_000, speed_mps: 10.0, direction_deg: 0 }).unwrap();
+        let (east, north) = winds.wind_at(500.0).unwrap();
+        assert!((east - 2.5).abs() < 1e-4 && (north + 5.0).abs() < 1e-4);
+        let (east, north) = winds.wind_at(3_000.0).unwrap();
+        assert!(east.abs() < 1e-4 && (north + 10.0).abs() < 1e-4);
+    }
+}
@@ -1,6 +1,74 @@
-#![no_std]
-#![no_main]
-// #![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![allow(non_snake_case)]
 
+//! The crate is layered so embedded users only build what they need:
+//!
+//! - `protocol`, `clock`, `geo`, `persistence`, `params` and `rng` are always available, `no_std` with no IO
+//! - `mesh` adds the node-side layers and runtime, and implies `radio`
+//! - `radio` adds LoRa airtime, duty cycle and region plans
+//! - `ground` adds the ground-station runtime and layers, and implies `mesh`
+//! - `sx127x` adds the SX127x LoRa driver, and `async` the async radio and KISS port traits; both imply `radio`
+//! - `crypto` adds sealing of custom command payloads against spoofing and replay, and HMAC trailers on telemetry
+//! - `fec` adds Reed-Solomon forward error correction around frames, for links with little margin
+//! - `std` enables desktop-only pieces within the enabled layers
+//!
+//! Downstream code should import from [`prelude`], which is the semver-stable surface; modules marked
+//! `#[doc(hidden)]` are internal and may change in any release.
+
+pub mod clock;
+#[doc(hidden)]
+pub mod geo;
+pub mod params;
+pub mod persistence;
+pub mod prelude;
 pub mod protocol;
+#[doc(hidden)]
+pub mod rng;
+
+#[cfg(feature = "crypto")]
+pub mod crypto;
+
+#[cfg(feature = "mesh")]
+pub mod arbitration;
+#[cfg(feature = "mesh")]
+pub mod bootloader;
+#[cfg(feature = "mesh")]
+pub mod checklist;
+#[cfg(feature = "mesh")]
+pub mod command;
+#[cfg(feature = "mesh")]
+pub mod countdown;
+#[cfg(feature = "mesh")]
+pub mod flight;
+#[cfg(feature = "mesh")]
+pub mod gse;
+#[cfg(feature = "mesh")]
+pub mod mesh;
+#[cfg(feature = "mesh")]
+pub mod node;
+#[cfg(feature = "mesh")]
+pub mod ping;
+#[cfg(feature = "mesh")]
+pub mod rangetest;
+#[cfg(feature = "mesh")]
+pub mod recovery;
+#[cfg(feature = "mesh")]
+pub mod selftest;
+
+#[cfg(feature = "radio")]
+pub mod radio;
+
+#[cfg(feature = "ground")]
+pub mod alerts;
+#[cfg(feature = "ground")]
+pub mod bridge;
+#[cfg(feature = "ground")]
+pub mod ground;
+#[cfg(all(feature = "ground", feature = "std"))]
+pub mod report;
+#[cfg(feature = "ground")]
+pub mod stats;
+#[cfg(feature = "ground")]
+pub mod tracker;
+#[cfg(feature = "ground")]
+pub mod trajectory;
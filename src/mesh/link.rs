@@ -0,0 +1,11 @@
+//! The transport the mesh layer runs over
+//!
+//! `RadioLink` is `Radio` under the name the routing and acknowledgement code knows it by: `send` and
+//! `recv` are `transmit` and `receive`, whose `LinkQuality` carries the RSSI and SNR of each frame, and
+//! `max_payload` bounds the MTU. The SX127x driver, the UDP and simulated transports, `MockRadio` and
+//! wrappers such as `FecRadio` all implement it, so `MeshNode` runs unchanged over any of them. With the
+//! `async` feature `AsyncRadioLink` is its async form.
+
+#[cfg(feature = "async")]
+pub use crate::radio::asynch::AsyncRadio as AsyncRadioLink;
+pub use crate::radio::Radio as RadioLink;
@@ -0,0 +1,5 @@
+//! Names the mesh layer uses for what it is built on
+//!
+//! The layers themselves live in `node`; this module only gathers the interfaces they run over.
+
+pub mod link;
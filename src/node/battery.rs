@@ -0,0 +1,151 @@
+//! Stepping telemetry down as the battery runs flat, so a landed vehicle keeps beaconing for as long as possible
+
+use super::scheduler::Priority;
+use crate::protocol::health::DegradationLevel;
+use crate::protocol::packet::Packet;
+
+const LEVELS: [DegradationLevel; 3] = [DegradationLevel::Reduced, DegradationLevel::Minimal, DegradationLevel::Beacon];
+
+/// What a node gives up at one degradation level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelPolicy {
+    /// The level applies once the battery falls below this voltage
+    pub below_v: f32,
+    /// Periodic telemetry intervals are multiplied by this
+    pub interval_factor: u16,
+    pub tx_power_dbm: i8,
+    /// Packets in a less urgent class are not sent
+    pub lowest_priority: Priority,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DegradationConfig {
+    /// Reduced, Minimal and Beacon, at descending voltages
+    pub levels: [LevelPolicy; 3],
+    /// TX power at Normal
+    pub max_power_dbm: i8,
+    /// Voltage must recover this far above a level's threshold before the level is left, as the battery
+    /// sags under transmit load and recovers at rest
+    pub hysteresis_v: f32,
+}
+
+impl Default for DegradationConfig {
+    fn default() -> Self {
+        // 2S LiPo, 6.0 V is empty
+        let level = |below_v, interval_factor, tx_power_dbm, lowest_priority| LevelPolicy {
+            below_v,
+            interval_factor,
+            tx_power_dbm,
+            lowest_priority,
+        };
+        Self {
+            levels: [
+                level(7.2, 2, 17, Priority::Normal),
+                level(6.9, 5, 14, Priority::High),
+                level(6.6, 20, 14, Priority::High),
+            ],
+            max_power_dbm: 20,
+            hysteresis_v: 0.15,
+        }
+    }
+}
+
+/// BatteryPolicy picks the DegradationLevel for each battery reading and what it allows
+///
+/// Feed it the voltage each time Health is built and report `level` in it. The node applies the level:
+/// it stretches its periodic intervals, such as `BeaconConfig::interval_ms`, with `interval_ms`, checks
+/// `allows` before sending, and sets the radio to `tx_power_dbm`, or to the lower of that and
+/// `ThermalMonitor::tx_power_dbm` where the amplifier is derated for heat too.
+#[derive(Debug, Clone, Copy)]
+pub struct BatteryPolicy {
+    config: DegradationConfig,
+    level: DegradationLevel,
+}
+
+impl BatteryPolicy {
+    pub fn new(config: DegradationConfig) -> Self {
+        Self { config, level: DegradationLevel::Normal }
+    }
+
+    pub fn level(&self) -> DegradationLevel {
+        self.level
+    }
+
+    /// Applies a battery reading, returning the new level when it changed
+    ///
+    /// Levels are entered as soon as the voltage drops below them and left only once it has recovered past the
+    /// hysteresis. Readings that aren't finite are ignored.
+    pub fn update(&mut self, battery_voltage: f32) -> Option<DegradationLevel> {
+        if !battery_voltage.is_finite() {
+            return None;
+        }
+        let depth = |margin: f32| {
+            self.config.levels.iter().rposition(|level| battery_voltage < level.below_v + margin).map_or(0, |i| i + 1)
+        };
+        let falling = depth(0.0);
+        let current = self.level as usize;
+        let target = if falling >= current { falling } else { depth(self.config.hysteresis_v).min(current) };
+        let level = target.checked_sub(1).map_or(DegradationLevel::Normal, |i| LEVELS[i]);
+        if level == self.level {
+            return None;
+        }
+        self.level = level;
+        #[cfg(feature = "tracing")]
+        tracing::warn!(battery_voltage, level = ?level, "battery degradation level changed");
+        Some(level)
+    }
+
+    fn policy(&self) -> Option<&LevelPolicy> {
+        (self.level as usize).checked_sub(1).map(|i| &self.config.levels[i])
+    }
+
+    pub fn tx_power_dbm(&self) -> i8 {
+        self.policy().map_or(self.config.max_power_dbm, |policy| policy.tx_power_dbm)
+    }
+
+    /// A periodic interval of `base_ms` stretched for the current level
+    pub fn interval_ms(&self, base_ms: u64) -> u64 {
+        base_ms.saturating_mul(self.policy().map_or(1, |policy| policy.interval_factor.max(1) as u64))
+    }
+
+    /// Whether `packet` is still sent at the current level
+    ///
+    /// Health always is, so the ground station keeps seeing the battery and the level.
+    pub fn allows(&self, packet: &Packet) -> bool {
+        let Some(policy) = self.policy() else {
+            return true;
+        };
+        matches!(packet, Packet::Health(_)) || Priority::of(packet) <= policy.lowest_priority
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::health::Health;
+    use crate::protocol::hello::Hello;
+
+    #[test]
+    fn test_steps_down_and_recovers_with_hysteresis() {
+        let mut policy = BatteryPolicy::new(DegradationConfig::default());
+        let hello = Packet::Hello(Hello { count: 1, interval_ms: 1_000 });
+        assert_eq!(policy.update(8.1), None);
+        assert_eq!((policy.tx_power_dbm(), policy.interval_ms(1_000)), (20, 1_000));
+
+        // A deep sag skips straight past Reduced
+        assert_eq!(policy.update(6.8), Some(DegradationLevel::Minimal));
+        assert_eq!((policy.tx_power_dbm(), policy.interval_ms(1_000)), (14, 5_000));
+        assert!(!policy.allows(&hello));
+        assert!(policy.allows(&Packet::Health(Health::default())));
+        assert!(policy.allows(&Packet::Event(FlightEvent::Landed)));
+
+        // Recovering inside the hysteresis band keeps the level, past it the level follows the voltage
+        assert_eq!(policy.update(6.95), None);
+        assert_eq!(policy.update(7.1), Some(DegradationLevel::Reduced));
+        assert_eq!(policy.update(f32::NAN), None);
+        assert_eq!(policy.update(6.5), Some(DegradationLevel::Beacon));
+        assert_eq!(policy.interval_ms(15_000), 300_000);
+        assert_eq!(policy.update(8.4), Some(DegradationLevel::Normal));
+    }
+}
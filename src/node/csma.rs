@@ -0,0 +1,117 @@
+//! Listen-before-talk with random backoff, for nodes sharing a frequency without a common time base
+//!
+//! Before each frame the node asks the radio whether the channel is busy. A busy channel defers the frame
+//! by a random wait from a window that doubles on every busy check in a row, as in 802.11 CSMA/CA; after
+//! `max_attempts` busy checks the frame is dropped and left to the reliable layer to retransmit.
+
+use crate::rng::RandomSource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsmaConfig {
+    /// Busy checks in a row before a frame is dropped
+    pub max_attempts: u8,
+    /// Backoff window after the first busy check
+    pub initial_window_ms: u32,
+    /// Largest backoff window, however many checks were busy
+    pub max_window_ms: u32,
+}
+
+impl Default for CsmaConfig {
+    fn default() -> Self {
+        Self { max_attempts: 6, initial_window_ms: 50, max_window_ms: 800 }
+    }
+}
+
+/// What the transmit path does with the frame it listened for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Transmit,
+    /// Keep the frame queued until the backoff ends
+    Defer,
+    /// Drop the frame, the channel stayed busy
+    Abort,
+}
+
+/// Counts of listen-before-talk outcomes since boot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CsmaStats {
+    /// Frames sent after a clear channel check, or without one on radios that cannot listen
+    pub transmitted: u32,
+    /// Busy checks that deferred a frame
+    pub deferred: u32,
+    /// Frames dropped after `max_attempts` busy checks
+    pub aborted: u32,
+}
+
+/// Csma is the backoff state consulted before every transmission
+#[derive(Debug, Clone)]
+pub struct Csma {
+    config: CsmaConfig,
+    /// Busy checks in a row for the frame at the head of the queue
+    attempts: u8,
+    backoff_until_ms: u64,
+    stats: CsmaStats,
+}
+
+impl Csma {
+    pub fn new(config: CsmaConfig) -> Self {
+        Self { config, attempts: 0, backoff_until_ms: 0, stats: CsmaStats::default() }
+    }
+
+    pub fn stats(&self) -> CsmaStats {
+        self.stats
+    }
+
+    /// Whether a backoff is still running, in which case the channel is not checked
+    pub fn backing_off(&self, now_ms: u64) -> bool {
+        now_ms < self.backoff_until_ms
+    }
+
+    /// Decides on a frame given the radio's channel check, `busy` being `None` if the radio cannot tell
+    pub fn on_listen(&mut self, busy: Option<bool>, now_ms: u64, rng: &mut impl RandomSource) -> Decision {
+        if busy != Some(true) {
+            self.attempts = 0;
+            self.stats.transmitted += 1;
+            return Decision::Transmit;
+        }
+        self.attempts += 1;
+        if self.attempts >= self.config.max_attempts.max(1) {
+            self.attempts = 0;
+            self.stats.aborted += 1;
+            return Decision::Abort;
+        }
+        let doublings = (self.attempts - 1).min(31) as u32;
+        let window = self.config.initial_window_ms.saturating_mul(1 << doublings).min(self.config.max_window_ms);
+        self.backoff_until_ms = now_ms + 1 + rng.below(window) as u64;
+        self.stats.deferred += 1;
+        Decision::Defer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::NodeRng;
+
+    #[test]
+    fn test_window_doubles_then_aborts() {
+        let config = CsmaConfig { max_attempts: 4, initial_window_ms: 100, max_window_ms: 300 };
+        let mut csma = Csma::new(config);
+        let mut rng = NodeRng::seeded(1, 0);
+        assert_eq!(csma.on_listen(None, 0, &mut rng), Decision::Transmit);
+
+        let mut now = 0;
+        for window in [100, 200, 300] {
+            assert_eq!(csma.on_listen(Some(true), now, &mut rng), Decision::Defer);
+            assert!(csma.backing_off(now));
+            assert!(!csma.backing_off(now + window));
+            now += window;
+        }
+        assert_eq!(csma.on_listen(Some(true), now, &mut rng), Decision::Abort);
+        // The next frame starts over with the smallest window
+        assert_eq!(csma.on_listen(Some(true), now, &mut rng), Decision::Defer);
+        assert!(!csma.backing_off(now + 100));
+        assert_eq!(csma.on_listen(Some(false), now + 100, &mut rng), Decision::Transmit);
+        assert_eq!(csma.stats(), CsmaStats { transmitted: 2, deferred: 4, aborted: 1 });
+    }
+}
@@ -0,0 +1,50 @@
+use heapless::Deque;
+
+/// Number of recently seen frames remembered for duplicate suppression
+pub const DEDUP_LEN: usize = 32;
+
+/// DedupCache remembers recently seen `(source, sequence)` pairs so flooded frames are handled once
+#[derive(Debug, Clone)]
+pub struct DedupCache {
+    window_ms: u64,
+    seen: Deque<(u8, u16, u64), DEDUP_LEN>,
+}
+
+impl DedupCache {
+    /// Entries older than `window_ms` are forgotten
+    pub fn new(window_ms: u64) -> Self {
+        Self { window_ms, seen: Deque::new() }
+    }
+
+    /// Returns true if the frame was already seen, otherwise records it
+    pub fn check(&mut self, source_uid: u8, sequence: u16, now_ms: u64) -> bool {
+        while let Some(&(_, _, at)) = self.seen.front() {
+            if now_ms.saturating_sub(at) <= self.window_ms {
+                break;
+            }
+            self.seen.pop_front();
+        }
+        if self.seen.iter().any(|&(uid, seq, _)| uid == source_uid && seq == sequence) {
+            return true;
+        }
+        if self.seen.is_full() {
+            self.seen.pop_front();
+        }
+        let _ = self.seen.push_back((source_uid, sequence, now_ms));
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_within_window() {
+        let mut cache = DedupCache::new(1_000);
+        assert!(!cache.check(1, 10, 0));
+        assert!(cache.check(1, 10, 500));
+        assert!(!cache.check(2, 10, 500));
+        assert!(!cache.check(1, 10, 1_501));
+    }
+}
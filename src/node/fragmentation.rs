@@ -0,0 +1,275 @@
+//! Splitting messages larger than the path MTU into Fragments and putting them back together
+
+use heapless::Vec;
+
+use crate::protocol::checksum;
+use crate::protocol::fragment::{Fragment, MAX_FRAGMENT_DATA};
+use crate::protocol::Error;
+
+/// Largest message that can be fragmented and reassembled
+pub const MAX_MESSAGE_LEN: usize = 1536;
+/// Most fragments one message may be split into
+pub const MAX_FRAGMENTS: usize = 64;
+/// Messages reassembled concurrently
+pub const REASSEMBLY_SLOTS: usize = 4;
+/// Worst-case frame bytes besides fragment data: checksum, source-routed mesh header, packet tag and fragment header
+pub const FRAGMENT_OVERHEAD: usize = checksum::OVERHEAD + 16 + 1 + 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FragmentError {
+    /// The message is longer than `MAX_MESSAGE_LEN` or needs more than `MAX_FRAGMENTS` fragments at this MTU
+    TooLarge,
+    /// The MTU leaves no room for fragment data
+    MtuTooSmall,
+}
+
+/// Fragment data that fits a frame of `mtu` bytes
+pub fn data_per_fragment(mtu: u16) -> usize {
+    (mtu as usize).saturating_sub(FRAGMENT_OVERHEAD).min(MAX_FRAGMENT_DATA)
+}
+
+/// Splits `message` into fragments sized for `mtu`, typically `MtuTable::path_mtu`
+pub fn split(message_id: u16, message: &[u8], mtu: u16) -> Result<Fragments<'_>, FragmentError> {
+    let chunk = data_per_fragment(mtu);
+    if chunk == 0 {
+        return Err(FragmentError::MtuTooSmall);
+    }
+    let count = message.len().div_ceil(chunk).max(1);
+    if message.len() > MAX_MESSAGE_LEN || count > MAX_FRAGMENTS {
+        return Err(FragmentError::TooLarge);
+    }
+    Ok(Fragments { message_id, message, chunk, count: count as u8, index: 0 })
+}
+
+/// Iterator over the fragments of one message, in order
+#[derive(Debug, Clone)]
+pub struct Fragments<'a> {
+    message_id: u16,
+    message: &'a [u8],
+    chunk: usize,
+    count: u8,
+    index: u8,
+}
+
+impl Fragments<'_> {
+    pub fn count(&self) -> u8 {
+        self.count
+    }
+}
+
+impl Iterator for Fragments<'_> {
+    type Item = Fragment;
+
+    fn next(&mut self) -> Option<Fragment> {
+        if self.index >= self.count {
+            return None;
+        }
+        let start = self.index as usize * self.chunk;
+        let end = (start + self.chunk).min(self.message.len());
+        let fragment = Fragment {
+            message_id: self.message_id,
+            index: self.index,
+            count: self.count,
+            total_len: self.message.len() as u16,
+            data: Vec::from_slice(&self.message[start..end]).expect("chunk is at most MAX_FRAGMENT_DATA"),
+        };
+        self.index += 1;
+        Some(fragment)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    source_uid: u8,
+    message_id: u16,
+    count: u8,
+    received: u64,
+    started_ms: u64,
+    message: Vec<u8, MAX_MESSAGE_LEN>,
+}
+
+/// Reassembler collects fragments per `(source, message_id)` and yields each message once complete
+///
+/// Incomplete messages are dropped `timeout_ms` after their first fragment arrived, or when a slot is
+/// needed and they are the oldest.
+#[derive(Debug, Clone)]
+pub struct Reassembler {
+    timeout_ms: u64,
+    slots: Vec<Slot, REASSEMBLY_SLOTS>,
+}
+
+impl Reassembler {
+    pub fn new(timeout_ms: u64) -> Self {
+        Self { timeout_ms, slots: Vec::new() }
+    }
+
+    /// Number of messages partially received
+    pub fn pending(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Adds a fragment from `source_uid`, returning the message once every fragment has arrived
+    ///
+    /// A fragment inconsistent with its header is `Malformed` and ignored. `FragmentTimeout` reports that the
+    /// earlier fragments of its message expired; the fragment itself starts the message over.
+    pub fn on_fragment(
+        &mut self,
+        source_uid: u8,
+        fragment: &Fragment,
+        now_ms: u64,
+    ) -> Result<Option<Vec<u8, MAX_MESSAGE_LEN>>, Error> {
+        let index = self.accept(source_uid, fragment, now_ms)?;
+        Ok(index.map(|index| self.slots.swap_remove(index).message))
+    }
+
+    /// Like `on_fragment`, but copies the completed message into `out` and returns its length
+    ///
+    /// Saves returning the message by value on targets with little stack. A completed message longer than
+    /// `out` is dropped with `BufferTooSmall`.
+    pub fn on_fragment_into(
+        &mut self,
+        source_uid: u8,
+        fragment: &Fragment,
+        now_ms: u64,
+        out: &mut [u8],
+    ) -> Result<Option<usize>, Error> {
+        let Some(index) = self.accept(source_uid, fragment, now_ms)? else {
+            return Ok(None);
+        };
+        let message = &self.slots[index].message;
+        let copied = out.get_mut(..message.len()).map(|out| out.copy_from_slice(message)).map(|_| message.len());
+        self.slots.swap_remove(index);
+        copied.map(Some).ok_or(Error::BufferTooSmall)
+    }
+
+    /// Stores a fragment, returning the index of its slot once the message is complete
+    fn accept(&mut self, source_uid: u8, fragment: &Fragment, now_ms: u64) -> Result<Option<usize>, Error> {
+        let key = (source_uid, fragment.message_id);
+        let expired = self.slots.iter().any(|slot| {
+            (slot.source_uid, slot.message_id) == key && now_ms.saturating_sub(slot.started_ms) > self.timeout_ms
+        });
+        self.evict(now_ms);
+        let total_len = fragment.total_len as usize;
+        if fragment.index >= fragment.count || fragment.count as usize > MAX_FRAGMENTS || total_len > MAX_MESSAGE_LEN {
+            return Err(Error::Malformed);
+        }
+        let last = fragment.index + 1 == fragment.count;
+        let offset = if last {
+            total_len.checked_sub(fragment.data.len()).ok_or(Error::Malformed)?
+        } else {
+            fragment.index as usize * fragment.data.len()
+        };
+        if offset + fragment.data.len() > total_len {
+            return Err(Error::Malformed);
+        }
+
+        let position = self.slots.iter().position(|slot| (slot.source_uid, slot.message_id) == key);
+        let matches = |slot: &Slot| slot.count == fragment.count && slot.message.len() == total_len;
+        let index = match position {
+            Some(index) if matches(&self.slots[index]) => index,
+            // A header that disagrees with earlier fragments means the ID was reused, start over
+            Some(index) => {
+                self.slots.swap_remove(index);
+                self.open(source_uid, fragment, now_ms)
+            }
+            None => self.open(source_uid, fragment, now_ms),
+        };
+        let slot = &mut self.slots[index];
+        slot.message[offset..offset + fragment.data.len()].copy_from_slice(&fragment.data);
+        slot.received |= 1 << fragment.index;
+        let complete = slot.received.count_ones() == slot.count as u32;
+        if expired && !complete {
+            return Err(Error::FragmentTimeout { message_id: fragment.message_id });
+        }
+        Ok(complete.then_some(index))
+    }
+
+    /// Drops messages whose first fragment is older than the timeout
+    pub fn evict(&mut self, now_ms: u64) {
+        self.slots.retain(|slot| now_ms.saturating_sub(slot.started_ms) <= self.timeout_ms);
+    }
+
+    fn open(&mut self, source_uid: u8, fragment: &Fragment, now_ms: u64) -> usize {
+        if self.slots.is_full() {
+            let oldest = (0..self.slots.len()).min_by_key(|i| self.slots[*i].started_ms).expect("slots are full");
+            self.slots.swap_remove(oldest);
+        }
+        let mut message = Vec::new();
+        message.resize(fragment.total_len as usize, 0).expect("total_len was checked against MAX_MESSAGE_LEN");
+        let slot = Slot {
+            source_uid,
+            message_id: fragment.message_id,
+            count: fragment.count,
+            received: 0,
+            started_ms: now_ms,
+            message,
+        };
+        let _ = self.slots.push(slot);
+        self.slots.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+    use crate::protocol::mesh::{MeshFrame, MeshHeader, MAX_FRAME_LEN};
+    use crate::protocol::packet::Packet;
+
+    #[test]
+    fn test_split_and_reassemble_out_of_order() {
+        let message: StdVec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let fragments: StdVec<Fragment> = split(u16::MAX, &message, 64).unwrap().collect();
+        assert_eq!(fragments.len(), 1000usize.div_ceil(data_per_fragment(64)));
+        let header = MeshHeader {
+            source_uid: 0xFF,
+            destination_uid: 0xFF,
+            sequence: u16::MAX,
+            hops_left: 0xFF,
+            ack_requested: true,
+            rebooted: true,
+            backup: true,
+            route: None,
+        };
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let frame = MeshFrame { header, packet: Packet::Fragment(fragments[0].clone()) };
+        assert!(postcard::to_slice(&frame, &mut buf).unwrap().len() + checksum::OVERHEAD <= 64);
+        assert!(matches!(split(7, &message, 20), Err(FragmentError::MtuTooSmall)));
+        assert!(matches!(split(7, &[0; MAX_MESSAGE_LEN + 1], 255), Err(FragmentError::TooLarge)));
+
+        let mut reassembler = Reassembler::new(5_000);
+        let (last, rest) = fragments.split_last().unwrap();
+        assert_eq!(reassembler.on_fragment(3, last, 0), Ok(None));
+        for fragment in rest[1..].iter().rev() {
+            assert_eq!(reassembler.on_fragment(3, fragment, 100), Ok(None));
+        }
+        // Same message ID from another node is a different message
+        assert_eq!(reassembler.on_fragment(4, &rest[0], 100), Ok(None));
+        assert_eq!(reassembler.pending(), 2);
+        assert_eq!(reassembler.on_fragment(3, &rest[0], 200).unwrap().unwrap(), message[..]);
+        assert_eq!(reassembler.pending(), 1);
+        let mut bad = rest[1].clone();
+        bad.index = bad.count;
+        assert_eq!(reassembler.on_fragment(3, &bad, 200), Err(Error::Malformed));
+        bad.index = u8::MAX;
+        assert_eq!(reassembler.on_fragment(3, &bad, 200), Err(Error::Malformed));
+
+        // The other node's message never completes, its next fragment finds the first one expired
+        let timeout = Err(Error::FragmentTimeout { message_id: u16::MAX });
+        assert_eq!(reassembler.on_fragment(4, &rest[1], 5_101), timeout);
+        assert_eq!(reassembler.pending(), 1);
+        reassembler.evict(10_102);
+        assert_eq!(reassembler.pending(), 0);
+
+        let mut out = [0u8; MAX_MESSAGE_LEN];
+        for fragment in rest {
+            assert_eq!(reassembler.on_fragment_into(5, fragment, 11_000, &mut out), Ok(None));
+        }
+        assert_eq!(reassembler.on_fragment_into(5, last, 11_000, &mut out), Ok(Some(message.len())));
+        assert_eq!(out[..message.len()], message[..]);
+        let short = fragments.iter().map(|fragment| reassembler.on_fragment_into(6, fragment, 11_000, &mut out[..16]));
+        assert_eq!(short.last(), Some(Err(Error::BufferTooSmall)));
+        assert_eq!(reassembler.pending(), 0);
+    }
+}
@@ -0,0 +1,162 @@
+use heapless::Vec;
+
+use crate::protocol::packet::{Packet, PacketKind};
+
+/// Maximum number of handlers that can be registered on a node
+pub const MAX_HANDLERS: usize = 8;
+
+/// When a handler runs relative to the mesh router
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Before the router forwards or delivers the packet, the handler may consume it
+    BeforeRouting,
+    /// After routing, for packets delivered to this node
+    AfterRouting,
+}
+
+/// What should happen to a packet after a handler has seen it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposition {
+    Continue,
+    /// Stop further handling and routing of this packet (only honored before routing)
+    Consume,
+}
+
+/// Metadata passed to handlers alongside the packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketContext {
+    pub source_uid: u8,
+    pub stage: Stage,
+    pub now_ms: u64,
+}
+
+/// PacketHandler lets payload teams hook custom behavior into a node without forking the mesh core
+///
+/// For example, triggering a camera when an Apogee event passes through.
+pub trait PacketHandler {
+    fn handle(&mut self, ctx: &PacketContext, packet: &Packet) -> Disposition;
+}
+
+/// Which packets a registered handler is called for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketFilter {
+    Any,
+    Kind(PacketKind),
+}
+
+impl PacketFilter {
+    pub fn matches(&self, kind: PacketKind) -> bool {
+        match self {
+            PacketFilter::Any => true,
+            PacketFilter::Kind(k) => *k == kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistryFull;
+
+struct Registration<'a> {
+    filter: PacketFilter,
+    stage: Stage,
+    handler: &'a mut dyn PacketHandler,
+}
+
+/// HandlerRegistry holds the handlers registered on a node, called in registration order
+#[derive(Default)]
+pub struct HandlerRegistry<'a> {
+    handlers: Vec<Registration<'a>, MAX_HANDLERS>,
+}
+
+impl<'a> HandlerRegistry<'a> {
+    pub fn new() -> Self {
+        Self { handlers: Vec::new() }
+    }
+
+    pub fn register(
+        &mut self,
+        filter: PacketFilter,
+        stage: Stage,
+        handler: &'a mut dyn PacketHandler,
+    ) -> Result<(), RegistryFull> {
+        self.handlers.push(Registration { filter, stage, handler }).map_err(|_| RegistryFull)
+    }
+
+    /// Calls every matching handler for the given stage, stopping early if one consumes the packet
+    pub fn dispatch(&mut self, ctx: &PacketContext, packet: &Packet) -> Disposition {
+        let kind = packet.kind();
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("route", ?kind, source_uid = ctx.source_uid, stage = ?ctx.stage, now_ms = ctx.now_ms)
+                .entered();
+        for registration in self.handlers.iter_mut() {
+            if registration.stage != ctx.stage || !registration.filter.matches(kind) {
+                continue;
+            }
+            let disposition = registration.handler.handle(ctx, packet);
+            if disposition == Disposition::Consume && ctx.stage == Stage::BeforeRouting {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("consumed by handler");
+                return Disposition::Consume;
+            }
+        }
+        Disposition::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::health::Health;
+
+    #[derive(Default)]
+    struct CameraTrigger {
+        fired: u32,
+    }
+
+    impl PacketHandler for CameraTrigger {
+        fn handle(&mut self, _ctx: &PacketContext, packet: &Packet) -> Disposition {
+            if let Packet::Event(FlightEvent::Apogee { .. }) = packet {
+                self.fired += 1;
+            }
+            Disposition::Continue
+        }
+    }
+
+    struct DropAll;
+
+    impl PacketHandler for DropAll {
+        fn handle(&mut self, _ctx: &PacketContext, _packet: &Packet) -> Disposition {
+            Disposition::Consume
+        }
+    }
+
+    fn ctx(stage: Stage) -> PacketContext {
+        PacketContext { source_uid: 2, stage, now_ms: 0 }
+    }
+
+    #[test]
+    fn test_dispatch_by_kind_and_stage() {
+        let mut camera = CameraTrigger::default();
+        let mut registry = HandlerRegistry::new();
+        registry.register(PacketFilter::Kind(PacketKind::Event), Stage::AfterRouting, &mut camera).unwrap();
+
+        let apogee = Packet::Event(FlightEvent::Apogee { altitude_m: 3_000.0 });
+        registry.dispatch(&ctx(Stage::BeforeRouting), &apogee);
+        registry.dispatch(&ctx(Stage::AfterRouting), &apogee);
+        registry.dispatch(&ctx(Stage::AfterRouting), &Packet::Health(Health::default()));
+        drop(registry);
+        assert_eq!(camera.fired, 1);
+    }
+
+    #[test]
+    fn test_consume_only_before_routing() {
+        let mut drop_all = DropAll;
+        let mut registry = HandlerRegistry::new();
+        registry.register(PacketFilter::Any, Stage::BeforeRouting, &mut drop_all).unwrap();
+        let packet = Packet::Health(Health::default());
+        assert_eq!(registry.dispatch(&ctx(Stage::BeforeRouting), &packet), Disposition::Consume);
+        assert_eq!(registry.dispatch(&ctx(Stage::AfterRouting), &packet), Disposition::Continue);
+    }
+}
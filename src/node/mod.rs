@@ -0,0 +1,22 @@
+pub mod battery;
+pub mod csma;
+#[doc(hidden)]
+pub mod dedup;
+pub mod fragmentation;
+pub mod handlers;
+pub mod mtu;
+pub mod neighbors;
+#[doc(hidden)]
+pub mod reliable;
+#[doc(hidden)]
+pub mod router;
+pub mod runtime;
+#[doc(hidden)]
+pub mod scheduler;
+#[doc(hidden)]
+pub mod store_forward;
+pub mod tdma;
+
+pub use reliable::ReliableConfig;
+pub use runtime::{MeshNode, NodeConfig, NodeError, NodeEvent};
+pub use scheduler::Priority;
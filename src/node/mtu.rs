@@ -0,0 +1,75 @@
+use heapless::FnvIndexMap;
+
+use crate::protocol::mesh::MAX_FRAME_LEN;
+use crate::protocol::node_info::NodeInfo;
+
+/// Number of nodes whose MTU is remembered
+pub const MAX_MTU_ENTRIES: usize = 16;
+/// No radio on the mesh is expected to carry less than this
+pub const MIN_MTU: u16 = 32;
+
+/// MtuTable learns each node's frame size from its NodeInfo announcement
+///
+/// A flooded frame may be relayed by any node, so the path MTU for flooding is the smallest MTU known
+/// anywhere on the mesh; an explicit route only needs to fit the nodes on it.
+#[derive(Debug, Clone)]
+pub struct MtuTable {
+    local: u16,
+    nodes: FnvIndexMap<u8, u16, MAX_MTU_ENTRIES>,
+}
+
+impl MtuTable {
+    pub fn new(local_mtu: u16) -> Self {
+        Self { local: clamp(local_mtu), nodes: FnvIndexMap::new() }
+    }
+
+    pub fn local(&self) -> u16 {
+        self.local
+    }
+
+    pub fn on_node_info(&mut self, info: &NodeInfo) {
+        if info.mtu == 0 {
+            return;
+        }
+        // When the table is full the new node is ignored, which only makes the path MTU optimistic for it
+        let _ = self.nodes.insert(info.uid, clamp(info.mtu));
+    }
+
+    pub fn mtu(&self, uid: u8) -> Option<u16> {
+        self.nodes.get(&uid).copied()
+    }
+
+    /// Largest frame that fits every hop of `route`, or every known node when flooding (`None`)
+    pub fn path_mtu(&self, route: Option<&[u8]>) -> u16 {
+        let smallest = match route {
+            Some(route) => route.iter().filter_map(|uid| self.mtu(*uid)).min(),
+            None => self.nodes.values().copied().min(),
+        };
+        smallest.map_or(self.local, |mtu| mtu.min(self.local))
+    }
+}
+
+fn clamp(mtu: u16) -> u16 {
+    mtu.clamp(MIN_MTU, MAX_FRAME_LEN as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(uid: u8, mtu: u16) -> NodeInfo {
+        NodeInfo { uid, mtu, ..Default::default() }
+    }
+
+    #[test]
+    fn test_path_mtu_is_smallest_hop() {
+        let mut table = MtuTable::new(255);
+        assert_eq!(table.path_mtu(None), 255);
+        table.on_node_info(&info(2, 255));
+        table.on_node_info(&info(3, 64));
+        table.on_node_info(&info(4, 0));
+        assert_eq!(table.path_mtu(None), 64);
+        assert_eq!(table.path_mtu(Some(&[2, 4])), 255);
+        assert_eq!(table.path_mtu(Some(&[2, 3])), 64);
+    }
+}
@@ -0,0 +1,210 @@
+//! Link quality to directly heard nodes, learned from their Hello packets
+
+use heapless::FnvIndexMap;
+
+use crate::protocol::hello::Hello;
+use crate::protocol::ping::LinkQuality;
+
+/// Number of neighbors tracked, the stalest is replaced once full
+pub const MAX_NEIGHBORS: usize = 16;
+/// Hellos a neighbor may miss in a row before it is forgotten
+pub const MISSED_HELLOS: u64 = 3;
+/// Success rate at which a link counts as good
+pub const GOOD_SUCCESS_RATE: f32 = 0.8;
+/// Hellos the success rate is computed over
+const HISTORY: u16 = 32;
+/// Weight of the newest reading in the smoothed RSSI and SNR
+const SMOOTHING: f32 = 0.25;
+
+/// What is known about one directly heard node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor {
+    pub uid: u8,
+    pub last_heard_ms: u64,
+    /// Quality of the latest hello, as reported by the radio driver
+    pub quality: LinkQuality,
+    /// Exponentially smoothed RSSI and SNR
+    pub rssi_avg_dbm: f32,
+    pub snr_avg_db: f32,
+    interval_ms: u32,
+    count: u16,
+    /// One bit per recent hello, newest in bit 0, set if it arrived
+    received: u32,
+    span: u16,
+}
+
+impl Neighbor {
+    fn new(uid: u8, hello: &Hello, quality: LinkQuality, now_ms: u64) -> Self {
+        Self {
+            uid,
+            last_heard_ms: now_ms,
+            quality,
+            rssi_avg_dbm: quality.rssi_dbm as f32,
+            snr_avg_db: quality.snr_db,
+            interval_ms: hello.interval_ms,
+            count: hello.count,
+            received: 1,
+            span: 1,
+        }
+    }
+
+    /// Share of the neighbor's recent hellos that arrived, from 0 to 1
+    pub fn success_rate(&self) -> f32 {
+        self.received.count_ones() as f32 / self.span as f32
+    }
+
+    /// The neighbor missed `MISSED_HELLOS` hellos in a row
+    pub fn is_stale(&self, now_ms: u64) -> bool {
+        now_ms.saturating_sub(self.last_heard_ms) > self.interval_ms as u64 * MISSED_HELLOS
+    }
+
+    /// Recently heard with a success rate of at least `GOOD_SUCCESS_RATE`
+    pub fn is_good(&self, now_ms: u64) -> bool {
+        !self.is_stale(now_ms) && self.success_rate() >= GOOD_SUCCESS_RATE
+    }
+
+    fn on_hello(&mut self, hello: &Hello, quality: LinkQuality, now_ms: u64) {
+        let gap = hello.count.wrapping_sub(self.count);
+        match gap {
+            0 => return,
+            // A count that went backwards means the neighbor rebooted
+            _ if gap > u16::MAX / 2 => {
+                let (rssi_avg_dbm, snr_avg_db) = (self.rssi_avg_dbm, self.snr_avg_db);
+                *self = Self { rssi_avg_dbm, snr_avg_db, ..Self::new(self.uid, hello, quality, now_ms) };
+            }
+            _ => {
+                self.received = self.received.checked_shl(gap as u32).unwrap_or(0) | 1;
+                self.span = (self.span + gap).min(HISTORY);
+                self.count = hello.count;
+            }
+        }
+        self.last_heard_ms = now_ms;
+        self.quality = quality;
+        self.interval_ms = hello.interval_ms;
+        self.rssi_avg_dbm += SMOOTHING * (quality.rssi_dbm as f32 - self.rssi_avg_dbm);
+        self.snr_avg_db += SMOOTHING * (quality.snr_db - self.snr_avg_db);
+    }
+}
+
+/// NeighborTable tracks every node heard directly, for routing decisions and mesh health displays
+#[derive(Debug, Clone, Default)]
+pub struct NeighborTable {
+    neighbors: FnvIndexMap<u8, Neighbor, MAX_NEIGHBORS>,
+}
+
+impl NeighborTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a Hello from `uid`, received at `quality`
+    pub fn on_hello(&mut self, uid: u8, hello: &Hello, quality: LinkQuality, now_ms: u64) {
+        if let Some(neighbor) = self.neighbors.get_mut(&uid) {
+            neighbor.on_hello(hello, quality, now_ms);
+            return;
+        }
+        if self.neighbors.len() == MAX_NEIGHBORS {
+            if let Some(stalest) = self.neighbors.values().min_by_key(|neighbor| neighbor.last_heard_ms) {
+                let uid = stalest.uid;
+                self.neighbors.remove(&uid);
+            }
+        }
+        let _ = self.neighbors.insert(uid, Neighbor::new(uid, hello, quality, now_ms));
+    }
+
+    pub fn get(&self, uid: u8) -> Option<&Neighbor> {
+        self.neighbors.get(&uid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Neighbor> {
+        self.neighbors.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neighbors.is_empty()
+    }
+
+    /// Whether `uid` is a direct neighbor with a good link
+    pub fn is_good(&self, uid: u8, now_ms: u64) -> bool {
+        self.get(uid).is_some_and(|neighbor| neighbor.is_good(now_ms))
+    }
+
+    /// The candidate with the best link, by success rate and then smoothed SNR, ignoring stale neighbors
+    ///
+    /// A neighbor whose SNR a radio reported as NaN or infinite ranks below every finite SNR.
+    pub fn preferred(&self, candidates: &[u8], now_ms: u64) -> Option<u8> {
+        let snr = |neighbor: &Neighbor| match neighbor.snr_avg_db {
+            snr if snr.is_finite() => snr,
+            _ => f32::NEG_INFINITY,
+        };
+        candidates
+            .iter()
+            .filter_map(|uid| self.get(*uid))
+            .filter(|neighbor| !neighbor.is_stale(now_ms))
+            .max_by(|a, b| a.success_rate().total_cmp(&b.success_rate()).then(snr(a).total_cmp(&snr(b))))
+            .map(|neighbor| neighbor.uid)
+    }
+
+    /// Forgets neighbors that went quiet
+    pub fn expire(&mut self, now_ms: u64) {
+        self.neighbors.retain(|_, neighbor| !neighbor.is_stale(now_ms));
+    }
+}
+
+/// The ground station's own table, of the nodes its receivers hear directly
+#[cfg(feature = "ground")]
+impl crate::ground::Sink for NeighborTable {
+    fn deliver(&mut self, event: &crate::ground::GroundEvent) {
+        if let crate::protocol::packet::Packet::Hello(hello) = &event.packet {
+            self.on_hello(event.header.source_uid, hello, event.quality, event.at_ms);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(count: u16) -> Hello {
+        Hello { count, interval_ms: 1_000 }
+    }
+
+    fn quality(rssi_dbm: i16, snr_db: f32) -> LinkQuality {
+        LinkQuality { rssi_dbm, snr_db }
+    }
+
+    #[test]
+    fn test_success_rate_staleness_and_preference() {
+        let mut table = NeighborTable::new();
+        // Node 2 is heard every time, node 3 misses one hello in two
+        for count in 0..10 {
+            table.on_hello(2, &hello(count), quality(-90, 5.0), count as u64 * 1_000);
+            if count % 2 == 0 {
+                table.on_hello(3, &hello(count), quality(-70, 9.0), count as u64 * 1_000);
+            }
+        }
+        table.on_hello(2, &hello(9), quality(-90, 5.0), 9_000);
+        let (two, three) = (table.get(2).unwrap(), table.get(3).unwrap());
+        assert_eq!(two.success_rate(), 1.0);
+        assert!((three.success_rate() - 5.0 / 9.0).abs() < 1e-6);
+        assert!(table.is_good(2, 9_000) && !table.is_good(3, 9_000));
+        assert_eq!(table.preferred(&[3, 2, 7], 9_000), Some(2));
+        table.on_hello(4, &hello(9), quality(-90, f32::NAN), 9_000);
+        table.on_hello(5, &hello(9), quality(-90, 1.0), 9_000);
+        assert_eq!(table.preferred(&[4, 5], 9_000), Some(5));
+
+        // Node 2 reboots, its history starts over but the smoothed quality is kept
+        table.on_hello(2, &hello(0), quality(-50, 10.0), 10_000);
+        let two = table.get(2).unwrap();
+        assert_eq!((two.success_rate(), two.quality.rssi_dbm), (1.0, -50));
+        assert_eq!(two.rssi_avg_dbm, -80.0);
+
+        assert!(table.get(3).unwrap().is_stale(12_001));
+        table.expire(12_001);
+        assert_eq!(table.iter().map(|neighbor| neighbor.uid).collect::<heapless::Vec<u8, 4>>(), [2]);
+    }
+}
@@ -0,0 +1,178 @@
+use heapless::Vec;
+
+use super::scheduler::{FrameBuf, Priority, QueueFull};
+use crate::protocol::mesh::Ack;
+use crate::protocol::Acknowledgement;
+
+/// Number of acknowledged sends that can be in flight at once
+pub const MAX_PENDING: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReliableConfig {
+    /// Wait before the first retransmission
+    pub retry_interval_ms: u64,
+    /// Total transmissions, including the first, before giving up
+    pub max_attempts: u8,
+    /// Each further wait is this many times the previous one, 1 for a fixed interval
+    pub backoff: u8,
+    /// Longest wait between attempts, however far the backoff has grown
+    pub max_retry_interval_ms: u64,
+}
+
+impl Default for ReliableConfig {
+    fn default() -> Self {
+        Self { retry_interval_ms: 2_000, max_attempts: 4, backoff: 2, max_retry_interval_ms: 4_000 }
+    }
+}
+
+impl ReliableConfig {
+    /// Wait after transmission number `attempt`, counting from 1
+    pub fn retry_after_ms(&self, attempt: u8) -> u64 {
+        let factor = (self.backoff.max(1) as u64).saturating_pow(attempt.saturating_sub(1) as u32);
+        self.retry_interval_ms.saturating_mul(factor).min(self.max_retry_interval_ms.max(self.retry_interval_ms))
+    }
+}
+
+/// Where a tracked send stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryState {
+    /// Waiting for an Ack after `attempts` transmissions
+    Pending { attempts: u8 },
+    /// Acknowledged, failed, or never tracked
+    Settled,
+}
+
+/// What the reliable layer needs done next
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Delivery {
+    /// Send the frame again, in the class it was first queued in
+    Retransmit(FrameBuf, Priority),
+    /// No Ack arrived after the last attempt
+    Failed { destination_uid: u8, sequence: u16 },
+}
+
+#[derive(Debug, Clone)]
+struct Pending {
+    destination_uid: u8,
+    sequence: u16,
+    frame: FrameBuf,
+    priority: Priority,
+    attempts: u8,
+    next_retry_ms: u64,
+}
+
+/// Reliable retransmits frames that requested an Ack until one arrives or attempts run out
+#[derive(Debug, Clone, Default)]
+pub struct Reliable {
+    pub config: ReliableConfig,
+    pending: Vec<Pending, MAX_PENDING>,
+}
+
+impl Reliable {
+    pub fn new(config: ReliableConfig) -> Self {
+        Self { config, pending: Vec::new() }
+    }
+
+    /// Starts tracking a frame that has just been queued for the first time with `priority`
+    pub fn track(
+        &mut self,
+        destination_uid: u8,
+        sequence: u16,
+        frame: &[u8],
+        priority: Priority,
+        now_ms: u64,
+    ) -> Result<(), QueueFull> {
+        let frame = FrameBuf::from_slice(frame).map_err(|_| QueueFull)?;
+        let next_retry_ms = now_ms + self.config.retry_after_ms(1);
+        self.pending
+            .push(Pending { destination_uid, sequence, frame, priority, attempts: 1, next_retry_ms })
+            .map_err(|_| QueueFull)
+    }
+
+    /// Whether `track` would be refused for want of room
+    pub fn is_full(&self) -> bool {
+        self.pending.is_full()
+    }
+
+    /// Handles an Ack from `from_uid`, returning true if it completed a tracked send
+    pub fn on_ack(&mut self, from_uid: u8, ack: &Ack) -> bool {
+        let before = self.pending.len();
+        self.pending.retain(|p| !(p.destination_uid == from_uid && p.sequence == ack.sequence));
+        self.pending.len() != before
+    }
+
+    /// Handles an Acknowledgement of comment `id` from `from_uid`, where comment IDs are tracked as sequences
+    ///
+    /// A negative acknowledgement makes the send due for retransmission immediately. Returns true if a
+    /// positive one completed a tracked send.
+    pub fn on_acknowledgement(&mut self, from_uid: u8, acknowledgement: &Acknowledgement, now_ms: u64) -> bool {
+        let sequence = acknowledgement.id as u16;
+        if acknowledgement.ack {
+            return self.on_ack(from_uid, &Ack { sequence });
+        }
+        let matches = |p: &&mut Pending| p.destination_uid == from_uid && p.sequence == sequence;
+        if let Some(pending) = self.pending.iter_mut().find(matches) {
+            pending.next_retry_ms = now_ms;
+        }
+        false
+    }
+
+    pub fn state(&self, destination_uid: u8, sequence: u16) -> DeliveryState {
+        self.pending
+            .iter()
+            .find(|p| p.destination_uid == destination_uid && p.sequence == sequence)
+            .map_or(DeliveryState::Settled, |p| DeliveryState::Pending { attempts: p.attempts })
+    }
+
+    pub fn poll(&mut self, now_ms: u64) -> Option<Delivery> {
+        let index = self.pending.iter().position(|p| p.next_retry_ms <= now_ms)?;
+        let pending = &mut self.pending[index];
+        if pending.attempts >= self.config.max_attempts {
+            let failed = self.pending.swap_remove(index);
+            return Some(Delivery::Failed { destination_uid: failed.destination_uid, sequence: failed.sequence });
+        }
+        pending.attempts += 1;
+        pending.next_retry_ms = now_ms + self.config.retry_after_ms(pending.attempts);
+        Some(Delivery::Retransmit(pending.frame.clone(), pending.priority))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retransmit_until_ack_or_give_up() {
+        let config = ReliableConfig { retry_interval_ms: 100, max_attempts: 2, backoff: 1, max_retry_interval_ms: 100 };
+        let mut reliable = Reliable::new(config);
+        reliable.track(4, 1, &[0xAA], Priority::Normal, 0).unwrap();
+        reliable.track(5, 2, &[0xBB], Priority::Critical, 0).unwrap();
+        assert_eq!(reliable.poll(50), None);
+
+        assert!(reliable.on_ack(4, &Ack { sequence: 1 }));
+        assert!(!reliable.on_ack(4, &Ack { sequence: 1 }));
+        let frame = FrameBuf::from_slice(&[0xBB]).unwrap();
+        assert_eq!(reliable.poll(100), Some(Delivery::Retransmit(frame, Priority::Critical)));
+        assert_eq!(reliable.poll(200), Some(Delivery::Failed { destination_uid: 5, sequence: 2 }));
+        assert_eq!(reliable.poll(1_000), None);
+    }
+
+    #[test]
+    fn test_exponential_backoff_and_nack() {
+        let config = ReliableConfig { retry_interval_ms: 100, max_attempts: 5, backoff: 2, max_retry_interval_ms: 300 };
+        assert_eq!([1, 2, 3, 4].map(|attempt| config.retry_after_ms(attempt)), [100, 200, 300, 300]);
+
+        let mut reliable = Reliable::new(config);
+        reliable.track(4, 9, &[0xAA], Priority::Normal, 0).unwrap();
+        assert!(reliable.poll(100).is_some());
+        assert_eq!(reliable.poll(299), None);
+        assert_eq!(reliable.state(4, 9), DeliveryState::Pending { attempts: 2 });
+
+        // A NACK skips the rest of the wait, an ACK settles the send
+        assert!(!reliable.on_acknowledgement(4, &Acknowledgement { id: 9, ack: false }, 150));
+        assert!(reliable.poll(150).is_some());
+        assert!(reliable.on_acknowledgement(4, &Acknowledgement { id: 9, ack: true }, 160));
+        assert_eq!(reliable.state(4, 9), DeliveryState::Settled);
+    }
+}
@@ -0,0 +1,195 @@
+use super::dedup::DedupCache;
+use crate::protocol::mesh::{MeshHeader, SourceRoute, BROADCAST_UID};
+use crate::protocol::Comment;
+
+/// What to do with a received frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    /// Hand the packet to this node's application
+    pub deliver: bool,
+    /// Rebroadcast with this header, which has one fewer hop left
+    pub forward: Option<MeshHeader>,
+}
+
+impl Route {
+    pub const DROP: Route = Route { deliver: false, forward: None };
+}
+
+/// A source route listed more than `MAX_ROUTE_HOPS` relays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteTooLong;
+
+/// What to do with a Comment received in an APRS report
+#[derive(Debug, Clone, Copy)]
+pub struct CommentRoute {
+    pub deliver: bool,
+    /// Resend with this comment, which has one fewer hop left and a fresh CRC
+    pub forward: Option<Comment>,
+}
+
+/// Router implements hop-limited flooding with duplicate suppression, and source routing along a fixed path
+///
+/// Mesh frames and APRS comments are deduplicated separately, by `(source, sequence)` and `(uid, msg_id)`.
+/// A source-routed frame is only forwarded by the relay it names next and spends no hops until a relay
+/// falls back to flooding by clearing the route.
+#[derive(Debug, Clone)]
+pub struct Router {
+    uid: u8,
+    dedup: DedupCache,
+    comments: DedupCache,
+}
+
+impl Router {
+    pub fn new(uid: u8, dedup_window_ms: u64) -> Self {
+        Self { uid, dedup: DedupCache::new(dedup_window_ms), comments: DedupCache::new(dedup_window_ms) }
+    }
+
+    /// Decides how to handle a received frame, frames are only ever routed once
+    pub fn route(&mut self, header: &MeshHeader, now_ms: u64) -> Route {
+        let for_us = header.destination_uid == self.uid;
+        let on_route = header.route.map(|route| route.next_hop() == Some(self.uid));
+        // Nodes off the path ignore a routed frame without remembering it, so they still relay it if a later
+        // hop falls back to flooding
+        if header.source_uid == self.uid || (on_route == Some(false) && !for_us) {
+            return Route::DROP;
+        }
+        if self.dedup.check(header.source_uid, header.sequence, now_ms) {
+            return Route::DROP;
+        }
+        let broadcast = header.destination_uid == BROADCAST_UID;
+        let forward = match header.route {
+            _ if for_us => None,
+            Some(route) => (on_route == Some(true)).then(|| MeshHeader { route: Some(route.advance()), ..*header }),
+            None => (header.hops_left > 0).then(|| MeshHeader { hops_left: header.hops_left - 1, ..*header }),
+        };
+        Route { deliver: for_us || broadcast, forward }
+    }
+
+    /// Attaches a route through `relays` to a frame this node originates, to be recorded with `originated`
+    /// once it is queued
+    pub fn send_source_routed(&self, header: &MeshHeader, relays: &[u8]) -> Result<MeshHeader, RouteTooLong> {
+        let route = SourceRoute::new(relays).ok_or(RouteTooLong)?;
+        Ok(MeshHeader { route: Some(route), ..*header })
+    }
+
+    /// Records a frame this node originated so its rebroadcast echoes are ignored
+    pub fn originated(&mut self, header: &MeshHeader, now_ms: u64) {
+        self.dedup.check(header.source_uid, header.sequence, now_ms);
+    }
+
+    /// Decides how to handle a Comment, with the same flooding rules as mesh frames
+    pub fn route_comment(&mut self, comment: &Comment, now_ms: u64) -> CommentRoute {
+        if comment.uid == self.uid || self.comments.check(comment.uid, comment.msg_id as u16, now_ms) {
+            return CommentRoute { deliver: false, forward: None };
+        }
+        let for_us = comment.destination_uid == self.uid;
+        let broadcast = comment.destination_uid == BROADCAST_UID;
+        let forward = (!for_us && comment.hops_left > 0).then(|| {
+            let mut forward = Comment { hops_left: comment.hops_left - 1, ..*comment };
+            forward.seal();
+            forward
+        });
+        CommentRoute { deliver: for_us || broadcast, forward }
+    }
+
+    /// Records a Comment this node sent so its rebroadcast echoes are ignored
+    pub fn originated_comment(&mut self, comment: &Comment, now_ms: u64) {
+        self.comments.check(comment.uid, comment.msg_id as u16, now_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(destination_uid: u8, hops_left: u8) -> MeshHeader {
+        MeshHeader {
+            source_uid: 1,
+            destination_uid,
+            sequence: 7,
+            hops_left,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        }
+    }
+
+    #[test]
+    fn test_flooding_rules() {
+        let mut router = Router::new(5, 10_000);
+        let route = router.route(&header(BROADCAST_UID, 2), 0);
+        assert!(route.deliver);
+        assert_eq!(route.forward.map(|h| h.hops_left), Some(1));
+        assert_eq!(router.route(&header(BROADCAST_UID, 2), 10), Route::DROP);
+
+        let mut router = Router::new(5, 10_000);
+        assert_eq!(router.route(&header(5, 2), 0), Route { deliver: true, forward: None });
+        let mut router = Router::new(5, 10_000);
+        assert_eq!(router.route(&header(9, 0), 0), Route::DROP);
+    }
+
+    #[test]
+    fn test_source_route_followed_strictly() {
+        let source = Router::new(1, 10_000);
+        let routed = source.send_source_routed(&header(9, 0), &[5, 6]).unwrap();
+        assert_eq!(routed.route.unwrap().hops(), [5, 6]);
+        assert_eq!(source.send_source_routed(&header(9, 0), &[2, 3, 4, 5, 6]), Err(RouteTooLong));
+
+        // Node 6 overhears the frame before 5 relays it, and relays it without spending a hop afterwards
+        let (mut five, mut six) = (Router::new(5, 10_000), Router::new(6, 10_000));
+        assert_eq!(six.route(&routed, 0), Route::DROP);
+        let relayed = five.route(&routed, 0).forward.unwrap();
+        assert_eq!((relayed.route.unwrap().next_hop(), relayed.hops_left), (Some(6), 0));
+        let last = six.route(&relayed, 0).forward.unwrap();
+        assert_eq!(last.route.unwrap().next_hop(), None);
+        assert_eq!(Router::new(7, 10_000).route(&last, 0), Route::DROP);
+        assert_eq!(Router::new(9, 10_000).route(&last, 0), Route { deliver: true, forward: None });
+
+        // Node 7, off the path, still floods the frame once a relay clears the route
+        let mut seven = Router::new(7, 10_000);
+        assert_eq!(seven.route(&relayed, 0), Route::DROP);
+        let flooded = MeshHeader { route: None, hops_left: 2, ..relayed };
+        assert_eq!(seven.route(&flooded, 0).forward.map(|h| h.hops_left), Some(1));
+    }
+
+    #[test]
+    fn test_comment_flooding_across_topologies() {
+        // Line 1-2-3-4-5 and a diamond 1-{2,3}-4-5, relaying comments from node 1 until none are left
+        let line: &[(u8, u8)] = &[(1, 2), (2, 3), (3, 4), (4, 5)];
+        let diamond: &[(u8, u8)] = &[(1, 2), (1, 3), (2, 4), (3, 4), (4, 5)];
+        let flood = |links: &[(u8, u8)], destination_uid: u8, hops_left: u8| {
+            let mut routers: std::vec::Vec<Router> = (0..=5).map(|uid| Router::new(uid, 10_000)).collect();
+            let mut comment = Comment { uid: 1, destination_uid, msg_id: 42, hops_left, ..Default::default() };
+            comment.seal();
+            routers[1].originated_comment(&comment, 0);
+            let (mut in_flight, mut delivered, mut sent) = (std::vec![(1u8, comment)], std::vec![], 1);
+            while let Some((from, comment)) = in_flight.pop() {
+                let neighbors = links.iter().filter_map(|&(a, b)| match from {
+                    _ if a == from => Some(b),
+                    _ if b == from => Some(a),
+                    _ => None,
+                });
+                for neighbor in neighbors {
+                    assert!(comment.verify().is_ok());
+                    let route = routers[neighbor as usize].route_comment(&comment, 0);
+                    if route.deliver {
+                        delivered.push(neighbor);
+                    }
+                    if let Some(forward) = route.forward {
+                        in_flight.push((neighbor, forward));
+                        sent += 1;
+                    }
+                }
+            }
+            delivered.sort();
+            (delivered, sent)
+        };
+
+        assert_eq!(flood(line, BROADCAST_UID, 3), (std::vec![2, 3, 4, 5], 4));
+        assert_eq!(flood(line, BROADCAST_UID, 2), (std::vec![2, 3, 4], 3));
+        // Node 4 hears both branches of the diamond but relays once, and the destination doesn't relay
+        assert_eq!(flood(diamond, 5, 3), (std::vec![5], 4));
+        assert_eq!(flood(diamond, 4, 3).0, [4]);
+    }
+}
@@ -0,0 +1,909 @@
+use super::csma::{Csma, CsmaConfig, Decision};
+use super::fragmentation::{self, FragmentError, Fragments};
+use super::mtu::MtuTable;
+use super::neighbors::NeighborTable;
+use super::reliable::{Delivery, Reliable, ReliableConfig};
+use super::router::Router;
+use super::scheduler::{FrameBuf, Priority, Scheduler};
+use super::store_forward::{StoreAndForward, StoreClass};
+use super::tdma::{Tdma, TdmaConfig};
+use crate::clock::Clock;
+#[cfg(feature = "crypto")]
+use crate::crypto::trailer::Trailer;
+use crate::persistence::counters::FrameCounters;
+use crate::persistence::{self, Persistence};
+use crate::ping::echo;
+use crate::protocol::checksum;
+use crate::protocol::finite::{FieldPath, Finite, NonFinitePolicy};
+use crate::protocol::hello::Hello;
+use crate::protocol::emergency::Emergency;
+use crate::protocol::mesh::{Ack, MeshFrame, MeshHeader, BROADCAST_UID, MAX_FRAME_LEN, MAX_HOPS};
+use crate::protocol::packet::Packet;
+use crate::protocol::ping::LinkQuality;
+use crate::radio::Radio;
+use crate::rng::NodeRng;
+
+#[derive(Debug, Clone, Copy)]
+pub struct NodeConfig {
+    /// Hop limit given to frames this node originates
+    pub default_hops: u8,
+    pub dedup_window_ms: u64,
+    /// Forwarded frames are delayed by a random amount up to this, so neighbors don't rebroadcast in lockstep
+    pub forward_jitter_ms: u32,
+    pub reliable: ReliableConfig,
+    /// Largest frame this node's radio handles, advertised in NodeInfo
+    pub mtu: u16,
+    /// Tag originated frames as coming from the backup flight computer
+    pub backup: bool,
+    /// What to do with NaN and infinite readings in originated packets
+    pub non_finite: NonFinitePolicy,
+    /// Broadcast a Hello this often so neighbors can measure the link, or never if `None`
+    pub hello_interval_ms: Option<u32>,
+    /// Relay frames for this node, typically the ground station, only while its Hellos are heard, holding
+    /// them in a `StoreAndForward` queue otherwise
+    pub store_for: Option<u8>,
+    /// How long held frames are kept
+    pub store_max_age_ms: u64,
+    /// Transmit only in this node's TDMA slots once synced to GPS time, see `tdma`
+    pub tdma: Option<TdmaConfig>,
+    /// Listen before every transmission and back off while the channel is busy, see `csma`
+    pub csma: Option<CsmaConfig>,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            default_hops: 3,
+            dedup_window_ms: 30_000,
+            forward_jitter_ms: 200,
+            reliable: ReliableConfig::default(),
+            mtu: MAX_FRAME_LEN as u16,
+            backup: false,
+            non_finite: NonFinitePolicy::Sentinel,
+            hello_interval_ms: None,
+            store_for: None,
+            store_max_age_ms: 120_000,
+            tdma: None,
+            csma: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NodeError<R, S> {
+    Radio(R),
+    Storage(persistence::Error<S>),
+    /// The transmit or retransmit queue is full
+    QueueFull,
+    /// The packet does not fit in one frame
+    Encoding(postcard::Error),
+    /// The encoded frame is larger than the smallest MTU on its path
+    TooLarge { len: usize, mtu: u16 },
+    /// The packet holds a NaN or infinite value and `NodeConfig::non_finite` is `Reject`
+    NonFinite(FieldPath),
+    /// A source route listed more than `MAX_ROUTE_HOPS` relays
+    RouteTooLong,
+    /// Not enough TDMA slots are free, or TDMA is not enabled
+    NoFreeSlot,
+}
+
+/// Something the application should know about, returned from `MeshNode::poll`
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum NodeEvent {
+    Received { header: MeshHeader, packet: Packet, quality: LinkQuality },
+    /// `destination_uid` acknowledged a reliable send
+    Delivered { destination_uid: u8, sequence: u16 },
+    /// A reliable send to `destination_uid` was never acknowledged
+    DeliveryFailed { destination_uid: u8, sequence: u16 },
+    /// A range-safety Emergency was received; it is relayed like any flood, this is the application's cue to act
+    Emergency { header: MeshHeader, emergency: Emergency, quality: LinkQuality },
+}
+
+/// MeshNode ties the radio, router, reliable layer and transmit scheduler together
+///
+/// Call `poll` from the main loop; it transmits at most one due frame and handles at most one received
+/// frame per call.
+pub struct MeshNode<R: Radio, C: Clock, S: Persistence> {
+    uid: u8,
+    pub config: NodeConfig,
+    radio: R,
+    clock: C,
+    store: S,
+    counters: FrameCounters,
+    router: Router,
+    reliable: Reliable,
+    scheduler: Scheduler,
+    mtu: MtuTable,
+    neighbors: NeighborTable,
+    held: StoreAndForward,
+    tdma: Option<Tdma>,
+    csma: Option<Csma>,
+    hellos: u16,
+    next_hello_ms: u64,
+    rng: NodeRng,
+    #[cfg(feature = "crypto")]
+    trailer: Option<Trailer>,
+}
+
+impl<R: Radio, C: Clock, S: Persistence> MeshNode<R, C, S> {
+    /// Creates the node, resuming frame counters from `store` so sequence numbers survive reboots
+    pub fn new(uid: u8, radio: R, clock: C, mut store: S, config: NodeConfig) -> Result<Self, NodeError<R::Error, S::Error>> {
+        let counters = FrameCounters::resume(&mut store).map_err(NodeError::Storage)?;
+        let rng = NodeRng::seeded(uid, clock.now_ms() as u32);
+        // A transport that carries less than `NodeConfig::mtu` caps the frames this node sends
+        let mtu = config.mtu.min(radio.max_payload().min(MAX_FRAME_LEN) as u16);
+        Ok(Self {
+            uid,
+            config,
+            radio,
+            clock,
+            store,
+            counters,
+            router: Router::new(uid, config.dedup_window_ms),
+            reliable: Reliable::new(config.reliable),
+            scheduler: Scheduler::new(),
+            mtu: MtuTable::new(mtu),
+            neighbors: NeighborTable::new(),
+            held: StoreAndForward::new(config.store_max_age_ms),
+            tdma: config.tdma.map(|tdma| Tdma::new(uid, tdma)),
+            csma: config.csma.map(Csma::new),
+            hellos: 0,
+            next_hello_ms: 0,
+            rng,
+            #[cfg(feature = "crypto")]
+            trailer: None,
+        })
+    }
+
+    pub fn uid(&self) -> u8 {
+        self.uid
+    }
+
+    pub fn radio_mut(&mut self) -> &mut R {
+        &mut self.radio
+    }
+
+    /// Appends an HMAC trailer to the packet kinds `trailer` signs from now on, for the ground to verify
+    #[cfg(feature = "crypto")]
+    pub fn sign_with(&mut self, trailer: Trailer) {
+        self.trailer = Some(trailer);
+    }
+
+    /// MTUs learned from NodeInfo announcements
+    pub fn mtu(&self) -> &MtuTable {
+        &self.mtu
+    }
+
+    /// Nodes heard directly, learned from their Hellos
+    pub fn neighbors(&self) -> &NeighborTable {
+        &self.neighbors
+    }
+
+    /// Frames held for `NodeConfig::store_for` while it is out of reach
+    pub fn held(&self) -> &StoreAndForward {
+        &self.held
+    }
+
+    /// Slot ownership, `None` unless `NodeConfig::tdma` is set
+    pub fn tdma(&self) -> Option<&Tdma> {
+        self.tdma.as_ref()
+    }
+
+    /// Listen-before-talk state and statistics, `None` unless `NodeConfig::csma` is set
+    pub fn csma(&self) -> Option<&Csma> {
+        self.csma.as_ref()
+    }
+
+    /// Aligns TDMA slots to GPS time, call with every GPS fix; `gps_ms` is GPS time in milliseconds
+    pub fn sync_time(&mut self, gps_ms: u64) {
+        let now = self.clock.now_ms();
+        if let Some(tdma) = self.tdma.as_mut() {
+            tdma.sync(gps_ms, now);
+        }
+    }
+
+    /// Claims `count` extra TDMA slots for high-rate telemetry and broadcasts the claim
+    pub fn request_slots(&mut self, count: usize) -> Result<u16, NodeError<R::Error, S::Error>> {
+        let tdma = self.tdma.as_mut().ok_or(NodeError::NoFreeSlot)?;
+        let claim = tdma.request_slots(count).map_err(|_| NodeError::NoFreeSlot)?;
+        self.send(BROADCAST_UID, Packet::SlotClaim(claim), false)
+    }
+
+    /// Returns to the default TDMA slot and broadcasts the release
+    pub fn release_slots(&mut self) -> Result<(), NodeError<R::Error, S::Error>> {
+        if let Some(tdma) = self.tdma.as_mut() {
+            let claim = tdma.release_slots();
+            self.send(BROADCAST_UID, Packet::SlotClaim(claim), false)?;
+        }
+        Ok(())
+    }
+
+    /// Splits `message` into Fragment packets that fit every node on the mesh, to be sent as queue space allows
+    pub fn fragment<'a>(&self, message_id: u16, message: &'a [u8]) -> Result<Fragments<'a>, FragmentError> {
+        fragmentation::split(message_id, message, self.mtu.path_mtu(None))
+    }
+
+    /// Queues a packet for `destination_uid` (or `BROADCAST_UID`) and returns its sequence number
+    ///
+    /// With `reliable` set, the frame is retransmitted until the destination acknowledges it; broadcasts
+    /// are never acknowledged. The frame is queued in the packet's default class, see `Priority::of`.
+    pub fn send(&mut self, destination_uid: u8, packet: Packet, reliable: bool) -> Result<u16, NodeError<R::Error, S::Error>> {
+        let priority = Priority::of(&packet);
+        self.enqueue(destination_uid, packet, reliable, priority)
+    }
+
+    /// Like `send`, queueing the frame in `priority` instead of the packet's default class
+    pub fn send_with(
+        &mut self,
+        destination_uid: u8,
+        packet: Packet,
+        reliable: bool,
+        priority: Priority,
+    ) -> Result<u16, NodeError<R::Error, S::Error>> {
+        self.enqueue(destination_uid, packet, reliable, priority)
+    }
+
+    /// Queues an unacknowledged packet ahead of normal traffic, for time-critical data such as shared state
+    /// estimates
+    pub fn send_urgent(&mut self, destination_uid: u8, packet: Packet) -> Result<u16, NodeError<R::Error, S::Error>> {
+        self.enqueue(destination_uid, packet, false, Priority::High)
+    }
+
+    /// Floods a range-safety announcement to every node with `MAX_HOPS`, ahead of all other traffic
+    ///
+    /// Emergency frames skip TDMA slots and listen-before-talk, and evict queued frames if the queue is full.
+    pub fn send_emergency(&mut self, emergency: Emergency) -> Result<u16, NodeError<R::Error, S::Error>> {
+        self.send(BROADCAST_UID, Packet::Emergency(emergency), false)
+    }
+
+    /// Queues a packet that relays forward strictly along `relays`, in order, for deterministic command paths
+    ///
+    /// If a relay does not hear the next hop's Hellos, it floods the frame on with the hop limit instead;
+    /// so does this node when it does not hear the first relay.
+    pub fn send_source_routed(
+        &mut self,
+        destination_uid: u8,
+        relays: &[u8],
+        packet: Packet,
+        reliable: bool,
+    ) -> Result<u16, NodeError<R::Error, S::Error>> {
+        let now = self.clock.now_ms();
+        let relays = match relays.first() {
+            Some(&first) if !self.reachable(first, now) => &[],
+            _ => relays,
+        };
+        let priority = Priority::of(&packet);
+        self.enqueue_with_hops(destination_uid, packet, reliable, priority, self.config.default_hops, relays)
+    }
+
+    fn enqueue(
+        &mut self,
+        destination_uid: u8,
+        packet: Packet,
+        reliable: bool,
+        priority: Priority,
+    ) -> Result<u16, NodeError<R::Error, S::Error>> {
+        self.enqueue_with_hops(destination_uid, packet, reliable, priority, self.config.default_hops, &[])
+    }
+
+    fn enqueue_with_hops(
+        &mut self,
+        destination_uid: u8,
+        packet: Packet,
+        reliable: bool,
+        priority: Priority,
+        hops: u8,
+        relays: &[u8],
+    ) -> Result<u16, NodeError<R::Error, S::Error>> {
+        // However it was sent, an emergency is flooded to every node as far as the mesh reaches
+        let (destination_uid, reliable, priority, hops, relays) = match packet {
+            Packet::Emergency(_) => (BROADCAST_UID, false, Priority::Emergency, MAX_HOPS, &[][..]),
+            _ => (destination_uid, reliable, priority, hops, relays),
+        };
+        let mut packet = packet;
+        packet.prepare_encode(self.config.non_finite).map_err(NodeError::NonFinite)?;
+        let now = self.clock.now_ms();
+        let header = MeshHeader {
+            source_uid: self.uid,
+            destination_uid,
+            // Widest sequence number so the size check holds for whichever is stamped
+            sequence: u16::MAX,
+            hops_left: hops,
+            ack_requested: reliable && destination_uid != BROADCAST_UID,
+            rebooted: false,
+            backup: self.config.backup,
+            route: None,
+        };
+        let header = match relays {
+            [] => header,
+            _ => self.router.send_source_routed(&header, relays).map_err(|_| NodeError::RouteTooLong)?,
+        };
+        // A frame that is refused leaves the counters, router and reliable layer as they were
+        let mut frame = MeshFrame { header, packet };
+        let len = self.encode_signed(&frame)?.len();
+        let mtu = self.mtu.path_mtu(None);
+        if len > mtu as usize {
+            return Err(NodeError::TooLarge { len, mtu });
+        }
+        if !self.scheduler.has_room(priority) || (header.ack_requested && self.reliable.is_full()) {
+            return Err(NodeError::QueueFull);
+        }
+        let stamp = self.counters.next(&mut self.store).map_err(NodeError::Storage)?;
+        frame.header.sequence = stamp.sequence;
+        frame.header.rebooted = stamp.rebooted;
+        let header = frame.header;
+        let encoded = self.encode_signed(&frame)?;
+        self.scheduler.push_with(&encoded, now, priority).map_err(|_| NodeError::QueueFull)?;
+        self.router.originated(&header, now);
+        if header.ack_requested {
+            self.reliable
+                .track(destination_uid, header.sequence, &encoded, priority, now)
+                .map_err(|_| NodeError::QueueFull)?;
+        }
+        Ok(header.sequence)
+    }
+
+    /// Encodes a frame this node originates, with a trailer if its kind is signed
+    fn encode_signed(&self, frame: &MeshFrame) -> Result<FrameBuf, NodeError<R::Error, S::Error>> {
+        #[cfg(feature = "crypto")]
+        let tag = match &self.trailer {
+            Some(trailer) => trailer.tag(frame).map_err(NodeError::Encoding)?,
+            None => heapless::Vec::new(),
+        };
+        #[cfg(not(feature = "crypto"))]
+        let tag: [u8; 0] = [];
+        encode(frame, &tag)
+    }
+
+    pub fn poll(&mut self) -> Result<Option<NodeEvent>, NodeError<R::Error, S::Error>> {
+        let now = self.clock.now_ms();
+        let mut event = None;
+        match self.reliable.poll(now) {
+            Some(Delivery::Retransmit(frame, priority)) => {
+                // A full queue skips this attempt; the next one, or the failure report, still follows
+                let _ = self.scheduler.push_with(&frame, now, priority);
+            }
+            Some(Delivery::Failed { destination_uid, sequence }) => {
+                event = Some(NodeEvent::DeliveryFailed { destination_uid, sequence })
+            }
+            None => {}
+        }
+        self.transmit_due(now)?;
+        self.hello(now)?;
+        if event.is_some() {
+            return Ok(event);
+        }
+        self.receive(now)
+    }
+
+    /// Transmits the most urgent due frame if this node's TDMA slot and listen-before-talk allow it
+    fn transmit_due(&mut self, now: u64) -> Result<(), NodeError<R::Error, S::Error>> {
+        if self.scheduler.due_priority(now) == Some(Priority::Emergency) {
+            if let Some(frame) = self.scheduler.pop_due(now) {
+                self.radio.transmit(&frame).map_err(NodeError::Radio)?;
+            }
+            return Ok(());
+        }
+        // Outside this node's TDMA slot or during a backoff, due frames wait in the queue
+        let in_slot = self.tdma.as_ref().is_none_or(|tdma| tdma.may_transmit(now));
+        let backing_off = self.csma.as_ref().is_some_and(|csma| csma.backing_off(now));
+        if !in_slot || backing_off || !self.scheduler.has_due(now) {
+            return Ok(());
+        }
+        if let Some(csma) = self.csma.as_mut() {
+            let busy = self.radio.channel_busy().map_err(NodeError::Radio)?;
+            match csma.on_listen(busy, now, &mut self.rng) {
+                Decision::Transmit => {}
+                Decision::Defer => return Ok(()),
+                Decision::Abort => {
+                    self.scheduler.pop_due(now);
+                    return Ok(());
+                }
+            }
+        }
+        if let Some(frame) = self.scheduler.pop_due(now) {
+            self.radio.transmit(&frame).map_err(NodeError::Radio)?;
+            if let Some(tdma) = self.tdma.as_mut() {
+                tdma.transmitted(now);
+            }
+        }
+        Ok(())
+    }
+
+    /// Queues a Hello for direct neighbors once the interval has passed and forgets neighbors gone quiet
+    fn hello(&mut self, now: u64) -> Result<(), NodeError<R::Error, S::Error>> {
+        self.neighbors.expire(now);
+        self.held.expire(now);
+        let Some(interval_ms) = self.config.hello_interval_ms else {
+            return Ok(());
+        };
+        if now < self.next_hello_ms {
+            return Ok(());
+        }
+        let hello = Hello { count: self.hellos, interval_ms };
+        // The beacon is best effort: with the queue full it is skipped until the next interval
+        match self.enqueue_with_hops(BROADCAST_UID, Packet::Hello(hello), false, Priority::Normal, 0, &[]) {
+            Ok(_) => self.hellos = self.hellos.wrapping_add(1),
+            Err(NodeError::QueueFull) => {}
+            Err(error) => return Err(error),
+        }
+        self.next_hello_ms = now + interval_ms as u64;
+        Ok(())
+    }
+
+    fn receive(&mut self, now: u64) -> Result<Option<NodeEvent>, NodeError<R::Error, S::Error>> {
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let Some((len, quality)) = self.radio.receive(&mut buf).map_err(NodeError::Radio)? else {
+            return Ok(None);
+        };
+        // Corrupt or foreign frames are dropped
+        let Ok(message) = checksum::verify_and_strip(&buf[..len]) else {
+            return Ok(None);
+        };
+        let Ok((MeshFrame { header, packet }, trailer)) = MeshFrame::decode_with_trailer(message) else {
+            return Ok(None);
+        };
+        if let Some(tdma) = self.tdma.as_mut() {
+            tdma.heard(header.source_uid);
+        }
+        match &packet {
+            Packet::NodeInfo(info) => self.mtu.on_node_info(info),
+            Packet::SlotClaim(claim) => {
+                if let Some(tdma) = self.tdma.as_mut() {
+                    tdma.on_claim(claim);
+                }
+            }
+            Packet::Hello(hello) => {
+                self.neighbors.on_hello(header.source_uid, hello, quality, now);
+                if self.config.store_for == Some(header.source_uid) {
+                    self.held.flush(header.source_uid, &mut self.scheduler, now);
+                }
+            }
+            _ => {}
+        }
+        let mut route = self.router.route(&header, now);
+        if let Some(forward) = route.forward.as_mut() {
+            if let Some(source_route) = forward.route {
+                let next_hop = source_route.next_hop().unwrap_or(forward.destination_uid);
+                if !self.reachable(next_hop, now) {
+                    forward.route = None;
+                }
+            }
+            // A destination we hear well gets it from this rebroadcast, flooding further only costs airtime
+            if self.neighbors.is_good(forward.destination_uid, now) {
+                forward.hops_left = 0;
+            }
+        }
+        if let Some(forward) = route.forward {
+            let frame = encode(&MeshFrame { header: forward, packet: packet.clone() }, trailer)?;
+            let destination_uid = forward.destination_uid;
+            if self.config.store_for == Some(destination_uid) && self.neighbors.get(destination_uid).is_none() {
+                // Held until the destination's next Hello; a full store drops the least important frame
+                let _ = self.held.store(destination_uid, StoreClass::of(&packet), &frame, now);
+            } else {
+                let jitter = self.rng.delay_ms(self.config.forward_jitter_ms) as u64;
+                // Forwarding is best effort, a full queue drops the rebroadcast rather than failing the poll
+                let _ = self.scheduler.push_with(&frame, now + jitter, Priority::of(&packet));
+            }
+        }
+        if !route.deliver {
+            return Ok(None);
+        }
+        if header.destination_uid == self.uid {
+            if let Packet::Ack(ack) = packet {
+                let delivered = self.reliable.on_ack(header.source_uid, &ack);
+                let event = NodeEvent::Delivered { destination_uid: header.source_uid, sequence: ack.sequence };
+                return Ok(delivered.then_some(event));
+            }
+            if header.ack_requested {
+                // A full queue loses the Ack rather than the frame, and the sender's retransmission is acked
+                match self.send(header.source_uid, Packet::Ack(Ack { sequence: header.sequence }), false) {
+                    Ok(_) | Err(NodeError::QueueFull) => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            if let Packet::Echo(echo) = &packet {
+                if let Some(reflection) = echo::reflect(self.uid, &header, echo, quality) {
+                    self.send(header.source_uid, Packet::Echo(reflection), false)?;
+                }
+            }
+        }
+        if let Packet::Emergency(emergency) = packet {
+            return Ok(Some(NodeEvent::Emergency { header, emergency, quality }));
+        }
+        Ok(Some(NodeEvent::Received { header, packet, quality }))
+    }
+
+    /// Whether `uid` can be expected to hear this node, assumed when no Hellos are heard at all
+    fn reachable(&self, uid: u8, now: u64) -> bool {
+        self.neighbors.is_empty() || self.neighbors.get(uid).is_some_and(|neighbor| !neighbor.is_stale(now))
+    }
+}
+
+fn encode<R, S>(frame: &MeshFrame, trailer: &[u8]) -> Result<FrameBuf, NodeError<R, S>> {
+    let mut buf = [0u8; MAX_FRAME_LEN];
+    let len = frame.encode_with_trailer(trailer, &mut buf).map_err(NodeError::Encoding)?;
+    Ok(FrameBuf::from_slice(&buf[..len]).expect("encoded frame fits the buffer it was written to"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::node::csma::CsmaStats;
+    use crate::persistence::MemoryStore;
+    use crate::protocol::echo::Echo;
+    use crate::protocol::emergency::EmergencyKind;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::fragment::Fragment;
+    use crate::protocol::gonogo::{Criterion, CriterionResult, GoNoGoReport, Verdict};
+    use crate::node::scheduler::{BULK_QUEUE_LEN, TX_QUEUE_LEN};
+    use crate::protocol::node_info::NodeInfo;
+    use crate::protocol::packet::PacketKind;
+    use crate::radio::mock::{relay, Fate, MockRadio, Script};
+
+    type Node<'a> = MeshNode<MockRadio, &'a MockClock, MemoryStore<2, 16>>;
+
+    fn node(uid: u8, clock: &MockClock, script: Script) -> Node<'_> {
+        MeshNode::new(uid, MockRadio::new(script), clock, MemoryStore::new(), NodeConfig::default()).unwrap()
+    }
+
+    /// Moves everything `from` transmitted into `to`'s receive queue
+    fn deliver(from: &mut Node, to: &mut Node) {
+        relay(from.radio_mut(), to.radio_mut());
+    }
+
+    #[test]
+    fn test_reliable_send_is_acknowledged() {
+        let clock = MockClock::new(0);
+        let mut a = node(1, &clock, Script::default());
+        let mut b = node(2, &clock, Script::default());
+
+        let sequence = a.send(2, Packet::Event(FlightEvent::Launch), true).unwrap();
+        assert_eq!(a.poll().unwrap(), None);
+        deliver(&mut a, &mut b);
+
+        let Some(NodeEvent::Received { header, packet, .. }) = b.poll().unwrap() else {
+            panic!("expected a received packet");
+        };
+        assert_eq!((header.source_uid, header.sequence), (1, sequence));
+        assert_eq!(packet, Packet::Event(FlightEvent::Launch));
+
+        // b's Ack goes out on its next poll
+        b.poll().unwrap();
+        deliver(&mut b, &mut a);
+        assert_eq!(a.poll().unwrap(), Some(NodeEvent::Delivered { destination_uid: 2, sequence }));
+
+        clock.set(60_000);
+        assert_eq!(a.poll().unwrap(), None);
+        assert_eq!(a.radio_mut().sent_len(), 0);
+    }
+
+    #[test]
+    fn test_lost_frame_retransmitted_and_duplicate_suppressed() {
+        let clock = MockClock::new(0);
+        let mut a = node(1, &clock, Script::default());
+        // The first transmission is lost, the retransmission arrives twice
+        let mut b = node(2, &clock, Script::once(&[Fate::Drop, Fate::Duplicate]));
+
+        a.send(2, Packet::Event(FlightEvent::Apogee { altitude_m: 3_000.0 }), true).unwrap();
+        let mut received = 0;
+        for step in 0..10 {
+            clock.set(step * 1_000);
+            a.poll().unwrap();
+            deliver(&mut a, &mut b);
+            while let Some(event) = b.poll().unwrap() {
+                received += matches!(event, NodeEvent::Received { .. }) as u32;
+            }
+            b.poll().unwrap();
+            deliver(&mut b, &mut a);
+        }
+        assert_eq!(received, 1);
+        clock.set(60_000);
+        assert_eq!(a.poll().unwrap(), None);
+    }
+
+    #[test]
+    fn test_send_honors_smallest_advertised_mtu() {
+        let clock = MockClock::new(0);
+        let mut a = node(1, &clock, Script::default());
+        let mut relay_node = node(3, &clock, Script::default());
+        let info = NodeInfo { uid: 3, mtu: 40, ..Default::default() };
+        relay_node.send(BROADCAST_UID, Packet::NodeInfo(info), false).unwrap();
+        relay_node.poll().unwrap();
+        deliver(&mut relay_node, &mut a);
+        a.poll().unwrap();
+        assert_eq!(a.mtu().path_mtu(None), 40);
+
+        let sequence = a.send(2, Packet::Event(FlightEvent::Landed), false).unwrap();
+        let criteria = [Criterion::GpsFix, Criterion::Sensors, Criterion::Battery, Criterion::LinkMargin, Criterion::Continuity];
+        let results = criteria.map(|criterion| CriterionResult { criterion, verdict: Verdict::Go, value: Some(1.0) });
+        let report = GoNoGoReport { uid: 1, overall: Verdict::Go, results: results.into_iter().collect() };
+        assert!(matches!(a.send(2, Packet::GoNoGo(report), false), Err(NodeError::TooLarge { mtu: 40, .. })));
+        // The refused frame took no sequence number
+        assert_eq!(a.send(2, Packet::Event(FlightEvent::Landed), false).unwrap(), sequence + 1);
+    }
+
+    #[test]
+    fn test_full_queue_loses_the_ack_not_the_frame() {
+        let clock = MockClock::new(0);
+        let mut a = node(1, &clock, Script::default());
+        let tdma = TdmaConfig { slots: 4, slot_ms: 100, guard_ms: 10 };
+        let config = NodeConfig { tdma: Some(tdma), ..NodeConfig::default() };
+        let mut b: Node = MeshNode::new(2, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        b.sync_time(40_000);
+        // Outside its TDMA slot b can't drain its queue
+        while b.send(3, Packet::Event(FlightEvent::Landed), false).is_ok() {}
+        a.send(2, Packet::Event(FlightEvent::Launch), true).unwrap();
+        a.poll().unwrap();
+        deliver(&mut a, &mut b);
+        let received = b.poll().unwrap();
+        assert!(matches!(received, Some(NodeEvent::Received { packet: Packet::Event(FlightEvent::Launch), .. })));
+    }
+
+    #[test]
+    fn test_frames_refused_for_queue_room_take_no_sequence_number() {
+        let clock = MockClock::new(0);
+        let mut a = node(1, &clock, Script::default());
+        let fragment = |index| {
+            Packet::Fragment(Fragment { message_id: 1, index, count: 8, total_len: 8, data: heapless::Vec::new() })
+        };
+        let mut sequence = 0;
+        for index in 0..BULK_QUEUE_LEN as u8 {
+            sequence = a.send_with(2, fragment(index), false, Priority::Bulk).unwrap();
+        }
+        assert!(matches!(a.send_with(2, fragment(7), false, Priority::Bulk), Err(NodeError::QueueFull)));
+        while let Ok(next) = a.send(2, Packet::Event(FlightEvent::Landed), false) {
+            sequence = next;
+        }
+        a.poll().unwrap();
+        assert_eq!(a.send(2, Packet::Event(FlightEvent::Landed), false).unwrap(), sequence + 1);
+    }
+
+    #[test]
+    fn test_echo_reflected_with_receipt() {
+        let clock = MockClock::new(0);
+        let mut a = node(1, &clock, Script::default());
+        let mut b = node(2, &clock, Script::default());
+        let echo = Echo { origin_uid: 1, id: 9, payload: Default::default(), receipt: None };
+        a.send(2, Packet::Echo(echo), false).unwrap();
+        a.poll().unwrap();
+        deliver(&mut a, &mut b);
+        b.poll().unwrap();
+        b.poll().unwrap();
+        deliver(&mut b, &mut a);
+        let Some(NodeEvent::Received { packet: Packet::Echo(reflected), .. }) = a.poll().unwrap() else {
+            panic!("expected the reflected echo");
+        };
+        assert_eq!((reflected.id, reflected.receipt.unwrap().responder_uid), (9, 2));
+    }
+
+    #[test]
+    fn test_unacknowledged_send_fails() {
+        let clock = MockClock::new(0);
+        let mut a = node(1, &clock, Script::default());
+        let sequence = a.send(9, Packet::Event(FlightEvent::Landed), true).unwrap();
+        let mut failed = None;
+        for step in 0..20 {
+            clock.set(step * 1_000);
+            if let Some(event) = a.poll().unwrap() {
+                failed = Some(event);
+                break;
+            }
+        }
+        assert_eq!(failed, Some(NodeEvent::DeliveryFailed { destination_uid: 9, sequence }));
+        assert_eq!(a.radio_mut().sent_len(), a.config.reliable.max_attempts as usize);
+    }
+
+    #[test]
+    fn test_hellos_fill_neighbor_table_and_limit_flooding() {
+        let clock = MockClock::new(0);
+        let config = NodeConfig { hello_interval_ms: Some(1_000), ..NodeConfig::default() };
+        let mut c = MeshNode::new(3, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        let (mut a, mut b) = (node(1, &clock, Script::default()), node(2, &clock, Script::default()));
+        for at in [0, 1_000, 2_000] {
+            clock.set(at);
+            // A due Hello is queued by one poll and sent by the next
+            c.poll().unwrap();
+            c.poll().unwrap();
+            deliver(&mut c, &mut b);
+            assert!(matches!(b.poll().unwrap(), Some(NodeEvent::Received { packet: Packet::Hello(_), .. })));
+        }
+        let neighbor = b.neighbors().get(3).unwrap();
+        assert_eq!((neighbor.success_rate(), neighbor.last_heard_ms), (1.0, 2_000));
+
+        // b relays a's frames; one for c, which b hears well, is not flooded past it
+        let mut forwarded_hops = |destination_uid| {
+            a.send(destination_uid, Packet::Event(FlightEvent::Launch), false).unwrap();
+            a.poll().unwrap();
+            deliver(&mut a, &mut b);
+            b.poll().unwrap();
+            clock.set(clock.now_ms() + 1_000);
+            b.poll().unwrap();
+            let frame = b.radio_mut().take_sent().unwrap();
+            postcard::from_bytes::<MeshFrame>(checksum::verify_and_strip(&frame).unwrap()).unwrap().header.hops_left
+        };
+        assert_eq!(forwarded_hops(3), 0);
+        assert_eq!(forwarded_hops(4), 2);
+    }
+
+    #[test]
+    fn test_full_queue_drains_with_a_hello_due() {
+        let clock = MockClock::new(0);
+        let config = NodeConfig { hello_interval_ms: Some(1_000), ..NodeConfig::default() };
+        let mut a: Node = MeshNode::new(1, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        a.poll().unwrap();
+        a.poll().unwrap();
+        while a.send(2, Packet::Event(FlightEvent::Landed), false).is_ok() {}
+        // The Hello due with the queue still full is skipped, and the next one goes out on time
+        clock.set(5_000);
+        for _ in 0..20 {
+            a.poll().unwrap();
+        }
+        clock.set(6_000);
+        a.poll().unwrap();
+        a.poll().unwrap();
+        let mut kinds = std::vec::Vec::new();
+        while let Some(frame) = a.radio_mut().take_sent() {
+            kinds.push(MeshFrame::decode(checksum::verify_and_strip(&frame).unwrap()).unwrap().packet.kind());
+        }
+        assert_eq!(kinds.iter().filter(|kind| **kind == PacketKind::Event).count(), TX_QUEUE_LEN);
+        assert_eq!(kinds.iter().filter(|kind| **kind == PacketKind::Hello).count(), 2);
+    }
+
+    #[test]
+    fn test_source_route_falls_back_to_flooding_past_an_unheard_hop() {
+        let clock = MockClock::new(0);
+        let config = NodeConfig { hello_interval_ms: Some(1_000), ..NodeConfig::default() };
+        let mut c = MeshNode::new(3, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        let (mut a, mut b) = (node(1, &clock, Script::default()), node(2, &clock, Script::default()));
+        c.poll().unwrap();
+        c.poll().unwrap();
+        deliver(&mut c, &mut b);
+        b.poll().unwrap();
+        let launch = Packet::Event(FlightEvent::Launch);
+        let too_long = a.send_source_routed(9, &[2, 3, 4, 5, 6], launch.clone(), false);
+        assert!(matches!(too_long, Err(NodeError::RouteTooLong)));
+
+        // b hears c's Hellos but not node 4's
+        let mut relayed = |relays: &[u8]| {
+            a.send_source_routed(9, relays, launch.clone(), false).unwrap();
+            a.poll().unwrap();
+            deliver(&mut a, &mut b);
+            b.poll().unwrap();
+            clock.set(clock.now_ms() + 1_000);
+            b.poll().unwrap();
+            let frame = b.radio_mut().take_sent().unwrap();
+            postcard::from_bytes::<MeshFrame>(checksum::verify_and_strip(&frame).unwrap()).unwrap().header
+        };
+        let header = relayed(&[2, 3]);
+        assert_eq!((header.route.and_then(|route| route.next_hop()), header.hops_left), (Some(3), 3));
+        let header = relayed(&[2, 4]);
+        assert_eq!((header.route, header.hops_left), (None, 3));
+    }
+
+    #[test]
+    fn test_relay_holds_frames_until_destination_is_heard() {
+        let clock = MockClock::new(0);
+        let config = NodeConfig { store_for: Some(3), ..NodeConfig::default() };
+        let mut relay_node = MeshNode::new(2, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        let config = NodeConfig { hello_interval_ms: Some(5_000), ..NodeConfig::default() };
+        let mut ground = MeshNode::new(3, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        let mut rocket = node(1, &clock, Script::default());
+
+        for event in [FlightEvent::Launch, FlightEvent::Landed] {
+            rocket.send(3, Packet::Event(event), false).unwrap();
+            rocket.poll().unwrap();
+        }
+        deliver(&mut rocket, &mut relay_node);
+        relay_node.poll().unwrap();
+        relay_node.poll().unwrap();
+        assert_eq!((relay_node.held().pending(3), relay_node.radio_mut().sent_len()), (2, 0));
+
+        // The ground station comes over the horizon and its Hello flushes the held frames
+        ground.poll().unwrap();
+        ground.poll().unwrap();
+        deliver(&mut ground, &mut relay_node);
+        for _ in 0..3 {
+            relay_node.poll().unwrap();
+        }
+        assert!(relay_node.held().is_empty());
+        deliver(&mut relay_node, &mut ground);
+        let mut received = std::vec::Vec::new();
+        while let Some(event) = ground.poll().unwrap() {
+            if let NodeEvent::Received { packet: Packet::Event(event), .. } = event {
+                received.push(event);
+            }
+        }
+        assert_eq!(received, [FlightEvent::Launch, FlightEvent::Landed]);
+    }
+
+    #[test]
+    fn test_tdma_holds_frames_for_own_slots() {
+        let clock = MockClock::new(0);
+        let tdma = TdmaConfig { slots: 4, slot_ms: 100, guard_ms: 10 };
+        let config = NodeConfig { tdma: Some(tdma), ..NodeConfig::default() };
+        let mut a = MeshNode::new(1, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        let mut b = MeshNode::new(2, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        a.sync_time(40_000);
+        b.sync_time(40_000);
+
+        // Slot 1 is a's by default, its extra slot lands halfway round the superframe
+        a.request_slots(1).unwrap();
+        a.poll().unwrap();
+        assert_eq!(a.radio_mut().sent_len(), 0);
+        clock.set(100);
+        a.poll().unwrap();
+        deliver(&mut a, &mut b);
+        b.poll().unwrap();
+        assert_eq!(b.tdma().unwrap().owner(3), Some(1));
+
+        // a's next frame waits for slot 3, then a has used it up
+        a.send(2, Packet::Event(FlightEvent::Launch), false).unwrap();
+        clock.set(200);
+        a.poll().unwrap();
+        assert_eq!(a.radio_mut().sent_len(), 0);
+        clock.set(300);
+        a.poll().unwrap();
+        assert_eq!(a.radio_mut().sent_len(), 1);
+        // Only slot 0 is left for b
+        assert!(matches!(b.request_slots(2), Err(NodeError::NoFreeSlot)));
+    }
+
+    #[test]
+    fn test_busy_channel_defers_then_drops_frames() {
+        let clock = MockClock::new(0);
+        let csma = CsmaConfig { max_attempts: 2, initial_window_ms: 100, max_window_ms: 100 };
+        let config = NodeConfig { csma: Some(csma), ..NodeConfig::default() };
+        let mut a: Node = MeshNode::new(1, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        a.radio_mut().busy = Some(true);
+
+        a.send(2, Packet::Event(FlightEvent::Launch), false).unwrap();
+        a.poll().unwrap();
+        clock.set(100);
+        a.poll().unwrap();
+        assert_eq!(a.radio_mut().sent_len(), 0);
+        a.send(2, Packet::Event(FlightEvent::Landed), false).unwrap();
+        a.radio_mut().busy = Some(false);
+        a.poll().unwrap();
+
+        let frame = a.radio_mut().take_sent().unwrap();
+        let MeshFrame { packet, .. } = postcard::from_bytes(checksum::verify_and_strip(&frame).unwrap()).unwrap();
+        assert_eq!(packet, Packet::Event(FlightEvent::Landed));
+        assert_eq!(a.csma().unwrap().stats(), CsmaStats { transmitted: 1, deferred: 1, aborted: 1 });
+    }
+
+    #[test]
+    fn test_emergency_flooded_past_busy_channel() {
+        let clock = MockClock::new(0);
+        let csma = CsmaConfig { max_attempts: 2, initial_window_ms: 100, max_window_ms: 100 };
+        let config = NodeConfig { csma: Some(csma), ..NodeConfig::default() };
+        let mut a: Node = MeshNode::new(1, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        let mut b: Node = MeshNode::new(2, MockRadio::default(), &clock, MemoryStore::new(), config).unwrap();
+        a.radio_mut().busy = Some(true);
+        b.radio_mut().busy = Some(true);
+
+        a.send(BROADCAST_UID, Packet::Event(FlightEvent::Launch), false).unwrap();
+        let hold = Emergency { uid: 1, kind: EmergencyKind::MisfireHold, text: "pad 3".try_into().unwrap() };
+        // Addressed to one node and reliable, but flooded anyway
+        a.send(2, Packet::Emergency(hold.clone()), true).unwrap();
+        a.poll().unwrap();
+        let frame = a.radio_mut().take_sent().unwrap();
+        assert_eq!(a.radio_mut().sent_len(), 0);
+
+        let MeshFrame { header, packet } = postcard::from_bytes(checksum::verify_and_strip(&frame).unwrap()).unwrap();
+        assert_eq!((header.destination_uid, header.hops_left, header.ack_requested), (BROADCAST_UID, MAX_HOPS, false));
+        b.radio_mut().inject(&frame);
+        let Some(NodeEvent::Emergency { header, emergency, .. }) = b.poll().unwrap() else {
+            panic!("expected an Emergency event");
+        };
+        assert_eq!((header.source_uid, emergency), (1, hold.clone()));
+        assert_eq!(packet, Packet::Emergency(hold));
+        clock.set(1_000);
+        b.poll().unwrap();
+        let relayed = b.radio_mut().take_sent().unwrap();
+        let MeshFrame { header, .. } = postcard::from_bytes(checksum::verify_and_strip(&relayed).unwrap()).unwrap();
+        assert_eq!(header.hops_left, MAX_HOPS - 1);
+    }
+}
@@ -0,0 +1,227 @@
+use heapless::Vec;
+
+use crate::protocol::mesh::MAX_FRAME_LEN;
+use crate::protocol::packet::Packet;
+
+/// Number of frames that can wait to be transmitted
+pub const TX_QUEUE_LEN: usize = 8;
+/// Bulk frames that can wait at once, so a satellite dump never fills the queue
+pub const BULK_QUEUE_LEN: usize = TX_QUEUE_LEN / 2;
+/// Slots kept for Emergency and Critical frames, so Acks and commands find room behind routine traffic
+pub const CRITICAL_RESERVE: usize = 2;
+/// Frames sent ahead of a due Bulk frame before it goes first, unless an Emergency or Critical frame is due
+pub const BULK_STARVATION_LIMIT: u8 = 4;
+
+/// An encoded frame ready for the radio
+pub type FrameBuf = Vec<u8, MAX_FRAME_LEN>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+/// Transmit priority class, due frames of a more urgent class go out first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    /// Range-safety broadcasts, sent outside TDMA slots and without listening first
+    Emergency,
+    /// Acknowledgements, commands and flight events
+    Critical,
+    /// Position and state for tracking
+    High,
+    #[default]
+    Normal,
+    /// Large transfers that can wait for idle airtime: satellite tables and fragmented messages
+    Bulk,
+}
+
+impl Priority {
+    /// The class a packet is sent in unless the caller picks one
+    pub fn of(packet: &Packet) -> Self {
+        match packet {
+            Packet::Emergency(_) => Priority::Emergency,
+            Packet::Ack(_) | Packet::Command(_) | Packet::Event(_) | Packet::Sealed(_) => Priority::Critical,
+            Packet::Sensors(sensors) => match sensors.gps {
+                Some(gps) if gps.sats_data.num_svs > 0 => Priority::Bulk,
+                Some(_) => Priority::High,
+                None => Priority::Normal,
+            },
+            Packet::StateEstimate(_) | Packet::TrackerStatus(_) | Packet::RecoveryReport(_) => Priority::High,
+            Packet::Fragment(_) => Priority::Bulk,
+            _ => Priority::Normal,
+        }
+    }
+}
+
+/// Scheduler holds encoded frames until their send time, releasing the most urgent due frame first
+///
+/// High, Normal and Bulk frames leave the last `CRITICAL_RESERVE` slots free. Bulk frames are further
+/// limited to `BULK_QUEUE_LEN` of the queue and go out after at most `BULK_STARVATION_LIMIT` more urgent
+/// frames, so a busy channel delays them without shutting them out.
+#[derive(Debug, Clone, Default)]
+pub struct Scheduler {
+    queue: Vec<(Priority, u64, FrameBuf), TX_QUEUE_LEN>,
+    /// Frames sent while a Bulk frame was due
+    bulk_passed_over: u8,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    pub fn push(&mut self, frame: &[u8], send_at_ms: u64) -> Result<(), QueueFull> {
+        self.push_with(frame, send_at_ms, Priority::Normal)
+    }
+
+    /// Whether `push_with` would accept a frame in `priority`
+    pub fn has_room(&self, priority: Priority) -> bool {
+        let count = |class| self.queue.iter().filter(|(p, _, _)| *p == class).count();
+        match priority {
+            // An Emergency frame can evict anything but another Emergency frame
+            Priority::Emergency => count(Priority::Emergency) < TX_QUEUE_LEN,
+            Priority::Critical => !self.queue.is_full(),
+            Priority::Bulk if count(Priority::Bulk) >= BULK_QUEUE_LEN => false,
+            _ => self.queue.len() < TX_QUEUE_LEN - CRITICAL_RESERVE,
+        }
+    }
+
+    /// Queues a frame in `priority`; an Emergency frame makes room by dropping the least urgent, latest frame
+    pub fn push_with(&mut self, frame: &[u8], send_at_ms: u64, priority: Priority) -> Result<(), QueueFull> {
+        if !self.has_room(priority) {
+            return Err(QueueFull);
+        }
+        let frame = FrameBuf::from_slice(frame).map_err(|_| QueueFull)?;
+        if self.queue.is_full() {
+            // Only an Emergency frame gets this far, and there is a less urgent one to drop
+            let evict = self.queue.iter().enumerate().max_by_key(|(i, (priority, at, _))| (*priority, *at, *i));
+            if let Some((index, _)) = evict {
+                self.queue.remove(index);
+            }
+        }
+        self.queue.push((priority, send_at_ms, frame)).map_err(|_| QueueFull)
+    }
+
+    /// Whether a frame is due at `now_ms`
+    pub fn has_due(&self, now_ms: u64) -> bool {
+        self.queue.iter().any(|(_, at, _)| *at <= now_ms)
+    }
+
+    /// Most urgent class among the frames due at `now_ms`
+    pub fn due_priority(&self, now_ms: u64) -> Option<Priority> {
+        self.queue.iter().filter(|(_, at, _)| *at <= now_ms).map(|(priority, _, _)| *priority).min()
+    }
+
+    /// Removes and returns the highest priority, then earliest, frame due at `now_ms`; ties go to the frame
+    /// queued first
+    pub fn pop_due(&mut self, now_ms: u64) -> Option<FrameBuf> {
+        let due = || self.queue.iter().enumerate().filter(|(_, (_, at, _))| *at <= now_ms);
+        let (mut index, &(priority, _, _)) = due().min_by_key(|(i, (priority, at, _))| (*priority, *at, *i))?;
+        let bulk = due().filter(|(_, (p, _, _))| *p == Priority::Bulk).min_by_key(|(i, (_, at, _))| (*at, *i));
+        let starved = priority > Priority::Critical && self.bulk_passed_over >= BULK_STARVATION_LIMIT;
+        match bulk {
+            Some((bulk_index, _)) if starved => {
+                index = bulk_index;
+                self.bulk_passed_over = 0;
+            }
+            Some(_) if priority != Priority::Bulk => self.bulk_passed_over = self.bulk_passed_over.saturating_add(1),
+            _ => self.bulk_passed_over = 0,
+        }
+        Some(self.queue.remove(index).2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_earliest_due_first() {
+        let mut scheduler = Scheduler::new();
+        scheduler.push(&[1], 100).unwrap();
+        scheduler.push(&[2], 50).unwrap();
+        scheduler.push(&[3], 50).unwrap();
+        assert_eq!(scheduler.pop_due(10), None);
+        assert_eq!(scheduler.pop_due(100).as_deref(), Some(&[2][..]));
+        assert_eq!(scheduler.pop_due(100).as_deref(), Some(&[3][..]));
+        assert_eq!(scheduler.pop_due(100).as_deref(), Some(&[1][..]));
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn test_high_priority_jumps_due_frames() {
+        let mut scheduler = Scheduler::new();
+        scheduler.push(&[1], 0).unwrap();
+        scheduler.push_with(&[2], 20, Priority::High).unwrap();
+        assert_eq!(scheduler.pop_due(10).as_deref(), Some(&[1][..]));
+        scheduler.push(&[3], 0).unwrap();
+        assert_eq!(scheduler.pop_due(20).as_deref(), Some(&[2][..]));
+    }
+
+    #[test]
+    fn test_bulk_capped_and_not_starved() {
+        let mut scheduler = Scheduler::new();
+        for n in 0..BULK_QUEUE_LEN as u8 {
+            scheduler.push_with(&[100 + n], 0, Priority::Bulk).unwrap();
+        }
+        assert_eq!(scheduler.push_with(&[99], 0, Priority::Bulk), Err(QueueFull));
+
+        // A steady stream of position updates lets one bulk frame through every BULK_STARVATION_LIMIT frames
+        let mut sent = std::vec::Vec::new();
+        for n in 0..2 * BULK_STARVATION_LIMIT {
+            scheduler.push_with(&[n], 0, Priority::High).unwrap();
+            sent.push(scheduler.pop_due(0).unwrap()[0]);
+        }
+        assert_eq!(sent, [0, 1, 2, 3, 100, 4, 5, 6]);
+        // but never ahead of an ack
+        assert_eq!(scheduler.pop_due(0).as_deref(), Some(&[7][..]));
+        scheduler.push_with(&[50], 0, Priority::Critical).unwrap();
+        assert_eq!(scheduler.pop_due(0).as_deref(), Some(&[50][..]));
+        assert_eq!(scheduler.pop_due(0).as_deref(), Some(&[101][..]));
+    }
+
+    #[test]
+    fn test_routine_traffic_leaves_room_for_critical() {
+        let mut scheduler = Scheduler::new();
+        for n in 0..(TX_QUEUE_LEN - CRITICAL_RESERVE) as u8 {
+            scheduler.push_with(&[n], 0, if n % 2 == 0 { Priority::Normal } else { Priority::High }).unwrap();
+        }
+        assert!(!scheduler.has_room(Priority::Normal));
+        assert_eq!(scheduler.push_with(&[20], 0, Priority::High), Err(QueueFull));
+        for n in 0..CRITICAL_RESERVE as u8 {
+            scheduler.push_with(&[30 + n], 0, Priority::Critical).unwrap();
+        }
+        assert!(scheduler.is_full() && !scheduler.has_room(Priority::Critical));
+        assert!(scheduler.has_room(Priority::Emergency));
+    }
+
+    #[test]
+    fn test_emergency_evicts_from_full_queue() {
+        let mut scheduler = Scheduler::new();
+        for n in 0..TX_QUEUE_LEN as u8 {
+            scheduler.push_with(&[n], 0, if n == 2 { Priority::Bulk } else { Priority::Critical }).unwrap();
+        }
+        assert_eq!(scheduler.push_with(&[50], 0, Priority::Critical), Err(QueueFull));
+        scheduler.push_with(&[60], 10, Priority::Emergency).unwrap();
+        assert_eq!(scheduler.due_priority(0), Some(Priority::Critical));
+        assert_eq!(scheduler.due_priority(10), Some(Priority::Emergency));
+        assert_eq!(scheduler.pop_due(10).as_deref(), Some(&[60][..]));
+
+        let mut sent = std::vec::Vec::new();
+        while let Some(frame) = scheduler.pop_due(10) {
+            sent.push(frame[0]);
+        }
+        // The bulk frame made room
+        assert_eq!(sent, [0, 1, 3, 4, 5, 6, 7]);
+    }
+}
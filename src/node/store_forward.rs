@@ -0,0 +1,200 @@
+//! Holding frames for a node that is out of reach until it is heard again
+
+use heapless::Vec;
+
+use super::scheduler::{FrameBuf, Priority, QueueFull, Scheduler};
+use crate::protocol::packet::Packet;
+
+/// Frames held across all destinations
+pub const STORE_LEN: usize = 16;
+
+/// Order in which held frames are flushed and kept, most important first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StoreClass {
+    Ack,
+    /// Sensor data with only a GPS fix, enough to find the vehicle
+    Position,
+    Other,
+    /// Full sensor dumps, the first to go when space runs out
+    Sensors,
+}
+
+impl StoreClass {
+    pub fn of(packet: &Packet) -> Self {
+        match packet {
+            Packet::Ack(_) => StoreClass::Ack,
+            Packet::Sensors(sensors) => {
+                let others = [sensors.ism330dhcx.is_some(), sensors.lsm6dso32.is_some(), sensors.bmp390.is_some()];
+                let others = others.contains(&true) || sensors.adxl375.is_some() || sensors.ism330dhcx2.is_some();
+                if sensors.gps.is_some() && !others {
+                    StoreClass::Position
+                } else {
+                    StoreClass::Sensors
+                }
+            }
+            Packet::SensorFrame(_) => StoreClass::Sensors,
+            _ => StoreClass::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Held {
+    destination_uid: u8,
+    class: StoreClass,
+    stored_ms: u64,
+    frame: FrameBuf,
+}
+
+/// StoreAndForward holds encoded frames per destination while it is unreachable
+///
+/// When full, a new frame displaces the least important held frame, the oldest among equals, if that one
+/// is no more important than the new frame. Frames older than `max_age_ms` are dropped.
+#[derive(Debug, Clone)]
+pub struct StoreAndForward {
+    max_age_ms: u64,
+    held: Vec<Held, STORE_LEN>,
+    dropped: u32,
+}
+
+impl StoreAndForward {
+    pub fn new(max_age_ms: u64) -> Self {
+        Self { max_age_ms, held: Vec::new(), dropped: 0 }
+    }
+
+    /// Holds `frame` for `destination_uid`
+    pub fn store(
+        &mut self,
+        destination_uid: u8,
+        class: StoreClass,
+        frame: &[u8],
+        now_ms: u64,
+    ) -> Result<(), QueueFull> {
+        let frame = FrameBuf::from_slice(frame).map_err(|_| QueueFull)?;
+        self.expire(now_ms);
+        if self.held.is_full() {
+            let (index, victim) = self
+                .held
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, held)| (held.class, core::cmp::Reverse(held.stored_ms)))
+                .expect("store is full");
+            if victim.class < class {
+                self.dropped += 1;
+                return Err(QueueFull);
+            }
+            self.held.remove(index);
+            self.dropped += 1;
+        }
+        let _ = self.held.push(Held { destination_uid, class, stored_ms: now_ms, frame });
+        Ok(())
+    }
+
+    /// Drops frames held longer than the maximum age
+    pub fn expire(&mut self, now_ms: u64) {
+        let before = self.held.len();
+        self.held.retain(|held| now_ms.saturating_sub(held.stored_ms) <= self.max_age_ms);
+        self.dropped += (before - self.held.len()) as u32;
+    }
+
+    /// Frames held for `destination_uid`
+    pub fn pending(&self, destination_uid: u8) -> usize {
+        self.held.iter().filter(|held| held.destination_uid == destination_uid).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Frames displaced, expired or refused since creation
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Removes the next frame for `destination_uid`, most important class first and oldest first within it
+    pub fn pop(&mut self, destination_uid: u8) -> Option<FrameBuf> {
+        let (index, _) = self
+            .held
+            .iter()
+            .enumerate()
+            .filter(|(_, held)| held.destination_uid == destination_uid)
+            .min_by_key(|(_, held)| (held.class, held.stored_ms))?;
+        Some(self.held.remove(index).frame)
+    }
+
+    /// Moves the frames for a destination that just reconnected into `scheduler`, as many as fit
+    ///
+    /// Returns how many were queued; the rest stay held for the next flush.
+    pub fn flush(&mut self, destination_uid: u8, scheduler: &mut Scheduler, now_ms: u64) -> usize {
+        self.expire(now_ms);
+        let mut flushed = 0;
+        while scheduler.has_room(Priority::Normal) {
+            let Some(frame) = self.pop(destination_uid) else {
+                break;
+            };
+            flushed += scheduler.push(&frame, now_ms).is_ok() as usize;
+        }
+        flushed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::mesh::Ack;
+    use crate::protocol::{AllSensorData, GpsFix, NavSat, BMP390, GPS, UTC};
+
+    fn sensors(bmp390: bool) -> Packet {
+        let gps = GPS {
+            latitude: 37.2,
+            longitude: -80.4,
+            altitude: 600.0,
+            altitude_msl: 610.0,
+            num_sats: 9,
+            fix_type: GpsFix::Fix3D,
+            utc_time: UTC::default(),
+            sats_data: NavSat::default(),
+        };
+        Packet::Sensors(AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: bmp390.then_some(BMP390 { pressure: 90_000.0, temperature: 20.0, altitude: 900.0 }),
+            gps: Some(gps),
+            adxl375: None,
+            ism330dhcx2: None,
+        })
+    }
+
+    #[test]
+    fn test_priority_eviction_age_and_flush() {
+        assert_eq!(StoreClass::of(&Packet::Ack(Ack { sequence: 1 })), StoreClass::Ack);
+        assert_eq!(StoreClass::of(&sensors(false)), StoreClass::Position);
+        assert_eq!(StoreClass::of(&sensors(true)), StoreClass::Sensors);
+
+        let mut store = StoreAndForward::new(60_000);
+        for i in 0..STORE_LEN as u8 {
+            store.store(1, StoreClass::Sensors, &[i], i as u64).unwrap();
+        }
+        // A full store gives up its oldest sensor dump for anything at least as important
+        store.store(1, StoreClass::Position, b"gps", 100).unwrap();
+        store.store(1, StoreClass::Ack, b"ack", 200).unwrap();
+        store.store(2, StoreClass::Sensors, b"other", 300).unwrap();
+        assert_eq!((store.len(), store.pending(2), store.dropped()), (STORE_LEN, 1, 3));
+        assert_eq!(store.pop(1).as_deref(), Some(&b"ack"[..]));
+        assert_eq!(store.pop(1).as_deref(), Some(&b"gps"[..]));
+        assert_eq!(store.pop(1).as_deref(), Some(&[3][..]));
+
+        // Dumps stored at 4..=15 ms expire, the one for node 2 is younger
+        store.expire(60_016);
+        assert_eq!((store.pending(1), store.pending(2)), (0, 1));
+
+        let mut scheduler = Scheduler::new();
+        assert_eq!(store.flush(2, &mut scheduler, 60_016), 1);
+        assert_eq!(scheduler.pop_due(60_016).as_deref(), Some(&b"other"[..]));
+        assert!(store.is_empty());
+    }
+}
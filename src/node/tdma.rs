@@ -0,0 +1,207 @@
+//! Time-division slots on a shared frequency, so nodes stop colliding when everyone talks at once
+//!
+//! GPS time is divided into superframes of `TdmaConfig::slots` slots. Every node owns the slot
+//! `uid % slots` by default and may claim free slots on top for high-rate telemetry, announcing them with
+//! a `SlotClaim`. A node only starts one frame per owned slot, and only before the guard time at the end of
+//! the slot, so `slot_ms` must cover the airtime of the largest frame plus the guard.
+
+use crate::protocol::tdma::SlotClaim;
+
+/// Largest superframe, one bit per slot in a SlotClaim
+pub const MAX_SLOTS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TdmaConfig {
+    /// Slots per superframe, 1..=MAX_SLOTS
+    pub slots: u8,
+    pub slot_ms: u32,
+    /// Time at the end of each slot in which no transmission starts, absorbing clock error between nodes
+    pub guard_ms: u32,
+}
+
+impl Default for TdmaConfig {
+    fn default() -> Self {
+        Self { slots: 8, slot_ms: 500, guard_ms: 30 }
+    }
+}
+
+impl TdmaConfig {
+    pub fn superframe_ms(&self) -> u64 {
+        self.slots() as u64 * self.slot_ms as u64
+    }
+
+    fn slots(&self) -> usize {
+        (self.slots as usize).clamp(1, MAX_SLOTS)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoFreeSlot;
+
+/// Tdma tracks slot ownership and decides when this node may transmit
+#[derive(Debug, Clone)]
+pub struct Tdma {
+    uid: u8,
+    config: TdmaConfig,
+    /// Owner of each claimed slot
+    claims: [Option<u8>; MAX_SLOTS],
+    /// Nodes heard on the mesh, whose default slots are not free to claim
+    heard: [u32; 8],
+    /// GPS time minus the local clock, unknown until the first fix
+    offset_ms: Option<i64>,
+    /// Absolute number of the last slot this node transmitted in
+    last_slot: Option<u64>,
+}
+
+impl Tdma {
+    pub fn new(uid: u8, config: TdmaConfig) -> Self {
+        let mut tdma = Self { uid, config, claims: [None; MAX_SLOTS], heard: [0; 8], offset_ms: None, last_slot: None };
+        tdma.heard(uid);
+        tdma
+    }
+
+    pub fn config(&self) -> &TdmaConfig {
+        &self.config
+    }
+
+    /// Aligns the superframe to GPS time, `gps_ms` being GPS milliseconds read at local time `now_ms`
+    pub fn sync(&mut self, gps_ms: u64, now_ms: u64) {
+        self.offset_ms = Some(gps_ms as i64 - now_ms as i64);
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.offset_ms.is_some()
+    }
+
+    /// Records that `uid` is on the mesh, reserving its default slot
+    pub fn heard(&mut self, uid: u8) {
+        self.heard[uid as usize / 32] |= 1 << (uid % 32);
+    }
+
+    /// Whether `uid` transmits in slot `index`
+    pub fn owner(&self, index: usize) -> Option<u8> {
+        self.claims.get(index).copied().flatten().or_else(|| {
+            (0..=u8::MAX).find(|&uid| self.is_heard(uid) && uid as usize % self.config.slots() == index)
+        })
+    }
+
+    /// Claims `count` more free slots, returning the claim to broadcast
+    ///
+    /// Slots are spread across the superframe so high-rate telemetry goes out at even intervals. Nothing is
+    /// claimed unless all `count` slots are free.
+    pub fn request_slots(&mut self, count: usize) -> Result<SlotClaim, NoFreeSlot> {
+        let slots = self.config.slots();
+        let home = self.uid as usize % slots;
+        let mut claims = self.claims;
+        for step in 1..=count {
+            // Ideal position of the step-th extra slot, then the nearest free slot after it
+            let ideal = home + step * slots / (count + 1);
+            let free = (0..slots).map(|offset| (ideal + offset) % slots).find(|&index| {
+                claims[index].is_none() && !self.defaults_to_other(index)
+            });
+            let index = free.ok_or(NoFreeSlot)?;
+            claims[index] = Some(self.uid);
+        }
+        self.claims = claims;
+        Ok(self.claim())
+    }
+
+    /// Gives up every extra slot, returning the claim to broadcast
+    pub fn release_slots(&mut self) -> SlotClaim {
+        self.clear(self.uid);
+        self.claim()
+    }
+
+    /// Applies another node's claim, replacing its previous one
+    ///
+    /// A claim on a slot this node also claimed wins, so two nodes racing for a slot never both keep it.
+    pub fn on_claim(&mut self, claim: &SlotClaim) {
+        self.heard(claim.uid);
+        self.clear(claim.uid);
+        for (index, owner) in self.claims.iter_mut().enumerate().take(self.config.slots()) {
+            if claim.slots & (1 << index) != 0 {
+                *owner = Some(claim.uid);
+            }
+        }
+    }
+
+    /// Whether this node may start a frame at local time `now_ms`
+    ///
+    /// Before the first GPS sync there is no common time base, so transmission is unrestricted.
+    pub fn may_transmit(&self, now_ms: u64) -> bool {
+        let Some((slot, index, into_ms)) = self.slot_at(now_ms) else {
+            return true;
+        };
+        let owned = self.claims[index] == Some(self.uid)
+            || (self.claims[index].is_none() && self.uid as usize % self.config.slots() == index);
+        let before_guard = into_ms + (self.config.guard_ms as u64) < self.config.slot_ms as u64;
+        owned && before_guard && self.last_slot != Some(slot)
+    }
+
+    /// Records a transmission started at `now_ms`, using up the current slot
+    pub fn transmitted(&mut self, now_ms: u64) {
+        self.last_slot = self.slot_at(now_ms).map(|(slot, _, _)| slot);
+    }
+
+    /// Absolute slot number, index within the superframe and time into the slot at local `now_ms`
+    fn slot_at(&self, now_ms: u64) -> Option<(u64, usize, u64)> {
+        let gps_ms = (now_ms as i64 + self.offset_ms?).max(0) as u64;
+        let slot = gps_ms / self.config.slot_ms.max(1) as u64;
+        Some((slot, (slot % self.config.slots() as u64) as usize, gps_ms % self.config.slot_ms.max(1) as u64))
+    }
+
+    fn claim(&self) -> SlotClaim {
+        let slots = self.claims.iter().enumerate().filter(|(_, owner)| **owner == Some(self.uid));
+        SlotClaim { uid: self.uid, slots: slots.fold(0, |mask, (index, _)| mask | 1 << index) }
+    }
+
+    fn clear(&mut self, uid: u8) {
+        for owner in self.claims.iter_mut().filter(|owner| **owner == Some(uid)) {
+            *owner = None;
+        }
+    }
+
+    fn is_heard(&self, uid: u8) -> bool {
+        self.heard[uid as usize / 32] & (1 << (uid % 32)) != 0
+    }
+
+    fn defaults_to_other(&self, index: usize) -> bool {
+        (0..=u8::MAX).any(|uid| self.is_heard(uid) && uid as usize % self.config.slots() == index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_and_extra_slots() {
+        let config = TdmaConfig { slots: 8, slot_ms: 100, guard_ms: 10 };
+        let mut tdma = Tdma::new(2, config);
+        assert!(tdma.may_transmit(0));
+
+        // Local 1_000 is the start of a superframe in GPS time, so slot 2 starts at local 1_200
+        tdma.sync(6_400, 1_000);
+        assert!(!tdma.may_transmit(1_150));
+        assert!(tdma.may_transmit(1_200));
+        tdma.transmitted(1_200);
+        assert!(!tdma.may_transmit(1_250));
+        assert!(!tdma.may_transmit(1_295));
+
+        // Slot 6 belongs to node 6 once it is heard, so the two extra slots go to 4 and 7
+        tdma.heard(6);
+        let claim = tdma.request_slots(2).unwrap();
+        assert_eq!(claim, SlotClaim { uid: 2, slots: 1 << 4 | 1 << 7 });
+        assert!(tdma.may_transmit(1_400));
+        assert_eq!(tdma.owner(7), Some(2));
+
+        // Node 5 wins slot 7 and takes its default slot back by releasing
+        tdma.on_claim(&SlotClaim { uid: 5, slots: 1 << 7 });
+        assert_eq!((tdma.owner(4), tdma.owner(7)), (Some(2), Some(5)));
+        assert!(!tdma.may_transmit(1_700));
+        tdma.on_claim(&SlotClaim { uid: 5, slots: 0 });
+        assert_eq!(tdma.owner(7), None);
+        assert!(tdma.request_slots(8).is_err());
+        assert_eq!(tdma.release_slots().slots, 0);
+    }
+}
@@ -0,0 +1,66 @@
+use super::{ParamStore, ParamValue};
+
+/// 32-bit FNV-1a, for detecting configuration drift rather than tampering
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv(u32);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0x811C_9DC5)
+    }
+}
+
+impl Fnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u32).wrapping_mul(0x0100_0193);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Hash of every parameter, independent of the order they were set in
+pub fn params_hash(params: &ParamStore) -> u32 {
+    let mut entries = params.params.clone();
+    entries.sort_unstable_by_key(|(id, _)| *id);
+    let mut hash = Fnv::new();
+    for (id, value) in &entries {
+        hash.write(&id.to_le_bytes());
+        // Tagged with the type so Bool(true) and U32(1) differ
+        let (tag, bytes) = match value {
+            ParamValue::Bool(v) => (0, (*v as u32).to_le_bytes()),
+            ParamValue::U32(v) => (1, v.to_le_bytes()),
+            ParamValue::I32(v) => (2, v.to_le_bytes()),
+            ParamValue::F32(v) => (3, v.to_bits().to_le_bytes()),
+        };
+        hash.write(&[tag]);
+        hash.write(&bytes);
+    }
+    hash.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_hash_ignores_order_but_not_values() {
+        let mut a = ParamStore::new();
+        a.set(1, ParamValue::U32(10)).unwrap();
+        a.set(2, ParamValue::Bool(true)).unwrap();
+        let mut b = ParamStore::new();
+        b.set(2, ParamValue::Bool(true)).unwrap();
+        b.set(1, ParamValue::U32(10)).unwrap();
+        assert_eq!(params_hash(&a), params_hash(&b));
+
+        b.set(1, ParamValue::U32(20)).unwrap();
+        assert_ne!(params_hash(&a), params_hash(&b));
+    }
+}
@@ -0,0 +1,91 @@
+pub mod digest;
+
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::{self, keys, Persistence};
+
+/// Maximum number of parameters held by a ParamStore
+pub const MAX_PARAMS: usize = 32;
+/// Scratch buffer size needed to load or save a full ParamStore
+pub const PARAMS_BLOB_LEN: usize = MAX_PARAMS * 10 + 4;
+
+/// Well-known parameter IDs
+pub mod ids {
+    pub const TELEMETRY_RATE_HZ: u16 = 1;
+    pub const TX_POWER_DBM: u16 = 2;
+    /// `radio::region::Region` as U32
+    pub const REGION: u16 = 3;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ParamValue {
+    Bool(bool),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+}
+
+/// ParamStore is the node's runtime-tunable parameter table, persisted through any Persistence backend
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ParamStore {
+    params: Vec<(u16, ParamValue), MAX_PARAMS>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamsFull;
+
+impl ParamStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: u16) -> Option<ParamValue> {
+        self.params.iter().find(|(k, _)| *k == id).map(|(_, v)| *v)
+    }
+
+    pub fn set(&mut self, id: u16, value: ParamValue) -> Result<(), ParamsFull> {
+        match self.params.iter_mut().find(|(k, _)| *k == id) {
+            Some(entry) => entry.1 = value,
+            None => self.params.push((id, value)).map_err(|_| ParamsFull)?,
+        }
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(u16, ParamValue)> {
+        self.params.iter()
+    }
+
+    /// Loads the stored parameters, or an empty store if none were saved yet
+    pub fn load<P: Persistence>(store: &mut P) -> Result<Self, persistence::Error<P::Error>> {
+        let mut buf = [0u8; PARAMS_BLOB_LEN];
+        Ok(persistence::load(store, keys::PARAMS, &mut buf)?.unwrap_or_default())
+    }
+
+    pub fn save<P: Persistence>(&self, store: &mut P) -> Result<(), persistence::Error<P::Error>> {
+        let mut buf = [0u8; PARAMS_BLOB_LEN];
+        persistence::save(store, keys::PARAMS, self, &mut buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryStore;
+
+    #[test]
+    fn test_params_persist() {
+        let mut store: MemoryStore<4, PARAMS_BLOB_LEN> = MemoryStore::new();
+        assert_eq!(ParamStore::load(&mut store).unwrap(), ParamStore::new());
+
+        let mut params = ParamStore::new();
+        params.set(ids::TELEMETRY_RATE_HZ, ParamValue::U32(10)).unwrap();
+        params.set(ids::TX_POWER_DBM, ParamValue::I32(20)).unwrap();
+        params.set(ids::TELEMETRY_RATE_HZ, ParamValue::U32(5)).unwrap();
+        params.save(&mut store).unwrap();
+
+        let loaded = ParamStore::load(&mut store).unwrap();
+        assert_eq!(loaded.get(ids::TELEMETRY_RATE_HZ), Some(ParamValue::U32(5)));
+        assert_eq!(loaded.get(ids::TX_POWER_DBM), Some(ParamValue::I32(20)));
+    }
+}
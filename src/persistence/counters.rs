@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use super::{keys, Error, Persistence};
+
+/// Counters are reserved in blocks of this size so storage is written once per block, not once per frame
+pub const RESERVE_BLOCK: u16 = 32;
+/// Number of frames after a restart that carry the `rebooted` flag
+pub const REBOOT_FLAG_FRAMES: u8 = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+struct Stored {
+    msg_id: u8,
+    sequence: u16,
+    boot_count: u32,
+}
+
+/// Identifiers to stamp on one outgoing frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStamp {
+    pub msg_id: u8,
+    pub sequence: u16,
+    /// Set for the first frames after a restart so the ground can tell a reboot from a replay
+    pub rebooted: bool,
+}
+
+/// FrameCounters hands out msg_ids and sequence numbers that keep increasing across reboots
+///
+/// The stored value is always a full block ahead of what has been handed out, so after a brownout the
+/// node resumes past every identifier it may have used without persisting on every frame.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameCounters {
+    msg_id: u8,
+    sequence: u16,
+    boot_count: u32,
+    /// Sequence number up to which the stored block is valid
+    reserved_until: u16,
+    reboot_frames_left: u8,
+}
+
+impl FrameCounters {
+    /// Resumes from the stored counters and records the restart
+    pub fn resume<P: Persistence>(store: &mut P) -> Result<Self, Error<P::Error>> {
+        let mut buf = [0u8; 16];
+        let stored: Option<Stored> = super::load(store, keys::FRAME_COUNTERS, &mut buf)?;
+        let mut counters = Self { msg_id: 0, sequence: 0, boot_count: 0, reserved_until: 0, reboot_frames_left: 0 };
+        if let Some(stored) = stored {
+            counters.msg_id = stored.msg_id;
+            counters.sequence = stored.sequence;
+            counters.boot_count = stored.boot_count + 1;
+            counters.reboot_frames_left = REBOOT_FLAG_FRAMES;
+        }
+        counters.reserve(store)?;
+        Ok(counters)
+    }
+
+    /// Number of restarts seen since the counters were first stored
+    pub fn boot_count(&self) -> u32 {
+        self.boot_count
+    }
+
+    /// Stamps the next frame, persisting a new block first when the current one is used up
+    pub fn next<P: Persistence>(&mut self, store: &mut P) -> Result<FrameStamp, Error<P::Error>> {
+        if self.sequence == self.reserved_until {
+            self.reserve(store)?;
+        }
+        let stamp = FrameStamp { msg_id: self.msg_id, sequence: self.sequence, rebooted: self.reboot_frames_left > 0 };
+        self.msg_id = self.msg_id.wrapping_add(1);
+        self.sequence = self.sequence.wrapping_add(1);
+        self.reboot_frames_left = self.reboot_frames_left.saturating_sub(1);
+        Ok(stamp)
+    }
+
+    fn reserve<P: Persistence>(&mut self, store: &mut P) -> Result<(), Error<P::Error>> {
+        self.reserved_until = self.sequence.wrapping_add(RESERVE_BLOCK);
+        let stored = Stored {
+            msg_id: self.msg_id.wrapping_add(RESERVE_BLOCK as u8),
+            sequence: self.reserved_until,
+            boot_count: self.boot_count,
+        };
+        let mut buf = [0u8; 16];
+        super::save(store, keys::FRAME_COUNTERS, &stored, &mut buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryStore;
+
+    #[test]
+    fn test_counters_resume_past_used_values() {
+        let mut store: MemoryStore<2, 16> = MemoryStore::new();
+        let mut counters = FrameCounters::resume(&mut store).unwrap();
+        let first = counters.next(&mut store).unwrap();
+        assert_eq!(first, FrameStamp { msg_id: 0, sequence: 0, rebooted: false });
+        let mut last = first;
+        for _ in 0..40 {
+            last = counters.next(&mut store).unwrap();
+        }
+
+        // Brownout: RAM state is lost, only the store survives
+        let mut counters = FrameCounters::resume(&mut store).unwrap();
+        assert_eq!(counters.boot_count(), 1);
+        let after = counters.next(&mut store).unwrap();
+        assert!(after.rebooted);
+        assert!(after.sequence > last.sequence);
+        assert!(after.msg_id.wrapping_sub(last.msg_id) < 0x80);
+
+        for _ in 1..REBOOT_FLAG_FRAMES {
+            counters.next(&mut store).unwrap();
+        }
+        assert!(!counters.next(&mut store).unwrap().rebooted);
+    }
+}
@@ -0,0 +1,164 @@
+use embedded_storage::Storage;
+
+use super::Persistence;
+
+/// Marks an unused slot, matches the erased state of EEPROM and flash
+const EMPTY_KEY: u16 = 0xFFFF;
+const HEADER_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EepromError<E> {
+    Storage(E),
+    /// Blob is larger than a slot
+    TooLarge,
+    /// Every slot is in use
+    Full,
+    /// Caller's buffer is smaller than the stored blob
+    BufferTooSmall,
+}
+
+/// SlotStore lays blobs out in fixed-size slots on any byte-addressable `embedded_storage::Storage`
+///
+/// Each slot starts with a 4 byte header (key, length) followed by up to `SLOT_SIZE - 4` bytes of data.
+pub struct SlotStore<S: Storage, const SLOT_SIZE: usize> {
+    storage: S,
+    base: u32,
+    slots: u32,
+}
+
+impl<S: Storage, const SLOT_SIZE: usize> SlotStore<S, SLOT_SIZE> {
+    /// Uses `slots` slots starting at byte offset `base`
+    pub fn new(storage: S, base: u32, slots: u32) -> Self {
+        Self { storage, base, slots }
+    }
+
+    pub fn release(self) -> S {
+        self.storage
+    }
+
+    fn offset(&self, slot: u32) -> u32 {
+        self.base + slot * SLOT_SIZE as u32
+    }
+
+    fn header(&mut self, slot: u32) -> Result<(u16, usize), EepromError<S::Error>> {
+        let mut header = [0u8; HEADER_LEN];
+        self.storage.read(self.offset(slot), &mut header).map_err(EepromError::Storage)?;
+        let key = u16::from_le_bytes([header[0], header[1]]);
+        let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        Ok((key, len))
+    }
+
+    fn find(&mut self, key: u16) -> Result<Option<(u32, usize)>, EepromError<S::Error>> {
+        for slot in 0..self.slots {
+            let (slot_key, len) = self.header(slot)?;
+            if slot_key == key {
+                return Ok(Some((slot, len)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<S: Storage, const SLOT_SIZE: usize> Persistence for SlotStore<S, SLOT_SIZE>
+where
+    S::Error: core::fmt::Debug,
+{
+    type Error = EepromError<S::Error>;
+
+    fn read(&mut self, key: u16, buf: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        let Some((slot, len)) = self.find(key)? else {
+            return Ok(None);
+        };
+        if len > SLOT_SIZE - HEADER_LEN {
+            // Header is corrupt, treat the blob as missing rather than reading past the slot
+            return Ok(None);
+        }
+        let dest = buf.get_mut(..len).ok_or(EepromError::BufferTooSmall)?;
+        self.storage.read(self.offset(slot) + HEADER_LEN as u32, dest).map_err(EepromError::Storage)?;
+        Ok(Some(len))
+    }
+
+    fn write(&mut self, key: u16, data: &[u8]) -> Result<(), Self::Error> {
+        if data.len() > SLOT_SIZE - HEADER_LEN {
+            return Err(EepromError::TooLarge);
+        }
+        let slot = match self.find(key)? {
+            Some((slot, _)) => slot,
+            None => self.find(EMPTY_KEY)?.ok_or(EepromError::Full)?.0,
+        };
+        let offset = self.offset(slot);
+        // Header last, so a blob written to an empty slot only becomes visible once complete
+        self.storage.write(offset + HEADER_LEN as u32, data).map_err(EepromError::Storage)?;
+        let mut header = [0u8; HEADER_LEN];
+        header[..2].copy_from_slice(&key.to_le_bytes());
+        header[2..].copy_from_slice(&(data.len() as u16).to_le_bytes());
+        self.storage.write(offset, &header).map_err(EepromError::Storage)
+    }
+
+    fn remove(&mut self, key: u16) -> Result<(), Self::Error> {
+        if let Some((slot, _)) = self.find(key)? {
+            let offset = self.offset(slot);
+            self.storage.write(offset, &[0xFF; HEADER_LEN]).map_err(EepromError::Storage)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{keys, load, save};
+    use embedded_storage::ReadStorage;
+
+    /// RAM-backed stand-in for an I2C EEPROM
+    pub struct RamEeprom(pub [u8; 256]);
+
+    impl ReadStorage for RamEeprom {
+        type Error = ();
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+            let start = offset as usize;
+            bytes.copy_from_slice(self.0.get(start..start + bytes.len()).ok_or(())?);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl Storage for RamEeprom {
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+            let start = offset as usize;
+            self.0.get_mut(start..start + bytes.len()).ok_or(())?.copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_read_overwrite_remove() {
+        let mut store: SlotStore<_, 64> = SlotStore::new(RamEeprom([0xFF; 256]), 0, 4);
+        let mut buf = [0u8; 64];
+        assert_eq!(store.read(7, &mut buf).unwrap(), None);
+
+        store.write(7, b"hello").unwrap();
+        store.write(8, b"world!").unwrap();
+        store.write(7, b"hi").unwrap();
+        assert_eq!(store.read(7, &mut buf).unwrap(), Some(2));
+        assert_eq!(&buf[..2], b"hi");
+
+        store.remove(7).unwrap();
+        assert_eq!(store.read(7, &mut buf).unwrap(), None);
+        assert_eq!(store.read(8, &mut buf).unwrap(), Some(6));
+        assert_eq!(store.write(9, &[0; 61]), Err(EepromError::TooLarge));
+    }
+
+    #[test]
+    fn test_typed_load_save() {
+        let mut store: SlotStore<_, 64> = SlotStore::new(RamEeprom([0xFF; 256]), 0, 4);
+        let mut buf = [0u8; 64];
+        save(&mut store, keys::CRYPTO_COUNTERS, &(42u32, 7u16), &mut buf).unwrap();
+        let counters: Option<(u32, u16)> = load(&mut store, keys::CRYPTO_COUNTERS, &mut buf).unwrap();
+        assert_eq!(counters, Some((42, 7)));
+    }
+}
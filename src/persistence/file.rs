@@ -0,0 +1,75 @@
+//! File-based persistence for desktop and ground-station builds (requires the `std` feature)
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use super::Persistence;
+
+/// FileStore keeps each blob in its own file, `<dir>/<key>.bin`
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Creates the directory if it does not exist
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: u16) -> PathBuf {
+        self.dir.join(format!("{}.bin", key))
+    }
+}
+
+impl Persistence for FileStore {
+    type Error = io::Error;
+
+    fn read(&mut self, key: u16, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        let data = match fs::read(self.path(key)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let dest = buf
+            .get_mut(..data.len())
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "buffer smaller than stored blob"))?;
+        dest.copy_from_slice(&data);
+        Ok(Some(data.len()))
+    }
+
+    fn write(&mut self, key: u16, data: &[u8]) -> io::Result<()> {
+        // Write to a temporary file and rename so a crash never leaves a truncated blob
+        let tmp = self.path(key).with_extension("tmp");
+        fs::write(&tmp, data)?;
+        fs::rename(tmp, self.path(key))
+    }
+
+    fn remove(&mut self, key: u16) -> io::Result<()> {
+        match fs::remove_file(self.path(key)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("mesh-filestore-{}", std::process::id()));
+        let mut store = FileStore::open(&dir).unwrap();
+        let mut buf = [0u8; 16];
+        store.write(3, b"calibration").unwrap();
+        assert_eq!(store.read(3, &mut buf).unwrap(), Some(11));
+        assert_eq!(&buf[..11], b"calibration");
+        store.remove(3).unwrap();
+        assert_eq!(store.read(3, &mut buf).unwrap(), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
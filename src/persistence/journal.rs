@@ -0,0 +1,258 @@
+use embedded_storage::Storage;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Length followed by its complement, so a torn header can be told apart from a valid one
+const HEADER_LEN: u32 = 4;
+const MARKER_LEN: u32 = 1;
+/// Written after the data once the whole record is in storage
+const COMMITTED: u8 = 0x5A;
+/// Reachable from any partially written marker by clearing bits, flags a torn record to skip
+const ABORTED: u8 = 0x00;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalError<E> {
+    Storage(E),
+    /// Record does not fit in the remaining space
+    Full,
+    /// Caller's buffer is smaller than the stored or serialized record
+    BufferTooSmall,
+    /// Record does not decode as the requested type
+    Malformed,
+}
+
+/// What the boot-time recovery scan found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Recovery {
+    pub records: u32,
+    /// Records (or headers) cut short by a power loss and marked aborted
+    pub torn: u32,
+}
+
+/// Journal is an append-only record log for the flight recorder, safe against brown-outs mid-write
+///
+/// Each record is written as header, data, then a commit marker, so a power loss (e.g. at landing
+/// impact) can only tear the record being written. `recover` scans the log on boot, marks a torn
+/// record aborted and resumes appending after it. Records are never rewritten in place, so the log
+/// works on flash that can only clear bits between erases.
+pub struct Journal<S: Storage> {
+    storage: S,
+    base: u32,
+    capacity: u32,
+    head: u32,
+}
+
+impl<S: Storage> Journal<S> {
+    /// Scans `capacity` bytes starting at `base` and positions the journal after the last record
+    pub fn recover(storage: S, base: u32, capacity: u32) -> Result<(Self, Recovery), JournalError<S::Error>> {
+        let mut journal = Self { storage, base, capacity, head: 0 };
+        let mut recovery = Recovery::default();
+        while journal.head + HEADER_LEN <= capacity {
+            let at = journal.head;
+            match journal.header(at)? {
+                Header::Erased => break,
+                Header::Torn => {
+                    // Data is only written after the header, so nothing follows a torn header
+                    journal.write(at, &[0; HEADER_LEN as usize])?;
+                    journal.head = at + HEADER_LEN;
+                    recovery.torn += 1;
+                }
+                Header::Record(len) => {
+                    let marker_at = at + HEADER_LEN + len;
+                    if marker_at + MARKER_LEN > capacity {
+                        break;
+                    }
+                    let mut marker = [0];
+                    journal.read(marker_at, &mut marker)?;
+                    match marker[0] {
+                        COMMITTED => recovery.records += 1,
+                        ABORTED => {}
+                        _ => {
+                            journal.write(marker_at, &[ABORTED])?;
+                            recovery.torn += 1;
+                        }
+                    }
+                    journal.head = marker_at + MARKER_LEN;
+                }
+            }
+        }
+        Ok((journal, recovery))
+    }
+
+    /// Appends a record, which only becomes visible once its commit marker is written
+    pub fn append(&mut self, data: &[u8]) -> Result<(), JournalError<S::Error>> {
+        let len = u16::try_from(data.len()).map_err(|_| JournalError::Full)?;
+        let end = self.head + HEADER_LEN + len as u32 + MARKER_LEN;
+        if end > self.capacity || len == u16::MAX {
+            return Err(JournalError::Full);
+        }
+        let mut header = [0; HEADER_LEN as usize];
+        header[..2].copy_from_slice(&len.to_le_bytes());
+        header[2..].copy_from_slice(&(!len).to_le_bytes());
+        self.write(self.head, &header)?;
+        self.write(self.head + HEADER_LEN, data)?;
+        self.write(end - MARKER_LEN, &[COMMITTED])?;
+        self.head = end;
+        Ok(())
+    }
+
+    /// Reads the next committed record at or after `cursor` into `buf`, advancing the cursor past it
+    ///
+    /// Start with a cursor of 0. Returns `None` at the end of the log.
+    pub fn read_next(&mut self, cursor: &mut u32, buf: &mut [u8]) -> Result<Option<usize>, JournalError<S::Error>> {
+        while *cursor + HEADER_LEN <= self.head {
+            let at = *cursor;
+            let Header::Record(len) = self.header(at)? else {
+                *cursor = at + HEADER_LEN;
+                continue;
+            };
+            *cursor = at + HEADER_LEN + len + MARKER_LEN;
+            let mut marker = [0];
+            self.read(at + HEADER_LEN + len, &mut marker)?;
+            if marker[0] == COMMITTED {
+                let dest = buf.get_mut(..len as usize).ok_or(JournalError::BufferTooSmall)?;
+                self.read(at + HEADER_LEN, dest)?;
+                return Ok(Some(len as usize));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Serializes `value` with postcard into `scratch` and appends it as one record
+    pub fn append_value<T: Serialize>(
+        &mut self,
+        value: &T,
+        scratch: &mut [u8],
+    ) -> Result<(), JournalError<S::Error>> {
+        let data = postcard::to_slice(value, scratch).map_err(|_| JournalError::BufferTooSmall)?;
+        self.append(data)
+    }
+
+    /// Reads the next committed record into `scratch` and decodes it, see `read_next`
+    pub fn read_value<T: DeserializeOwned>(
+        &mut self,
+        cursor: &mut u32,
+        scratch: &mut [u8],
+    ) -> Result<Option<T>, JournalError<S::Error>> {
+        let Some(len) = self.read_next(cursor, scratch)? else {
+            return Ok(None);
+        };
+        postcard::from_bytes(&scratch[..len]).map(Some).map_err(|_| JournalError::Malformed)
+    }
+
+    /// Bytes left for new records, including their header and marker
+    pub fn remaining(&self) -> u32 {
+        self.capacity - self.head
+    }
+
+    pub fn release(self) -> S {
+        self.storage
+    }
+
+    fn header(&mut self, at: u32) -> Result<Header, JournalError<S::Error>> {
+        let mut header = [0; HEADER_LEN as usize];
+        self.read(at, &mut header)?;
+        let len = u16::from_le_bytes([header[0], header[1]]);
+        let check = u16::from_le_bytes([header[2], header[3]]);
+        Ok(if header == [0xFF; HEADER_LEN as usize] {
+            Header::Erased
+        } else if check == !len {
+            Header::Record(len as u32)
+        } else {
+            Header::Torn
+        })
+    }
+
+    fn read(&mut self, at: u32, bytes: &mut [u8]) -> Result<(), JournalError<S::Error>> {
+        self.storage.read(self.base + at, bytes).map_err(JournalError::Storage)
+    }
+
+    fn write(&mut self, at: u32, bytes: &[u8]) -> Result<(), JournalError<S::Error>> {
+        self.storage.write(self.base + at, bytes).map_err(JournalError::Storage)
+    }
+}
+
+enum Header {
+    Erased,
+    Torn,
+    Record(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_storage::ReadStorage;
+
+    /// RAM storage that loses power after writing `budget` more bytes
+    struct Brownout {
+        mem: [u8; 128],
+        budget: usize,
+    }
+
+    impl ReadStorage for Brownout {
+        type Error = ();
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+            let start = offset as usize;
+            bytes.copy_from_slice(&self.mem[start..start + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.mem.len()
+        }
+    }
+
+    impl Storage for Brownout {
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+            let start = offset as usize;
+            let written = bytes.len().min(self.budget);
+            self.mem[start..start + written].copy_from_slice(&bytes[..written]);
+            self.budget -= written;
+            if written < bytes.len() {
+                return Err(());
+            }
+            Ok(())
+        }
+    }
+
+    fn records(journal: &mut Journal<Brownout>) -> std::vec::Vec<std::vec::Vec<u8>> {
+        let (mut cursor, mut buf, mut out) = (0, [0; 32], std::vec::Vec::new());
+        while let Some(len) = journal.read_next(&mut cursor, &mut buf).unwrap() {
+            out.push(buf[..len].to_vec());
+        }
+        out
+    }
+
+    #[test]
+    fn test_torn_record_is_skipped_after_recovery() {
+        let storage = Brownout { mem: [0xFF; 128], budget: 30 };
+        let (mut journal, _) = Journal::recover(storage, 0, 128).unwrap();
+        journal.append(b"pad").unwrap();
+        journal.append(b"boost").unwrap();
+        // Power is lost halfway through the data of the third record
+        assert!(journal.append(b"landing impact").is_err());
+
+        let mut storage = journal.release();
+        storage.budget = usize::MAX;
+        let (mut journal, recovery) = Journal::recover(storage, 0, 128).unwrap();
+        assert_eq!(recovery, Recovery { records: 2, torn: 1 });
+        journal.append(b"landed").unwrap();
+        assert_eq!(records(&mut journal), [&b"pad"[..], b"boost", b"landed"]);
+    }
+
+    #[test]
+    fn test_torn_header_is_voided() {
+        let storage = Brownout { mem: [0xFF; 128], budget: 10 };
+        let (mut journal, _) = Journal::recover(storage, 0, 128).unwrap();
+        journal.append(b"pad").unwrap();
+        assert!(journal.append(b"boost").is_err());
+
+        let mut storage = journal.release();
+        storage.budget = usize::MAX;
+        let (mut journal, recovery) = Journal::recover(storage, 0, 128).unwrap();
+        assert_eq!(recovery, Recovery { records: 1, torn: 1 });
+        journal.append(b"coast").unwrap();
+        assert_eq!(records(&mut journal), [&b"pad"[..], b"coast"]);
+    }
+}
@@ -0,0 +1,99 @@
+pub mod counters;
+pub mod eeprom;
+#[cfg(feature = "std")]
+pub mod file;
+pub mod journal;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Well-known persistence keys
+pub mod keys {
+    pub const PARAMS: u16 = 1;
+    pub const CRYPTO_COUNTERS: u16 = 2;
+    pub const CALIBRATION: u16 = 3;
+    pub const FRAME_COUNTERS: u16 = 4;
+    /// Scratch blob written and removed by the storage self-test
+    pub const SELF_TEST: u16 = 5;
+    /// VehicleConfig announced at boot and written at the head of flight logs
+    pub const VEHICLE_CONFIG: u16 = 6;
+    /// Last sealed-message counter accepted from each sender, see `crypto::Opener`
+    pub const CRYPTO_REPLAY: u16 = 7;
+}
+
+/// Persistence stores small blobs by key, so embedded and desktop builds share the same higher-level code
+pub trait Persistence {
+    type Error: core::fmt::Debug;
+
+    /// Reads the blob stored under `key` into `buf`, returning its length or `None` if absent
+    fn read(&mut self, key: u16, buf: &mut [u8]) -> Result<Option<usize>, Self::Error>;
+    fn write(&mut self, key: u16, data: &[u8]) -> Result<(), Self::Error>;
+    fn remove(&mut self, key: u16) -> Result<(), Self::Error>;
+}
+
+/// MemoryStore keeps blobs in RAM, for the simulator and for nodes without non-volatile storage
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore<const SLOTS: usize, const BLOB: usize> {
+    blobs: heapless::Vec<(u16, heapless::Vec<u8, BLOB>), SLOTS>,
+}
+
+/// Error returned by MemoryStore when a blob or the number of keys exceeds its capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError;
+
+impl<const SLOTS: usize, const BLOB: usize> MemoryStore<SLOTS, BLOB> {
+    pub fn new() -> Self {
+        Self { blobs: heapless::Vec::new() }
+    }
+}
+
+impl<const SLOTS: usize, const BLOB: usize> Persistence for MemoryStore<SLOTS, BLOB> {
+    type Error = CapacityError;
+
+    fn read(&mut self, key: u16, buf: &mut [u8]) -> Result<Option<usize>, CapacityError> {
+        let Some((_, blob)) = self.blobs.iter().find(|(k, _)| *k == key) else {
+            return Ok(None);
+        };
+        buf.get_mut(..blob.len()).ok_or(CapacityError)?.copy_from_slice(blob);
+        Ok(Some(blob.len()))
+    }
+
+    fn write(&mut self, key: u16, data: &[u8]) -> Result<(), CapacityError> {
+        let blob = heapless::Vec::from_slice(data).map_err(|_| CapacityError)?;
+        match self.blobs.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = blob,
+            None => self.blobs.push((key, blob)).map_err(|_| CapacityError)?,
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, key: u16) -> Result<(), CapacityError> {
+        self.blobs.retain(|(k, _)| *k != key);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error<E> {
+    Storage(E),
+    /// Stored blob could not be encoded or decoded
+    Encoding(postcard::Error),
+}
+
+/// Loads a serde value stored under `key`, `buf` must be large enough for the encoded value
+pub fn load<T: DeserializeOwned, P: Persistence>(
+    store: &mut P,
+    key: u16,
+    buf: &mut [u8],
+) -> Result<Option<T>, Error<P::Error>> {
+    match store.read(key, buf).map_err(Error::Storage)? {
+        Some(len) => postcard::from_bytes(&buf[..len]).map(Some).map_err(Error::Encoding),
+        None => Ok(None),
+    }
+}
+
+/// Stores a serde value under `key`, using `buf` as scratch space for the encoding
+pub fn save<T: Serialize, P: Persistence>(store: &mut P, key: u16, value: &T, buf: &mut [u8]) -> Result<(), Error<P::Error>> {
+    let bytes = postcard::to_slice(value, buf).map_err(Error::Encoding)?;
+    store.write(key, bytes).map_err(Error::Storage)
+}
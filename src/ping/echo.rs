@@ -0,0 +1,201 @@
+use core::fmt;
+
+use heapless::Vec;
+
+use crate::protocol::echo::{Echo, EchoReceipt, MAX_ECHO_PAYLOAD};
+use crate::protocol::mesh::MeshHeader;
+use crate::protocol::ping::LinkQuality;
+
+/// Node side: the reflection of an outbound Echo addressed to this node, with its receive metadata
+pub fn reflect(uid: u8, header: &MeshHeader, echo: &Echo, heard: LinkQuality) -> Option<Echo> {
+    if header.destination_uid != uid || echo.receipt.is_some() {
+        return None;
+    }
+    let receipt = EchoReceipt { responder_uid: uid, heard, hops_left: header.hops_left };
+    Some(Echo { receipt: Some(receipt), ..echo.clone() })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EchoConfig {
+    pub count: u16,
+    pub interval_ms: u64,
+    /// An echo not reflected within this long counts as lost
+    pub timeout_ms: u64,
+    pub payload_len: usize,
+}
+
+impl Default for EchoConfig {
+    fn default() -> Self {
+        Self { count: 20, interval_ms: 1_000, timeout_ms: 5_000, payload_len: 16 }
+    }
+}
+
+/// RTT and loss summary of an echo run
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EchoReport {
+    pub target_uid: u8,
+    pub sent: u16,
+    pub received: u16,
+    pub rtt_min_ms: u32,
+    pub rtt_max_ms: u32,
+    rtt_sum_ms: u64,
+    /// Lowest RSSI the target heard our echoes at
+    pub worst_uplink_dbm: Option<i16>,
+    pub worst_downlink_dbm: Option<i16>,
+}
+
+impl EchoReport {
+    pub fn loss_ratio(&self) -> f32 {
+        if self.sent == 0 {
+            0.0
+        } else {
+            1.0 - self.received as f32 / self.sent as f32
+        }
+    }
+
+    pub fn rtt_mean_ms(&self) -> Option<u32> {
+        (self.received > 0).then(|| (self.rtt_sum_ms / self.received as u64) as u32)
+    }
+}
+
+/// Summary in the style of `ping`, printed by the ground-station CLI
+impl fmt::Display for EchoReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "node {}: {} sent, {} received, {:.0}% loss",
+            self.target_uid,
+            self.sent,
+            self.received,
+            self.loss_ratio() * 100.0
+        )?;
+        if let Some(mean) = self.rtt_mean_ms() {
+            write!(f, ", rtt min/avg/max {}/{}/{} ms", self.rtt_min_ms, mean, self.rtt_max_ms)?;
+        }
+        Ok(())
+    }
+}
+
+/// Ground side: sends `count` echoes to one node and collects RTT and loss statistics
+///
+/// Several echoes may be in flight, so reflections that arrive out of order are still matched.
+#[derive(Debug, Clone)]
+pub struct EchoSession {
+    uid: u8,
+    config: EchoConfig,
+    next_id: u16,
+    next_at_ms: u64,
+    in_flight: Vec<(u16, u64), 8>,
+    report: EchoReport,
+}
+
+impl EchoSession {
+    pub fn new(uid: u8, target_uid: u8, config: EchoConfig) -> Self {
+        Self {
+            uid,
+            config,
+            next_id: 0,
+            next_at_ms: 0,
+            in_flight: Vec::new(),
+            report: EchoReport { target_uid, ..Default::default() },
+        }
+    }
+
+    pub fn target_uid(&self) -> u8 {
+        self.report.target_uid
+    }
+
+    /// Expires lost echoes and returns the next echo to send to the target when one is due
+    pub fn poll(&mut self, now_ms: u64) -> Option<Echo> {
+        let timeout = self.config.timeout_ms;
+        self.in_flight.retain(|(_, sent)| now_ms.saturating_sub(*sent) <= timeout);
+        if self.report.sent >= self.config.count || now_ms < self.next_at_ms || self.in_flight.is_full() {
+            return None;
+        }
+        self.next_at_ms = now_ms + self.config.interval_ms;
+        self.next_id = self.next_id.wrapping_add(1);
+        let _ = self.in_flight.push((self.next_id, now_ms));
+        self.report.sent += 1;
+        let payload = (0..self.config.payload_len.min(MAX_ECHO_PAYLOAD)).map(|i| i as u8).collect();
+        Some(Echo { origin_uid: self.uid, id: self.next_id, payload, receipt: None })
+    }
+
+    /// Matches a reflected echo, `heard` is the quality our radio received it with
+    pub fn on_reflection(&mut self, echo: &Echo, heard: LinkQuality, now_ms: u64) {
+        let Some(receipt) = echo.receipt.filter(|r| r.responder_uid == self.report.target_uid) else {
+            return;
+        };
+        if echo.origin_uid != self.uid {
+            return;
+        }
+        let Some(index) = self.in_flight.iter().position(|(id, _)| *id == echo.id) else {
+            return;
+        };
+        let (_, sent_ms) = self.in_flight.swap_remove(index);
+        let rtt = now_ms.saturating_sub(sent_ms).min(u32::MAX as u64) as u32;
+        let report = &mut self.report;
+        report.rtt_min_ms = if report.received == 0 { rtt } else { report.rtt_min_ms.min(rtt) };
+        report.rtt_max_ms = report.rtt_max_ms.max(rtt);
+        report.rtt_sum_ms += rtt as u64;
+        report.received += 1;
+        let worst = |current: Option<i16>, rssi: i16| Some(current.map_or(rssi, |w| w.min(rssi)));
+        report.worst_uplink_dbm = worst(report.worst_uplink_dbm, receipt.heard.rssi_dbm);
+        report.worst_downlink_dbm = worst(report.worst_downlink_dbm, heard.rssi_dbm);
+    }
+
+    /// True once every echo has been sent and answered or timed out
+    pub fn is_done(&self) -> bool {
+        self.report.sent >= self.config.count && self.in_flight.is_empty()
+    }
+
+    pub fn report(&self) -> EchoReport {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(destination_uid: u8) -> MeshHeader {
+        MeshHeader {
+            source_uid: 1,
+            destination_uid,
+            sequence: 0,
+            hops_left: 2,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        }
+    }
+
+    #[test]
+    fn test_echo_run_reports_rtt_and_loss() {
+        let config = EchoConfig { count: 3, interval_ms: 100, timeout_ms: 500, payload_len: 4 };
+        let mut session = EchoSession::new(1, 6, config);
+        let uplink = LinkQuality { rssi_dbm: -90, snr_db: 5.0 };
+
+        let first = session.poll(0).unwrap();
+        assert_eq!(first.payload.as_slice(), [0, 1, 2, 3]);
+        assert!(reflect(7, &header(6), &first, uplink).is_none());
+        let second = session.poll(100).unwrap();
+        let _lost = session.poll(200).unwrap();
+        assert!(session.poll(300).is_none());
+
+        // Reflections arrive out of order
+        let reflected = reflect(6, &header(6), &second, uplink).unwrap();
+        assert!(reflect(6, &header(6), &reflected, uplink).is_none());
+        session.on_reflection(&reflected, LinkQuality { rssi_dbm: -95, snr_db: 1.0 }, 250);
+        session.on_reflection(&reflect(6, &header(6), &first, uplink).unwrap(), LinkQuality::default(), 300);
+        assert!(!session.is_done());
+        session.poll(800);
+        assert!(session.is_done());
+
+        let report = session.report();
+        assert_eq!((report.sent, report.received), (3, 2));
+        assert_eq!((report.rtt_min_ms, report.rtt_mean_ms(), report.rtt_max_ms), (150, Some(225), 300));
+        assert_eq!(report.worst_downlink_dbm, Some(-95));
+        assert_eq!(std::format!("{report}"), "node 6: 3 sent, 2 received, 33% loss, rtt min/avg/max 150/225/300 ms");
+    }
+}
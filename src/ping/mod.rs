@@ -0,0 +1,99 @@
+pub mod echo;
+pub mod ranging;
+
+use crate::protocol::ping::{LinkQuality, Ping, Pong};
+
+/// Node side: answers a Ping addressed to this node with the quality it was heard at
+pub fn respond(uid: u8, ping: &Ping, heard: LinkQuality) -> Option<Pong> {
+    (ping.target_uid == uid).then_some(Pong { responder_uid: uid, origin_uid: ping.origin_uid, id: ping.id, heard })
+}
+
+/// Two-way link report for one completed Ping/Pong exchange
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalReport {
+    pub responder_uid: u8,
+    /// Quality of our Ping as heard by the responder
+    pub uplink: LinkQuality,
+    /// Quality of the Pong as heard by us
+    pub downlink: LinkQuality,
+    pub round_trip_ms: u32,
+}
+
+/// Ground side: issues Pings and matches Pongs into signal reports
+///
+/// Only one Ping is outstanding at a time, a new Ping replaces an unanswered one.
+#[derive(Debug, Clone, Copy)]
+pub struct Pinger {
+    uid: u8,
+    next_id: u16,
+    outstanding: Option<(Ping, u64)>,
+}
+
+impl Pinger {
+    pub fn new(uid: u8) -> Self {
+        Self { uid, next_id: 0, outstanding: None }
+    }
+
+    pub fn ping(&mut self, target_uid: u8, now_ms: u64) -> Ping {
+        self.next_id = self.next_id.wrapping_add(1);
+        let ping = Ping { origin_uid: self.uid, target_uid, id: self.next_id };
+        self.outstanding = Some((ping, now_ms));
+        ping
+    }
+
+    /// Matches a received Pong, `heard` is the quality our radio received it with
+    pub fn on_pong(&mut self, pong: &Pong, heard: LinkQuality, now_ms: u64) -> Option<SignalReport> {
+        let (ping, sent_ms) = self.outstanding?;
+        if pong.origin_uid != self.uid || pong.id != ping.id || pong.responder_uid != ping.target_uid {
+            return None;
+        }
+        self.outstanding = None;
+        Some(SignalReport {
+            responder_uid: pong.responder_uid,
+            uplink: pong.heard,
+            downlink: heard,
+            round_trip_ms: now_ms.saturating_sub(sent_ms).min(u32::MAX as u64) as u32,
+        })
+    }
+
+    /// Drops an unanswered Ping older than `timeout_ms`, returning true if one timed out
+    pub fn expire(&mut self, now_ms: u64, timeout_ms: u64) -> bool {
+        match self.outstanding {
+            Some((_, sent_ms)) if now_ms.saturating_sub(sent_ms) > timeout_ms => {
+                self.outstanding = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_pong_report() {
+        let mut pinger = Pinger::new(1);
+        let ping = pinger.ping(5, 1_000);
+        assert_eq!(respond(6, &ping, LinkQuality::default()), None);
+
+        let uplink = LinkQuality { rssi_dbm: -97, snr_db: 4.5 };
+        let pong = respond(5, &ping, uplink).unwrap();
+        let downlink = LinkQuality { rssi_dbm: -101, snr_db: 2.0 };
+        let report = pinger.on_pong(&pong, downlink, 1_350).unwrap();
+        assert_eq!(report.uplink, uplink);
+        assert_eq!(report.downlink, downlink);
+        assert_eq!(report.round_trip_ms, 350);
+        assert_eq!(pinger.on_pong(&pong, downlink, 1_400), None);
+    }
+
+    #[test]
+    fn test_stale_pong_ignored_after_expiry() {
+        let mut pinger = Pinger::new(1);
+        let ping = pinger.ping(5, 0);
+        assert!(pinger.expire(6_000, 5_000));
+        let pong = respond(5, &ping, LinkQuality::default()).unwrap();
+        assert_eq!(pinger.on_pong(&pong, LinkQuality::default(), 6_100), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Two-way RF time-of-flight ranging, a distance estimate that does not depend on GPS
+//!
+//! Timestamps are microseconds taken by the radio driver in its TX-done and RX-done interrupts, each node
+//! on its own free-running timer. One microsecond is 150 m of range, so results are only good to a few
+//! hundred meters: enough to cross-check GPS or to search for a node whose GPS has failed.
+
+use crate::protocol::ranging::{RangingRequest, RangingResponse};
+
+/// Speed of light in meters per microsecond
+pub const SPEED_OF_LIGHT_M_PER_US: f64 = 299.792_458;
+
+/// Node side: answers a RangingRequest addressed to this node
+///
+/// `rx_done_us` is when the request finished arriving and `now_us` is read just before the response is
+/// handed to the radio, so the response must go out straight away rather than through the transmit queue.
+pub fn respond(uid: u8, request: &RangingRequest, rx_done_us: u64, now_us: u64) -> Option<RangingResponse> {
+    (request.target_uid == uid).then(|| RangingResponse {
+        responder_uid: uid,
+        origin_uid: request.origin_uid,
+        id: request.id,
+        turnaround_us: now_us.saturating_sub(rx_done_us).min(u32::MAX as u64) as u32,
+    })
+}
+
+/// Distance measured by one completed exchange
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeReport {
+    pub responder_uid: u8,
+    pub distance_m: f32,
+    /// Round trip minus the responder's turnaround and the response's airtime, before calibration
+    pub raw_us: f64,
+}
+
+/// Originating side: issues RangingRequests and turns the responses into distances
+///
+/// Only one request is outstanding at a time, a new request replaces an unanswered one. Radio and
+/// interrupt latencies add a constant delay that is learned with `calibrate` at a known distance.
+#[derive(Debug, Clone, Copy)]
+pub struct Ranger {
+    uid: u8,
+    next_id: u16,
+    outstanding: Option<(RangingRequest, Option<u64>)>,
+    calibration_us: f64,
+    calibration_samples: u32,
+}
+
+impl Ranger {
+    pub fn new(uid: u8) -> Self {
+        Self { uid, next_id: 0, outstanding: None, calibration_us: 0.0, calibration_samples: 0 }
+    }
+
+    pub fn request(&mut self, target_uid: u8) -> RangingRequest {
+        self.next_id = self.next_id.wrapping_add(1);
+        let request = RangingRequest { origin_uid: self.uid, target_uid, id: self.next_id };
+        self.outstanding = Some((request, None));
+        request
+    }
+
+    /// Records when the outstanding request finished transmitting
+    pub fn sent(&mut self, tx_done_us: u64) {
+        if let Some((_, sent)) = self.outstanding.as_mut() {
+            *sent = Some(tx_done_us);
+        }
+    }
+
+    /// Matches a received response, `response_airtime_us` being its time on air from `LoraParams::airtime_us`
+    pub fn on_response(
+        &mut self,
+        response: &RangingResponse,
+        rx_done_us: u64,
+        response_airtime_us: u64,
+    ) -> Option<RangeReport> {
+        let (request, Some(tx_done_us)) = self.outstanding? else {
+            return None;
+        };
+        let ours = response.origin_uid == self.uid && response.id == request.id;
+        if !ours || response.responder_uid != request.target_uid {
+            return None;
+        }
+        self.outstanding = None;
+        let round_trip_us = rx_done_us.saturating_sub(tx_done_us) as f64;
+        let raw_us = round_trip_us - response.turnaround_us as f64 - response_airtime_us as f64;
+        let flight_us = ((raw_us - self.calibration_us) / 2.0).max(0.0);
+        let distance_m = (flight_us * SPEED_OF_LIGHT_M_PER_US) as f32;
+        Some(RangeReport { responder_uid: response.responder_uid, distance_m, raw_us })
+    }
+
+    /// Folds an exchange at a surveyed `known_distance_m` into the latency estimate, averaging every sample
+    pub fn calibrate(&mut self, report: &RangeReport, known_distance_m: f32) {
+        let latency_us = report.raw_us - 2.0 * known_distance_m as f64 / SPEED_OF_LIGHT_M_PER_US;
+        self.calibration_samples += 1;
+        self.calibration_us += (latency_us - self.calibration_us) / self.calibration_samples as f64;
+    }
+
+    /// Constant delay subtracted from every exchange, in microseconds
+    pub fn calibration_us(&self) -> f64 {
+        self.calibration_us
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::LoraParams;
+
+    /// One exchange with `flight_us` each way, 35 us RX latency and 120 us TX startup at both ends
+    fn exchange(ranger: &mut Ranger, flight_us: u64) -> Option<RangeReport> {
+        let airtime_us = LoraParams::default().airtime_us(12);
+        let request = ranger.request(5);
+        ranger.sent(1_000);
+        // The responder's timer runs from a different origin
+        let rx_done_us = 70_000 + flight_us + 35;
+        let response = respond(5, &request, rx_done_us, rx_done_us + 2_000)?;
+        let rx_done_us = 1_000 + flight_us + 35 + 2_000 + 120 + airtime_us + flight_us + 35;
+        ranger.on_response(&response, rx_done_us, airtime_us)
+    }
+
+    #[test]
+    fn test_time_of_flight_after_calibration() {
+        let mut ranger = Ranger::new(1);
+        assert_eq!(respond(6, &ranger.request(5), 0, 100), None);
+
+        // Surveyed 1 us apart, about 300 m
+        let report = exchange(&mut ranger, 1).unwrap();
+        ranger.calibrate(&report, SPEED_OF_LIGHT_M_PER_US as f32);
+        assert!((ranger.calibration_us() - 190.0).abs() < 1e-3);
+
+        let report = exchange(&mut ranger, 10).unwrap();
+        assert_eq!(report.responder_uid, 5);
+        assert!((report.distance_m - 2_997.9).abs() < 0.1);
+
+        // A response to a request never sent is ignored
+        let response = RangingResponse { responder_uid: 5, origin_uid: 1, id: ranger.request(5).id, turnaround_us: 0 };
+        assert_eq!(ranger.on_response(&response, 5_000, 0), None);
+    }
+}
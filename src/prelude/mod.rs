@@ -0,0 +1,34 @@
+//! Stable public API
+//!
+//! Everything re-exported here follows semver: fields and variants are only added in minor releases, never
+//! renamed, reordered or removed. Firmware should import from here rather than reaching into modules directly.
+//! The surface is pinned by `tests/api.rs` and checked against the last release with `cargo semver-checks`.
+
+pub use crate::clock::Clock;
+pub use crate::params::{ParamStore, ParamValue};
+pub use crate::persistence::{MemoryStore, Persistence};
+pub use crate::protocol::command::{Command, SignedCommand};
+pub use crate::protocol::events::FlightEvent;
+pub use crate::protocol::health::{ArmingState, DegradationLevel, Health};
+pub use crate::protocol::mesh::{MeshFrame, MeshHeader, BROADCAST_UID};
+pub use crate::protocol::node_info::{NodeInfo, Role};
+pub use crate::protocol::packet::{Packet, PacketKind};
+pub use crate::protocol::serial::{decode_frame, encode_frame, FrameAccumulator};
+pub use crate::protocol::{AllSensorData, DeviceType, GpsFix, ADXL375, BMP390, GPS, ISM330DHCX, LSM6DSO32};
+
+#[cfg(feature = "mesh")]
+pub use crate::command::{Authenticator, CommandProcessor};
+#[cfg(feature = "mesh")]
+pub use crate::node::handlers::{Disposition, HandlerRegistry, PacketContext, PacketFilter, PacketHandler, Stage};
+#[cfg(feature = "mesh")]
+pub use crate::node::{MeshNode, NodeConfig, NodeError, NodeEvent};
+
+#[cfg(feature = "radio")]
+pub use crate::radio::region::Region;
+#[cfg(feature = "radio")]
+pub use crate::radio::{LoraParams, Radio};
+
+#[cfg(feature = "ground")]
+pub use crate::alerts::{Alert, AlertMonitor, Notifier};
+#[cfg(feature = "ground")]
+pub use crate::ground::{GroundEvent, MeshGround, Receiver, Sink, Subscription};
@@ -0,0 +1,346 @@
+//! APRS symbol selection per device type, with overlay characters for team identification, and plain-text comments
+
+use core::fmt;
+
+use heapless::String;
+use serde::{Deserialize, Serialize};
+
+use super::checksum::crc16;
+use super::mesh::BROADCAST_UID;
+use super::{Acknowledgement, AprsCompressedPositionReport, Comment, DeviceType, MessageType};
+
+/// Primary symbol table identifier
+pub const PRIMARY_TABLE: char = '/';
+/// Alternate symbol table identifier, replaced by the overlay character when one is used
+pub const ALTERNATE_TABLE: char = '\\';
+/// Hops given to automatic Ack comments, the most the 3-bit field allows
+pub const ACK_HOPS: u8 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolError {
+    /// Table is not '/', '\\' or an overlay character
+    InvalidTable(char),
+    /// Symbol code outside the printable range '!'..='~'
+    InvalidCode(char),
+    /// Overlays are only allowed on alternate table symbols
+    OverlayOnPrimary,
+}
+
+/// A validated APRS symbol: table identifier (or overlay) and symbol code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AprsSymbol {
+    table: char,
+    code: char,
+}
+
+impl AprsSymbol {
+    pub fn new(table: char, code: char) -> Result<Self, SymbolError> {
+        if !matches!(table, PRIMARY_TABLE | ALTERNATE_TABLE) && !is_overlay(table) {
+            return Err(SymbolError::InvalidTable(table));
+        }
+        if !('!'..='~').contains(&code) {
+            return Err(SymbolError::InvalidCode(code));
+        }
+        Ok(Self { table, code })
+    }
+
+    /// Places an overlay character (0-9, A-Z) on an alternate table symbol
+    pub fn with_overlay(self, overlay: char) -> Result<Self, SymbolError> {
+        if !is_overlay(overlay) {
+            return Err(SymbolError::InvalidTable(overlay));
+        }
+        if self.table == PRIMARY_TABLE {
+            return Err(SymbolError::OverlayOnPrimary);
+        }
+        Ok(Self { table: overlay, ..self })
+    }
+
+    pub fn table(&self) -> char {
+        self.table
+    }
+
+    pub fn code(&self) -> char {
+        self.code
+    }
+
+    pub fn overlay(&self) -> Option<char> {
+        is_overlay(self.table).then_some(self.table)
+    }
+}
+
+fn is_overlay(c: char) -> bool {
+    c.is_ascii_digit() || c.is_ascii_uppercase()
+}
+
+/// Symbol shown on APRS maps for each device type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolConfig {
+    /// Used for both Top and Bottom rocket sections
+    pub rocket: AprsSymbol,
+    /// Used for ground stations and pad GSE nodes
+    pub ground: AprsSymbol,
+    pub mobile: AprsSymbol,
+}
+
+impl Default for SymbolConfig {
+    fn default() -> Self {
+        Self {
+            rocket: AprsSymbol { table: ALTERNATE_TABLE, code: 'O' },
+            ground: AprsSymbol { table: PRIMARY_TABLE, code: '-' },
+            mobile: AprsSymbol { table: PRIMARY_TABLE, code: '>' },
+        }
+    }
+}
+
+impl SymbolConfig {
+    pub fn symbol(&self, device: DeviceType) -> AprsSymbol {
+        match device {
+            DeviceType::Top | DeviceType::Bottom => self.rocket,
+            DeviceType::Ground | DeviceType::Gse => self.ground,
+            DeviceType::Mobile => self.mobile,
+        }
+    }
+
+    /// Overlays the team character on every alternate table symbol, primary table symbols are left as is
+    pub fn with_team_overlay(self, overlay: char) -> Result<Self, SymbolError> {
+        let apply = |symbol: AprsSymbol| match symbol.with_overlay(overlay) {
+            Err(SymbolError::OverlayOnPrimary) => Ok(symbol),
+            result => result,
+        };
+        Ok(Self { rocket: apply(self.rocket)?, ground: apply(self.ground)?, mobile: apply(self.mobile)? })
+    }
+}
+
+/// Longest comment carried by a compressed position report
+pub const MAX_COMMENT_LEN: usize = 40;
+
+/// How the comment of a position report is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommentMode {
+    /// Packed mesh header and ADS data, for mesh nodes
+    #[default]
+    Binary,
+    /// Human-readable text, for APRS-IS, digipeaters and map viewers
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentError {
+    TooLong,
+    /// Not printable ASCII, or one of the '|' and '~' characters APRS reserves
+    InvalidChar(char),
+}
+
+/// A plain-text comment such as "RVT APOGEE 10234ft"
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TextComment(String<MAX_COMMENT_LEN>);
+
+impl TextComment {
+    pub fn new(text: &str) -> Result<Self, CommentError> {
+        let mut comment = Self::default();
+        fmt::Write::write_str(&mut comment, text).map_err(|_| comment.error(text))?;
+        Ok(comment)
+    }
+
+    /// Formats a comment, e.g. `TextComment::format(format_args!("RVT APOGEE {apogee}ft"))`
+    pub fn format(args: fmt::Arguments) -> Result<Self, CommentError> {
+        let mut comment = Self::default();
+        let mut last = None;
+        let mut writer = Validating { comment: &mut comment, rejected: &mut last };
+        match fmt::write(&mut writer, args) {
+            Ok(()) => Ok(comment),
+            Err(_) => Err(last.unwrap_or(CommentError::TooLong)),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn error(&self, text: &str) -> CommentError {
+        text.chars().find(|c| !is_allowed(*c)).map_or(CommentError::TooLong, CommentError::InvalidChar)
+    }
+}
+
+impl fmt::Write for TextComment {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if !s.chars().all(is_allowed) {
+            return Err(fmt::Error);
+        }
+        self.0.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+/// Records why a formatted write was rejected, since `fmt::Error` carries no cause
+struct Validating<'a> {
+    comment: &'a mut TextComment,
+    rejected: &'a mut Option<CommentError>,
+}
+
+impl fmt::Write for Validating<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        fmt::Write::write_str(self.comment, s).inspect_err(|_| *self.rejected = Some(self.comment.error(s)))
+    }
+}
+
+fn is_allowed(c: char) -> bool {
+    (' '..='~').contains(&c) && c != '|' && c != '~'
+}
+
+/// Why the packed comment of a received report can't be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentCheckError {
+    /// The report carries a text comment instead of packed data
+    Missing,
+    /// The packed comment's CRC doesn't match, it was altered somewhere along the APRS path
+    Corrupted,
+}
+
+impl Comment {
+    /// CRC-16/CCITT of every field except `crc`
+    pub fn checksum(&self) -> u16 {
+        let mut buf = [0u8; 64];
+        let unsealed = Comment { crc: 0, ..*self };
+        let bytes = postcard::to_slice(&unsealed, &mut buf).expect("a comment encodes in well under 64 bytes");
+        // The zeroed `crc` varint is the last byte
+        crc16(&bytes[..bytes.len() - 1])
+    }
+
+    /// Stores the checksum, call after the last field is set and before sending
+    pub fn seal(&mut self) {
+        self.crc = self.checksum();
+    }
+
+    pub fn verify(&self) -> Result<&Self, CommentCheckError> {
+        if self.crc == self.checksum() {
+            Ok(self)
+        } else {
+            Err(CommentCheckError::Corrupted)
+        }
+    }
+
+    /// Unicast comments other than acks and beacons expect an acknowledgement
+    pub fn requests_ack(&self) -> bool {
+        self.destination_uid != BROADCAST_UID && !matches!(self.msg_type, MessageType::Ack | MessageType::Beacon)
+    }
+
+    /// The Acknowledgement an Ack comment carries, its `msg_id` naming the acknowledged comment
+    pub fn acknowledgement(&self) -> Option<Acknowledgement> {
+        (self.msg_type == MessageType::Ack).then_some(Acknowledgement { id: self.msg_id, ack: true })
+    }
+
+    /// Sealed Ack comment to send back when this one is addressed to `own_uid` and requests an ack
+    pub fn ack_reply(&self, own_uid: u8) -> Option<Comment> {
+        if self.destination_uid != own_uid || !self.requests_ack() {
+            return None;
+        }
+        let mut reply = Comment {
+            uid: own_uid,
+            destination_uid: self.uid,
+            msg_id: self.msg_id,
+            hops_left: ACK_HOPS,
+            msg_type: MessageType::Ack,
+            team_number: self.team_number,
+            ..Default::default()
+        };
+        reply.seal();
+        Some(reply)
+    }
+}
+
+impl AprsCompressedPositionReport {
+    pub fn set_symbol(&mut self, symbol: AprsSymbol) {
+        self.symbol_table = symbol.table;
+        self.symbol_code = symbol.code;
+    }
+
+    pub fn comment_mode(&self) -> CommentMode {
+        if self.text_comment.is_some() {
+            CommentMode::Text
+        } else {
+            CommentMode::Binary
+        }
+    }
+
+    /// Sends `text` in place of the packed ADS comment
+    pub fn set_text_comment(&mut self, text: TextComment) {
+        self.text_comment = Some(text);
+    }
+
+    /// Returns to the packed ADS comment
+    pub fn clear_text_comment(&mut self) {
+        self.text_comment = None;
+    }
+
+    /// The packed comment, if this report carries one and it arrived intact
+    pub fn verified_comment(&self) -> Result<&Comment, CommentCheckError> {
+        match self.comment_mode() {
+            CommentMode::Text => Err(CommentCheckError::Missing),
+            CommentMode::Binary => self.comment.verify(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_team_overlay_and_validation() {
+        let symbols = SymbolConfig::default().with_team_overlay('V').unwrap();
+        let rocket = symbols.symbol(DeviceType::Top);
+        assert_eq!((rocket.table(), rocket.code(), rocket.overlay()), ('V', 'O', Some('V')));
+        assert_eq!(symbols.symbol(DeviceType::Mobile).table(), PRIMARY_TABLE);
+
+        let mut report = AprsCompressedPositionReport::default();
+        report.set_symbol(rocket);
+        assert_eq!((report.symbol_table, report.symbol_code), ('V', 'O'));
+
+        assert_eq!(AprsSymbol::new('x', 'O'), Err(SymbolError::InvalidTable('x')));
+        assert_eq!(AprsSymbol::new('/', ' '), Err(SymbolError::InvalidCode(' ')));
+        assert_eq!(AprsSymbol::new('/', '>').unwrap().with_overlay('3'), Err(SymbolError::OverlayOnPrimary));
+        assert_eq!(SymbolConfig::default().with_team_overlay('v'), Err(SymbolError::InvalidTable('v')));
+    }
+
+    #[test]
+    fn test_sealed_comment_detects_corruption() {
+        let mut report = AprsCompressedPositionReport::default();
+        report.comment.ads.alt = 1_024;
+        report.comment.seal();
+        assert!(report.verified_comment().is_ok());
+        report.comment.ads.alt = 1_025;
+        assert_eq!(report.verified_comment().err(), Some(CommentCheckError::Corrupted));
+    }
+
+    #[test]
+    fn test_ack_reply() {
+        let comment = Comment { uid: 3, destination_uid: 5, msg_id: 17, ..Default::default() };
+        assert!(comment.ack_reply(4).is_none());
+        let reply = comment.ack_reply(5).unwrap();
+        assert!(reply.verify().is_ok() && !reply.requests_ack());
+        assert_eq!((reply.destination_uid, reply.acknowledgement()), (3, Some(Acknowledgement { id: 17, ack: true })));
+        let broadcast = Comment { destination_uid: BROADCAST_UID, ..comment };
+        assert!(!broadcast.requests_ack());
+    }
+
+    #[test]
+    fn test_text_comment_mode() {
+        let mut report = AprsCompressedPositionReport::default();
+        assert_eq!(report.comment_mode(), CommentMode::Binary);
+
+        let apogee = 10_234;
+        let comment = TextComment::format(format_args!("RVT APOGEE {apogee}ft")).unwrap();
+        assert_eq!(comment.as_str(), "RVT APOGEE 10234ft");
+        report.set_text_comment(comment);
+        assert_eq!(report.comment_mode(), CommentMode::Text);
+        report.clear_text_comment();
+        assert_eq!(report.comment_mode(), CommentMode::Binary);
+
+        assert_eq!(TextComment::new("GO|NOGO"), Err(CommentError::InvalidChar('|')));
+        assert_eq!(TextComment::new(&"A".repeat(MAX_COMMENT_LEN + 1)), Err(CommentError::TooLong));
+        report.set_text_comment(TextComment::new("RVT").unwrap());
+        assert_eq!(report.verified_comment().err(), Some(CommentCheckError::Missing));
+        let bad = '\u{1}';
+        assert_eq!(TextComment::format(format_args!("RVT {bad}")), Err(CommentError::InvalidChar(bad)));
+    }
+}
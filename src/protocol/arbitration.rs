@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Role of a flight computer in a redundant pair
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComputerRole {
+    Primary = 0,
+    #[default]
+    Backup = 1,
+}
+
+/// Arbitration is the heartbeat exchanged by two redundant flight computers to agree on a primary
+///
+/// `term` increases on every takeover, so a primary that was presumed dead steps down when it hears a
+/// newer term.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Arbitration {
+    pub uid: u8,
+    pub role: ComputerRole,
+    pub term: u16,
+    /// Set on the first heartbeat after the sender took over from a silent primary
+    pub takeover: bool,
+}
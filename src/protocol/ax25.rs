@@ -0,0 +1,273 @@
+//! AX.25 UI frames, the link layer APRS packets travel in on standard APRS infrastructure
+//!
+//! ```text
+//! destination (7) | source (7) | digipeaters (7 each, up to 8) | control 0x03 | PID 0xF0 | info | FCS (u16 LE)
+//! ```
+//!
+//! Flags and bit stuffing are left to the modem or TNC.
+
+use core::fmt;
+use core::str::FromStr;
+
+use heapless::{String, Vec};
+
+use super::{AprsCompressedPositionReport, Error};
+
+/// Longest callsign
+pub const MAX_CALLSIGN_LEN: usize = 6;
+/// Most digipeaters a path may hold
+pub const MAX_DIGIPEATERS: usize = 8;
+/// Largest information field
+pub const MAX_INFO_LEN: usize = 256;
+/// Largest encoded frame, with a full path and information field
+pub const MAX_UI_FRAME_LEN: usize = 7 * (2 + MAX_DIGIPEATERS) + 2 + MAX_INFO_LEN + 2;
+
+const ADDRESS_LEN: usize = 7;
+/// Unnumbered information, poll/final clear
+const CONTROL_UI: u8 = 0x03;
+/// No layer 3 protocol
+const PID_NO_LAYER3: u8 = 0xF0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ax25Error {
+    /// Callsigns are 1 to 6 uppercase letters and digits
+    InvalidCallsign,
+    /// SSIDs are 0 to 15
+    InvalidSsid,
+    TooManyDigipeaters,
+    InfoTooLong,
+    /// The output buffer is too short
+    Truncated,
+}
+
+/// Callsign and SSID, such as `KJ4ABC-9`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ax25Address {
+    callsign: String<MAX_CALLSIGN_LEN>,
+    ssid: u8,
+    /// Has-been-repeated bit, set by a digipeater that relayed the frame
+    pub repeated: bool,
+}
+
+impl Ax25Address {
+    pub fn new(callsign: &str, ssid: u8) -> Result<Self, Ax25Error> {
+        let valid = |c: char| c.is_ascii_uppercase() || c.is_ascii_digit();
+        if callsign.is_empty() || !callsign.chars().all(valid) {
+            return Err(Ax25Error::InvalidCallsign);
+        }
+        if ssid > 15 {
+            return Err(Ax25Error::InvalidSsid);
+        }
+        let callsign = String::try_from(callsign).map_err(|_| Ax25Error::InvalidCallsign)?;
+        Ok(Self { callsign, ssid, repeated: false })
+    }
+
+    pub fn callsign(&self) -> &str {
+        &self.callsign
+    }
+
+    pub fn ssid(&self) -> u8 {
+        self.ssid
+    }
+
+    fn encode(&self, last: bool) -> [u8; ADDRESS_LEN] {
+        let mut bytes = [b' ' << 1; ADDRESS_LEN];
+        for (byte, c) in bytes.iter_mut().zip(self.callsign.bytes()) {
+            *byte = c << 1;
+        }
+        bytes[6] = 0b0110_0000 | (self.repeated as u8) << 7 | self.ssid << 1 | last as u8;
+        bytes
+    }
+
+    /// Decodes the address at `offset` in a frame, returning it and whether it is the last one in the header
+    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, bool), Error> {
+        let mut callsign = [0u8; MAX_CALLSIGN_LEN];
+        for (c, byte) in callsign.iter_mut().zip(bytes) {
+            *c = byte >> 1;
+        }
+        let len = callsign.iter().position(|c| *c == b' ').unwrap_or(MAX_CALLSIGN_LEN);
+        let invalid = callsign[..len].iter().position(|c| !c.is_ascii_uppercase() && !c.is_ascii_digit());
+        if let Some(i) = invalid.or((len == 0).then_some(0)) {
+            return Err(Error::InvalidByte { offset: offset + i, byte: bytes[i] });
+        }
+        let callsign = core::str::from_utf8(&callsign[..len]).map_err(|_| Error::Malformed)?;
+        let mut address = Self::new(callsign, (bytes[6] >> 1) & 0x0F).map_err(|_| Error::Malformed)?;
+        address.repeated = bytes[6] & 0x80 != 0;
+        Ok((address, bytes[6] & 1 != 0))
+    }
+}
+
+impl FromStr for Ax25Address {
+    type Err = Ax25Error;
+
+    fn from_str(s: &str) -> Result<Self, Ax25Error> {
+        let (callsign, repeated) = s.strip_suffix('*').map_or((s, false), |s| (s, true));
+        let (callsign, ssid) = match callsign.split_once('-') {
+            Some((callsign, ssid)) => (callsign, ssid.parse().map_err(|_| Ax25Error::InvalidSsid)?),
+            None => (callsign, 0),
+        };
+        Ok(Self { repeated, ..Self::new(callsign, ssid)? })
+    }
+}
+
+impl fmt::Display for Ax25Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.callsign)?;
+        if self.ssid != 0 {
+            write!(f, "-{}", self.ssid)?;
+        }
+        if self.repeated {
+            f.write_str("*")?;
+        }
+        Ok(())
+    }
+}
+
+/// An AX.25 unnumbered information frame carrying an APRS packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UiFrame {
+    /// APRS uses the destination for the software or device identifier, such as `APZRVT`
+    pub destination: Ax25Address,
+    pub source: Ax25Address,
+    /// Digipeaters in order, such as `WIDE1-1,WIDE2-1`
+    pub path: Vec<Ax25Address, MAX_DIGIPEATERS>,
+    pub info: Vec<u8, MAX_INFO_LEN>,
+}
+
+impl UiFrame {
+    pub fn new(
+        destination: Ax25Address,
+        source: Ax25Address,
+        path: &[Ax25Address],
+        info: &[u8],
+    ) -> Result<Self, Ax25Error> {
+        Ok(Self {
+            destination,
+            source,
+            path: Vec::from_slice(path).map_err(|_| Ax25Error::TooManyDigipeaters)?,
+            info: Vec::from_slice(info).map_err(|_| Ax25Error::InfoTooLong)?,
+        })
+    }
+
+    /// Frames a compressed position report
+    pub fn position_report(
+        destination: Ax25Address,
+        source: Ax25Address,
+        path: &[Ax25Address],
+        report: &AprsCompressedPositionReport,
+    ) -> Result<Self, Ax25Error> {
+        Self::new(destination, source, path, &report.encode_info())
+    }
+
+    /// Writes the frame with its FCS into `buf`, returning the length
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, Ax25Error> {
+        let len = self.encode_without_fcs(buf)?;
+        let fcs = fcs(&buf[..len]);
+        buf.get_mut(len..len + 2).ok_or(Ax25Error::Truncated)?.copy_from_slice(&fcs.to_le_bytes());
+        Ok(len + 2)
+    }
+
+    /// Writes the frame without an FCS, as handed to a KISS TNC that adds it on transmit
+    pub fn encode_without_fcs(&self, buf: &mut [u8]) -> Result<usize, Ax25Error> {
+        let addresses = 2 + self.path.len();
+        let header = ADDRESS_LEN * addresses;
+        let len = header + 2 + self.info.len();
+        let out = buf.get_mut(..len).ok_or(Ax25Error::Truncated)?;
+        let all = [&self.destination, &self.source].into_iter().chain(self.path.iter());
+        for (i, (address, chunk)) in all.zip(out.chunks_exact_mut(ADDRESS_LEN)).enumerate() {
+            chunk.copy_from_slice(&address.encode(i + 1 == addresses));
+        }
+        out[header] = CONTROL_UI;
+        out[header + 1] = PID_NO_LAYER3;
+        out[header + 2..].copy_from_slice(&self.info);
+        Ok(len)
+    }
+
+    /// Parses a frame, checking its FCS
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let (body, fcs_bytes) = bytes.split_at_checked(bytes.len().wrapping_sub(2)).ok_or(Error::Truncated)?;
+        if fcs(body) != u16::from_le_bytes([fcs_bytes[0], fcs_bytes[1]]) {
+            return Err(Error::BadCrc);
+        }
+        Self::decode_without_fcs(body)
+    }
+
+    /// Parses a frame without an FCS, as received from a KISS TNC that has already checked it
+    ///
+    /// A path longer than `MAX_DIGIPEATERS` or an information field longer than `MAX_INFO_LEN` is `TooLong`,
+    /// a control or PID byte of anything but an APRS UI frame an `InvalidByte`.
+    pub fn decode_without_fcs(body: &[u8]) -> Result<Self, Error> {
+        let mut addresses = body.chunks(ADDRESS_LEN).enumerate();
+        let mut next = || {
+            let (i, bytes) = addresses.next().filter(|(_, a)| a.len() == ADDRESS_LEN).ok_or(Error::Truncated)?;
+            Ax25Address::decode(bytes, i * ADDRESS_LEN)
+        };
+        let (destination, _) = next()?;
+        let (source, mut last) = next()?;
+        let mut path = Vec::new();
+        while !last {
+            let (digipeater, end) = next()?;
+            path.push(digipeater).map_err(|_| Error::TooLong)?;
+            last = end;
+        }
+        let header = ADDRESS_LEN * (2 + path.len());
+        match body.get(header..header + 2) {
+            Some([CONTROL_UI, PID_NO_LAYER3]) => {}
+            Some([CONTROL_UI, byte]) => return Err(Error::InvalidByte { offset: header + 1, byte: *byte }),
+            Some([byte, _]) => return Err(Error::InvalidByte { offset: header, byte: *byte }),
+            _ => return Err(Error::Truncated),
+        }
+        let info = Vec::from_slice(&body[header + 2..]).map_err(|_| Error::TooLong)?;
+        Ok(Self { destination, source, path, info })
+    }
+
+    /// Parses the information field as a compressed position report
+    pub fn to_position_report(&self) -> Result<AprsCompressedPositionReport, Error> {
+        AprsCompressedPositionReport::decode(&self.info)
+    }
+}
+
+/// CRC-16/X.25 frame check sequence
+pub fn fcs(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for byte in bytes {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x8408 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_frame_round_trip() {
+        assert_eq!(fcs(b"123456789"), 0x906E);
+
+        let path: [Ax25Address; 2] = ["WIDE1-1".parse().unwrap(), "WIDE2-1".parse().unwrap()];
+        let report = AprsCompressedPositionReport {
+            symbol_table: '/',
+            symbol_code: 'O',
+            ..AprsCompressedPositionReport::encode(37.2296, -80.4139, 1_500.0, None, 0.0)
+        };
+        let (destination, source) = ("APZRVT".parse().unwrap(), "KJ4ABC-9".parse().unwrap());
+        let frame = UiFrame::position_report(destination, source, &path, &report).unwrap();
+        let mut buf = [0u8; MAX_UI_FRAME_LEN];
+        let len = frame.encode(&mut buf).unwrap();
+
+        let decoded = UiFrame::decode(&buf[..len]).unwrap();
+        assert_eq!(decoded, frame);
+        assert_eq!(std::format!("{}", decoded.source), "KJ4ABC-9");
+        let position = decoded.to_position_report().unwrap();
+        assert_eq!(position.compressed_lat, report.compressed_lat);
+        assert!((position.alt - 1_500.0).abs() < 5.0);
+
+        buf[20] ^= 0x02;
+        assert_eq!(UiFrame::decode(&buf[..len]), Err(Error::BadCrc));
+        assert_eq!("kj4abc".parse::<Ax25Address>(), Err(Ax25Error::InvalidCallsign));
+        assert_eq!("KJ4ABC-16".parse::<Ax25Address>(), Err(Ax25Error::InvalidSsid));
+    }
+}
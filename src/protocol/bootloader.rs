@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a device refused to enter its bootloader
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RefuseReason {
+    /// The vehicle is not SAFE
+    Armed,
+    /// The image does not fit in the application flash region
+    ImageTooLarge,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BootStatus {
+    Ready,
+    Refused(RefuseReason),
+}
+
+/// Handshake between the ground-station CLI and a device's serial bootloader
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BootloaderMessage {
+    /// Host to device: reboot into the serial bootloader to receive an image
+    Enter { target_uid: u8, image_size: u32, image_crc: u32 },
+    /// Device to host: response to Enter
+    Ack { uid: u8, status: BootStatus },
+    /// Device to host: sent periodically while the image is written
+    Progress { uid: u8, bytes_written: u32, total: u32 },
+    /// Device to host: image written and verified (or not) against `image_crc`
+    Complete { uid: u8, success: bool },
+}
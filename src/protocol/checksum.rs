@@ -0,0 +1,79 @@
+//! CRC-16/CCITT integrity check wrapped around every frame sent over the radio
+//!
+//! ```text
+//! length (u8) | message | CRC-16/CCITT (u16 LE)
+//! ```
+//!
+//! The length prefix lets a short read be told apart from corruption, the CRC covers the length and the message.
+
+use super::Error;
+
+/// Bytes before the message
+pub const PREFIX_LEN: usize = 1;
+pub const CRC_LEN: usize = 2;
+/// Bytes the checksum layer adds to a message
+pub const OVERHEAD: usize = PREFIX_LEN + CRC_LEN;
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Wraps the `len` byte message at `buf[PREFIX_LEN..]`, returning the length of the whole frame
+///
+/// Serialize the message into `&mut buf[PREFIX_LEN..buf.len() - CRC_LEN]` first. `BufferTooSmall` if the message
+/// doesn't fit, or is longer than the length prefix can describe.
+pub fn append(buf: &mut [u8], len: usize) -> Result<usize, Error> {
+    let end = PREFIX_LEN + len;
+    if end + CRC_LEN > buf.len() {
+        return Err(Error::BufferTooSmall);
+    }
+    buf[0] = u8::try_from(len).map_err(|_| Error::BufferTooSmall)?;
+    let crc = crc16(&buf[..end]);
+    buf[end..end + CRC_LEN].copy_from_slice(&crc.to_le_bytes());
+    Ok(end + CRC_LEN)
+}
+
+/// Checks a received frame and returns the message inside it
+///
+/// Bytes after the CRC, such as radio padding, are ignored. `Truncated` if there are fewer bytes than the length
+/// prefix announced.
+pub fn verify_and_strip(frame: &[u8]) -> Result<&[u8], Error> {
+    let (&len, rest) = frame.split_first().ok_or(Error::Truncated)?;
+    let end = PREFIX_LEN + len as usize;
+    let crc = rest.get(len as usize..len as usize + CRC_LEN).ok_or(Error::Truncated)?;
+    if crc16(&frame[..end]) != u16::from_le_bytes([crc[0], crc[1]]) {
+        return Err(Error::BadCrc);
+    }
+    Ok(&frame[PREFIX_LEN..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_and_verify() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+
+        let mut buf = [0u8; 16];
+        buf[PREFIX_LEN..PREFIX_LEN + 5].copy_from_slice(b"hello");
+        let len = append(&mut buf, 5).unwrap();
+        assert_eq!(len, 5 + OVERHEAD);
+        assert_eq!(verify_and_strip(&buf[..len]), Ok(&b"hello"[..]));
+        assert_eq!(verify_and_strip(&buf), Ok(&b"hello"[..]));
+
+        assert_eq!(verify_and_strip(&buf[..len - 1]), Err(Error::Truncated));
+        assert_eq!(verify_and_strip(&[]), Err(Error::Truncated));
+        buf[3] ^= 0x04;
+        assert_eq!(verify_and_strip(&buf[..len]), Err(Error::BadCrc));
+        assert_eq!(append(&mut buf, 14), Err(Error::BufferTooSmall));
+    }
+}
@@ -0,0 +1,249 @@
+//! On-air binary frame for sensor data and APRS reports
+//!
+//! ```text
+//! 0x7E | version | type | length (u16 LE) | postcard payload | reserved | CRC-16/CCITT (u16 LE)
+//! ```
+//!
+//! The CRC covers everything after the start delimiter up to the CRC. The length includes the reserved
+//! bytes, which are sent as zero. Decoders ignore whatever follows the message they know, so a later
+//! revision may append fields or assign the reserved bytes without bumping `VERSION`; only changes that
+//! move or reinterpret existing fields need a new version.
+//!
+//! Flight computers and ground stations are updated at different times, so decoders also accept frames
+//! from the previous `ProtocolVersion` and upgrade them through the layouts kept in `legacy`.
+//!
+//! Byte order is fixed and never the host's: the length and CRC are little endian, written with
+//! `to_le_bytes`, and postcard encodes integers as little-endian varints and floats as little-endian IEEE 754.
+//! A big-endian ground device therefore decodes the same bytes as everyone else; `tests/endian.rs` holds
+//! fixtures for checking that on such a target.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use super::checksum::CRC_LEN;
+use super::checksum::crc16;
+use super::legacy::AprsCompressedPositionReportV1;
+use super::{AllSensorData, AprsCompressedPositionReport, Error};
+
+pub const START: u8 = 0x7E;
+/// Frame format version written by this build, see `ProtocolVersion`
+pub const VERSION: u8 = ProtocolVersion::CURRENT as u8;
+/// Start delimiter, version, type and length
+pub const HEADER_LEN: usize = 5;
+/// Zero bytes after each payload, left for additive changes
+pub const RESERVED_LEN: usize = 2;
+
+/// Frame format version, bumped whenever the header or a payload layout changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    V1 = 1,
+    /// Comments carry a CRC and a 4-bit message type, where the old Placeholder reads as Beacon, and APRS
+    /// reports an optional plain-text comment
+    V2 = 2,
+}
+
+impl ProtocolVersion {
+    pub const CURRENT: ProtocolVersion = ProtocolVersion::V2;
+    /// Oldest version this build still decodes
+    pub const OLDEST: ProtocolVersion = ProtocolVersion::V1;
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(ProtocolVersion::V1),
+            2 => Some(ProtocolVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+/// A message that can be carried in a codec frame
+pub trait WireMessage: Serialize + DeserializeOwned {
+    /// Type byte identifying the payload
+    const TYPE: u8;
+
+    /// Decodes a payload written by a sender on an older `version`, upgrading it to the current layout
+    fn upgrade(version: ProtocolVersion, payload: &[u8]) -> Result<Self, Error> {
+        let _ = payload;
+        Err(Error::UnsupportedVersion(version as u8))
+    }
+}
+
+impl WireMessage for AllSensorData {
+    const TYPE: u8 = 1;
+
+    /// The sensor layout has not changed since V1
+    fn upgrade(_version: ProtocolVersion, payload: &[u8]) -> Result<Self, Error> {
+        take(payload)
+    }
+}
+
+impl WireMessage for AprsCompressedPositionReport {
+    const TYPE: u8 = 2;
+
+    fn upgrade(version: ProtocolVersion, payload: &[u8]) -> Result<Self, Error> {
+        match version {
+            ProtocolVersion::V1 => take::<AprsCompressedPositionReportV1>(payload).map(Into::into),
+            ProtocolVersion::V2 => take(payload),
+        }
+    }
+}
+
+/// Encodes `message` into `buf`, returning the frame
+pub fn encode<'a, T: WireMessage>(message: &T, buf: &'a mut [u8]) -> Result<&'a mut [u8], Error> {
+    if buf.len() < HEADER_LEN + RESERVED_LEN + CRC_LEN {
+        return Err(Error::BufferTooSmall);
+    }
+    let payload_end = buf.len() - RESERVED_LEN - CRC_LEN;
+    let message_len =
+        postcard::to_slice(message, &mut buf[HEADER_LEN..payload_end]).map_err(|_| Error::BufferTooSmall)?.len();
+    buf[HEADER_LEN + message_len..][..RESERVED_LEN].fill(0);
+    let payload_len = message_len + RESERVED_LEN;
+    let len = u16::try_from(payload_len).map_err(|_| Error::BufferTooSmall)?;
+    buf[..HEADER_LEN].copy_from_slice(&[START, VERSION, T::TYPE, len.to_le_bytes()[0], len.to_le_bytes()[1]]);
+    let end = HEADER_LEN + payload_len;
+    let crc = crc16(&buf[1..end]);
+    buf[end..end + CRC_LEN].copy_from_slice(&crc.to_le_bytes());
+    Ok(&mut buf[..end + CRC_LEN])
+}
+
+/// Type byte of a frame, to pick which message type to decode it as
+pub fn peek_type(frame: &[u8]) -> Result<u8, Error> {
+    peek_version(frame)?;
+    frame.get(2).copied().ok_or(Error::Truncated)
+}
+
+/// Version the frame's sender speaks, an error for versions newer than this build or older than `OLDEST`
+pub fn peek_version(frame: &[u8]) -> Result<ProtocolVersion, Error> {
+    match frame {
+        [START, version, ..] => ProtocolVersion::from_byte(*version).ok_or(Error::UnsupportedVersion(*version)),
+        [byte, ..] => Err(Error::InvalidByte { offset: 0, byte: *byte }),
+        [] => Err(Error::Truncated),
+    }
+}
+
+/// Decodes one frame from the start of `frame`, returning the message and the number of bytes consumed
+///
+/// A frame not beginning with `START` is an `InvalidByte` at offset 0, a payload that doesn't decode as `T`
+/// despite a matching CRC is `Malformed`.
+pub fn decode<T: WireMessage>(frame: &[u8]) -> Result<(T, usize), Error> {
+    decode_versioned(frame).map(|(message, _, used)| (message, used))
+}
+
+/// Like `decode`, also returning the sender's version so a ground station can tell who needs updating
+pub fn decode_versioned<T: WireMessage>(frame: &[u8]) -> Result<(T, ProtocolVersion, usize), Error> {
+    let version = peek_version(frame)?;
+    let found = peek_type(frame)?;
+    if frame.len() < HEADER_LEN {
+        return Err(Error::Truncated);
+    }
+    if found != T::TYPE {
+        return Err(Error::WrongType { expected: T::TYPE, found });
+    }
+    let len = u16::from_le_bytes([frame[3], frame[4]]) as usize;
+    let end = HEADER_LEN + len;
+    let crc = frame.get(end..end + CRC_LEN).ok_or(Error::Truncated)?;
+    if crc16(&frame[1..end]) != u16::from_le_bytes([crc[0], crc[1]]) {
+        return Err(Error::BadCrc);
+    }
+    let payload = &frame[HEADER_LEN..end];
+    let message = match version {
+        ProtocolVersion::CURRENT => take(payload)?,
+        older => T::upgrade(older, payload)?,
+    };
+    Ok((message, version, end + CRC_LEN))
+}
+
+/// Reserved bytes and fields from newer revisions follow the message and are ignored
+fn take<T: DeserializeOwned>(payload: &[u8]) -> Result<T, Error> {
+    postcard::take_from_bytes(payload).map(|(message, _)| message).map_err(|_| Error::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::legacy::CommentV1;
+    use crate::protocol::MessageType;
+    use crate::protocol::BMP390;
+
+    #[derive(Serialize, serde::Deserialize)]
+    struct Extended {
+        sensors: AllSensorData,
+        added: u8,
+    }
+
+    impl WireMessage for Extended {
+        const TYPE: u8 = AllSensorData::TYPE;
+    }
+
+    fn sensors() -> AllSensorData {
+        AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: Some(BMP390 { pressure: 95_000.0, temperature: 21.5, altitude: 540.0 }),
+            gps: None,
+            adxl375: None,
+            ism330dhcx2: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_rejections() {
+        let mut buf = [0u8; 128];
+        let frame = encode(&sensors(), &mut buf).unwrap().to_vec();
+        assert_eq!(frame[..3], [START, VERSION, AllSensorData::TYPE]);
+        let (decoded, used) = decode::<AllSensorData>(&frame).unwrap();
+        assert_eq!((decoded, used), (sensors(), frame.len()));
+
+        let mut corrupt = frame.clone();
+        corrupt[HEADER_LEN + 2] ^= 0x10;
+        assert_eq!(decode::<AllSensorData>(&corrupt), Err(Error::BadCrc));
+        assert_eq!(decode::<AllSensorData>(&frame[..frame.len() - 1]), Err(Error::Truncated));
+        assert_eq!(
+            decode::<AprsCompressedPositionReport>(&frame).err(),
+            Some(Error::WrongType { expected: 2, found: 1 })
+        );
+        let mut newer = frame.clone();
+        newer[1] = VERSION + 1;
+        assert_eq!(decode::<AllSensorData>(&newer), Err(Error::UnsupportedVersion(VERSION + 1)));
+
+        // A newer sender appending a field is still understood
+        let extended = encode(&Extended { sensors: sensors(), added: 0xAB }, &mut buf).unwrap();
+        assert_eq!(decode::<AllSensorData>(extended).unwrap().0, sensors());
+
+        let report = AprsCompressedPositionReport { symbol_table: '/', symbol_code: '>', ..Default::default() };
+        let frame = encode(&report, &mut buf).unwrap();
+        let (decoded, _) = decode::<AprsCompressedPositionReport>(frame).unwrap();
+        assert_eq!((decoded.symbol_table, decoded.symbol_code), ('/', '>'));
+    }
+
+    impl WireMessage for AprsCompressedPositionReportV1 {
+        const TYPE: u8 = AprsCompressedPositionReport::TYPE;
+    }
+
+    /// Rewrites a frame as a sender on `version` would have stamped it
+    fn restamp(frame: &mut [u8], version: ProtocolVersion) {
+        frame[1] = version as u8;
+        let end = frame.len() - CRC_LEN;
+        let crc = crc16(&frame[1..end]);
+        frame[end..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    #[test]
+    fn test_previous_version_upgraded() {
+        let mut buf = [0u8; 128];
+        let comment = CommentV1 { uid: 4, msg_id: 9, msg_type: 2, ..Default::default() };
+        let old = AprsCompressedPositionReportV1 { symbol_code: '>', comment, alt: 1_200.0, ..Default::default() };
+        let frame = encode(&old, &mut buf).unwrap();
+        restamp(frame, ProtocolVersion::V1);
+        let (report, version, _) = decode_versioned::<AprsCompressedPositionReport>(frame).unwrap();
+        assert_eq!((version, report.symbol_code, report.alt), (ProtocolVersion::V1, '>', 1_200.0));
+        assert!(report.text_comment.is_none());
+        let comment = report.verified_comment().unwrap();
+        assert_eq!((comment.uid, comment.msg_id, comment.msg_type), (4, 9, MessageType::Beacon));
+
+        let frame = encode(&sensors(), &mut buf).unwrap();
+        restamp(frame, ProtocolVersion::V1);
+        assert_eq!(decode_versioned::<AllSensorData>(frame).unwrap().0, sensors());
+        assert_eq!(peek_version(&[START, 0]), Err(Error::UnsupportedVersion(0)));
+    }
+}
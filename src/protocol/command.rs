@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// Length of the authentication tag carried by every command
+pub const TAG_LEN: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Abort the flight sequence, requires confirmation from two distinct UIDs
+    Abort,
+    /// SAFE -> ARMED_PAD
+    ArmPad,
+    /// ARMED_PAD -> ARMED_FLIGHT
+    ArmFlight,
+    /// Any state -> SAFE
+    Safe,
+}
+
+/// SignedCommand is a command uplinked from a ground station along with its authentication tag
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SignedCommand {
+    pub sender_uid: u8,
+    pub target_uid: u8,
+    /// Per-sender counter, must increase on every command to prevent replay
+    pub sequence: u16,
+    pub command: Command,
+    /// Tag over the serialized fields above, computed with the sender's key
+    pub tag: [u8; TAG_LEN],
+}
+
+impl SignedCommand {
+    /// Serializes the authenticated fields (everything except the tag) into `buf`
+    pub fn signed_bytes<'a>(&self, buf: &'a mut [u8]) -> postcard::Result<&'a mut [u8]> {
+        postcard::to_slice(&(self.sender_uid, self.target_uid, self.sequence, self.command), buf)
+    }
+}
@@ -0,0 +1,269 @@
+//! APRS compressed position format (APRS 1.01, chapter 9): base-91 latitude, longitude and course/speed
+//! or altitude
+
+use heapless::Vec;
+
+use super::aprs::{TextComment, MAX_COMMENT_LEN};
+use super::ax25::{Ax25Error, MAX_INFO_LEN};
+use super::{AprsCompressedPositionReport, Comment, Error};
+
+/// Offset of base-91 digits from their ASCII character
+const BASE91_OFFSET: u8 = 33;
+const FEET_PER_METER: f64 = 3.280_84;
+
+/// Compression type byte: current GPS fix, positions computed by software
+const TYPE_CURRENT_SOFTWARE: u8 = 0b10_0010;
+/// NMEA source bits of the compression type, GGA means the cs bytes carry altitude
+const NMEA_MASK: u8 = 0b1_1000;
+const NMEA_GGA: u8 = 0b1_0000;
+const NMEA_RMC: u8 = 0b1_1000;
+
+impl AprsCompressedPositionReport {
+    /// Builds a report for `lat`/`lon` in degrees and `alt_m` in meters
+    ///
+    /// The two cs bytes carry either course (degrees) and speed (knots) or altitude: with `course` set
+    /// they carry course and speed, otherwise altitude. The raw `lat`, `lon` and `alt` fields are kept
+    /// alongside. Symbol, time, comment and format fields keep their defaults.
+    pub fn encode(lat: f64, lon: f64, alt_m: f64, course: Option<u16>, speed_knots: f32) -> Self {
+        let mut report = Self { lat, lon, alt: alt_m, ..Default::default() };
+        report.compressed_lat = base91::<4>(libm::floor(380_926.0 * (90.0 - lat.clamp(-90.0, 90.0))));
+        report.compressed_long = base91::<4>(libm::floor(190_463.0 * (180.0 + lon.clamp(-180.0, 180.0))));
+        let nmea = match course {
+            Some(course) => {
+                let c = (course % 360) / 4;
+                let s = libm::round(libm::log(speed_knots.max(0.0) as f64 + 1.0) / libm::log(1.08)).min(90.0);
+                report.compressed_altitude = [c as u8 + BASE91_OFFSET, s as u8 + BASE91_OFFSET];
+                NMEA_RMC
+            }
+            None => {
+                let feet = (alt_m * FEET_PER_METER).max(1.0);
+                report.compressed_altitude = base91::<2>(libm::round(libm::log(feet) / libm::log(1.002)));
+                NMEA_GGA
+            }
+        };
+        report.compression_type = ((TYPE_CURRENT_SOFTWARE | nmea) + BASE91_OFFSET) as char;
+        report
+    }
+
+    /// Latitude and longitude in degrees, decoded from the compressed fields
+    pub fn position(&self) -> (f64, f64) {
+        let lat = 90.0 - from_base91(&self.compressed_lat) / 380_926.0;
+        let lon = -180.0 + from_base91(&self.compressed_long) / 190_463.0;
+        (lat, lon)
+    }
+
+    /// Altitude in meters, when the cs bytes carry altitude
+    pub fn altitude_m(&self) -> Option<f64> {
+        let feet = libm::pow(1.002, from_base91(&self.compressed_altitude));
+        (self.nmea_source() == Some(NMEA_GGA)).then_some(feet / FEET_PER_METER)
+    }
+
+    /// Course in degrees and speed in knots, when the cs bytes carry them
+    pub fn course_speed(&self) -> Option<(u16, f32)> {
+        if self.nmea_source() == Some(NMEA_GGA) || self.compressed_altitude[0] == b' ' {
+            return None;
+        }
+        let [c, s] = self.compressed_altitude.map(|b| b.wrapping_sub(BASE91_OFFSET));
+        (c < 90).then(|| (c as u16 * 4, (libm::pow(1.08, s as f64) - 1.0) as f32))
+    }
+
+    /// Parses an APRS information field holding a compressed position, such as `=/5L!!<*e7>7P[`
+    ///
+    /// Accepts the '!' and '=' formats and the timestamped '/' and '@' formats. The comment is kept as
+    /// text, truncated to `MAX_COMMENT_LEN`, or as a packed Comment when it isn't text. A byte out of range
+    /// for its field is an `InvalidByte` at its offset in `bytes`, an unknown format an `UnknownDiscriminant`.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut report = Self::default();
+        let (&format, mut rest) = bytes.split_first().ok_or(Error::Truncated)?;
+        let mut offset = 1;
+        match format {
+            b'!' | b'=' => {}
+            b'/' | b'@' => {
+                let time = rest.get(..7).ok_or(Error::Truncated)?;
+                // Six digits followed by 'z', '/' or 'h'
+                let suffix = (!matches!(time[6], b'z' | b'/' | b'h')).then_some(6);
+                if let Some(i) = time[..6].iter().position(|b| !b.is_ascii_digit()).or(suffix) {
+                    return Err(Error::InvalidByte { offset: offset + i, byte: time[i] });
+                }
+                report.time.copy_from_slice(time);
+                (rest, offset) = (&rest[7..], offset + 7);
+            }
+            other => return Err(Error::UnknownDiscriminant(other)),
+        }
+        report.compression_format = format as char;
+        let body = rest.get(..13).ok_or(Error::Truncated)?;
+        let base91_at = |range: core::ops::Range<usize>| {
+            body[range.clone()].iter().zip(range).try_for_each(|(&byte, i)| match byte {
+                b'!'..=b'{' => Ok(()),
+                _ => Err(Error::InvalidByte { offset: offset + i, byte }),
+            })
+        };
+
+        report.symbol_table = match body[0] {
+            b'/' | b'\\' | b'A'..=b'Z' => body[0] as char,
+            // Overlay digits are sent as 'a'-'j' in compressed positions
+            b'a'..=b'j' => (body[0] - b'a' + b'0') as char,
+            byte => return Err(Error::InvalidByte { offset, byte }),
+        };
+        base91_at(1..9)?;
+        report.compressed_lat.copy_from_slice(&body[1..5]);
+        report.compressed_long.copy_from_slice(&body[5..9]);
+        report.symbol_code = match body[9] {
+            b'!'..=b'~' => body[9] as char,
+            byte => return Err(Error::InvalidByte { offset: offset + 9, byte }),
+        };
+        report.compressed_altitude.copy_from_slice(&body[10..12]);
+        report.compression_type = body[12] as char;
+        // A space in place of the cs bytes means they and the compression type are absent
+        if body[10] != b' ' {
+            base91_at(10..12)?;
+            if !(b'!'..=b'`').contains(&body[12]) {
+                return Err(Error::InvalidByte { offset: offset + 12, byte: body[12] });
+            }
+        }
+
+        (report.lat, report.lon) = report.position();
+        report.alt = report.altitude_m().unwrap_or_default();
+        let comment = &rest[13..];
+        if comment.is_empty() {
+            return Ok(report);
+        }
+        match core::str::from_utf8(comment).ok().and_then(|text| TextComment::new(truncate(text)).ok()) {
+            Some(text) => report.text_comment = Some(text),
+            None => report.comment = postcard::from_bytes::<Comment>(comment).map_err(|_| Error::Malformed)?,
+        }
+        Ok(report)
+    }
+
+    /// Formats the report as an APRS information field, the inverse of `decode`
+    ///
+    /// Formats other than '/', '@' and '=' are written as '!', a text comment is written as is and a
+    /// packed Comment in its postcard encoding.
+    pub fn encode_info(&self) -> Vec<u8, MAX_INFO_LEN> {
+        let mut buf = [0u8; MAX_INFO_LEN];
+        let len = self.write_info(&mut buf).expect("a report's information field fits MAX_INFO_LEN");
+        Vec::from_slice(&buf[..len]).expect("written within MAX_INFO_LEN")
+    }
+
+    /// Writes the information field into `buf`, returning its length, for callers without room for a copy
+    pub fn write_info(&self, buf: &mut [u8]) -> Result<usize, Ax25Error> {
+        let format = match self.compression_format {
+            format @ ('/' | '@' | '=') => format as u8,
+            _ => b'!',
+        };
+        let table = match self.symbol_table {
+            digit @ '0'..='9' => digit as u8 - b'0' + b'a',
+            table => table as u8,
+        };
+        let mut info = Writer { buf, len: 0 };
+        info.put(&[format])?;
+        if matches!(format, b'/' | b'@') {
+            info.put(&self.time)?;
+        }
+        info.put(&[table])?;
+        info.put(&self.compressed_lat)?;
+        info.put(&self.compressed_long)?;
+        info.put(&[self.symbol_code as u8])?;
+        info.put(&self.compressed_altitude)?;
+        info.put(&[self.compression_type as u8])?;
+        match &self.text_comment {
+            Some(text) => info.put(text.as_str().as_bytes())?,
+            None => {
+                let rest = &mut info.buf[info.len..];
+                info.len += postcard::to_slice(&self.comment, rest).map_err(|_| Ax25Error::InfoTooLong)?.len();
+            }
+        }
+        Ok(info.len)
+    }
+
+    fn nmea_source(&self) -> Option<u8> {
+        let byte = u8::try_from(self.compression_type).ok()?.checked_sub(BASE91_OFFSET)?;
+        Some(byte & NMEA_MASK)
+    }
+}
+
+/// Appends to a caller's buffer, failing once it is full
+pub(super) struct Writer<'a> {
+    pub(super) buf: &'a mut [u8],
+    pub(super) len: usize,
+}
+
+impl Writer<'_> {
+    pub(super) fn put(&mut self, bytes: &[u8]) -> Result<(), Ax25Error> {
+        let out = self.buf.get_mut(self.len..self.len + bytes.len()).ok_or(Ax25Error::InfoTooLong)?;
+        out.copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+}
+
+/// Big-endian base-91 digits of `value`, clamped to what `N` digits hold
+pub(super) fn base91<const N: usize>(value: f64) -> [u8; N] {
+    let max = libm::pow(91.0, N as f64) - 1.0;
+    let mut value = value.clamp(0.0, max) as u32;
+    let mut digits = [BASE91_OFFSET; N];
+    for digit in digits.iter_mut().rev() {
+        *digit += (value % 91) as u8;
+        value /= 91;
+    }
+    digits
+}
+
+pub(super) fn truncate(text: &str) -> &str {
+    text.char_indices().nth(MAX_COMMENT_LEN).map_or(text, |(end, _)| &text[..end])
+}
+
+pub(super) fn from_base91(digits: &[u8]) -> f64 {
+    digits.iter().fold(0.0, |value, digit| value * 91.0 + digit.wrapping_sub(BASE91_OFFSET) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_example_and_round_trip() {
+        // APRS 1.01 examples: 49°30'N 72°45'W is "5L!!<*e7", 10004 ft is "S]"
+        let report = AprsCompressedPositionReport::encode(49.5, -72.75, 10_004.0 / FEET_PER_METER, None, 0.0);
+        assert_eq!(&report.compressed_lat, b"5L!!");
+        assert_eq!(&report.compressed_long, b"<*e7");
+        assert_eq!(&report.compressed_altitude, b"S]");
+        assert!((report.altitude_m().unwrap() * FEET_PER_METER - 10_004.0).abs() < 20.0);
+        assert_eq!(report.course_speed(), None);
+
+        let (lat, lon) = AprsCompressedPositionReport::encode(37.2296, -80.4139, 0.0, None, 0.0).position();
+        // Base-91 resolution is about 0.3 m
+        assert!((lat - 37.2296).abs() < 1e-5 && (lon + 80.4139).abs() < 1e-5);
+
+        // Course 88° and 36.2 knots are "7P"
+        let moving = AprsCompressedPositionReport::encode(37.0, -80.0, 600.0, Some(88), 36.2);
+        assert_eq!(&moving.compressed_altitude, b"7P");
+        let (course, speed) = moving.course_speed().unwrap();
+        assert_eq!(course, 88);
+        assert!((speed - 36.2).abs() < 1.5);
+        assert_eq!(moving.altitude_m(), None);
+    }
+
+    #[test]
+    fn test_decode_and_typed_errors() {
+        let report = AprsCompressedPositionReport::decode(b"=/5L!!<*e7>7P[RVT tracker").unwrap();
+        assert_eq!((report.symbol_table, report.symbol_code), ('/', '>'));
+        assert!((report.lat - 49.5).abs() < 1e-5 && (report.lon + 72.75).abs() < 1e-5);
+        assert_eq!(report.course_speed().map(|(course, _)| course), Some(88));
+        assert_eq!(report.text_comment.unwrap().as_str(), "RVT tracker");
+
+        let timed = AprsCompressedPositionReport::decode(b"@092345zd5L!!<*e7OS]S").unwrap();
+        assert_eq!((&timed.time, timed.symbol_table), (b"092345z", '3'));
+        assert!((timed.alt * FEET_PER_METER - 10_004.0).abs() < 20.0);
+
+        use Error::*;
+        let decode = AprsCompressedPositionReport::decode;
+        assert_eq!(decode(b"=/5L!!<*e7>7P").err(), Some(Truncated));
+        assert_eq!(decode(b";/5L!!<*e7>7P[").err(), Some(UnknownDiscriminant(b';')));
+        assert_eq!(decode(b"@0923z/5L!!<*e7>7P[").err(), Some(InvalidByte { offset: 5, byte: b'z' }));
+        assert_eq!(decode(b"=x5L!!<*e7>7P[").err(), Some(InvalidByte { offset: 1, byte: b'x' }));
+        assert_eq!(decode(b"=/5L!!<*e~>7P[").err(), Some(InvalidByte { offset: 9, byte: b'~' }));
+        assert_eq!(decode(b"=/5L!!<*e7>7P~").err(), Some(InvalidByte { offset: 13, byte: b'~' }));
+        assert!(decode(b"=/5L!!<*e7>  ~").is_ok());
+    }
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// ConfigHash is reported by the vehicle so the ground can check it flies the configuration it expects
+///
+/// Each section is hashed separately (see `params::digest` and `RegionPlan::hash`) so a mismatch points
+/// at what is stale.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConfigHash {
+    pub uid: u8,
+    pub params: u32,
+    /// Hash of the command key identifiers and versions, never of key material
+    pub keys: u32,
+    pub channel_plan: u32,
+}
+
+impl ConfigHash {
+    /// Number of sections that differ from `expected`
+    pub fn mismatches(&self, expected: &ConfigHash) -> u8 {
+        [self.params != expected.params, self.keys != expected.keys, self.channel_plan != expected.channel_plan]
+            .iter()
+            .filter(|differs| **differs)
+            .count() as u8
+    }
+}
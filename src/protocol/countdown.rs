@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CountdownPhase {
+    #[default]
+    Idle = 0,
+    Counting = 1,
+    Hold = 2,
+    Aborted = 3,
+}
+
+/// CountdownState is originated by the ground station and redistributed across the mesh
+///
+/// Receivers take their local receive time as the reference for `t_minus_ms`, so every node
+/// shares the countdown to within one link latency without needing synchronized clocks.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CountdownState {
+    /// UID of the ground station that issued this state
+    pub origin_uid: u8,
+    /// Incremented by the originator on every change, used to drop stale or repeated copies
+    pub sequence: u16,
+    pub phase: CountdownPhase,
+    /// Time remaining to T-0 when the state was issued, negative once past T-0
+    pub t_minus_ms: i32,
+}
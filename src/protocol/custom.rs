@@ -0,0 +1,202 @@
+//! Team-defined payloads carried in the APRS comment when `msg_type` is `MessageType::Custom`
+//!
+//! The 26 bytes normally holding ADS data are laid out as
+//!
+//! ```text
+//! sub-type (u8) | length (u8) | data (up to 24 bytes, zero padded)
+//! ```
+//!
+//! Sub-types are assigned by the teams sharing a launch; receivers dispatch each one to the handler
+//! registered for it.
+
+use heapless::Vec;
+
+use super::{AdsCompressed, Comment, Error, MessageType};
+
+/// Bytes of the ADS area
+const AREA_LEN: usize = 26;
+/// Largest custom payload
+pub const MAX_CUSTOM_DATA: usize = AREA_LEN - 2;
+/// Maximum number of sub-type handlers in a registry
+pub const MAX_CUSTOM_HANDLERS: usize = 8;
+
+/// A custom payload with its sub-type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomPayload {
+    pub sub_type: u8,
+    pub data: Vec<u8, MAX_CUSTOM_DATA>,
+}
+
+impl CustomPayload {
+    /// Returns `None` if `data` is longer than `MAX_CUSTOM_DATA`
+    pub fn new(sub_type: u8, data: &[u8]) -> Option<Self> {
+        Some(Self { sub_type, data: Vec::from_slice(data).ok()? })
+    }
+
+    /// Places the payload in `comment`'s ADS area and marks it Custom
+    pub fn write_to(&self, comment: &mut Comment) {
+        let mut area = [0u8; AREA_LEN];
+        area[0] = self.sub_type;
+        area[1] = self.data.len() as u8;
+        area[2..2 + self.data.len()].copy_from_slice(&self.data);
+        comment.msg_type = MessageType::Custom;
+        comment.ads = unpack_area(&area);
+    }
+
+    /// `WrongType` unless the comment is Custom, a length byte over `MAX_CUSTOM_DATA` is an `InvalidByte` at
+    /// its offset in the ADS area
+    pub fn read_from(comment: &Comment) -> Result<Self, Error> {
+        if !matches!(comment.msg_type, MessageType::Custom) {
+            return Err(Error::WrongType { expected: MessageType::Custom as u8, found: comment.msg_type as u8 });
+        }
+        let area = pack_area(&comment.ads);
+        let len = area[1];
+        let data = area.get(2..2 + len as usize).and_then(|data| Vec::from_slice(data).ok());
+        let data = data.ok_or(Error::InvalidByte { offset: 1, byte: len })?;
+        Ok(Self { sub_type: area[0], data })
+    }
+}
+
+fn ads_fields(ads: &mut AdsCompressed) -> [&mut i16; 11] {
+    [
+        &mut ads.lat,
+        &mut ads.lon,
+        &mut ads.vel_x,
+        &mut ads.vel_y,
+        &mut ads.vel_z,
+        &mut ads.acc_x,
+        &mut ads.acc_y,
+        &mut ads.acc_z,
+        &mut ads.alt,
+        &mut ads.predicted_apogee,
+        &mut ads.flap_deploy_angle,
+    ]
+}
+
+/// Reads the ADS area as bytes, fields in declaration order, little endian
+fn pack_area(ads: &AdsCompressed) -> [u8; AREA_LEN] {
+    let mut copy = *ads;
+    let mut area = [0u8; AREA_LEN];
+    for (chunk, field) in area.chunks_exact_mut(2).zip(ads_fields(&mut copy)) {
+        chunk.copy_from_slice(&field.to_le_bytes());
+    }
+    area[22..].copy_from_slice(&ads.timestamp.to_le_bytes());
+    area
+}
+
+fn unpack_area(area: &[u8; AREA_LEN]) -> AdsCompressed {
+    let mut ads = AdsCompressed::default();
+    for (chunk, field) in area.chunks_exact(2).zip(ads_fields(&mut ads)) {
+        *field = i16::from_le_bytes([chunk[0], chunk[1]]);
+    }
+    ads.timestamp = i32::from_le_bytes([area[22], area[23], area[24], area[25]]);
+    ads
+}
+
+/// Receives the custom payloads of one sub-type
+pub trait CustomHandler {
+    fn handle(&mut self, source_uid: u8, data: &[u8]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterError {
+    Full,
+    /// Another handler already owns the sub-type
+    Taken(u8),
+}
+
+/// CustomRegistry routes custom payloads to the handler registered for their sub-type
+#[derive(Default)]
+pub struct CustomRegistry<'a> {
+    /// Sub-type, whether it is only taken sealed, and its handler
+    handlers: Vec<(u8, bool, &'a mut dyn CustomHandler), MAX_CUSTOM_HANDLERS>,
+}
+
+impl<'a> CustomRegistry<'a> {
+    pub fn new() -> Self {
+        Self { handlers: Vec::new() }
+    }
+
+    pub fn register(&mut self, sub_type: u8, handler: &'a mut dyn CustomHandler) -> Result<(), RegisterError> {
+        self.insert(sub_type, false, handler)
+    }
+
+    /// Registers a handler for payloads that must not be spoofable, such as a command firing a pyro channel
+    ///
+    /// Plain payloads of the sub-type are refused as `Unauthenticated`; it is only reached through
+    /// `dispatch_opened` with payloads `crypto::Opener` has authenticated.
+    pub fn register_sealed(&mut self, sub_type: u8, handler: &'a mut dyn CustomHandler) -> Result<(), RegisterError> {
+        self.insert(sub_type, true, handler)
+    }
+
+    fn insert(&mut self, sub_type: u8, sealed: bool, handler: &'a mut dyn CustomHandler) -> Result<(), RegisterError> {
+        if self.handlers.iter().any(|(registered, _, _)| *registered == sub_type) {
+            return Err(RegisterError::Taken(sub_type));
+        }
+        self.handlers.push((sub_type, sealed, handler)).map_err(|_| RegisterError::Full)
+    }
+
+    /// Hands the custom payload in `comment` to its handler, a sub-type with none is an `UnknownDiscriminant`
+    pub fn dispatch(&mut self, comment: &Comment) -> Result<u8, Error> {
+        let payload = CustomPayload::read_from(comment)?;
+        self.deliver(comment.uid, &payload, false)
+    }
+
+    /// Hands a payload that arrived sealed and has been opened to its handler
+    pub fn dispatch_opened(&mut self, source_uid: u8, payload: &CustomPayload) -> Result<u8, Error> {
+        self.deliver(source_uid, payload, true)
+    }
+
+    fn deliver(&mut self, source_uid: u8, payload: &CustomPayload, opened: bool) -> Result<u8, Error> {
+        let (_, sealed, handler) = self
+            .handlers
+            .iter_mut()
+            .find(|(sub_type, _, _)| *sub_type == payload.sub_type)
+            .ok_or(Error::UnknownDiscriminant(payload.sub_type))?;
+        if *sealed && !opened {
+            return Err(Error::Unauthenticated);
+        }
+        handler.handle(source_uid, &payload.data);
+        Ok(payload.sub_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Collect(StdVec<(u8, StdVec<u8>)>);
+
+    impl CustomHandler for Collect {
+        fn handle(&mut self, source_uid: u8, data: &[u8]) {
+            self.0.push((source_uid, data.to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_dispatch() {
+        let mut comment = Comment { uid: 7, ..Default::default() };
+        assert_eq!(CustomPayload::read_from(&comment), Err(Error::WrongType { expected: 3, found: 1 }));
+        let payload = CustomPayload::new(0x42, b"camera on, 24 fps").unwrap();
+        payload.write_to(&mut comment);
+        assert_eq!(CustomPayload::read_from(&comment), Ok(payload));
+        assert!(CustomPayload::new(1, &[0; MAX_CUSTOM_DATA + 1]).is_none());
+
+        let mut camera = Collect::default();
+        let mut other = Collect::default();
+        let mut registry = CustomRegistry::new();
+        registry.register(0x42, &mut camera).unwrap();
+        assert!(matches!(registry.register(0x42, &mut other), Err(RegisterError::Taken(0x42))));
+        assert_eq!(registry.dispatch(&comment), Ok(0x42));
+
+        CustomPayload::new(0x43, &[]).unwrap().write_to(&mut comment);
+        assert_eq!(registry.dispatch(&comment), Err(Error::UnknownDiscriminant(0x43)));
+        comment.ads.lat = i16::from_le_bytes([0x43, 200]);
+        assert_eq!(registry.dispatch(&comment), Err(Error::InvalidByte { offset: 1, byte: 200 }));
+        drop(registry);
+        assert_eq!(camera.0, [(7, b"camera on, 24 fps".to_vec())]);
+    }
+}
@@ -0,0 +1,334 @@
+//! Delta encoding of consecutive AllSensorData frames
+//!
+//! A keyframe carries the full AllSensorData. Until the receiver acknowledges one, every frame is a
+//! keyframe; afterwards frames carry only the fields that differ from the acknowledged keyframe. Each
+//! changed field is sent as the XOR of its bit pattern with the reference's, with trailing zero bits
+//! stripped, so values that moved by a little cost a few bytes and unchanged ones cost nothing.
+//!
+//! Delta frames cover every scalar field; `NavSat` satellite details are only refreshed by keyframes.
+
+use heapless::{Deque, Vec};
+use serde::{Deserialize, Serialize};
+
+use super::{AllSensorData, Error, GpsFix, ISM330DHCX};
+
+/// Scalar fields covered by a delta frame
+pub const DELTA_FIELDS: usize = 42;
+/// Keyframes the decoder remembers, the encoder may still reference the previous one while a new one is
+/// being acknowledged
+pub const DECODER_KEYFRAMES: usize = 2;
+
+/// Telemetry frame, either complete or relative to an acknowledged keyframe
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum SensorFrame {
+    /// The receiver should acknowledge `id` so later frames can be sent as deltas against it
+    Key { id: u16, data: AllSensorData },
+    Delta {
+        base: u16,
+        /// Bit `i` is set when field `i` changed
+        changed: u64,
+        /// `(shift, xor >> shift)` for every changed field, in field order
+        values: Vec<(u8, u64), DELTA_FIELDS>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DeltaConfig {
+    /// Frames between keyframes, bounding how long a receiver that missed one stays out of sync
+    pub keyframe_interval: u16,
+}
+
+impl Default for DeltaConfig {
+    fn default() -> Self {
+        // 5 s at 10 Hz
+        Self { keyframe_interval: 50 }
+    }
+}
+
+/// DeltaEncoder turns consecutive sensor frames into keyframes and deltas
+#[derive(Debug, Clone)]
+pub struct DeltaEncoder {
+    config: DeltaConfig,
+    next_id: u16,
+    since_key: u16,
+    pending: Option<(u16, AllSensorData)>,
+    acked: Option<(u16, AllSensorData)>,
+}
+
+impl DeltaEncoder {
+    pub fn new(config: DeltaConfig) -> Self {
+        Self { config, next_id: 0, since_key: 0, pending: None, acked: None }
+    }
+
+    pub fn encode(&mut self, data: &AllSensorData) -> SensorFrame {
+        self.since_key = self.since_key.saturating_add(1);
+        let due = self.since_key >= self.config.keyframe_interval;
+        let (base, reference) = match &self.acked {
+            Some((id, reference)) if !due && layout(reference) == layout(data) => (*id, reference),
+            _ => return self.keyframe(data),
+        };
+        let (current, reference) = (flatten(data), flatten(reference));
+        let mut changed = 0u64;
+        let mut values = Vec::new();
+        for (i, (a, b)) in current.iter().zip(reference.iter()).enumerate() {
+            let xor = a ^ b;
+            if xor != 0 {
+                let shift = xor.trailing_zeros() as u8;
+                changed |= 1 << i;
+                let _ = values.push((shift, xor >> shift));
+            }
+        }
+        SensorFrame::Delta { base, changed, values }
+    }
+
+    /// Call when the receiver acknowledges keyframe `id`
+    ///
+    /// Only the latest keyframe can be acknowledged, acks for keyframes it superseded are ignored.
+    pub fn on_ack(&mut self, id: u16) {
+        if self.pending.is_some_and(|(pending, _)| pending == id) {
+            self.acked = self.pending.take();
+        }
+    }
+
+    fn keyframe(&mut self, data: &AllSensorData) -> SensorFrame {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.since_key = 0;
+        self.pending = Some((id, *data));
+        SensorFrame::Key { id, data: *data }
+    }
+}
+
+/// DeltaDecoder rebuilds complete frames from keyframes and deltas
+#[derive(Debug, Clone, Default)]
+pub struct DeltaDecoder {
+    keys: Deque<(u16, AllSensorData), DECODER_KEYFRAMES>,
+}
+
+impl DeltaDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A delta against a keyframe never received or since forgotten is `UnknownKeyframe`, one whose values
+    /// don't match its changed bitmap `Malformed`
+    pub fn decode(&mut self, frame: &SensorFrame) -> Result<AllSensorData, Error> {
+        match frame {
+            SensorFrame::Key { id, data } => {
+                if self.keys.iter().all(|(key, _)| key != id) {
+                    if self.keys.is_full() {
+                        self.keys.pop_front();
+                    }
+                    let _ = self.keys.push_back((*id, *data));
+                }
+                Ok(*data)
+            }
+            SensorFrame::Delta { base, changed, values } => {
+                let (_, reference) =
+                    self.keys.iter().find(|(key, _)| key == base).ok_or(Error::UnknownKeyframe(*base))?;
+                if changed.count_ones() as usize != values.len() {
+                    return Err(Error::Malformed);
+                }
+                let mut bits = flatten(reference);
+                let mut values = values.iter();
+                for (i, field) in bits.iter_mut().enumerate() {
+                    if changed & (1 << i) != 0 {
+                        let (shift, xor) = values.next().ok_or(Error::Malformed)?;
+                        *field ^= xor.checked_shl(*shift as u32).ok_or(Error::Malformed)?;
+                    }
+                }
+                let mut data = *reference;
+                let mut bits = bits.iter();
+                // The reference has the same layout, so there is one value per field
+                visit(&mut data, |mut field| {
+                    if let Some(value) = bits.next() {
+                        field.set(*value);
+                    }
+                });
+                Ok(data)
+            }
+        }
+    }
+}
+
+/// Which sensors are present; deltas are only taken between frames with the same layout
+fn layout(data: &AllSensorData) -> [bool; 6] {
+    [
+        data.ism330dhcx.is_some(),
+        data.lsm6dso32.is_some(),
+        data.bmp390.is_some(),
+        data.gps.is_some(),
+        data.adxl375.is_some(),
+        data.ism330dhcx2.is_some(),
+    ]
+}
+
+fn flatten(data: &AllSensorData) -> [u64; DELTA_FIELDS] {
+    let mut bits = [0u64; DELTA_FIELDS];
+    let mut copy = *data;
+    let mut i = 0;
+    visit(&mut copy, |field| {
+        bits[i] = field.get();
+        i += 1;
+    });
+    bits
+}
+
+enum Field<'a> {
+    F64(&'a mut f64),
+    F32(&'a mut f32),
+    U32(&'a mut u32),
+    I32(&'a mut i32),
+    U16(&'a mut u16),
+    I16(&'a mut i16),
+    U8(&'a mut u8),
+    Fix(&'a mut GpsFix),
+}
+
+impl Field<'_> {
+    fn get(&self) -> u64 {
+        match self {
+            Field::F64(v) => v.to_bits(),
+            Field::F32(v) => v.to_bits() as u64,
+            Field::U32(v) => **v as u64,
+            Field::I32(v) => **v as u32 as u64,
+            Field::U16(v) => **v as u64,
+            Field::I16(v) => **v as u16 as u64,
+            Field::U8(v) => **v as u64,
+            Field::Fix(v) => u8::from(**v) as u64,
+        }
+    }
+
+    fn set(&mut self, bits: u64) {
+        match self {
+            Field::F64(v) => **v = f64::from_bits(bits),
+            Field::F32(v) => **v = f32::from_bits(bits as u32),
+            Field::U32(v) => **v = bits as u32,
+            Field::I32(v) => **v = bits as u32 as i32,
+            Field::U16(v) => **v = bits as u16,
+            Field::I16(v) => **v = bits as u16 as i16,
+            Field::U8(v) => **v = bits as u8,
+            Field::Fix(v) => **v = GpsFix::from(bits as u8),
+        }
+    }
+}
+
+/// Calls `f` for all `DELTA_FIELDS` scalar fields in a fixed order, absent sensors are visited as zeros
+fn visit(data: &mut AllSensorData, mut f: impl FnMut(Field)) {
+    ism330dhcx(&mut data.ism330dhcx, &mut f);
+    match &mut data.lsm6dso32 {
+        Some(imu) => six_axis(
+            [&mut imu.accel_x, &mut imu.accel_y, &mut imu.accel_z, &mut imu.gyro_x, &mut imu.gyro_y, &mut imu.gyro_z],
+            &mut f,
+        ),
+        None => zeros(6, &mut f),
+    }
+    match &mut data.bmp390 {
+        Some(baro) => {
+            [&mut baro.pressure, &mut baro.temperature, &mut baro.altitude].into_iter().for_each(|v| f(Field::F32(v)))
+        }
+        None => zeros(3, &mut f),
+    }
+    match &mut data.gps {
+        Some(gps) => {
+            for v in [&mut gps.latitude, &mut gps.longitude, &mut gps.altitude, &mut gps.altitude_msl] {
+                f(Field::F64(v));
+            }
+            f(Field::U8(&mut gps.num_sats));
+            f(Field::Fix(&mut gps.fix_type));
+            let utc = &mut gps.utc_time;
+            f(Field::U32(&mut utc.itow));
+            f(Field::U32(&mut utc.time_accuracy_estimate_ns));
+            f(Field::I32(&mut utc.nanos));
+            f(Field::U16(&mut utc.year));
+            for v in [&mut utc.month, &mut utc.day, &mut utc.hour, &mut utc.min, &mut utc.sec, &mut utc.valid] {
+                f(Field::U8(v));
+            }
+        }
+        None => zeros(16, &mut f),
+    }
+    match &mut data.adxl375 {
+        Some(accel) => {
+            [&mut accel.accel_x, &mut accel.accel_y, &mut accel.accel_z].into_iter().for_each(|v| f(Field::I16(v)))
+        }
+        None => zeros(3, &mut f),
+    }
+    ism330dhcx(&mut data.ism330dhcx2, &mut f);
+}
+
+fn ism330dhcx(imu: &mut Option<ISM330DHCX>, f: &mut impl FnMut(Field)) {
+    let Some(imu) = imu else {
+        return zeros(7, f);
+    };
+    f(Field::F32(&mut imu.temp));
+    six_axis(
+        [&mut imu.accel_x, &mut imu.accel_y, &mut imu.accel_z, &mut imu.gyro_x, &mut imu.gyro_y, &mut imu.gyro_z],
+        f,
+    );
+}
+
+fn six_axis(axes: [&mut f64; 6], f: &mut impl FnMut(Field)) {
+    axes.into_iter().for_each(|v| f(Field::F64(v)));
+}
+
+fn zeros(n: usize, f: &mut impl FnMut(Field)) {
+    for _ in 0..n {
+        f(Field::U8(&mut 0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ADXL375, BMP390, LSM6DSO32};
+
+    fn frame(t: f32) -> AllSensorData {
+        AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: Some(LSM6DSO32 {
+                accel_x: 0.1,
+                accel_y: 0.0,
+                accel_z: 9.8,
+                gyro_x: 0.0,
+                gyro_y: 0.0,
+                gyro_z: 0.0,
+            }),
+            bmp390: Some(BMP390 { pressure: 101_325.0 - t, temperature: 20.0, altitude: t * 0.08 }),
+            gps: None,
+            adxl375: Some(ADXL375 { accel_x: 0, accel_y: 0, accel_z: 20 }),
+            ism330dhcx2: None,
+        }
+    }
+
+    fn size(frame: &SensorFrame) -> usize {
+        postcard::to_slice(frame, &mut [0u8; 2048]).unwrap().len()
+    }
+
+    #[test]
+    fn test_deltas_against_acknowledged_keyframe() {
+        let mut encoder = DeltaEncoder::new(DeltaConfig { keyframe_interval: 3 });
+        let mut decoder = DeltaDecoder::new();
+
+        let key = encoder.encode(&frame(0.0));
+        let SensorFrame::Key { id, .. } = key else { panic!("first frame must be a keyframe") };
+        assert_eq!(decoder.decode(&key), Ok(frame(0.0)));
+        // Not acknowledged yet, so still a keyframe, which supersedes the first
+        let key = encoder.encode(&frame(1.0));
+        assert_eq!(decoder.decode(&key), Ok(frame(1.0)));
+        encoder.on_ack(id);
+        encoder.on_ack(id + 1);
+
+        let delta = encoder.encode(&frame(2.0));
+        let SensorFrame::Delta { base, changed, .. } = &delta else { panic!("expected a delta") };
+        assert_eq!((*base, changed.count_ones()), (id + 1, 2));
+        assert!(size(&delta) * 4 < size(&key));
+        assert_eq!(decoder.decode(&delta), Ok(frame(2.0)));
+
+        // A change of layout forces a keyframe
+        let mut without_baro = frame(3.0);
+        without_baro.bmp390 = None;
+        assert!(matches!(encoder.encode(&without_baro), SensorFrame::Key { .. }));
+        assert_eq!(DeltaDecoder::new().decode(&delta), Err(Error::UnknownKeyframe(id + 1)));
+    }
+}
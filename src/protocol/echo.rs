@@ -0,0 +1,26 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use super::ping::LinkQuality;
+
+/// Largest filler payload an Echo may carry, to test with frames near the MTU
+pub const MAX_ECHO_PAYLOAD: usize = 128;
+
+/// How the responder received an Echo, appended when it reflects it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct EchoReceipt {
+    pub responder_uid: u8,
+    pub heard: LinkQuality,
+    /// Hops left on the frame when it arrived
+    pub hops_left: u8,
+}
+
+/// Echo is reflected back to its origin by the addressed node, for end-to-end link checks
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Echo {
+    pub origin_uid: u8,
+    pub id: u16,
+    pub payload: Vec<u8, MAX_ECHO_PAYLOAD>,
+    /// `None` on the way out, set by the responder on the way back
+    pub receipt: Option<EchoReceipt>,
+}
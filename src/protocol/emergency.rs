@@ -0,0 +1,43 @@
+use heapless::String;
+use serde::{Deserialize, Serialize};
+
+/// Longest free-text note carried in an Emergency
+pub const MAX_EMERGENCY_TEXT: usize = 32;
+
+/// Range-safety announcement carried by an Emergency
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum EmergencyKind {
+    /// Range is on hold, every countdown stops
+    RangeHold = 0,
+    /// A motor failed to ignite, nobody approaches the pads
+    MisfireHold = 1,
+    /// Vehicle off nominal or coming down near people
+    HeadsUp = 2,
+    /// The previous emergency is over
+    AllClear = 3,
+}
+
+impl EmergencyKind {
+    pub fn from_code(code: u8) -> Option<Self> {
+        [Self::RangeHold, Self::MisfireHold, Self::HeadsUp, Self::AllClear].into_iter().find(|kind| *kind as u8 == code)
+    }
+
+    /// Short description for alert text and callouts
+    pub fn describe(self) -> &'static str {
+        match self {
+            EmergencyKind::RangeHold => "range hold",
+            EmergencyKind::MisfireHold => "misfire hold",
+            EmergencyKind::HeadsUp => "heads up",
+            EmergencyKind::AllClear => "all clear",
+        }
+    }
+}
+
+/// Emergency is flooded to every node ahead of all other traffic, see `MeshNode::send_emergency`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Emergency {
+    /// Node the announcement was raised on, normally the RSO's ground station
+    pub uid: u8,
+    pub kind: EmergencyKind,
+    pub text: String<MAX_EMERGENCY_TEXT>,
+}
@@ -0,0 +1,91 @@
+//! The error every parse and decode function in the crate returns
+//!
+//! Encoders sharing a module with a decoder return it too when their only failure is running out of buffer;
+//! encoders that validate their input, such as AX.25 addresses, keep their own errors.
+
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Input ended before the message was complete
+    Truncated,
+    /// The message's CRC or FCS doesn't match its contents
+    BadCrc,
+    /// An enum tag, type byte or command this build doesn't know, usually from a sender running newer firmware
+    UnknownDiscriminant(u8),
+    /// Written with a protocol version this build can't decode
+    UnsupportedVersion(u8),
+    /// A well-formed message of another type than the one asked for
+    WrongType { expected: u8, found: u8 },
+    /// The fragments received of `message_id` expired before the rest arrived
+    FragmentTimeout { message_id: u16 },
+    /// A byte that can't appear at `offset` in the input
+    InvalidByte { offset: usize, byte: u8 },
+    /// A delta frame against a keyframe the decoder never received
+    UnknownKeyframe(u16),
+    /// Longer than the decoder accepts
+    TooLong,
+    /// A sealed message that is forged, corrupted or for another node, or a plain one where only sealed are taken
+    Unauthenticated,
+    /// An authentic sealed message no newer than one already accepted from its sender
+    Replayed,
+    /// An authentic sealed message from a new sender while the replay table is full
+    TooManySenders,
+    /// More bytes corrupted than forward error correction repairs, see `fec`
+    Uncorrectable,
+    /// The output doesn't fit the caller's buffer
+    BufferTooSmall,
+    /// Any other invalid content
+    Malformed,
+}
+
+impl From<postcard::Error> for Error {
+    fn from(error: postcard::Error) -> Self {
+        match error {
+            postcard::Error::DeserializeUnexpectedEnd => Error::Truncated,
+            postcard::Error::DeserializeBadCrc => Error::BadCrc,
+            _ => Error::Malformed,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "input ended early"),
+            Error::BadCrc => write!(f, "checksum mismatch"),
+            Error::UnknownDiscriminant(value) => write!(f, "unknown discriminant {value:#04x}"),
+            Error::UnsupportedVersion(version) => write!(f, "unsupported protocol version {version}"),
+            Error::WrongType { expected, found } => write!(f, "expected message type {expected}, found {found}"),
+            Error::FragmentTimeout { message_id } => write!(f, "fragments of message {message_id} timed out"),
+            Error::InvalidByte { offset, byte } => write!(f, "invalid byte {byte:#04x} at offset {offset}"),
+            Error::UnknownKeyframe(id) => write!(f, "delta against unknown keyframe {id}"),
+            Error::TooLong => write!(f, "input too long"),
+            Error::Unauthenticated => write!(f, "authentication failed"),
+            Error::Replayed => write!(f, "replayed message"),
+            Error::TooManySenders => write!(f, "too many senders to track"),
+            Error::Uncorrectable => write!(f, "too many errors to correct"),
+            Error::BufferTooSmall => write!(f, "output buffer too small"),
+            Error::Malformed => write!(f, "malformed message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use std::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_postcard_errors_and_display() {
+        let truncated = postcard::from_bytes::<u32>(&[0x80]).unwrap_err();
+        assert_eq!(Error::from(truncated), Error::Truncated);
+        assert_eq!(Error::from(postcard::from_bytes::<bool>(&[2]).unwrap_err()), Error::Malformed);
+        assert_eq!(Error::InvalidByte { offset: 3, byte: 0x7F }.to_string(), "invalid byte 0x7f at offset 3");
+    }
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// StateEstimate shares a node's fused vertical state so other boards act on the same estimate
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct StateEstimate {
+    pub uid: u8,
+    /// Source node's clock when the estimate was computed, for ordering only
+    pub at_ms: u32,
+    pub altitude_m: f32,
+    pub vertical_velocity_mps: f32,
+    pub vertical_accel_mps2: Option<f32>,
+}
+
+/// Asks the addressed node to stream StateEstimates to the sender for `lease_ms`
+///
+/// Subscribers renew before the lease runs out; `interval_ms` of 0 cancels the subscription.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EstimateSubscribe {
+    pub interval_ms: u16,
+    pub lease_ms: u16,
+}
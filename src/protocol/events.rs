@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Flight events detected onboard and sent to the ground as they happen
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum FlightEvent {
+    Launch,
+    Burnout,
+    /// Apogee with the peak altitude above the pad in meters
+    Apogee { altitude_m: f32 },
+    DrogueDeployed,
+    MainDeployed,
+    Landed,
+}
@@ -0,0 +1,296 @@
+//! Reed-Solomon forward error correction wrapped around whole frames, for links with too little margin
+//!
+//! The encoded frame is the frame cut into blocks of up to `BLOCK_LEN - parity` bytes, each followed by
+//! its parity bytes:
+//!
+//! ```text
+//! data (up to 255 - parity bytes) | parity | data | parity | ...
+//! ```
+//!
+//! Every block corrects up to `parity / 2` wrong bytes anywhere in it, so with RS(255,223) a burst of
+//! 16 bytes, 128 bits, is repaired. The last block is shortened rather than padded. The checksum layer
+//! still runs inside, so a miscorrection is caught by the CRC.
+//!
+//! Over GF(2^8) with the 0x11D polynomial and generator roots α^0..α^(parity-1), as in CCSDS and DVB.
+
+use super::Error;
+
+/// Longest codeword
+pub const BLOCK_LEN: usize = 255;
+/// Most parity bytes per block
+pub const MAX_PARITY: usize = 64;
+
+const POLY: u16 = 0x11D;
+
+const fn tables() -> ([u8; 512], [u8; 256]) {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= POLY;
+        }
+        i += 1;
+    }
+    // Doubled so a sum of two logs indexes without reducing mod 255
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    (exp, log)
+}
+
+const EXP: [u8; 512] = tables().0;
+const LOG: [u8; 256] = tables().1;
+
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+}
+
+fn div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + 255 - LOG[b as usize] as usize]
+}
+
+/// α^power
+fn alpha(power: usize) -> u8 {
+    EXP[power % 255]
+}
+
+/// Evaluates a polynomial with coefficients in ascending powers
+fn eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |acc, &coefficient| mul(acc, x) ^ coefficient)
+}
+
+/// A frame recovered by `Fec::decode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    /// Length of the frame, now at the start of the buffer
+    pub len: usize,
+    /// Bytes that were repaired
+    pub corrected: usize,
+}
+
+/// Fec encodes and decodes frames with a fixed number of parity bytes per block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fec {
+    parity: usize,
+    /// Generator polynomial in descending powers, monic
+    generator: [u8; MAX_PARITY + 1],
+}
+
+impl Fec {
+    /// The classic RS(255,223), 32 parity bytes correcting 16 per block
+    pub fn rs_255_223() -> Self {
+        Self::new(32).expect("32 parity bytes are supported")
+    }
+
+    /// `None` unless `parity` is 2 to `MAX_PARITY`
+    pub fn new(parity: usize) -> Option<Self> {
+        if !(2..=MAX_PARITY).contains(&parity) {
+            return None;
+        }
+        let mut generator = [0u8; MAX_PARITY + 1];
+        generator[0] = 1;
+        // Multiply in (x - α^i) one root at a time
+        for i in 0..parity {
+            let root = alpha(i);
+            for j in (1..=i + 1).rev() {
+                generator[j] ^= mul(generator[j - 1], root);
+            }
+        }
+        Some(Self { parity, generator })
+    }
+
+    pub fn parity(&self) -> usize {
+        self.parity
+    }
+
+    /// Frame bytes carried by each full block
+    fn data_len(&self) -> usize {
+        BLOCK_LEN - self.parity
+    }
+
+    /// Length of a `len` byte frame once encoded
+    pub fn encoded_len(&self, len: usize) -> usize {
+        len + len.div_ceil(self.data_len()) * self.parity
+    }
+
+    /// Longest frame whose encoding fits `capacity` bytes
+    pub fn max_frame_len(&self, capacity: usize) -> usize {
+        let full = capacity / BLOCK_LEN;
+        let rest = (capacity % BLOCK_LEN).saturating_sub(self.parity);
+        full * self.data_len() + rest
+    }
+
+    /// Writes `frame` with its parity into `out`, returning the encoded length
+    pub fn encode(&self, frame: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+        let len = self.encoded_len(frame.len());
+        let out = out.get_mut(..len).ok_or(Error::BufferTooSmall)?;
+        for (data, block) in frame.chunks(self.data_len()).zip(out.chunks_mut(BLOCK_LEN)) {
+            let (head, parity) = block.split_at_mut(data.len());
+            head.copy_from_slice(data);
+            self.parity_of(data, parity);
+        }
+        Ok(len)
+    }
+
+    /// Remainder of data(x)·x^parity divided by the generator, computed as a shift register
+    fn parity_of(&self, data: &[u8], parity: &mut [u8]) {
+        parity.fill(0);
+        for &byte in data {
+            let feedback = byte ^ parity[0];
+            parity.copy_within(1.., 0);
+            parity[self.parity - 1] = 0;
+            if feedback != 0 {
+                for (register, &coefficient) in parity.iter_mut().zip(&self.generator[1..]) {
+                    *register ^= mul(coefficient, feedback);
+                }
+            }
+        }
+    }
+
+    /// Repairs the encoded frame in `buf` in place and moves the frame to its start
+    ///
+    /// A block with more errors than its parity corrects is `Uncorrectable`, a length that can't be an
+    /// encoding is `Truncated`.
+    pub fn decode(&self, buf: &mut [u8]) -> Result<Decoded, Error> {
+        let last = buf.len() % BLOCK_LEN;
+        if last != 0 && last <= self.parity {
+            return Err(Error::Truncated);
+        }
+        let mut corrected = 0;
+        let mut len = 0;
+        for start in (0..buf.len()).step_by(BLOCK_LEN) {
+            let end = (start + BLOCK_LEN).min(buf.len());
+            corrected += self.correct(&mut buf[start..end])?;
+            let data = end - start - self.parity;
+            buf.copy_within(start..start + data, len);
+            len += data;
+        }
+        Ok(Decoded { len, corrected })
+    }
+
+    /// Corrects one codeword, returning the number of bytes repaired
+    fn correct(&self, block: &mut [u8]) -> Result<usize, Error> {
+        let parity = self.parity;
+        let mut syndromes = [0u8; MAX_PARITY];
+        for (i, syndrome) in syndromes[..parity].iter_mut().enumerate() {
+            let x = alpha(i);
+            *syndrome = block.iter().fold(0, |acc, &byte| mul(acc, x) ^ byte);
+        }
+        let syndromes = &syndromes[..parity];
+        if syndromes.iter().all(|&syndrome| syndrome == 0) {
+            return Ok(0);
+        }
+
+        // Berlekamp-Massey: the error locator, in ascending powers
+        let mut locator = [0u8; MAX_PARITY + 1];
+        let mut previous = [0u8; MAX_PARITY + 1];
+        locator[0] = 1;
+        previous[0] = 1;
+        let (mut errors, mut shift, mut last_discrepancy) = (0, 1, 1);
+        for r in 0..parity {
+            let discrepancy = (1..=errors).fold(syndromes[r], |acc, i| acc ^ mul(locator[i], syndromes[r - i]));
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let scale = div(discrepancy, last_discrepancy);
+            let before = locator;
+            for i in 0..=MAX_PARITY - shift {
+                locator[i + shift] ^= mul(scale, previous[i]);
+            }
+            if 2 * errors <= r {
+                errors = r + 1 - errors;
+                previous = before;
+                last_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+        }
+        if 2 * errors > parity {
+            return Err(Error::Uncorrectable);
+        }
+        let locator = &locator[..=errors];
+
+        // Error evaluator: syndromes times locator, mod x^parity
+        let mut evaluator = [0u8; MAX_PARITY];
+        for (i, term) in evaluator[..parity].iter_mut().enumerate() {
+            *term = (0..=i.min(errors)).fold(0, |acc, j| acc ^ mul(locator[j], syndromes[i - j]));
+        }
+        let evaluator = &evaluator[..parity];
+        // Formal derivative: in characteristic 2 only the odd powers survive
+        let mut derivative = [0u8; MAX_PARITY];
+        for i in (1..=errors).step_by(2) {
+            derivative[i - 1] = locator[i];
+        }
+        let derivative = &derivative[..errors];
+
+        // Chien search over the positions this, possibly shortened, block has, then Forney for each value
+        let n = block.len();
+        let mut found = 0;
+        for (position, byte) in block.iter_mut().enumerate() {
+            let location = alpha(n - 1 - position);
+            let inverse = div(1, location);
+            if eval(locator, inverse) != 0 {
+                continue;
+            }
+            let denominator = eval(derivative, inverse);
+            if denominator == 0 {
+                return Err(Error::Uncorrectable);
+            }
+            *byte ^= mul(location, div(eval(evaluator, inverse), denominator));
+            found += 1;
+        }
+        if found != errors {
+            return Err(Error::Uncorrectable);
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corrects_bursts_up_to_half_the_parity() {
+        let fec = Fec::rs_255_223();
+        let frame: [u8; 240] = core::array::from_fn(|i| (i * 7 + 3) as u8);
+        let mut buf = [0u8; 320];
+        let len = fec.encode(&frame, &mut buf).unwrap();
+        assert_eq!((len, fec.encoded_len(240), fec.max_frame_len(255)), (240 + 64, 304, 223));
+        assert_eq!(&buf[..223], &frame[..223]);
+
+        // A 16-byte burst in the first block and scattered errors in the shortened last one
+        let mut received = buf;
+        for byte in &mut received[100..116] {
+            *byte ^= 0xA5;
+        }
+        received[len - 1] ^= 0x01;
+        received[260] ^= 0x5A;
+        let decoded = fec.decode(&mut received[..len]).unwrap();
+        assert_eq!(decoded, Decoded { len: 240, corrected: 18 });
+        assert_eq!(&received[..240], &frame[..]);
+
+        // One more wrong byte than the parity covers
+        let mut received = buf;
+        for byte in &mut received[..17] {
+            *byte ^= 0xFF;
+        }
+        assert_eq!(fec.decode(&mut received[..len]), Err(Error::Uncorrectable));
+        assert_eq!(fec.decode(&mut buf[..len - 40]), Err(Error::Truncated));
+        assert!(Fec::new(1).is_none());
+    }
+}
@@ -0,0 +1,194 @@
+//! Runtime field metadata for telemetry structs, so displays can label and scale values without hard-coding them
+
+use super::estimate::StateEstimate;
+use super::gse::{FillStatus, PadWeather};
+use super::health::Health;
+use super::thermal::RadioThermal;
+use super::{AllSensorData, ADXL375, BMP390, GPS, ISM330DHCX, LSM6DSO32, UTC};
+
+/// Describes one field of a telemetry struct
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldInfo {
+    /// Rust field name
+    pub name: &'static str,
+    /// Unit symbol, empty for counts and identifiers
+    pub unit: &'static str,
+    /// Expected `(min, max)` of the value, `None` where it is unbounded or not numeric
+    pub range: Option<(f64, f64)>,
+    pub description: &'static str,
+}
+
+const fn field(
+    name: &'static str,
+    unit: &'static str,
+    range: Option<(f64, f64)>,
+    description: &'static str,
+) -> FieldInfo {
+    FieldInfo { name, unit, range, description }
+}
+
+/// A struct whose fields are described by `FieldInfo`
+pub trait Telemetry {
+    /// Fields in declaration order
+    const FIELDS: &'static [FieldInfo];
+
+    fn field(name: &str) -> Option<&'static FieldInfo> {
+        Self::FIELDS.iter().find(|field| field.name == name)
+    }
+}
+
+const ACCEL_16G: Option<(f64, f64)> = Some((-16.0 * 9.806_65, 16.0 * 9.806_65));
+const GYRO_4000: Option<(f64, f64)> = Some((-4000.0, 4000.0));
+
+impl Telemetry for ISM330DHCX {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("temp", "°C", Some((-40.0, 85.0)), "IMU die temperature"),
+        field("accel_x", "m/s²", ACCEL_16G, "Acceleration along the board X axis"),
+        field("accel_y", "m/s²", ACCEL_16G, "Acceleration along the board Y axis"),
+        field("accel_z", "m/s²", ACCEL_16G, "Acceleration along the board Z axis"),
+        field("gyro_x", "°/s", GYRO_4000, "Angular rate about the board X axis"),
+        field("gyro_y", "°/s", GYRO_4000, "Angular rate about the board Y axis"),
+        field("gyro_z", "°/s", GYRO_4000, "Angular rate about the board Z axis"),
+    ];
+}
+
+impl Telemetry for LSM6DSO32 {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("accel_x", "m/s²", Some((-32.0 * 9.806_65, 32.0 * 9.806_65)), "Acceleration along the board X axis"),
+        field("accel_y", "m/s²", Some((-32.0 * 9.806_65, 32.0 * 9.806_65)), "Acceleration along the board Y axis"),
+        field("accel_z", "m/s²", Some((-32.0 * 9.806_65, 32.0 * 9.806_65)), "Acceleration along the board Z axis"),
+        field("gyro_x", "°/s", Some((-2000.0, 2000.0)), "Angular rate about the board X axis"),
+        field("gyro_y", "°/s", Some((-2000.0, 2000.0)), "Angular rate about the board Y axis"),
+        field("gyro_z", "°/s", Some((-2000.0, 2000.0)), "Angular rate about the board Z axis"),
+    ];
+}
+
+impl Telemetry for BMP390 {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("pressure", "Pa", Some((30_000.0, 125_000.0)), "Static pressure"),
+        field("temperature", "°C", Some((-40.0, 85.0)), "Barometer temperature"),
+        field("altitude", "m", None, "Pressure altitude above sea level"),
+    ];
+}
+
+impl Telemetry for GPS {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("latitude", "°", Some((-90.0, 90.0)), "WGS84 latitude"),
+        field("longitude", "°", Some((-180.0, 180.0)), "WGS84 longitude"),
+        field("altitude", "m", None, "Height above the WGS84 ellipsoid"),
+        field("altitude_msl", "m", None, "Height above mean sea level"),
+        field("num_sats", "", Some((0.0, 98.0)), "Satellites used in the solution"),
+        field("fix_type", "", None, "Fix type, see GpsFix"),
+        field("utc_time", "", None, "UTC time of the solution, see UTC"),
+        field("sats_data", "", None, "Per-satellite signal information"),
+    ];
+}
+
+impl Telemetry for UTC {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("itow", "ms", Some((0.0, 604_800_000.0)), "GPS time of week"),
+        field("time_accuracy_estimate_ns", "ns", None, "Time accuracy estimate"),
+        field("nanos", "ns", Some((-1e9, 1e9)), "Fraction of the second"),
+        field("year", "", Some((1999.0, 2099.0)), "Year"),
+        field("month", "", Some((1.0, 12.0)), "Month"),
+        field("day", "", Some((1.0, 31.0)), "Day of month"),
+        field("hour", "h", Some((0.0, 23.0)), "Hour of day"),
+        field("min", "min", Some((0.0, 59.0)), "Minute of hour"),
+        field("sec", "s", Some((0.0, 59.0)), "Second of minute"),
+        field("valid", "", None, "Validity flags"),
+    ];
+}
+
+impl Telemetry for ADXL375 {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("accel_x", "LSB", Some((-4096.0, 4095.0)), "High-g acceleration along X, 49 mg per LSB"),
+        field("accel_y", "LSB", Some((-4096.0, 4095.0)), "High-g acceleration along Y, 49 mg per LSB"),
+        field("accel_z", "LSB", Some((-4096.0, 4095.0)), "High-g acceleration along Z, 49 mg per LSB"),
+    ];
+}
+
+impl Telemetry for Health {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("uid", "", None, "Reporting node"),
+        field("uptime_ms", "ms", None, "Time since the node started"),
+        field("battery_voltage", "V", Some((0.0, 16.8)), "Battery voltage"),
+        field("arming", "", None, "Arming interlock state"),
+        field("rebooted", "", None, "Node restarted recently"),
+        field("degradation", "", None, "Battery degradation level in effect"),
+    ];
+}
+
+impl Telemetry for StateEstimate {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("uid", "", None, "Estimating node"),
+        field("at_ms", "ms", None, "Source node's clock when the estimate was computed"),
+        field("altitude_m", "m", None, "Fused altitude above the pad"),
+        field("vertical_velocity_mps", "m/s", None, "Fused vertical velocity, positive up"),
+        field("vertical_accel_mps2", "m/s²", None, "Vertical acceleration with gravity removed"),
+    ];
+}
+
+impl Telemetry for RadioThermal {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("uid", "", None, "Reporting node"),
+        field("pa_temperature_c", "°C", Some((-40.0, 125.0)), "Radio power amplifier temperature"),
+        field("tx_power_dbm", "dBm", Some((-9.0, 22.0)), "Transmit power in use"),
+        field("derated", "", None, "TX power reduced because the amplifier is too hot"),
+    ];
+}
+
+impl Telemetry for FillStatus {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("uid", "", None, "Reporting GSE node"),
+        field("fill_pressure_kpa", "kPa", Some((0.0, 10_000.0)), "Fill line gauge pressure"),
+        field("tank_mass_kg", "kg", None, "Load cell reading under the tank"),
+        field("filling", "", None, "Fill valve open"),
+    ];
+}
+
+impl Telemetry for PadWeather {
+    const FIELDS: &'static [FieldInfo] = &[
+        field("uid", "", None, "Reporting GSE node"),
+        field("temperature_c", "°C", Some((-40.0, 60.0)), "Ambient air temperature"),
+        field("pressure_hpa", "hPa", Some((300.0, 1_100.0)), "Station pressure"),
+        field("humidity_pct", "%", Some((0.0, 100.0)), "Relative humidity"),
+        field("wind_speed_mps", "m/s", Some((0.0, 60.0)), "Mean wind speed"),
+        field("wind_gust_mps", "m/s", Some((0.0, 60.0)), "Peak gust"),
+        field("wind_direction_deg", "°", Some((0.0, 360.0)), "Direction the wind blows from"),
+    ];
+}
+
+impl AllSensorData {
+    /// Sensor sections in declaration order, with the fields of each
+    pub const SECTIONS: &'static [(&'static str, &'static [FieldInfo])] = &[
+        ("ism330dhcx", ISM330DHCX::FIELDS),
+        ("lsm6dso32", LSM6DSO32::FIELDS),
+        ("bmp390", BMP390::FIELDS),
+        ("gps", GPS::FIELDS),
+        ("adxl375", ADXL375::FIELDS),
+        ("ism330dhcx2", ISM330DHCX::FIELDS),
+    ];
+
+    /// Looks up a field by dotted path, such as `"bmp390.pressure"`
+    pub fn field(path: &str) -> Option<&'static FieldInfo> {
+        let (section, name) = path.split_once('.')?;
+        let (_, fields) = Self::SECTIONS.iter().find(|(s, _)| *s == section)?;
+        fields.iter().find(|field| field.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_by_name_and_path() {
+        let pressure = BMP390::field("pressure").unwrap();
+        assert_eq!((pressure.unit, pressure.range), ("Pa", Some((30_000.0, 125_000.0))));
+        assert_eq!(AllSensorData::field("bmp390.pressure"), Some(pressure));
+        assert_eq!(AllSensorData::field("ism330dhcx2.gyro_z").unwrap().unit, "°/s");
+        assert!(AllSensorData::field("bmp390").is_none());
+        assert!(Health::field("voltage").is_none());
+        assert!(RadioThermal::FIELDS.iter().all(|f| !f.description.is_empty()));
+    }
+}
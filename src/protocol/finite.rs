@@ -0,0 +1,259 @@
+//! Handling of NaN and infinite float readings, so IEEE special values never reach displays or JSON exports
+//!
+//! Senders check packets before encoding and either refuse them or write a sentinel in place of each
+//! non-finite value. Receivers replace non-finite values and sentinels with zero (or `None` for optional
+//! fields) and flag the packet, so a single bad reading can't break downstream consumers.
+
+use core::fmt;
+
+use super::delta::SensorFrame;
+use super::estimate::StateEstimate;
+use super::gse::{FillStatus, PadWeather, WindsAloft};
+use super::health::Health;
+use super::packet::Packet;
+use super::prediction::FlightPrediction;
+use super::recovery::RecoveryReport;
+use super::thermal::RadioThermal;
+use super::{AllSensorData, BMP390, GPS, ISM330DHCX, LSM6DSO32};
+
+/// Written in place of non-finite `f32` values under `NonFinitePolicy::Sentinel`
+pub const SENTINEL_F32: f32 = f32::MIN;
+/// Written in place of non-finite `f64` values under `NonFinitePolicy::Sentinel`
+pub const SENTINEL_F64: f64 = f64::MIN;
+
+/// What encoding does with a NaN or infinite value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Refuse to encode the packet
+    Reject,
+    /// Encode the sentinel instead, which receivers treat as missing
+    #[default]
+    Sentinel,
+}
+
+/// Names a float field, such as `bmp390.altitude`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldPath {
+    /// Sensor section within AllSensorData, empty for other structs
+    pub section: &'static str,
+    /// Field name, as in `Telemetry::FIELDS`
+    pub name: &'static str,
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.section.is_empty() {
+            write!(f, "{}.", self.section)?;
+        }
+        f.write_str(self.name)
+    }
+}
+
+/// A float field being visited
+pub enum FloatMut<'a> {
+    F32(&'a mut f32),
+    F64(&'a mut f64),
+    /// Optional fields are cleared rather than zeroed on decode
+    OptionF32(&'a mut Option<f32>),
+}
+
+impl FloatMut<'_> {
+    fn is_finite(&self) -> bool {
+        match self {
+            FloatMut::F32(value) => value.is_finite(),
+            FloatMut::F64(value) => value.is_finite(),
+            FloatMut::OptionF32(value) => value.is_none_or(f32::is_finite),
+        }
+    }
+
+    fn is_sentinel(&self) -> bool {
+        match self {
+            FloatMut::F32(value) => **value == SENTINEL_F32,
+            FloatMut::F64(value) => **value == SENTINEL_F64,
+            FloatMut::OptionF32(value) => **value == Some(SENTINEL_F32),
+        }
+    }
+
+    fn set_sentinel(&mut self) {
+        match self {
+            FloatMut::F32(value) => **value = SENTINEL_F32,
+            FloatMut::F64(value) => **value = SENTINEL_F64,
+            FloatMut::OptionF32(value) => **value = Some(SENTINEL_F32),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            FloatMut::F32(value) => **value = 0.0,
+            FloatMut::F64(value) => **value = 0.0,
+            FloatMut::OptionF32(value) => **value = None,
+        }
+    }
+}
+
+/// A struct whose float fields can be checked for NaN and infinities
+pub trait Finite {
+    /// Calls `visit` with every float field
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(FieldPath, FloatMut<'_>));
+
+    /// Applies `policy` before encoding, returning the number of values replaced by the sentinel or the
+    /// first non-finite field under `NonFinitePolicy::Reject`
+    fn prepare_encode(&mut self, policy: NonFinitePolicy) -> Result<u32, FieldPath> {
+        let mut replaced = 0;
+        let mut rejected = None;
+        self.visit_floats(&mut |path, mut value| {
+            if value.is_finite() || rejected.is_some() {
+                return;
+            }
+            match policy {
+                NonFinitePolicy::Reject => rejected = Some(path),
+                NonFinitePolicy::Sentinel => {
+                    value.set_sentinel();
+                    replaced += 1;
+                }
+            }
+        });
+        rejected.map_or(Ok(replaced), Err)
+    }
+
+    /// Replaces non-finite values and sentinels after decoding, returning how many were replaced
+    fn sanitize_decoded(&mut self) -> u32 {
+        let mut replaced = 0;
+        self.visit_floats(&mut |_, mut value| {
+            if !value.is_finite() || value.is_sentinel() {
+                value.clear();
+                replaced += 1;
+            }
+        });
+        replaced
+    }
+}
+
+macro_rules! finite_fields {
+    ($ty:ty { $($field:ident: $kind:ident),* $(,)? }) => {
+        impl Finite for $ty {
+            fn visit_floats(&mut self, visit: &mut dyn FnMut(FieldPath, FloatMut<'_>)) {
+                $(visit(FieldPath { section: "", name: stringify!($field) }, FloatMut::$kind(&mut self.$field));)*
+            }
+        }
+    };
+}
+
+finite_fields!(ISM330DHCX {
+    temp: F32,
+    accel_x: F64,
+    accel_y: F64,
+    accel_z: F64,
+    gyro_x: F64,
+    gyro_y: F64,
+    gyro_z: F64,
+});
+finite_fields!(LSM6DSO32 { accel_x: F64, accel_y: F64, accel_z: F64, gyro_x: F64, gyro_y: F64, gyro_z: F64 });
+finite_fields!(BMP390 { pressure: F32, temperature: F32, altitude: F32 });
+finite_fields!(GPS { latitude: F64, longitude: F64, altitude: F64, altitude_msl: F64 });
+finite_fields!(Health { battery_voltage: F32 });
+finite_fields!(RadioThermal { pa_temperature_c: F32 });
+finite_fields!(FillStatus { fill_pressure_kpa: F32, tank_mass_kg: F32 });
+finite_fields!(PadWeather { temperature_c: F32, pressure_hpa: F32, wind_speed_mps: F32, wind_gust_mps: F32 });
+finite_fields!(FlightPrediction {
+    apogee_m: F32,
+    time_to_apogee_s: F32,
+    apogee_east_m: F32,
+    apogee_north_m: F32,
+    landing_east_m: F32,
+    landing_north_m: F32,
+    landing_radius_m: F32,
+    flight_time_s: F32,
+});
+finite_fields!(RecoveryReport {
+    latitude: F64,
+    longitude: F64,
+    battery_voltage: F32,
+    max_altitude_m: F32,
+    tilt_deg: F32,
+});
+finite_fields!(StateEstimate { altitude_m: F32, vertical_velocity_mps: F32, vertical_accel_mps2: OptionF32 });
+
+impl Finite for AllSensorData {
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(FieldPath, FloatMut<'_>)) {
+        fn section<T: Finite>(
+            name: &'static str,
+            sensor: Option<&mut T>,
+            visit: &mut dyn FnMut(FieldPath, FloatMut<'_>),
+        ) {
+            if let Some(sensor) = sensor {
+                sensor.visit_floats(&mut |path, value| visit(FieldPath { section: name, ..path }, value));
+            }
+        }
+        section("ism330dhcx", self.ism330dhcx.as_mut(), visit);
+        section("lsm6dso32", self.lsm6dso32.as_mut(), visit);
+        section("bmp390", self.bmp390.as_mut(), visit);
+        section("gps", self.gps.as_mut(), visit);
+        section("ism330dhcx2", self.ism330dhcx2.as_mut(), visit);
+    }
+}
+
+impl Finite for WindsAloft {
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(FieldPath, FloatMut<'_>)) {
+        for layer in self.layers.iter_mut() {
+            visit(FieldPath { section: "layers", name: "speed_mps" }, FloatMut::F32(&mut layer.speed_mps));
+        }
+    }
+}
+
+impl Finite for Packet {
+    /// Delta-encoded sensor frames carry bit patterns rather than floats and are checked once rebuilt
+    fn visit_floats(&mut self, visit: &mut dyn FnMut(FieldPath, FloatMut<'_>)) {
+        match self {
+            Packet::Sensors(sensors) | Packet::SensorFrame(SensorFrame::Key { data: sensors, .. }) => {
+                sensors.visit_floats(visit)
+            }
+            Packet::Health(health) => health.visit_floats(visit),
+            Packet::StateEstimate(estimate) => estimate.visit_floats(visit),
+            Packet::RadioThermal(thermal) => thermal.visit_floats(visit),
+            Packet::FillStatus(fill) => fill.visit_floats(visit),
+            Packet::PadWeather(weather) => weather.visit_floats(visit),
+            Packet::WindsAloft(winds) => winds.visit_floats(visit),
+            Packet::FlightPrediction(prediction) => prediction.visit_floats(visit),
+            Packet::RecoveryReport(report) => report.visit_floats(visit),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_policy_and_decode_sanitizing() {
+        let bmp = BMP390 { pressure: 95_000.0, temperature: f32::INFINITY, altitude: f32::NAN };
+        let mut packet = Packet::Sensors(AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: Some(bmp),
+            gps: None,
+            adxl375: None,
+            ism330dhcx2: None,
+        });
+        let rejected = packet.clone().prepare_encode(NonFinitePolicy::Reject).unwrap_err();
+        assert_eq!(std::format!("{rejected}"), "bmp390.temperature");
+
+        assert_eq!(packet.prepare_encode(NonFinitePolicy::Sentinel), Ok(2));
+        let mut buf = [0u8; 64];
+        let mut decoded: Packet = postcard::from_bytes(postcard::to_slice(&packet, &mut buf).unwrap()).unwrap();
+        assert_eq!(decoded.sanitize_decoded(), 2);
+        let Packet::Sensors(AllSensorData { bmp390: Some(bmp), .. }) = decoded else { unreachable!() };
+        assert_eq!((bmp.pressure, bmp.temperature, bmp.altitude), (95_000.0, 0.0, 0.0));
+
+        let mut estimate = StateEstimate {
+            uid: 1,
+            at_ms: 0,
+            altitude_m: 10.0,
+            vertical_velocity_mps: 1.0,
+            vertical_accel_mps2: Some(f32::NEG_INFINITY),
+        };
+        assert_eq!(estimate.sanitize_decoded(), 1);
+        assert_eq!(estimate.vertical_accel_mps2, None);
+    }
+}
@@ -0,0 +1,20 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Most message bytes carried by one fragment, the path MTU usually allows fewer
+pub const MAX_FRAGMENT_DATA: usize = 224;
+
+/// One piece of a message too large for a single frame, see `node::fragmentation`
+///
+/// Every fragment but the last carries the same number of bytes, so a fragment's offset in the message
+/// follows from its index and length alone.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    /// Chosen by the sender, unique among its messages in flight
+    pub message_id: u16,
+    pub index: u8,
+    pub count: u8,
+    /// Length of the whole message
+    pub total_len: u16,
+    pub data: Vec<u8, MAX_FRAGMENT_DATA>,
+}
@@ -0,0 +1,52 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of criteria carried in a single Go/NoGo report
+pub const MAX_CRITERIA: usize = 8;
+
+/// A single prelaunch criterion evaluated by the checklist
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    GpsFix = 0,
+    Sensors = 1,
+    Battery = 2,
+    LinkMargin = 3,
+    Continuity = 4,
+    SelfTest = 5,
+    /// Vehicle's reported configuration hash matches the ground's expected configuration
+    Config = 6,
+}
+
+/// Outcome of a criterion, or of the whole checklist
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verdict {
+    Go = 0,
+    NoGo = 1,
+    /// The telemetry needed to evaluate the criterion has not been received
+    #[default]
+    Unknown = 2,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct CriterionResult {
+    pub criterion: Criterion,
+    pub verdict: Verdict,
+    /// Measured value the verdict was based on, for display (sats, volts, dB, continuity mask, failure or mismatch count)
+    pub value: Option<f32>,
+}
+
+/// GoNoGoReport is the structured result of a prelaunch checklist run, sent to the ground display
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GoNoGoReport {
+    pub uid: u8,
+    /// Go only if every evaluated criterion is Go
+    pub overall: Verdict,
+    pub results: Vec<CriterionResult, MAX_CRITERIA>,
+}
+
+impl GoNoGoReport {
+    /// Returns the result for a given criterion, if it was evaluated
+    pub fn result(&self, criterion: Criterion) -> Option<&CriterionResult> {
+        self.results.iter().find(|r| r.criterion == criterion)
+    }
+}
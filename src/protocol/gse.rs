@@ -0,0 +1,90 @@
+//! Packets from ground support equipment at the pad, sent by nodes with `DeviceType::Gse`
+
+use heapless::Vec;
+use libm::{cosf, sinf};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of layers in one WindsAloft
+pub const MAX_WIND_LAYERS: usize = 8;
+
+/// FillStatus reports propellant loading from the fill cart
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct FillStatus {
+    pub uid: u8,
+    /// Fill line pressure in kPa gauge
+    pub fill_pressure_kpa: f32,
+    /// Load cell reading under the run tank or vehicle, in kilograms
+    pub tank_mass_kg: f32,
+    /// The fill valve is open
+    pub filling: bool,
+}
+
+/// PadWeather is the pad weather station's ambient conditions
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PadWeather {
+    pub uid: u8,
+    pub temperature_c: f32,
+    /// Station pressure, not reduced to sea level, in hPa
+    pub pressure_hpa: f32,
+    pub humidity_pct: u8,
+    /// Mean wind over the station's averaging period
+    pub wind_speed_mps: f32,
+    pub wind_gust_mps: f32,
+    /// Direction the wind blows from, degrees clockwise from true north
+    pub wind_direction_deg: u16,
+}
+
+impl PadWeather {
+    /// Velocity of the air as (east, north) in m/s, the wind blowing from `wind_direction_deg`
+    pub fn wind_vector_mps(&self) -> (f32, f32) {
+        wind_vector(self.wind_speed_mps, self.wind_direction_deg)
+    }
+}
+
+/// Wind measured or forecast at one altitude
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct WindLayer {
+    /// Height above the pad in meters
+    pub altitude_m: u16,
+    pub speed_mps: f32,
+    /// Direction the wind blows from, degrees clockwise from true north
+    pub direction_deg: u16,
+}
+
+impl WindLayer {
+    /// Velocity of the air as (east, north) in m/s
+    pub fn vector_mps(&self) -> (f32, f32) {
+        wind_vector(self.speed_mps, self.direction_deg)
+    }
+}
+
+/// WindsAloft is a wind profile over the pad, from a balloon sounding or a forecast, lowest layer first
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WindsAloft {
+    pub uid: u8,
+    pub layers: Vec<WindLayer, MAX_WIND_LAYERS>,
+}
+
+impl WindsAloft {
+    /// Air velocity as (east, north) in m/s at `altitude_m` above the pad
+    ///
+    /// Interpolates the wind vectors of the layers either side, holding the nearest layer outside the
+    /// profile. `None` when there are no layers.
+    pub fn wind_at(&self, altitude_m: f32) -> Option<(f32, f32)> {
+        let above = self.layers.iter().position(|layer| layer.altitude_m as f32 >= altitude_m);
+        let (low, high) = match above {
+            Some(0) => (self.layers.first()?, self.layers.first()?),
+            Some(index) => (&self.layers[index - 1], &self.layers[index]),
+            None => (self.layers.last()?, self.layers.last()?),
+        };
+        let span = high.altitude_m as f32 - low.altitude_m as f32;
+        let t = if span > 0.0 { (altitude_m - low.altitude_m as f32) / span } else { 0.0 };
+        let ((low_e, low_n), (high_e, high_n)) = (low.vector_mps(), high.vector_mps());
+        Some((low_e + (high_e - low_e) * t, low_n + (high_n - low_n) * t))
+    }
+}
+
+fn wind_vector(speed_mps: f32, from_deg: u16) -> (f32, f32) {
+    let from = (from_deg as f32).to_radians();
+    (-speed_mps * sinf(from), -speed_mps * cosf(from))
+}
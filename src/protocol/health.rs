@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Arming interlock state of the vehicle
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArmingState {
+    #[default]
+    Safe = 0,
+    ArmedPad = 1,
+    ArmedFlight = 2,
+}
+
+/// How far a node has cut back to stretch its battery, each level shedding more than the last
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum DegradationLevel {
+    #[default]
+    Normal = 0,
+    /// Slower telemetry, lower TX power, bulk transfers stopped
+    Reduced = 1,
+    /// Only tracking, commands and events are sent
+    Minimal = 2,
+    /// Little more than the recovery report, as rarely as still useful for finding the vehicle
+    Beacon = 3,
+}
+
+/// Health is sent periodically by every node so the ground station can see its status
+///
+/// The arming state is always included so the LCO knows the vehicle's arming status at a glance.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct Health {
+    pub uid: u8,
+    pub uptime_ms: u32,
+    pub battery_voltage: f32,
+    pub arming: ArmingState,
+    /// Set in the first frames after the node restarts, see `persistence::counters`
+    pub rebooted: bool,
+    /// Battery degradation in effect, see `node::battery`
+    pub degradation: DegradationLevel,
+}
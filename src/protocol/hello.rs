@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Hello is broadcast periodically with no hops left, so only direct neighbors hear it
+///
+/// Receivers count gaps in `count` to estimate how many of a neighbor's frames get through, see
+/// `node::neighbors`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Hello {
+    /// Increments on every hello, starting from zero when the node boots
+    pub count: u16,
+    /// Time until the sender's next hello
+    pub interval_ms: u32,
+}
@@ -0,0 +1,433 @@
+//! KISS framing for AX.25 frames exchanged with a hardware TNC over a serial link
+//!
+//! ```text
+//! FEND | port << 4 | command | data with FEND and FESC escaped | FEND
+//! ```
+//!
+//! Data frames carry AX.25 without its FCS; the TNC adds it on transmit and checks it on receive.
+
+#[cfg(feature = "async")]
+use core::future::Future;
+
+use heapless::Vec;
+
+use super::ax25::{Ax25Error, UiFrame, MAX_UI_FRAME_LEN};
+use super::Error;
+
+pub const FEND: u8 = 0xC0;
+pub const FESC: u8 = 0xDB;
+/// Escaped FEND
+pub const TFEND: u8 = 0xDC;
+/// Escaped FESC
+pub const TFESC: u8 = 0xDD;
+/// Largest encoded data frame, with every byte escaped
+pub const MAX_KISS_FRAME_LEN: usize = 2 * MAX_UI_FRAME_LEN + 3;
+
+/// Low nibble of the type byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KissCommand {
+    Data,
+    TxDelay,
+    Persistence,
+    SlotTime,
+    TxTail,
+    FullDuplex,
+    SetHardware,
+    /// Leaves KISS mode; sent as 0xFF regardless of port
+    Return,
+}
+
+impl KissCommand {
+    fn type_byte(self, port: u8) -> u8 {
+        let command = match self {
+            KissCommand::Data => 0,
+            KissCommand::TxDelay => 1,
+            KissCommand::Persistence => 2,
+            KissCommand::SlotTime => 3,
+            KissCommand::TxTail => 4,
+            KissCommand::FullDuplex => 5,
+            KissCommand::SetHardware => 6,
+            KissCommand::Return => return 0xFF,
+        };
+        (port & 0x0F) << 4 | command
+    }
+
+    fn from_type_byte(byte: u8) -> Option<(u8, Self)> {
+        let command = match byte & 0x0F {
+            _ if byte == 0xFF => return Some((0, KissCommand::Return)),
+            0 => KissCommand::Data,
+            1 => KissCommand::TxDelay,
+            2 => KissCommand::Persistence,
+            3 => KissCommand::SlotTime,
+            4 => KissCommand::TxTail,
+            5 => KissCommand::FullDuplex,
+            6 => KissCommand::SetHardware,
+            _ => return None,
+        };
+        Some((byte >> 4, command))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KissError {
+    BufferTooSmall,
+    Ax25(Ax25Error),
+}
+
+/// A decoded KISS frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KissFrame {
+    pub port: u8,
+    pub command: KissCommand,
+    pub data: Vec<u8, MAX_UI_FRAME_LEN>,
+}
+
+impl KissFrame {
+    /// Parses a data frame's payload as an AX.25 UI frame
+    pub fn ui_frame(&self) -> Result<UiFrame, Error> {
+        UiFrame::decode_without_fcs(&self.data)
+    }
+}
+
+/// Encodes a frame for `port` into `buf`, returning its length
+pub fn encode(port: u8, command: KissCommand, data: &[u8], buf: &mut [u8]) -> Result<usize, KissError> {
+    let mut len = 0;
+    let mut put = |byte: u8| {
+        *buf.get_mut(len).ok_or(KissError::BufferTooSmall)? = byte;
+        len += 1;
+        Ok(())
+    };
+    put(FEND)?;
+    put(command.type_byte(port))?;
+    for byte in data {
+        match *byte {
+            FEND => [FESC, TFEND].into_iter().try_for_each(&mut put)?,
+            FESC => [FESC, TFESC].into_iter().try_for_each(&mut put)?,
+            byte => put(byte)?,
+        }
+    }
+    put(FEND)?;
+    Ok(len)
+}
+
+/// Encodes an AX.25 UI frame as a data frame for `port`
+pub fn encode_ui(port: u8, frame: &UiFrame, buf: &mut [u8]) -> Result<usize, KissError> {
+    let mut ax25 = [0u8; MAX_UI_FRAME_LEN];
+    let len = frame.encode_without_fcs(&mut ax25).map_err(KissError::Ax25)?;
+    encode(port, KissCommand::Data, &ax25[..len], buf)
+}
+
+/// KissDecoder unescapes a byte stream into frames
+///
+/// Bytes before the first FEND and empty frames between back-to-back FENDs are skipped, as TNCs commonly
+/// send a FEND to open and close every frame. After an error the rest of that frame is discarded.
+///
+/// FESC followed by anything but TFEND or TFESC is an `InvalidByte` at its offset in the unescaped frame, a
+/// frame longer than `MAX_UI_FRAME_LEN` is `TooLong` and an unknown command an `UnknownDiscriminant`.
+#[derive(Debug, Clone, Default)]
+pub struct KissDecoder {
+    buf: Vec<u8, { MAX_UI_FRAME_LEN + 1 }>,
+    in_frame: bool,
+    escaped: bool,
+    error: Option<Error>,
+}
+
+impl KissDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one byte, returning a frame or error once its closing FEND arrives
+    pub fn feed(&mut self, byte: u8) -> Option<Result<KissFrame, Error>> {
+        if byte == FEND {
+            let result = self.finish();
+            self.in_frame = true;
+            return result;
+        }
+        if !self.in_frame || self.error.is_some() {
+            return None;
+        }
+        let byte = match (self.escaped, byte) {
+            (false, FESC) => {
+                self.escaped = true;
+                return None;
+            }
+            (false, byte) => byte,
+            (true, TFEND) => FEND,
+            (true, TFESC) => FESC,
+            (true, byte) => {
+                self.error = Some(Error::InvalidByte { offset: self.buf.len(), byte });
+                return None;
+            }
+        };
+        self.escaped = false;
+        if self.buf.push(byte).is_err() {
+            self.error = Some(Error::TooLong);
+        }
+        None
+    }
+
+    fn finish(&mut self) -> Option<Result<KissFrame, Error>> {
+        let error = self.error.take();
+        self.escaped = false;
+        let (type_byte, data) = self.buf.split_first()?;
+        let result = match error {
+            Some(error) => Err(error),
+            None => match KissCommand::from_type_byte(*type_byte) {
+                Some((port, command)) => {
+                    Vec::from_slice(data).map(|data| KissFrame { port, command, data }).map_err(|_| Error::TooLong)
+                }
+                None => Err(Error::UnknownDiscriminant(*type_byte)),
+            },
+        };
+        self.buf.clear();
+        Some(result)
+    }
+}
+
+/// Byte transport to a TNC, typically a UART
+///
+/// `read` returns 0 when nothing is available. With `std` it is implemented for any
+/// `std::io::Read + std::io::Write`; firmware on an async HAL uses `AsyncKissIo` instead.
+pub trait KissIo {
+    type Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read + std::io::Write> KissIo for T {
+    type Error = std::io::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        match std::io::Read::read(self, buf) {
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
+            result => result,
+        }
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), std::io::Error> {
+        std::io::Write::write_all(self, bytes)
+    }
+}
+
+/// Async counterpart of KissIo, for UART drivers that await their interrupts (requires the `async` feature)
+#[cfg(feature = "async")]
+pub trait AsyncKissIo {
+    type Error;
+
+    /// Waits until at least one byte has arrived and reads as many as fit in `buf`
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<usize, Self::Error>>;
+    fn write_all(&mut self, bytes: &[u8]) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KissPortError<E> {
+    Io(E),
+    Kiss(KissError),
+    /// What the TNC sent could not be decoded
+    Decode(Error),
+}
+
+/// Bytes read from the TNC but not yet decoded, shared by the blocking and async ports
+#[derive(Debug, Default)]
+struct Inbound {
+    decoder: KissDecoder,
+    pending: Vec<u8, 64>,
+}
+
+impl Inbound {
+    /// Decodes buffered bytes until a data frame for `port` completes or the buffer runs dry
+    fn next_frame(&mut self, port: u8) -> Option<Result<UiFrame, Error>> {
+        while !self.pending.is_empty() {
+            let byte = self.pending.remove(0);
+            match self.decoder.feed(byte) {
+                Some(Ok(frame)) if frame.port == port && frame.command == KissCommand::Data => {
+                    return Some(frame.ui_frame());
+                }
+                Some(Err(error)) => return Some(Err(error)),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn buffer(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk).expect("chunk fits pending");
+    }
+}
+
+/// KissPort sends and receives AX.25 UI frames through a TNC on one KISS port
+pub struct KissPort<T: KissIo> {
+    io: T,
+    port: u8,
+    inbound: Inbound,
+}
+
+impl<T: KissIo> KissPort<T> {
+    pub fn new(io: T, port: u8) -> Self {
+        Self { io, port, inbound: Inbound::default() }
+    }
+
+    pub fn send(&mut self, frame: &UiFrame) -> Result<(), KissPortError<T::Error>> {
+        let mut buf = [0u8; MAX_KISS_FRAME_LEN];
+        let len = encode_ui(self.port, frame, &mut buf).map_err(KissPortError::Kiss)?;
+        self.io.write_all(&buf[..len]).map_err(KissPortError::Io)
+    }
+
+    /// Sets a TNC parameter such as `KissCommand::TxDelay`, in the TNC's units
+    pub fn configure(&mut self, command: KissCommand, value: u8) -> Result<(), KissPortError<T::Error>> {
+        let mut buf = [0u8; 6];
+        let len = encode(self.port, command, &[value], &mut buf).map_err(KissPortError::Kiss)?;
+        self.io.write_all(&buf[..len]).map_err(KissPortError::Io)
+    }
+
+    /// Reads what the TNC has sent, returning the next UI frame heard on this port
+    ///
+    /// Returns `Ok(None)` once the transport has nothing more; frames for other ports and non-data frames
+    /// are skipped.
+    pub fn receive(&mut self) -> Result<Option<UiFrame>, KissPortError<T::Error>> {
+        loop {
+            if let Some(frame) = self.inbound.next_frame(self.port) {
+                return frame.map(Some).map_err(KissPortError::Decode);
+            }
+            let mut chunk = [0u8; 64];
+            let n = self.io.read(&mut chunk).map_err(KissPortError::Io)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            self.inbound.buffer(&chunk[..n]);
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+/// AsyncKissPort is KissPort over an `AsyncKissIo` transport (requires the `async` feature)
+#[cfg(feature = "async")]
+pub struct AsyncKissPort<T: AsyncKissIo> {
+    io: T,
+    port: u8,
+    inbound: Inbound,
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncKissIo> AsyncKissPort<T> {
+    pub fn new(io: T, port: u8) -> Self {
+        Self { io, port, inbound: Inbound::default() }
+    }
+
+    pub async fn send(&mut self, frame: &UiFrame) -> Result<(), KissPortError<T::Error>> {
+        let mut buf = [0u8; MAX_KISS_FRAME_LEN];
+        let len = encode_ui(self.port, frame, &mut buf).map_err(KissPortError::Kiss)?;
+        self.io.write_all(&buf[..len]).await.map_err(KissPortError::Io)
+    }
+
+    /// Sets a TNC parameter such as `KissCommand::TxDelay`, in the TNC's units
+    pub async fn configure(&mut self, command: KissCommand, value: u8) -> Result<(), KissPortError<T::Error>> {
+        let mut buf = [0u8; 6];
+        let len = encode(self.port, command, &[value], &mut buf).map_err(KissPortError::Kiss)?;
+        self.io.write_all(&buf[..len]).await.map_err(KissPortError::Io)
+    }
+
+    /// Waits for the next UI frame heard on this port, skipping other ports and non-data frames
+    pub async fn receive(&mut self) -> Result<UiFrame, KissPortError<T::Error>> {
+        loop {
+            if let Some(frame) = self.inbound.next_frame(self.port) {
+                return frame.map_err(KissPortError::Decode);
+            }
+            let mut chunk = [0u8; 64];
+            let n = self.io.read(&mut chunk).await.map_err(KissPortError::Io)?;
+            self.inbound.buffer(&chunk[..n]);
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Loopback(VecDeque<u8>);
+
+    impl KissIo for Loopback {
+        type Error = ();
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            let len = self.0.len().min(buf.len());
+            buf.iter_mut().zip(self.0.drain(..len)).for_each(|(out, byte)| *out = byte);
+            Ok(len)
+        }
+
+        fn write_all(&mut self, bytes: &[u8]) -> Result<(), ()> {
+            self.0.extend(bytes);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_escaping_and_port_round_trip() {
+        let mut buf = [0u8; 16];
+        let len = encode(2, KissCommand::Data, &[0x01, FEND, FESC, 0x02], &mut buf).unwrap();
+        assert_eq!(buf[..len], [FEND, 0x20, 0x01, FESC, TFEND, FESC, TFESC, 0x02, FEND]);
+
+        let mut decoder = KissDecoder::new();
+        let mut frames: StdVec<_> = [0x55, FEND].iter().chain(&buf[..len]).filter_map(|b| decoder.feed(*b)).collect();
+        let frame = frames.pop().unwrap().unwrap();
+        assert!(frames.is_empty());
+        assert_eq!((frame.port, frame.command, &frame.data[..]), (2, KissCommand::Data, &[0x01, FEND, FESC, 0x02][..]));
+        let bad: StdVec<_> = [FEND, 0x00, FESC, 0x01, FEND].iter().filter_map(|b| decoder.feed(*b)).collect();
+        assert_eq!(bad, [Err(Error::InvalidByte { offset: 1, byte: 0x01 })]);
+    }
+
+    #[test]
+    fn test_port_sends_and_receives_ui_frames() {
+        let path = ["WIDE2-1".parse().unwrap()];
+        let frame = UiFrame::new("APZRVT".parse().unwrap(), "KJ4ABC-9".parse().unwrap(), &path, b">hello").unwrap();
+        // A frame for another port ahead of ours is skipped
+        let mut other = [0u8; 8];
+        let len = encode(1, KissCommand::Data, b"x", &mut other).unwrap();
+        let mut port = KissPort::new(Loopback(other[..len].iter().copied().collect()), 0);
+        port.send(&frame).unwrap();
+        assert_eq!(port.receive().unwrap(), Some(frame));
+        assert_eq!(port.receive().unwrap(), None);
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncKissIo for Loopback {
+        type Error = ();
+
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            KissIo::read(self, buf)
+        }
+
+        async fn write_all(&mut self, bytes: &[u8]) -> Result<(), ()> {
+            KissIo::write_all(self, bytes)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_port_round_trip() {
+        use core::pin::pin;
+        use core::task::{Context, Poll, Waker};
+
+        let frame = UiFrame::new("APZRVT".parse().unwrap(), "KJ4ABC-9".parse().unwrap(), &[], b">hi").unwrap();
+        let mut port = AsyncKissPort::new(Loopback::default(), 3);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(pin!(port.configure(KissCommand::TxDelay, 30)).poll(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(pin!(port.send(&frame)).poll(&mut cx), Poll::Ready(Ok(())));
+        // The parameter frame ahead of the data frame is skipped
+        assert_eq!(pin!(port.receive()).poll(&mut cx), Poll::Ready(Ok(frame)));
+    }
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// LatencyProbe is sent periodically by a node so the ground can measure end-to-end latency
+///
+/// Timestamps are GPS time of week in milliseconds, the time base shared by nodes and the ground station.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyProbe {
+    pub uid: u8,
+    /// When the sensor frame sent alongside this probe was read
+    pub sensor_read_itow: u32,
+    /// When the frame was handed to the radio
+    pub tx_itow: u32,
+}
@@ -0,0 +1,78 @@
+//! Payload layouts of earlier protocol versions, decoded by `codec` and upgraded into the current structs
+//!
+//! Each struct mirrors the layout exactly as that version put it on the air and is never changed again.
+
+use serde::{Deserialize, Serialize};
+
+use super::{AdsCompressed, AprsCompressedPositionReport, Comment, DeviceType, MessageType};
+
+/// Comment as sent by version 1, before the CRC was added
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct CommentV1 {
+    pub uid: u8,
+    pub destination_uid: u8,
+    pub msg_id: u8,
+    pub hops_left: u8,
+    pub comment_type: DeviceType,
+    /// One of the four 2-bit values, upgraded through `MessageType::from_legacy`
+    pub msg_type: u8,
+    pub team_number: u8,
+    pub ads: AdsCompressed,
+}
+
+impl From<CommentV1> for Comment {
+    /// Version 1 comments were only protected by the codec frame's CRC, so the upgraded comment is sealed
+    /// here for `Comment::verify` to accept it
+    fn from(old: CommentV1) -> Self {
+        let mut comment = Comment {
+            uid: old.uid,
+            destination_uid: old.destination_uid,
+            msg_id: old.msg_id,
+            hops_left: old.hops_left,
+            comment_type: old.comment_type,
+            msg_type: MessageType::from_legacy(old.msg_type),
+            team_number: old.team_number,
+            ads: old.ads,
+            crc: 0,
+        };
+        comment.seal();
+        comment
+    }
+}
+
+/// AprsCompressedPositionReport as sent by version 1, before plain-text comments
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct AprsCompressedPositionReportV1 {
+    pub compression_format: char,
+    pub time: [u8; 7],
+    pub symbol_table: char,
+    pub compressed_lat: [u8; 4],
+    pub compressed_long: [u8; 4],
+    pub symbol_code: char,
+    pub compressed_altitude: [u8; 2],
+    pub compression_type: char,
+    pub comment: CommentV1,
+    pub lat: f64,
+    pub lon: f64,
+    pub alt: f64,
+}
+
+impl From<AprsCompressedPositionReportV1> for AprsCompressedPositionReport {
+    fn from(old: AprsCompressedPositionReportV1) -> Self {
+        AprsCompressedPositionReport {
+            compression_format: old.compression_format,
+            time: old.time,
+            symbol_table: old.symbol_table,
+            compressed_lat: old.compressed_lat,
+            compressed_long: old.compressed_long,
+            symbol_code: old.symbol_code,
+            compressed_altitude: old.compressed_altitude,
+            compression_type: old.compression_type,
+            comment: old.comment.into(),
+            text_comment: None,
+            lat: old.lat,
+            lon: old.lon,
+            alt: old.alt,
+        }
+    }
+}
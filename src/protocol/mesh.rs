@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use super::checksum::{self, CRC_LEN, PREFIX_LEN};
+use super::packet::{Packet, PacketKind};
+use super::Error;
+
+/// Destination UID that addresses every node
+pub const BROADCAST_UID: u8 = 0xFF;
+/// Largest encoded MeshFrame that fits in one LoRa payload
+pub const MAX_FRAME_LEN: usize = 255;
+/// Most relays a source route can list
+pub const MAX_ROUTE_HOPS: usize = 4;
+/// Hop limit of emergency broadcasts, beyond the diameter of any mesh flown so far
+pub const MAX_HOPS: u8 = 15;
+
+/// Routing header carried by every frame on the mesh
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct MeshHeader {
+    pub source_uid: u8,
+    pub destination_uid: u8,
+    /// Per-source counter, together with `source_uid` it identifies the frame for duplicate suppression
+    pub sequence: u16,
+    /// Remaining times the frame may be rebroadcast
+    pub hops_left: u8,
+    /// The destination should answer with an Ack
+    pub ack_requested: bool,
+    /// Set in the first frames after the source restarted
+    pub rebooted: bool,
+    /// Sent by the backup computer of a redundant pair, see `arbitration`
+    pub backup: bool,
+    /// Relays the frame must take, `None` for a flooded frame
+    pub route: Option<SourceRoute>,
+}
+
+/// The relays a source-routed frame travels through, in order from the source
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SourceRoute {
+    hops: [u8; MAX_ROUTE_HOPS],
+    len: u8,
+    /// Index in `hops` of the relay that forwards the frame next
+    next: u8,
+}
+
+impl SourceRoute {
+    /// A route through `hops`, `None` if it lists more than `MAX_ROUTE_HOPS` relays
+    pub fn new(relays: &[u8]) -> Option<Self> {
+        let mut hops = [0; MAX_ROUTE_HOPS];
+        hops.get_mut(..relays.len())?.copy_from_slice(relays);
+        Some(Self { hops, len: relays.len() as u8, next: 0 })
+    }
+
+    pub fn hops(&self) -> &[u8] {
+        &self.hops[..(self.len as usize).min(MAX_ROUTE_HOPS)]
+    }
+
+    /// The relay expected to forward the frame next, `None` once the last relay has
+    pub fn next_hop(&self) -> Option<u8> {
+        self.hops().get(self.next as usize).copied()
+    }
+
+    /// The route as forwarded by the current next hop
+    pub fn advance(self) -> Self {
+        Self { next: self.next.saturating_add(1), ..self }
+    }
+}
+
+/// A packet with its routing header, the unit transmitted over the radio
+///
+/// Receivers ignore bytes after the packet, so a field appended to the end of a packet struct is skipped
+/// by nodes that predate it. Relays forward those bytes unchanged, along with any authentication trailer.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MeshFrame {
+    pub header: MeshHeader,
+    pub packet: Packet,
+}
+
+impl MeshFrame {
+    /// Writes the frame with its length prefix and CRC into `buf`, returning the length
+    ///
+    /// At most `MAX_FRAME_LEN` bytes of `buf` are used, so a larger buffer yields a frame that still fits
+    /// one LoRa payload.
+    pub fn encode_into(&self, buf: &mut [u8]) -> postcard::Result<usize> {
+        self.encode_with_trailer(&[], buf)
+    }
+
+    /// Like `encode_into`, with `trailer` written after the packet inside the CRC
+    pub fn encode_with_trailer(&self, trailer: &[u8], buf: &mut [u8]) -> postcard::Result<usize> {
+        let len = buf.len().min(MAX_FRAME_LEN);
+        let buf = &mut buf[..len];
+        let end = buf.len().checked_sub(CRC_LEN).filter(|&end| end > PREFIX_LEN);
+        let message = &mut buf[PREFIX_LEN..end.ok_or(postcard::Error::SerializeBufferFull)?];
+        let len = postcard::to_slice(self, message)?.len();
+        let trailer_at = message.get_mut(len..len + trailer.len()).ok_or(postcard::Error::SerializeBufferFull)?;
+        trailer_at.copy_from_slice(trailer);
+        checksum::append(buf, len + trailer.len()).map_err(|_| postcard::Error::SerializeBufferFull)
+    }
+
+    /// Decodes the message `checksum::verify_and_strip` returned
+    ///
+    /// A packet tag past the last `PacketKind` is `UnknownDiscriminant`, usually from a sender on newer firmware.
+    pub fn decode(message: &[u8]) -> Result<Self, Error> {
+        Self::decode_with_trailer(message).map(|(frame, _)| frame)
+    }
+
+    /// Like `decode`, also returning the bytes after the packet: a trailer, fields this build doesn't know, or both
+    pub fn decode_with_trailer(message: &[u8]) -> Result<(Self, &[u8]), Error> {
+        postcard::take_from_bytes(message).map_err(|error| {
+            // Packet tags are varints, every known tag fits in one byte
+            match (Error::from(error), postcard::take_from_bytes::<MeshHeader>(message)) {
+                (Error::Malformed, Ok((_, [tag, ..]))) if *tag as usize >= PacketKind::COUNT => {
+                    Error::UnknownDiscriminant(*tag)
+                }
+                (error, _) => error,
+            }
+        })
+    }
+}
+
+/// Acknowledges the frame with `sequence` from the node this Ack is addressed to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Ack {
+    pub sequence: u16,
+}
@@ -0,0 +1,357 @@
+//! Mic-E position reports, which carry the latitude and a status message in the AX.25 destination address
+//!
+//! Some trackers only speak Mic-E, so positions can be sent and parsed in either this or the compressed
+//! format, chosen with `PositionFormat`.
+
+use heapless::Vec;
+
+use super::aprs::TextComment;
+use super::ax25::{Ax25Address, Ax25Error, UiFrame, MAX_INFO_LEN};
+use super::compressed::{base91, from_base91, truncate, Writer};
+use super::{AprsCompressedPositionReport, Error};
+
+/// Data type byte for a current GPS fix
+const CURRENT: u8 = b'`';
+/// Data type byte for a fix that is no longer current
+const OLD: u8 = b'\'';
+const OFFSET: u8 = 28;
+/// Mic-E altitudes are meters above -10 km
+const ALTITUDE_BIAS: f64 = 10_000.0;
+
+/// The standard Mic-E status messages, carried in three bits of the destination address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MicEMessage {
+    #[default]
+    OffDuty,
+    EnRoute,
+    InService,
+    Returning,
+    Committed,
+    Special,
+    Priority,
+    Emergency,
+    /// One of the seven custom messages, 1 to 7
+    Custom(u8),
+}
+
+impl MicEMessage {
+    /// Message bits A, B and C, and whether they are sent as custom
+    fn bits(self) -> (u8, bool) {
+        match self {
+            MicEMessage::OffDuty => (0b111, false),
+            MicEMessage::EnRoute => (0b110, false),
+            MicEMessage::InService => (0b101, false),
+            MicEMessage::Returning => (0b100, false),
+            MicEMessage::Committed => (0b011, false),
+            MicEMessage::Special => (0b010, false),
+            MicEMessage::Priority => (0b001, false),
+            MicEMessage::Emergency => (0b000, false),
+            MicEMessage::Custom(n) => (7 - n.clamp(1, 7), true),
+        }
+    }
+
+    fn from_bits(bits: u8, custom: bool) -> Self {
+        match (bits, custom) {
+            (0b000, _) => MicEMessage::Emergency,
+            (bits, true) => MicEMessage::Custom(7 - bits),
+            (0b111, false) => MicEMessage::OffDuty,
+            (0b110, false) => MicEMessage::EnRoute,
+            (0b101, false) => MicEMessage::InService,
+            (0b100, false) => MicEMessage::Returning,
+            (0b011, false) => MicEMessage::Committed,
+            (0b010, false) => MicEMessage::Special,
+            _ => MicEMessage::Priority,
+        }
+    }
+}
+
+/// A Mic-E position report
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MicEReport {
+    /// Degrees, north positive, at 0.01 minute resolution
+    pub latitude: f64,
+    /// Degrees, east positive, at 0.01 minute resolution
+    pub longitude: f64,
+    pub speed_knots: u16,
+    /// Degrees, 0 when unknown and 360 for north
+    pub course_deg: u16,
+    pub altitude_m: Option<f64>,
+    pub message: MicEMessage,
+    pub symbol_table: char,
+    pub symbol_code: char,
+    /// The fix is current rather than the last one before losing GPS
+    pub current: bool,
+    pub status: Option<TextComment>,
+}
+
+impl MicEReport {
+    /// A current fix with the rocket symbol
+    pub fn new(latitude: f64, longitude: f64, altitude_m: Option<f64>, course_deg: u16, speed_knots: u16) -> Self {
+        Self {
+            latitude,
+            longitude,
+            speed_knots: speed_knots.min(799),
+            course_deg: course_deg.min(360),
+            altitude_m,
+            symbol_table: '\\',
+            symbol_code: 'O',
+            current: true,
+            ..Default::default()
+        }
+    }
+
+    /// Encodes the report as the destination address and information field of a UI frame
+    pub fn encode(&self) -> (Ax25Address, Vec<u8, MAX_INFO_LEN>) {
+        let mut buf = [0u8; MAX_INFO_LEN];
+        let (destination, len) = self.write_info(&mut buf).expect("a Mic-E information field fits MAX_INFO_LEN");
+        (destination, Vec::from_slice(&buf[..len]).expect("written within MAX_INFO_LEN"))
+    }
+
+    /// Writes the information field into `buf`, returning the destination address and the field's length
+    pub fn write_info(&self, buf: &mut [u8]) -> Result<(Ax25Address, usize), Ax25Error> {
+        let (lat_deg, lat_min, lat_hundredths) = degrees_minutes(self.latitude, 90);
+        let digits =
+            [lat_deg / 10, lat_deg % 10, lat_min / 10, lat_min % 10, lat_hundredths / 10, lat_hundredths % 10];
+        let (lon_deg, lon_min, lon_hundredths) = degrees_minutes(self.longitude, 180);
+        let (bits, custom) = self.message.bits();
+        let flags = [
+            bits & 0b100 != 0,
+            bits & 0b010 != 0,
+            bits & 0b001 != 0,
+            self.latitude >= 0.0,
+            !(10..100).contains(&lon_deg),
+            self.longitude < 0.0,
+        ];
+        let mut callsign = [0u8; 6];
+        for (i, (c, (digit, flag))) in callsign.iter_mut().zip(digits.into_iter().zip(flags)).enumerate() {
+            *c = match (flag, i < 3 && custom) {
+                (false, _) => b'0' + digit as u8,
+                (true, true) => b'A' + digit as u8,
+                (true, false) => b'P' + digit as u8,
+            };
+        }
+        let callsign = core::str::from_utf8(&callsign).expect("Mic-E destination is ASCII");
+        let destination = Ax25Address::new(callsign, 0).expect("Mic-E destination is a valid callsign");
+
+        let lon_byte = match lon_deg {
+            0..=9 => lon_deg + 90,
+            10..=99 => lon_deg,
+            100..=109 => lon_deg - 20,
+            _ => lon_deg - 100,
+        };
+        let min_byte = if lon_min < 10 { lon_min + 60 } else { lon_min };
+        let (speed, course) = (self.speed_knots.min(799), self.course_deg.min(360));
+        let mut info = Writer { buf, len: 0 };
+        info.put(&[
+            if self.current { CURRENT } else { OLD },
+            lon_byte as u8 + OFFSET,
+            min_byte as u8 + OFFSET,
+            lon_hundredths as u8 + OFFSET,
+            (speed / 10) as u8 + OFFSET,
+            ((speed % 10) * 10 + course / 100) as u8 + OFFSET,
+            (course % 100) as u8 + OFFSET,
+            self.symbol_code as u8,
+            self.symbol_table as u8,
+        ])?;
+        if let Some(altitude) = self.altitude_m {
+            info.put(&base91::<3>(libm::round(altitude + ALTITUDE_BIAS)))?;
+            info.put(b"}")?;
+        }
+        if let Some(status) = &self.status {
+            info.put(status.as_str().as_bytes())?;
+        }
+        Ok((destination, info.len))
+    }
+
+    /// Parses a report from the destination address and information field of a UI frame
+    ///
+    /// Position ambiguity (spaces in the destination) is read as zero digits. A destination character that
+    /// can't appear at its position is an `InvalidByte` at its offset in the callsign, a longitude, speed or
+    /// course byte out of range one at its offset in `info`.
+    pub fn decode(destination: &Ax25Address, info: &[u8]) -> Result<Self, Error> {
+        let callsign = destination.callsign().as_bytes();
+        if callsign.len() != 6 {
+            return Err(Error::InvalidByte { offset: callsign.len(), byte: b' ' });
+        }
+        let (mut digits, mut flags, mut custom) = ([0u32; 6], [false; 6], false);
+        for (i, &byte) in callsign.iter().enumerate() {
+            (digits[i], flags[i]) = match byte {
+                b'0'..=b'9' => ((byte - b'0') as u32, false),
+                b'L' => (0, false),
+                b'P'..=b'Y' => ((byte - b'P') as u32, true),
+                b'Z' => (0, true),
+                b'A'..=b'J' if i < 3 => {
+                    custom = true;
+                    ((byte - b'A') as u32, true)
+                }
+                b'K' if i < 3 => {
+                    custom = true;
+                    (0, true)
+                }
+                _ => return Err(Error::InvalidByte { offset: i, byte }),
+            };
+        }
+        let bits = (flags[0] as u8) << 2 | (flags[1] as u8) << 1 | flags[2] as u8;
+
+        let (&kind, body) = info.split_first().ok_or(Error::Truncated)?;
+        if !matches!(kind, CURRENT | OLD) {
+            return Err(Error::UnknownDiscriminant(kind));
+        }
+        let body = body.get(..8).ok_or(Error::Truncated)?;
+        let field = |i: usize| match body[i] {
+            byte @ OFFSET..=127 => Ok((byte - OFFSET) as u32),
+            byte => Err(Error::InvalidByte { offset: i + 1, byte }),
+        };
+        let mut lon_deg = field(0)? + if flags[4] { 100 } else { 0 };
+        lon_deg = match lon_deg {
+            180..=189 => lon_deg - 80,
+            190..=199 => lon_deg - 190,
+            deg => deg,
+        };
+        let lon_min = field(1)? % 60;
+        let (sp, dc, se) = (field(3)?, field(4)?, field(5)?);
+        let speed = (sp * 10 + dc / 10) % 800;
+        let course = ((dc % 10) * 100 + se) % 400;
+
+        let pair = |i: usize| (digits[i] * 10 + digits[i + 1]) as f64;
+        let latitude = pair(0) + (pair(2) + pair(4) / 100.0) / 60.0;
+        let longitude = lon_deg as f64 + (lon_min as f64 + field(2)? as f64 / 100.0) / 60.0;
+
+        let mut rest = &info[9..];
+        let mut altitude_m = None;
+        // Some radios put a type byte ahead of the altitude
+        let skip = match rest {
+            [_, _, _, b'}', ..] => Some(0),
+            [b'>' | b']' | b'`' | b'\'', _, _, _, b'}', ..] => Some(1),
+            _ => None,
+        };
+        if let Some(skip) = skip {
+            altitude_m = Some(from_base91(&rest[skip..skip + 3]) - ALTITUDE_BIAS);
+            rest = &rest[skip + 4..];
+        }
+        let status = match rest {
+            [] => None,
+            text => {
+                let text = core::str::from_utf8(text).map_err(|_| Error::Malformed)?;
+                Some(TextComment::new(truncate(text)).map_err(|_| Error::Malformed)?)
+            }
+        };
+
+        Ok(Self {
+            latitude: if flags[3] { latitude } else { -latitude },
+            longitude: if flags[5] { -longitude } else { longitude },
+            speed_knots: speed as u16,
+            course_deg: course as u16,
+            altitude_m,
+            message: MicEMessage::from_bits(bits, custom),
+            symbol_code: body[6] as char,
+            symbol_table: body[7] as char,
+            current: kind == CURRENT,
+            status,
+        })
+    }
+}
+
+/// Whole degrees, minutes and hundredths of a minute of `|value|`, rounded to the nearest hundredth
+fn degrees_minutes(value: f64, max: u32) -> (u32, u32, u32) {
+    let hundredths = libm::round(value.abs().min(max as f64) * 6_000.0) as u32;
+    (hundredths / 6_000, hundredths / 100 % 60, hundredths % 100)
+}
+
+/// Position report format used on the APRS link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionFormat {
+    #[default]
+    Compressed,
+    MicE,
+}
+
+/// A fix to report, independent of the format it is sent in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PositionFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_m: f64,
+    /// Course in degrees and speed in knots, when moving
+    pub course_speed: Option<(u16, f32)>,
+}
+
+impl PositionFormat {
+    /// Frames `fix` in this format, with the rocket symbol
+    ///
+    /// `destination` is the software identifier for compressed reports; Mic-E replaces it with the encoded
+    /// latitude.
+    pub fn ui_frame(
+        self,
+        destination: Ax25Address,
+        source: Ax25Address,
+        path: &[Ax25Address],
+        fix: &PositionFix,
+    ) -> Result<UiFrame, Ax25Error> {
+        match self {
+            PositionFormat::Compressed => {
+                let (course, speed) = fix.course_speed.map_or((None, 0.0), |(course, speed)| (Some(course), speed));
+                let report = AprsCompressedPositionReport {
+                    symbol_table: '\\',
+                    symbol_code: 'O',
+                    ..AprsCompressedPositionReport::encode(fix.latitude, fix.longitude, fix.altitude_m, course, speed)
+                };
+                UiFrame::position_report(destination, source, path, &report)
+            }
+            PositionFormat::MicE => {
+                let (course, speed) = fix.course_speed.unwrap_or_default();
+                let report = MicEReport::new(fix.latitude, fix.longitude, Some(fix.altitude_m), course, speed as u16);
+                let (destination, info) = report.encode();
+                UiFrame::new(destination, source, path, &info)
+            }
+        }
+    }
+
+    /// Format of a received frame's position report, from its data type byte
+    pub fn of(frame: &UiFrame) -> Option<Self> {
+        match frame.info.first()? {
+            b'!' | b'=' | b'/' | b'@' => Some(PositionFormat::Compressed),
+            &CURRENT | &OLD => Some(PositionFormat::MicE),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mic_e_round_trip() {
+        // 33°25.64'N with the Returning message (bits 100), +100° longitude offset and west
+        let mut report = MicEReport::new(33.0 + 25.64 / 60.0, -(112.0 + 7.74 / 60.0), Some(1_234.0), 251, 20);
+        report.message = MicEMessage::Returning;
+        report.status = TextComment::new("RVT").ok();
+        let (destination, info) = report.encode();
+        assert_eq!(destination.callsign(), "S32UVT");
+        let decoded = MicEReport::decode(&destination, &info).unwrap();
+        assert!((decoded.latitude - report.latitude).abs() < 1e-6, "{}", decoded.latitude);
+        assert!((decoded.longitude - report.longitude).abs() < 1e-6, "{}", decoded.longitude);
+        assert_eq!((decoded.speed_knots, decoded.course_deg, decoded.altitude_m), (20, 251, Some(1_234.0)));
+        assert_eq!((decoded.message, decoded.status), (MicEMessage::Returning, report.status));
+
+        for lon in [-5.5, 45.25, -105.0, 179.99] {
+            let report = MicEReport { message: MicEMessage::Emergency, ..MicEReport::new(-12.5, lon, None, 0, 0) };
+            let (destination, info) = report.encode();
+            let decoded = MicEReport::decode(&destination, &info).unwrap();
+            assert!((decoded.longitude - lon).abs() < 1e-6 && decoded.latitude == -12.5, "{lon}");
+            assert_eq!(decoded.message, MicEMessage::Emergency);
+        }
+    }
+
+    #[test]
+    fn test_format_selection() {
+        let fix =
+            PositionFix { latitude: 37.2296, longitude: -80.4139, altitude_m: 900.0, course_speed: Some((90, 12.0)) };
+        let source: Ax25Address = "KJ4ABC-9".parse().unwrap();
+        for format in [PositionFormat::Compressed, PositionFormat::MicE] {
+            let frame = format.ui_frame("APZRVT".parse().unwrap(), source.clone(), &[], &fix).unwrap();
+            assert_eq!(PositionFormat::of(&frame), Some(format));
+        }
+    }
+}
@@ -1,5 +1,61 @@
+// modular-bitfield wraps `#[bits = N]` field types in parentheses when expanding
+#![allow(unused_parens)]
+
+pub mod aprs;
+pub mod arbitration;
+pub mod ax25;
+pub mod bootloader;
+pub mod checksum;
+pub mod codec;
+pub mod compressed;
+pub mod command;
+pub mod config_hash;
+pub mod countdown;
+pub mod custom;
+pub mod delta;
+pub mod echo;
+pub mod emergency;
+pub mod error;
+pub mod estimate;
+pub mod events;
+#[cfg(feature = "fec")]
+pub mod fec;
+pub mod fields;
+pub mod finite;
+pub mod fragment;
+pub mod gonogo;
+pub mod gse;
+pub mod health;
+pub mod hello;
+pub mod kiss;
+pub mod latency;
+pub mod legacy;
+pub mod mesh;
+pub mod mic_e;
+pub mod node_info;
+pub mod packet;
+pub mod ping;
+pub mod prediction;
+pub mod rangetest;
+pub mod ranging;
+pub mod recovery;
+mod scaled;
+pub mod sealed;
+pub mod selftest;
+pub mod serial;
+pub mod tdma;
+pub mod thermal;
+pub mod tracker;
+pub mod valve;
+pub mod vehicle;
+#[cfg(feature = "wire")]
+pub mod wire;
+
+pub use error::Error;
+
 use modular_bitfield::prelude::*;
-use serde::{de, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ublox")]
 use ublox::{GpsFix as UbloxGPSFix, NavSatQualityIndicator as UbloxNavSatQualityIndicator, NavSatSvHealth as UbloxNavSatSvHealth, NavSatOrbitSource as UbloxNavSatOrbitSource};
 
 /// AllSensorData is a struct that contains the data that is sent over the two radios
@@ -12,7 +68,7 @@ use ublox::{GpsFix as UbloxGPSFix, NavSatQualityIndicator as UbloxNavSatQualityI
 /// - GPS Data, including Latitude, Longitude, Altitude, Speed, and Course, Number of Sats and UTC Time
 /// - ADXL375 Accelerometer data
 /// - The Second ISM330DHCX Accelerometer and Gyroscope data (In the future this will be hard mounted to the payload)
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct AllSensorData{
     pub ism330dhcx: Option<ISM330DHCX>,
     pub lsm6dso32: Option<LSM6DSO32>,
@@ -23,6 +79,7 @@ pub struct AllSensorData{
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum SensorUpdate {
     ISM330DHCX(ISM330DHCX),
     LSM6DSO32(LSM6DSO32),
@@ -33,7 +90,7 @@ pub enum SensorUpdate {
 }
 
 /// ISM330DHCX Accelerometer and Gyroscope data
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct ISM330DHCX{
     pub temp: f32,
     pub accel_x: f64,
@@ -45,7 +102,7 @@ pub struct ISM330DHCX{
 }
 
 /// LSM6DSO32 is a struct that contains the data from the LSM6DSO32 Accelerometer and Gyroscope
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct LSM6DSO32{
     pub accel_x: f64,
     pub accel_y: f64,
@@ -56,7 +113,7 @@ pub struct LSM6DSO32{
 }
 
 /// BMP390 is a struct that contains the data from the BMP390 Pressure, Temperature, and Altitude sensor
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct BMP390{
     pub pressure: f32,
     pub temperature: f32,
@@ -65,7 +122,7 @@ pub struct BMP390{
 
 /// GPS is a struct that contains the data from the GPS module
 /// The data includes Latitude, Longitude, Altitude, Speed, Course, Number of Sats, and UTC Time
-#[derive(Debug, serde::Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, serde::Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct GPS{
     pub latitude: f64,
     pub longitude: f64,
@@ -77,8 +134,9 @@ pub struct GPS{
     pub sats_data: NavSat 
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum GpsFix {
+    #[default]
     NoFix = 0,
     DeadReckoningOnly = 1,
     Fix2D = 2,
@@ -87,18 +145,13 @@ pub enum GpsFix {
     TimeOnlyFix = 5,
 }
 
-impl Default for GpsFix {
-    fn default() -> Self {
-        GpsFix::NoFix
-    }
-}
-
-impl Into<u8> for GpsFix {
-    fn into(self) -> u8 {
-        self as u8
+impl From<GpsFix> for u8 {
+    fn from(value: GpsFix) -> Self {
+        value as u8
     }
 }
 
+#[cfg(feature = "ublox")]
 impl From<UbloxGPSFix> for GpsFix {
     fn from(value: UbloxGPSFix) -> Self {
         match value {
@@ -182,6 +235,7 @@ pub enum NavSatQualityIndicator {
     CarrierLock = 5,
 }
 
+#[cfg(feature = "ublox")]
 impl From<UbloxNavSatQualityIndicator> for NavSatQualityIndicator {
     fn from(value: UbloxNavSatQualityIndicator) -> Self {
         match value {
@@ -206,6 +260,7 @@ pub enum NavSatOrbitSource {
     Other(u8),
 }
 
+#[cfg(feature = "ublox")]
 impl From<UbloxNavSatOrbitSource> for NavSatOrbitSource {
     fn from(value: UbloxNavSatOrbitSource) -> Self {
         match value {
@@ -227,6 +282,7 @@ pub enum NavSatSvHealth {
     Unknown = 3,
 }
 
+#[cfg(feature = "ublox")]
 impl From<UbloxNavSatSvHealth> for NavSatSvHealth {
     fn from(value: UbloxNavSatSvHealth) -> Self {
         match value {
@@ -238,7 +294,7 @@ impl From<UbloxNavSatSvHealth> for NavSatSvHealth {
 }
 
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
 pub struct UTC {
     /// GPS Millisecond Time of Week
     pub itow: u32,
@@ -261,7 +317,7 @@ pub struct UTC {
 }
 
 /// ADXL375 is a struct that contains the data from the ADXL375 Accelerometer
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct ADXL375{
     pub accel_x: i16,
     pub accel_y: i16,
@@ -275,7 +331,7 @@ pub struct MiniData {
     pub alt: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct AprsCompressedPositionReport {
     pub compression_format: char,   // Symbol Format Identifier either '/' or '@' (1 byte)
     pub time: [u8; 7],         // Time in DHM or HMS format (7 bytes)
@@ -286,11 +342,16 @@ pub struct AprsCompressedPositionReport {
     pub compressed_altitude: [u8; 2], // Compressed Altitude/Speed/Course Speed/Radio Range (XX) (2 bytes)
     pub compression_type: char, // Compressed Type (1 byte)
     pub comment: Comment, // Optional Comment (max 40 chars) (40 bytes)
+    pub text_comment: Option<aprs::TextComment>, // Plain-text comment sent in place of `comment` when set
     pub lat: f64,
     pub lon: f64,
     pub alt: f64,
 }
 
+/// Acknowledgement of the Comment with `msg_id` equal to `id`, sent back as a Comment of type Ack
+///
+/// `ack` is false for a negative acknowledgement, asking for a retransmission straight away.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub struct Acknowledgement {
     pub id: u8,
     pub ack: bool,
@@ -309,6 +370,12 @@ pub struct CompressionType {
     pub compression_origin: CompressionOrigin, // Compression Origin (3 bits)
 }
 
+impl Default for CompressionType {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(BitfieldSpecifier)]
 #[derive(Debug, Serialize, Deserialize)]
 pub enum APRSGPSFix {
@@ -347,32 +414,68 @@ pub struct Comment {
     pub destination_uid: u8, // Destination Unique Identifier (8 bits)
     pub msg_id: u8, // Message ID (8 bits)
     pub hops_left : u8, // Hops Left (3 bits)
-    pub comment_type: DeviceType, // Type (2 bits)
-    pub msg_type: MessageType, // Message Type (2 bit)
+    pub comment_type: DeviceType, // Type (3 bits)
+    pub msg_type: MessageType, // Message Type (4 bits)
     pub team_number: u8, // Team ID (6 bits)
-    // 39 Bits for above fields
+    // 41 Bits for above fields
     // 28 Bytes or 224 Bits for ADS data
-    pub ads: AdsCompressed
+    pub ads: AdsCompressed,
+    pub crc: u16, // CRC-16 over the fields above, see `Comment::seal` (16 bits)
 }
 
+/// Kind of node, 3 bits wide
+///
+/// Variants are only ever appended: serde encodes the declaration index.
 #[derive(BitfieldSpecifier)]
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[bits = 3]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
 pub enum DeviceType {
     #[default]
     Ground = 0,
     Top = 1,
     Bottom = 2,
     Mobile = 3,
+    /// Ground support equipment at the pad: fill cart, load cells, weather station
+    Gse = 4,
 }
 
+/// Message type of a Comment, 4 bits wide
+///
+/// Version 1 frames, from before the field grew from 2 bits, decode through `MessageType::from_legacy`.
+/// Variants are only ever appended: serde encodes the declaration index.
 #[derive(BitfieldSpecifier)]
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[bits = 4]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
 pub enum MessageType {
     Ack = 0,
     #[default]
     Data = 1,
-    Placeholder = 2,
+    /// Presence announcement with no payload, takes the value of the old unused Placeholder
+    Beacon = 2,
+    /// Team-defined payload in place of ADS data, see `custom`
     Custom = 3,
+    /// Asks for a route to the comment's destination
+    RouteRequest = 4,
+    /// Answers a RouteRequest, sent back along the discovered path
+    RouteReply = 5,
+    /// Reads a parameter on the destination node
+    ParamGet = 6,
+    /// Writes a parameter on the destination node
+    ParamSet = 7,
+}
+
+impl MessageType {
+    /// Decodes a 2-bit message type from frames sent before the field was widened
+    ///
+    /// Old firmware only sent the Placeholder value as an empty keep-alive, so it reads as Beacon.
+    pub fn from_legacy(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => MessageType::Ack,
+            1 => MessageType::Data,
+            2 => MessageType::Beacon,
+            _ => MessageType::Custom,
+        }
+    }
 }
 
 #[bitfield(bits = 416)]
@@ -391,20 +494,41 @@ pub struct AdsUncompressed {
     pub timestamp : B64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
-pub struct AdsCompressed {
-    pub lat: i16,
-    pub lon: i16,
-    pub vel_x: i16,
-    pub vel_y: i16,
-    pub vel_z: i16,
-    pub acc_x: i16,
-    pub acc_y: i16,
-    pub acc_z: i16,
-    pub alt: i16,
-    pub predicted_apogee: i16,
-    pub flap_deploy_angle: i16,
-    pub timestamp: i32,
+impl Default for AdsUncompressed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+scaled::scaled_struct! {
+    /// ADS state packed into the APRS comment, see `AdsValues` for the decoded form
+    #[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+    pub struct AdsCompressed => AdsValues {
+        /// Latitude offset from the report's position
+        lat: i16 => f32, scale = 1e-5, unit = "°",
+        /// Longitude offset from the report's position
+        lon: i16 => f32, scale = 1e-5, unit = "°",
+        /// Velocity along X
+        vel_x: i16 => f32, scale = 0.1, unit = "m/s",
+        /// Velocity along Y
+        vel_y: i16 => f32, scale = 0.1, unit = "m/s",
+        /// Vertical velocity, positive up
+        vel_z: i16 => f32, scale = 0.1, unit = "m/s",
+        /// Acceleration along X
+        acc_x: i16 => f32, scale = 0.01, unit = "m/s²",
+        /// Acceleration along Y
+        acc_y: i16 => f32, scale = 0.01, unit = "m/s²",
+        /// Vertical acceleration, positive up
+        acc_z: i16 => f32, scale = 0.01, unit = "m/s²",
+        /// Altitude above the pad
+        alt: i16 => f32, scale = 1.0, unit = "m",
+        /// Predicted apogee above the pad
+        predicted_apogee: i16 => f32, scale = 1.0, unit = "m",
+        /// Air brake flap deployment angle
+        flap_deploy_angle: i16 => f32, scale = 0.01, unit = "°",
+        /// Time since the flight computer started
+        timestamp: i32 => f64, scale = 0.001, unit = "s",
+    }
 }
 
 // impl AprsCompressedPositionReport {
@@ -462,28 +586,34 @@ mod tests {
             symbol_code: '{',
             compressed_altitude: *b"?!",
             compression_type: 'T',
-            comment: Comment::new()
-                .with_uid(1)
-                .with_destination_uid(2)
-                .with_msg_id(3)
-                .with_hops_left(4)
-                .with_comment_type(DeviceType::Ground)
-                .with_msg_type(MessageType::Data)
-                .with_team_number(5)
-                .with_ads(AdsCompressedPart1::new()
-                    .with_lat(100)
-                    .with_lon(200)
-                    .with_vel_x(300)
-                    .with_vel_y(400)
-                    .with_vel_z(500)
-                    .with_acc_x(600)
-                    .with_acc_y(700)
-                    .with_acc_z(800))
-                .with_ads2(AdsCompressedPart2::new()
-                    .with_alt(900)
-                    .with_predicted_apogee(1000)
-                    .with_flap_deploy_angle(1100)
-                    .with_timestamp(1200)),
+            comment: Comment {
+                uid: 1,
+                destination_uid: 2,
+                msg_id: 3,
+                hops_left: 4,
+                comment_type: DeviceType::Ground,
+                msg_type: MessageType::Data,
+                team_number: 5,
+                ads: AdsCompressed {
+                    lat: 100,
+                    lon: 200,
+                    vel_x: 300,
+                    vel_y: 400,
+                    vel_z: 500,
+                    acc_x: 600,
+                    acc_y: 700,
+                    acc_z: 800,
+                    alt: 900,
+                    predicted_apogee: 1000,
+                    flap_deploy_angle: 1100,
+                    timestamp: 1200,
+                },
+                crc: 0,
+            },
+            text_comment: None,
+            lat: 0.0,
+            lon: 0.0,
+            alt: 0.0,
         };
 
         assert_eq!(report.time, *b"092345z");
@@ -494,4 +624,21 @@ mod tests {
         assert_eq!(report.compressed_altitude, *b"?!");
         assert_eq!(report.compression_type, 'T');
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_message_type_legacy_frames() {
+        assert_eq!(MessageType::from_legacy(2), MessageType::Beacon);
+        assert_eq!(MessageType::from_legacy(3), MessageType::Custom);
+
+        // Old frames carrying the Placeholder index still decode
+        #[derive(Serialize)]
+        enum OldMessageType {
+            _Ack,
+            _Data,
+            Placeholder,
+        }
+        let mut buf = [0u8; 4];
+        let old = postcard::to_slice(&OldMessageType::Placeholder, &mut buf).unwrap();
+        assert_eq!(postcard::from_bytes::<MessageType>(old).unwrap(), MessageType::Beacon);
+    }
+}
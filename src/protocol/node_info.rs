@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use super::DeviceType;
+
+/// Operating role of a ground node, decides which messages it may transmit
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Role {
+    /// Launch control officer, may send commands
+    Lco = 0,
+    /// Recovery crew, may only send text and position
+    Recovery = 1,
+    /// Never transmits commands
+    #[default]
+    Observer = 2,
+}
+
+/// NodeInfo is announced by every node when it joins the mesh
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NodeInfo {
+    pub uid: u8,
+    pub device_type: DeviceType,
+    pub role: Role,
+    pub firmware_version: u16,
+    /// Largest frame this node's radio can send and receive, 0 if not advertised
+    pub mtu: u16,
+}
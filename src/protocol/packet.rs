@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+
+use super::arbitration::Arbitration;
+use super::command::SignedCommand;
+use super::config_hash::ConfigHash;
+use super::countdown::CountdownState;
+use super::delta::SensorFrame;
+use super::echo::Echo;
+use super::emergency::Emergency;
+use super::estimate::{EstimateSubscribe, StateEstimate};
+use super::events::FlightEvent;
+use super::fragment::Fragment;
+use super::gonogo::GoNoGoReport;
+use super::gse::{FillStatus, PadWeather, WindsAloft};
+use super::health::Health;
+use super::hello::Hello;
+use super::latency::LatencyProbe;
+use super::mesh::Ack;
+use super::node_info::NodeInfo;
+use super::ping::{Ping, Pong};
+use super::prediction::FlightPrediction;
+use super::rangetest::RangeBeacon;
+use super::ranging::{RangingRequest, RangingResponse};
+use super::recovery::{RecoveryReport, RecoveryStatus};
+use super::sealed::Sealed;
+use super::selftest::SelfTestReport;
+use super::tdma::SlotClaim;
+use super::thermal::RadioThermal;
+use super::tracker::TrackerStatus;
+use super::valve::ValveState;
+use super::vehicle::VehicleConfig;
+use super::AllSensorData;
+
+/// Packet is every message that travels over the mesh
+///
+/// New variants must be appended so existing variant indices stay stable on the wire.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Packet {
+    Sensors(AllSensorData),
+    Health(Health),
+    NodeInfo(NodeInfo),
+    Event(FlightEvent),
+    Command(SignedCommand),
+    Countdown(CountdownState),
+    GoNoGo(GoNoGoReport),
+    TrackerStatus(TrackerStatus),
+    Ping(Ping),
+    Pong(Pong),
+    RangeBeacon(RangeBeacon),
+    LatencyProbe(LatencyProbe),
+    Ack(Ack),
+    SelfTest(SelfTestReport),
+    RadioThermal(RadioThermal),
+    Arbitration(Arbitration),
+    StateEstimate(StateEstimate),
+    EstimateSubscribe(EstimateSubscribe),
+    VehicleConfig(VehicleConfig),
+    ConfigHash(ConfigHash),
+    Echo(Echo),
+    Fragment(Fragment),
+    SensorFrame(SensorFrame),
+    Hello(Hello),
+    RangingRequest(RangingRequest),
+    RangingResponse(RangingResponse),
+    RecoveryStatus(RecoveryStatus),
+    ValveState(ValveState),
+    FillStatus(FillStatus),
+    PadWeather(PadWeather),
+    SlotClaim(SlotClaim),
+    WindsAloft(WindsAloft),
+    FlightPrediction(FlightPrediction),
+    RecoveryReport(RecoveryReport),
+    Emergency(Emergency),
+    Sealed(Sealed),
+}
+
+/// Discriminant of a Packet, used to select packets without matching on their contents
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PacketKind {
+    Sensors,
+    Health,
+    NodeInfo,
+    Event,
+    Command,
+    Countdown,
+    GoNoGo,
+    TrackerStatus,
+    Ping,
+    Pong,
+    RangeBeacon,
+    LatencyProbe,
+    Ack,
+    SelfTest,
+    RadioThermal,
+    Arbitration,
+    StateEstimate,
+    EstimateSubscribe,
+    VehicleConfig,
+    ConfigHash,
+    Echo,
+    Fragment,
+    SensorFrame,
+    Hello,
+    RangingRequest,
+    RangingResponse,
+    RecoveryStatus,
+    ValveState,
+    FillStatus,
+    PadWeather,
+    SlotClaim,
+    WindsAloft,
+    FlightPrediction,
+    RecoveryReport,
+    Emergency,
+    Sealed,
+}
+
+impl PacketKind {
+    /// Number of packet kinds, update when appending a variant
+    pub const COUNT: usize = PacketKind::Sealed as usize + 1;
+}
+
+impl Packet {
+    pub fn kind(&self) -> PacketKind {
+        match self {
+            Packet::Sensors(_) => PacketKind::Sensors,
+            Packet::Health(_) => PacketKind::Health,
+            Packet::NodeInfo(_) => PacketKind::NodeInfo,
+            Packet::Event(_) => PacketKind::Event,
+            Packet::Command(_) => PacketKind::Command,
+            Packet::Countdown(_) => PacketKind::Countdown,
+            Packet::GoNoGo(_) => PacketKind::GoNoGo,
+            Packet::TrackerStatus(_) => PacketKind::TrackerStatus,
+            Packet::Ping(_) => PacketKind::Ping,
+            Packet::Pong(_) => PacketKind::Pong,
+            Packet::RangeBeacon(_) => PacketKind::RangeBeacon,
+            Packet::LatencyProbe(_) => PacketKind::LatencyProbe,
+            Packet::Ack(_) => PacketKind::Ack,
+            Packet::SelfTest(_) => PacketKind::SelfTest,
+            Packet::RadioThermal(_) => PacketKind::RadioThermal,
+            Packet::Arbitration(_) => PacketKind::Arbitration,
+            Packet::StateEstimate(_) => PacketKind::StateEstimate,
+            Packet::EstimateSubscribe(_) => PacketKind::EstimateSubscribe,
+            Packet::VehicleConfig(_) => PacketKind::VehicleConfig,
+            Packet::ConfigHash(_) => PacketKind::ConfigHash,
+            Packet::Echo(_) => PacketKind::Echo,
+            Packet::Fragment(_) => PacketKind::Fragment,
+            Packet::SensorFrame(_) => PacketKind::SensorFrame,
+            Packet::Hello(_) => PacketKind::Hello,
+            Packet::RangingRequest(_) => PacketKind::RangingRequest,
+            Packet::RangingResponse(_) => PacketKind::RangingResponse,
+            Packet::RecoveryStatus(_) => PacketKind::RecoveryStatus,
+            Packet::ValveState(_) => PacketKind::ValveState,
+            Packet::FillStatus(_) => PacketKind::FillStatus,
+            Packet::PadWeather(_) => PacketKind::PadWeather,
+            Packet::SlotClaim(_) => PacketKind::SlotClaim,
+            Packet::WindsAloft(_) => PacketKind::WindsAloft,
+            Packet::FlightPrediction(_) => PacketKind::FlightPrediction,
+            Packet::RecoveryReport(_) => PacketKind::RecoveryReport,
+            Packet::Emergency(_) => PacketKind::Emergency,
+            Packet::Sealed(_) => PacketKind::Sealed,
+        }
+    }
+}
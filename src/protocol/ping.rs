@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Signal quality at which a frame was received
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct LinkQuality {
+    pub rssi_dbm: i16,
+    pub snr_db: f32,
+}
+
+/// Ping asks `target_uid` to report the signal quality it heard this frame at
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Ping {
+    pub origin_uid: u8,
+    pub target_uid: u8,
+    pub id: u16,
+}
+
+/// Pong answers a Ping, carrying the quality the Ping was received with
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Pong {
+    pub responder_uid: u8,
+    pub origin_uid: u8,
+    pub id: u16,
+    pub heard: LinkQuality,
+}
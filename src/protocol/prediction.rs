@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// FlightPrediction is the pre-flight simulation of a vehicle under the current weather, see `trajectory`
+///
+/// Positions are meters east and north of the pad. The landing zone is a circle around the nominal landing
+/// point covering the simulated wind uncertainty.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct FlightPrediction {
+    /// Vehicle the prediction is for
+    pub uid: u8,
+    /// Apogee above the pad in meters
+    pub apogee_m: f32,
+    pub time_to_apogee_s: f32,
+    pub apogee_east_m: f32,
+    pub apogee_north_m: f32,
+    pub landing_east_m: f32,
+    pub landing_north_m: f32,
+    pub landing_radius_m: f32,
+    /// Liftoff to touchdown
+    pub flight_time_s: f32,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// RangeBeacon is sent at fixed intervals by a mobile node during range characterization
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RangeBeacon {
+    pub uid: u8,
+    /// Increments on every beacon so the ground can count losses
+    pub sequence: u32,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f32,
+    pub tx_power_dbm: i8,
+}
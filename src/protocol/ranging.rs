@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// RangingRequest asks `target_uid` to answer straight away so the RF time of flight can be measured
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RangingRequest {
+    pub origin_uid: u8,
+    pub target_uid: u8,
+    pub id: u16,
+}
+
+/// RangingResponse answers a RangingRequest with how long the responder held it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RangingResponse {
+    pub responder_uid: u8,
+    pub origin_uid: u8,
+    pub id: u16,
+    /// From the end of the received request to the start of this response's transmission, in microseconds
+    pub turnaround_us: u32,
+}
@@ -0,0 +1,81 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of deployment devices in one RecoveryStatus
+pub const MAX_DEVICES: usize = 4;
+
+/// What deploys the parachute on a channel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Mechanism {
+    /// Black powder charge lit by an e-match
+    Pyro = 0,
+    /// Nichrome wire that melts through a retaining cord
+    BurnWire = 1,
+    /// CO2 cartridge punctured to pressurize the bay
+    Co2 = 2,
+}
+
+/// Why a deployment device cannot be relied on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceFault {
+    /// The igniter or wire circuit is broken
+    OpenCircuit = 0,
+    ShortCircuit = 1,
+    /// The CO2 cartridge has leaked or was already spent
+    LowPressure = 2,
+    /// The driver did not respond
+    NoResponse = 3,
+}
+
+/// Where a device is in its safe, armed and fired sequence
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+    Safe,
+    Armed,
+    Fired,
+    Fault(DeviceFault),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceStatus {
+    pub channel: u8,
+    pub mechanism: Mechanism,
+    pub state: DeviceState,
+    /// Igniter or wire continuity, `None` for mechanisms without a circuit to test
+    pub continuity: Option<bool>,
+}
+
+/// RecoveryStatus reports every deployment device on a vehicle in one schema, whatever its mechanism
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct RecoveryStatus {
+    pub uid: u8,
+    pub devices: Vec<DeviceStatus, MAX_DEVICES>,
+}
+
+impl RecoveryStatus {
+    /// Bitmask of channels showing continuity, as `checklist::ChecklistInputs::continuity` expects
+    pub fn continuity(&self) -> u8 {
+        self.devices
+            .iter()
+            .filter(|device| device.continuity == Some(true) && device.channel < 8)
+            .fold(0, |mask, device| mask | 1 << device.channel)
+    }
+
+    /// Whether any device reports a fault
+    pub fn has_fault(&self) -> bool {
+        self.devices.iter().any(|device| matches!(device.state, DeviceState::Fault(_)))
+    }
+}
+
+/// RecoveryReport is repeated by a landed vehicle until the ground acknowledges it, see `recovery::beacon`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct RecoveryReport {
+    pub uid: u8,
+    /// Last GPS fix, WGS84
+    pub latitude: f64,
+    pub longitude: f64,
+    pub battery_voltage: f32,
+    pub max_altitude_m: f32,
+    /// Angle of the airframe's long axis from vertical, 90 for a vehicle lying on its side
+    pub tilt_deg: f32,
+}
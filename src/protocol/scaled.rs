@@ -0,0 +1,97 @@
+//! Declares wire structs of scaled integers together with their physical units
+//!
+//! The scale and unit of each field are written once and used for the generated documentation, the
+//! conversions in both directions and the `FieldInfo` metadata, so none of them can drift apart.
+
+/// Defines a wire struct of scaled integer fields, its physical counterpart and the conversions between them
+///
+/// ```ignore
+/// scaled_struct! {
+///     #[derive(Debug, Clone, Copy, Default)]
+///     pub struct Wire => Physical {
+///         /// Description
+///         altitude: i16 => f32, scale = 0.5, unit = "m",
+///     }
+/// }
+/// ```
+///
+/// `Wire::encode` rounds to the nearest count and saturates at the integer type's limits.
+macro_rules! scaled_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $wire:ident => $physical:ident {
+            $(
+                #[doc = $description:literal]
+                $field:ident : $int:ty => $float:ty, scale = $scale:literal, unit = $unit:literal
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $wire {
+            $(
+                #[doc = $description]
+                #[doc = ""]
+                #[doc = concat!("One count is ", stringify!($scale), " ", $unit, ".")]
+                pub $field: $int,
+            )*
+        }
+
+        #[doc = concat!("Physical values of a [`", stringify!($wire), "`]")]
+        #[derive(Debug, Clone, Copy, PartialEq, Default)]
+        pub struct $physical {
+            $(
+                #[doc = concat!($description, ", in ", $unit)]
+                pub $field: $float,
+            )*
+        }
+
+        $(const _: () = assert!($scale > 0.0, "scales must be positive");)*
+
+        impl $wire {
+            pub fn encode(value: &$physical) -> Self {
+                Self { $($field: libm::round(value.$field as f64 / $scale) as $int,)* }
+            }
+
+            pub fn decode(&self) -> $physical {
+                $physical { $($field: (self.$field as f64 * $scale) as $float,)* }
+            }
+        }
+
+        impl $crate::protocol::fields::Telemetry for $wire {
+            const FIELDS: &'static [$crate::protocol::fields::FieldInfo] = &[
+                $(
+                    $crate::protocol::fields::FieldInfo {
+                        name: stringify!($field),
+                        unit: $unit,
+                        range: Some((<$int>::MIN as f64 * $scale, <$int>::MAX as f64 * $scale)),
+                        description: $description,
+                    },
+                )*
+            ];
+        }
+    };
+}
+
+pub(crate) use scaled_struct;
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::fields::Telemetry;
+    use crate::protocol::{AdsCompressed, AdsValues};
+
+    #[test]
+    fn test_ads_scaling_round_trips_and_saturates() {
+        let values = AdsValues { vel_z: 251.37, acc_z: -98.1, alt: 3_048.0, timestamp: 12.345, ..Default::default() };
+        let wire = AdsCompressed::encode(&values);
+        assert_eq!((wire.vel_z, wire.acc_z, wire.alt, wire.timestamp), (2_514, -9_810, 3_048, 12_345));
+        assert_eq!(wire.decode().alt, 3_048.0);
+        assert!((wire.decode().vel_z - 251.4).abs() < 1e-3);
+
+        let fast = AdsCompressed::encode(&AdsValues { vel_z: 5_000.0, ..Default::default() });
+        assert_eq!(fast.vel_z, i16::MAX);
+        let velocity = AdsCompressed::field("vel_z").unwrap();
+        let (min, max) = velocity.range.unwrap();
+        assert_eq!(velocity.unit, "m/s");
+        assert!((min + 3276.8).abs() < 1e-9 && (max - 3276.7).abs() < 1e-9);
+    }
+}
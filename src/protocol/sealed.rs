@@ -0,0 +1,22 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use super::custom::MAX_CUSTOM_DATA;
+
+/// Length of the authentication tag ending every ciphertext
+pub const SEAL_TAG_LEN: usize = 16;
+/// Largest ciphertext: a custom payload's sub-type and data, then the tag
+pub const MAX_SEALED_LEN: usize = 1 + MAX_CUSTOM_DATA + SEAL_TAG_LEN;
+
+/// A custom payload encrypted and authenticated under the team key, see `crypto`
+///
+/// Only the ciphertext is secret; the sender, target and counter travel in the clear and are authenticated
+/// along with it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Sealed {
+    pub sender_uid: u8,
+    pub target_uid: u8,
+    /// Per-sender message counter, never repeated under one key; part of the nonce and the replay check
+    pub counter: u32,
+    pub ciphertext: Vec<u8, MAX_SEALED_LEN>,
+}
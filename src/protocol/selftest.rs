@@ -0,0 +1,65 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of subsystem results carried in a single self-test report
+pub const MAX_RESULTS: usize = 8;
+
+/// Hardware exercised by the self-test
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    Ism330dhcx = 0,
+    Lsm6dso32 = 1,
+    Bmp390 = 2,
+    Gps = 3,
+    Adxl375 = 4,
+    Ism330dhcx2 = 5,
+    Radio = 6,
+    Storage = 7,
+}
+
+/// Why a subsystem failed its self-test
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// Device did not respond on its bus
+    NoResponse = 0,
+    /// WHO_AM_I (or equivalent) did not match the expected identifier
+    WrongId = 1,
+    /// Readings are outside the range possible on the pad
+    Implausible = 2,
+    /// Data written or transmitted did not come back intact
+    Mismatch = 3,
+    /// Driver returned an error
+    Io = 4,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail(Failure),
+    /// Hardware does not support the test
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TestResult {
+    pub subsystem: Subsystem,
+    pub outcome: Outcome,
+}
+
+/// SelfTestReport is the structured result of a power-on hardware self-test
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct SelfTestReport {
+    pub uid: u8,
+    pub results: Vec<TestResult, MAX_RESULTS>,
+}
+
+impl SelfTestReport {
+    pub fn result(&self, subsystem: Subsystem) -> Option<Outcome> {
+        self.results.iter().find(|r| r.subsystem == subsystem).map(|r| r.outcome)
+    }
+
+    /// Number of subsystems that failed
+    pub fn failures(&self) -> usize {
+        self.results.iter().filter(|r| matches!(r.outcome, Outcome::Fail(_))).count()
+    }
+}
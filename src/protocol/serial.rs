@@ -0,0 +1,39 @@
+//! Framing for wired serial/USB links: postcard-encoded, COBS-stuffed, 0x00-terminated frames
+
+use heapless::Vec;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::mesh::MAX_FRAME_LEN;
+use super::ping::LinkQuality;
+use super::Error;
+
+/// Largest encoded BridgedFrame, including COBS overhead and the delimiter
+pub const BRIDGED_FRAME_LEN: usize = MAX_FRAME_LEN + 16;
+
+/// A mesh frame passed over a wired link by the radio node that heard it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BridgedFrame {
+    pub quality: LinkQuality,
+    pub frame: Vec<u8, MAX_FRAME_LEN>,
+}
+
+/// Accumulates bytes read from a serial port until a complete frame has arrived
+pub type FrameAccumulator<const N: usize> = postcard::accumulator::CobsAccumulator<N>;
+
+/// Encodes a value into a single frame, including the trailing 0x00 delimiter
+pub fn encode_frame<'a, T: Serialize>(value: &T, buf: &'a mut [u8]) -> postcard::Result<&'a mut [u8]> {
+    postcard::to_slice_cobs(value, buf)
+}
+
+/// Decodes one frame in place, `frame` may include the trailing delimiter
+pub fn decode_frame<T: DeserializeOwned>(frame: &mut [u8]) -> Result<T, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("decode", len = frame.len()).entered();
+    let result = postcard::from_bytes_cobs(frame).map_err(Error::from);
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &result {
+        tracing::warn!(?error, "frame decode failed");
+    }
+    result
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// SlotClaim announces every TDMA slot a node transmits in, see `node::tdma`
+///
+/// Broadcast when a node takes extra slots and whenever it gives them up. A claim replaces the sender's
+/// previous one, so an empty claim returns the node to its default slot.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SlotClaim {
+    pub uid: u8,
+    /// One bit per slot of the superframe, slot 0 in bit 0, not counting the sender's default slot
+    pub slots: u32,
+}
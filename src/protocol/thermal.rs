@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// RadioThermal reports the radio power amplifier's temperature and the TX power in use
+///
+/// Sent periodically and immediately whenever the node derates or restores its TX power.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RadioThermal {
+    pub uid: u8,
+    pub pa_temperature_c: f32,
+    pub tx_power_dbm: i8,
+    /// TX power is below the configured maximum because the amplifier is too hot
+    pub derated: bool,
+}
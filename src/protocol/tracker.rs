@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the tracker's current target position came from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TargetSource {
+    /// No usable position yet, the tracker holds still
+    #[default]
+    None = 0,
+    Gps = 1,
+    /// GPS dropped out, the position is extrapolated from the last fixes
+    DeadReckoned = 2,
+}
+
+/// TrackerStatus is reported by the antenna tracker for the operator display
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct TrackerStatus {
+    pub source: TargetSource,
+    /// Age of the last GPS fix used, in milliseconds
+    pub fix_age_ms: u32,
+    pub target_azimuth_deg: f32,
+    pub target_elevation_deg: f32,
+    /// Position reported by the encoders
+    pub azimuth_deg: f32,
+    pub elevation_deg: f32,
+    /// True when a rate command was clipped by the slew limit
+    pub slew_limited: bool,
+    pub range_m: f32,
+}
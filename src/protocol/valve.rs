@@ -0,0 +1,62 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of valves in one ValveState
+pub const MAX_VALVES: usize = 12;
+
+/// Interlock bits in `ValveState::interlocks`, a set bit holds the sequence
+pub mod interlock {
+    /// The bunker arming key is out
+    pub const KEY_SWITCH: u8 = 1 << 0;
+    /// An emergency stop is latched
+    pub const ESTOP: u8 = 1 << 1;
+    /// A tank or line pressure is above its redline
+    pub const OVERPRESSURE: u8 = 1 << 2;
+    /// The pad area has not been reported clear
+    pub const AREA_NOT_CLEAR: u8 = 1 << 3;
+    /// The stand lost the control link and safed itself
+    pub const LOST_LINK: u8 = 1 << 4;
+}
+
+/// Position of one valve, in percent open so throttling valves fit alongside on/off ones
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Valve {
+    pub id: u8,
+    pub commanded_pct: u8,
+    /// From the position sensor or limit switches, `None` for a valve without feedback
+    pub actual_pct: Option<u8>,
+}
+
+impl Valve {
+    /// The valve is reported more than `tolerance_pct` away from where it was commanded
+    pub fn disagrees(&self, tolerance_pct: u8) -> bool {
+        self.actual_pct.is_some_and(|actual| actual.abs_diff(self.commanded_pct) > tolerance_pct)
+    }
+}
+
+/// ValveState reports an engine test stand's valves, sequence progress and interlocks to the bunker
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct ValveState {
+    pub uid: u8,
+    /// Step of the running sequence, `None` when no sequence is running
+    pub sequence_step: Option<u8>,
+    /// Tripped interlocks, see `interlock`
+    pub interlocks: u8,
+    pub valves: Vec<Valve, MAX_VALVES>,
+}
+
+impl ValveState {
+    pub fn valve(&self, id: u8) -> Option<&Valve> {
+        self.valves.iter().find(|valve| valve.id == id)
+    }
+
+    /// Whether no interlock holds the sequence
+    pub fn interlocks_clear(&self) -> bool {
+        self.interlocks == 0
+    }
+
+    /// Valves more than `tolerance_pct` from their commanded position, e.g. a stuck main valve
+    pub fn disagreeing(&self, tolerance_pct: u8) -> impl Iterator<Item = &Valve> {
+        self.valves.iter().filter(move |valve| valve.disagrees(tolerance_pct))
+    }
+}
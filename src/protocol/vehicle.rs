@@ -0,0 +1,49 @@
+use heapless::String;
+use serde::{Deserialize, Serialize};
+
+use super::Error;
+
+/// Longest airframe name or motor designation carried in a VehicleConfig
+pub const MAX_NAME_LEN: usize = 16;
+
+/// VehicleConfig describes the airframe as flown, announced at boot and written at the head of flight logs
+///
+/// Post-processing (thrust reconstruction, drag estimation) reads it from the log instead of relying on
+/// someone remembering which airframe and motor flew.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct VehicleConfig {
+    pub uid: u8,
+    pub airframe: String<MAX_NAME_LEN>,
+    /// Liftoff mass without propellant
+    pub dry_mass_kg: f32,
+    pub propellant_mass_kg: f32,
+    pub reference_area_m2: f32,
+    /// Identifier of the Cd-versus-Mach table used for this airframe, 0 if none
+    pub cd_table: u16,
+    pub motor: String<MAX_NAME_LEN>,
+}
+
+/// Marks the first frame of a flight log as a LogHeader
+pub const LOG_MAGIC: [u8; 4] = *b"RVTL";
+
+/// First frame of a flight log, before any records
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LogHeader {
+    pub magic: [u8; 4],
+    pub vehicle: VehicleConfig,
+}
+
+impl LogHeader {
+    pub fn new(vehicle: VehicleConfig) -> Self {
+        Self { magic: LOG_MAGIC, vehicle }
+    }
+
+    /// Decodes a COBS frame as a header in place, an error if it is a record from a log without one
+    pub fn decode(frame: &mut [u8]) -> Result<Self, Error> {
+        let header = postcard::from_bytes_cobs::<Self>(frame)?;
+        if header.magic != LOG_MAGIC {
+            return Err(Error::Malformed);
+        }
+        Ok(header)
+    }
+}
@@ -0,0 +1,151 @@
+//! Compact on-air serialization: postcard with varint integers and one-byte Option tags
+//!
+//! `MAX_WIRE_LEN` is the worst case for each type, with every Option present and every varint at its
+//! longest; typical frames are much smaller. Size buffers with it.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::estimate::StateEstimate;
+use super::health::Health;
+use super::{AllSensorData, AprsCompressedPositionReport, Error, ADXL375, BMP390, GPS, ISM330DHCX, LSM6DSO32};
+
+/// A protocol struct with a known worst-case encoded size
+pub trait Wire: Serialize + DeserializeOwned {
+    const MAX_WIRE_LEN: usize;
+
+    fn to_wire_bytes<'a>(&self, buf: &'a mut [u8]) -> postcard::Result<&'a mut [u8]> {
+        postcard::to_slice(self, buf)
+    }
+
+    fn from_wire_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(postcard::from_bytes(bytes)?)
+    }
+}
+
+impl Wire for ISM330DHCX {
+    const MAX_WIRE_LEN: usize = 52;
+}
+
+impl Wire for LSM6DSO32 {
+    const MAX_WIRE_LEN: usize = 48;
+}
+
+impl Wire for BMP390 {
+    const MAX_WIRE_LEN: usize = 12;
+}
+
+impl Wire for ADXL375 {
+    const MAX_WIRE_LEN: usize = 9;
+}
+
+/// Dominated by the 32 satellite slots of `NavSat`, 29 bytes each
+impl Wire for GPS {
+    const MAX_WIRE_LEN: usize = 993;
+}
+
+impl Wire for AllSensorData {
+    const MAX_WIRE_LEN: usize = 2 * (ISM330DHCX::MAX_WIRE_LEN + 1)
+        + (LSM6DSO32::MAX_WIRE_LEN + 1)
+        + (BMP390::MAX_WIRE_LEN + 1)
+        + (GPS::MAX_WIRE_LEN + 1)
+        + (ADXL375::MAX_WIRE_LEN + 1);
+}
+
+/// Characters count as five bytes, in practice they are ASCII and take two
+impl Wire for AprsCompressedPositionReport {
+    const MAX_WIRE_LEN: usize = 151;
+}
+
+impl Wire for Health {
+    const MAX_WIRE_LEN: usize = 13;
+}
+
+impl Wire for StateEstimate {
+    const MAX_WIRE_LEN: usize = 19;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::protocol::aprs::{TextComment, MAX_COMMENT_LEN};
+    use crate::protocol::*;
+
+    fn len<T: Wire>(value: &T) -> usize {
+        let mut buf = vec![0u8; 4096];
+        value.to_wire_bytes(&mut buf).unwrap().len()
+    }
+
+    #[test]
+    fn test_worst_case_sizes() {
+        let imu =
+            ISM330DHCX { temp: 0.0, accel_x: 0.0, accel_y: 0.0, accel_z: 0.0, gyro_x: 0.0, gyro_y: 0.0, gyro_z: 0.0 };
+        let lsm = LSM6DSO32 { accel_x: 0.0, accel_y: 0.0, accel_z: 0.0, gyro_x: 0.0, gyro_y: 0.0, gyro_z: 0.0 };
+        let flags = NavSatSvFlags { orbit_sources: NavSatOrbitSource::Other(u8::MAX), ..Default::default() };
+        let sv = NavSatSvInfo { azim: i16::MIN, pr_res: i16::MIN, flags, ..Default::default() };
+        let utc = UTC {
+            itow: u32::MAX,
+            time_accuracy_estimate_ns: u32::MAX,
+            nanos: i32::MIN,
+            year: u16::MAX,
+            ..Default::default()
+        };
+        let gps = GPS {
+            latitude: 0.0,
+            longitude: 0.0,
+            altitude: 0.0,
+            altitude_msl: 0.0,
+            num_sats: 0,
+            fix_type: GpsFix::NoFix,
+            utc_time: utc,
+            sats_data: NavSat { itow: u32::MAX, version: 0, num_svs: 32, svs: [Some(sv); 32] },
+        };
+        let sensors = AllSensorData {
+            ism330dhcx: Some(imu),
+            lsm6dso32: Some(lsm),
+            bmp390: Some(BMP390 { pressure: 0.0, temperature: 0.0, altitude: 0.0 }),
+            gps: Some(gps),
+            adxl375: Some(ADXL375 { accel_x: i16::MIN, accel_y: i16::MIN, accel_z: i16::MIN }),
+            ism330dhcx2: Some(imu),
+        };
+        assert_eq!(len(&gps), GPS::MAX_WIRE_LEN);
+        assert_eq!(len(&sensors), AllSensorData::MAX_WIRE_LEN);
+        assert_eq!(AllSensorData::from_wire_bytes(sensors.to_wire_bytes(&mut [0; 2048]).unwrap()).unwrap(), sensors);
+
+        let mut report = AprsCompressedPositionReport {
+            compression_format: '\u{1F680}',
+            symbol_table: '\u{1F680}',
+            symbol_code: '\u{1F680}',
+            compression_type: '\u{1F680}',
+            ..Default::default()
+        };
+        report.comment.ads.timestamp = i32::MIN;
+        report.comment.ads.lat = i16::MIN;
+        report.comment.ads.lon = i16::MIN;
+        report.comment.crc = u16::MAX;
+        let text: Vec<u8> = vec![b'A'; MAX_COMMENT_LEN];
+        report.set_text_comment(TextComment::new(core::str::from_utf8(&text).unwrap()).unwrap());
+        let ads = &mut report.comment.ads;
+        for field in [&mut ads.vel_x, &mut ads.vel_y, &mut ads.vel_z, &mut ads.acc_x, &mut ads.acc_y, &mut ads.acc_z] {
+            *field = i16::MIN;
+        }
+        ads.alt = i16::MIN;
+        ads.predicted_apogee = i16::MIN;
+        ads.flap_deploy_angle = i16::MIN;
+        assert_eq!(len(&report), AprsCompressedPositionReport::MAX_WIRE_LEN);
+
+        let health = Health { uptime_ms: u32::MAX, ..Default::default() };
+        assert_eq!(len(&health), Health::MAX_WIRE_LEN);
+        let estimate = StateEstimate {
+            uid: 0,
+            at_ms: u32::MAX,
+            altitude_m: 0.0,
+            vertical_velocity_mps: 0.0,
+            vertical_accel_mps2: Some(0.0),
+        };
+        assert_eq!(len(&estimate), StateEstimate::MAX_WIRE_LEN);
+    }
+}
@@ -0,0 +1,89 @@
+//! Async counterpart of the Radio trait, for drivers built on an async HAL (requires the `async` feature)
+
+use core::future::Future;
+
+use super::Radio;
+use crate::protocol::mesh::MAX_FRAME_LEN;
+use crate::protocol::ping::LinkQuality;
+
+/// AsyncRadio is Radio for transceivers whose driver awaits the TX-done and RX-done interrupts
+pub trait AsyncRadio {
+    type Error: core::fmt::Debug;
+
+    fn transmit(&mut self, frame: &[u8]) -> impl Future<Output = Result<(), Self::Error>>;
+    /// Waits for a frame and copies it into `buf`, returning its length and link quality
+    fn receive(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<(usize, LinkQuality), Self::Error>>;
+
+    /// Largest frame the transport carries
+    fn max_payload(&self) -> usize {
+        MAX_FRAME_LEN
+    }
+}
+
+/// Adapts a blocking Radio to AsyncRadio, so one driver serves both kinds of firmware
+///
+/// `receive` yields to the executor between polls of the wrapped radio.
+#[derive(Debug, Clone, Default)]
+pub struct Blocking<R>(pub R);
+
+impl<R: Radio> AsyncRadio for Blocking<R> {
+    type Error = R::Error;
+
+    async fn transmit(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        self.0.transmit(frame)
+    }
+
+    async fn receive(&mut self, buf: &mut [u8]) -> Result<(usize, LinkQuality), Self::Error> {
+        loop {
+            if let Some(received) = self.0.receive(buf)? {
+                return Ok(received);
+            }
+            YieldNow(false).await;
+        }
+    }
+
+    fn max_payload(&self) -> usize {
+        self.0.max_payload()
+    }
+}
+
+/// Returns pending once, waking itself so the executor polls again after other tasks
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: core::pin::Pin<&mut Self>, cx: &mut core::task::Context<'_>) -> core::task::Poll<()> {
+        if self.0 {
+            return core::task::Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        core::task::Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    use super::*;
+    use crate::radio::mock::MockRadio;
+
+    #[test]
+    fn test_blocking_radio_yields_until_a_frame_arrives() {
+        let mut radio = Blocking(MockRadio::default());
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(pin!(radio.transmit(b"ping")).as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(radio.0.take_sent().as_deref(), Some(&b"ping"[..]));
+
+        let mut buf = [0u8; 8];
+        assert!(pin!(radio.receive(&mut buf)).as_mut().poll(&mut cx).is_pending());
+        radio.0.inject(b"pong");
+        let Poll::Ready(Ok((len, _))) = pin!(radio.receive(&mut buf)).as_mut().poll(&mut cx) else {
+            panic!("expected the injected frame");
+        };
+        assert_eq!(&buf[..len], b"pong");
+    }
+}
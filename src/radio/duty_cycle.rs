@@ -0,0 +1,122 @@
+use heapless::Vec;
+
+/// Maximum number of sub-bands tracked at once
+pub const MAX_BANDS: usize = 8;
+
+/// A regulatory sub-band with its duty-cycle limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubBand {
+    pub start_hz: u32,
+    pub end_hz: u32,
+    /// Allowed duty cycle in tenths of a percent (10 = 1%, 100 = 10%, 1000 = unlimited)
+    pub duty_permille: u16,
+}
+
+/// ETSI EN 300 220 sub-bands for the EU 868 MHz SRD band
+pub const EU868_BANDS: [SubBand; 5] = [
+    SubBand { start_hz: 863_000_000, end_hz: 868_000_000, duty_permille: 10 },
+    SubBand { start_hz: 868_000_000, end_hz: 868_600_000, duty_permille: 10 },
+    SubBand { start_hz: 868_700_000, end_hz: 869_200_000, duty_permille: 1 },
+    SubBand { start_hz: 869_400_000, end_hz: 869_650_000, duty_permille: 100 },
+    SubBand { start_hz: 869_700_000, end_hz: 870_000_000, duty_permille: 10 },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DutyCycleError {
+    /// The frequency is not inside any configured sub-band
+    OutOfBand,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BandState {
+    band: SubBand,
+    /// Earliest time the band may be used again
+    available_at_ms: u64,
+    total_airtime_ms: u64,
+}
+
+/// DutyCycle paces transmissions so each sub-band stays within its duty-cycle limit
+///
+/// After a transmission of airtime `T` in a band limited to duty cycle `d`, the band is off-limits for
+/// `T * (1/d - 1)`, which keeps usage under the limit over any observation window.
+#[derive(Debug, Clone, Default)]
+pub struct DutyCycle {
+    bands: Vec<BandState, MAX_BANDS>,
+    deferred: u32,
+}
+
+impl DutyCycle {
+    pub fn new(bands: &[SubBand]) -> Self {
+        let mut tracker = Self::default();
+        for band in bands.iter().take(MAX_BANDS) {
+            let _ = tracker.bands.push(BandState { band: *band, available_at_ms: 0, total_airtime_ms: 0 });
+        }
+        tracker
+    }
+
+    fn band_mut(&mut self, frequency_hz: u32) -> Result<&mut BandState, DutyCycleError> {
+        self.bands
+            .iter_mut()
+            .find(|b| frequency_hz >= b.band.start_hz && frequency_hz < b.band.end_hz)
+            .ok_or(DutyCycleError::OutOfBand)
+    }
+
+    /// How long the transmit path must wait before sending on `frequency_hz`
+    pub fn delay_ms(&mut self, frequency_hz: u32, now_ms: u64) -> Result<u64, DutyCycleError> {
+        let band = self.band_mut(frequency_hz)?;
+        Ok(band.available_at_ms.saturating_sub(now_ms))
+    }
+
+    /// Reserves airtime for a frame and returns the time at which it may start
+    ///
+    /// The returned start time is at or after `now_ms`; frames are queued behind the band's time-off.
+    pub fn reserve(&mut self, frequency_hz: u32, airtime_ms: u32, now_ms: u64) -> Result<u64, DutyCycleError> {
+        let band = self.band_mut(frequency_hz)?;
+        let start = band.available_at_ms.max(now_ms);
+        let deferred = start > now_ms;
+        let duty = band.band.duty_permille.clamp(1, 1000) as u64;
+        let off_time = airtime_ms as u64 * (1000 - duty) / duty;
+        band.available_at_ms = start + airtime_ms as u64 + off_time;
+        band.total_airtime_ms += airtime_ms as u64;
+        if deferred {
+            self.deferred += 1;
+        }
+        Ok(start)
+    }
+
+    /// Total airtime used in the band containing `frequency_hz`
+    pub fn airtime_used_ms(&mut self, frequency_hz: u32) -> Result<u64, DutyCycleError> {
+        Ok(self.band_mut(frequency_hz)?.total_airtime_ms)
+    }
+
+    /// Number of frames that had to be delayed to respect the duty cycle
+    pub fn deferred_count(&self) -> u32 {
+        self.deferred
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_percent_band_paces_transmissions() {
+        let mut duty = DutyCycle::new(&EU868_BANDS);
+        assert_eq!(duty.reserve(868_100_000, 100, 0), Ok(0));
+        // 100 ms at 1% means 9.9 s off
+        assert_eq!(duty.delay_ms(868_100_000, 1_000), Ok(9_000));
+        assert_eq!(duty.reserve(868_100_000, 100, 1_000), Ok(10_000));
+        assert_eq!(duty.deferred_count(), 1);
+        assert_eq!(duty.airtime_used_ms(868_300_000), Ok(200));
+    }
+
+    #[test]
+    fn test_bands_are_independent() {
+        let mut duty = DutyCycle::new(&EU868_BANDS);
+        duty.reserve(868_100_000, 100, 0).unwrap();
+        assert_eq!(duty.delay_ms(869_525_000, 0), Ok(0));
+        assert_eq!(duty.reserve(869_525_000, 100, 0), Ok(0));
+        assert_eq!(duty.delay_ms(869_525_000, 0), Ok(1_000));
+        assert_eq!(duty.reserve(915_000_000, 100, 0), Err(DutyCycleError::OutOfBand));
+    }
+}
@@ -0,0 +1,87 @@
+//! Free-space link budget for a LoRa link, to check that a configuration closes at the expected range
+
+use libm::{log10f, powf};
+
+use super::LoraParams;
+
+/// Receiver noise figure of the SX127x family in dB
+const NOISE_FIGURE_DB: f32 = 6.0;
+/// Thermal noise density at room temperature in dBm/Hz
+const THERMAL_NOISE_DBM_HZ: f32 = -174.0;
+/// Free-space path loss constant for meters and hertz, 20 log10(4 pi / c)
+const FSPL_CONSTANT_DB: f32 = -147.55;
+
+/// Lowest SNR at which the demodulator still decodes, per spreading factor (SX1276 datasheet)
+pub fn required_snr_db(spreading_factor: u8) -> f32 {
+    match spreading_factor {
+        ..=6 => -5.0,
+        7 => -7.5,
+        8 => -10.0,
+        9 => -12.5,
+        10 => -15.0,
+        11 => -17.5,
+        _ => -20.0,
+    }
+}
+
+/// Receiver sensitivity for `params` in dBm
+pub fn sensitivity_dbm(params: &LoraParams) -> f32 {
+    THERMAL_NOISE_DBM_HZ + 10.0 * log10f(params.bandwidth_hz as f32) + NOISE_FIGURE_DB
+        + required_snr_db(params.spreading_factor)
+}
+
+/// Free-space path loss over `range_m` at `frequency_hz` in dB
+pub fn path_loss_db(range_m: f32, frequency_hz: u32) -> f32 {
+    20.0 * log10f(range_m.max(1.0)) + 20.0 * log10f(frequency_hz as f32) + FSPL_CONSTANT_DB
+}
+
+/// Gains and losses of one link, independent of the modulation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkBudget {
+    pub tx_power_dbm: f32,
+    pub frequency_hz: u32,
+    /// Sum of the transmit and receive antenna gains in dBi
+    pub antenna_gain_db: f32,
+    /// Cable, connector, polarization and pointing losses in dB
+    pub losses_db: f32,
+}
+
+impl LinkBudget {
+    /// Signal received at `range_m` minus the sensitivity for `params`, negative when the link does not close
+    pub fn margin_db(&self, params: &LoraParams, range_m: f32) -> f32 {
+        self.received_dbm(range_m) - sensitivity_dbm(params)
+    }
+
+    /// Range at which the link for `params` is left with `margin_db`
+    pub fn max_range_m(&self, params: &LoraParams, margin_db: f32) -> f32 {
+        let allowed_loss = self.received_dbm(1.0) - sensitivity_dbm(params) - margin_db;
+        powf(10.0, allowed_loss / 20.0)
+    }
+
+    fn received_dbm(&self, range_m: f32) -> f32 {
+        self.tx_power_dbm + self.antenna_gain_db - self.losses_db - path_loss_db(range_m, self.frequency_hz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensitivity_and_margin() {
+        // SX1276 datasheet lists -129 dBm at SF9/125 kHz and -137 dBm at SF12/125 kHz
+        let sf9 = LoraParams::default();
+        assert!((sensitivity_dbm(&sf9) + 129.5).abs() < 0.1);
+        let sf12 = LoraParams { spreading_factor: 12, ..sf9 };
+        assert!((sensitivity_dbm(&sf12) + 137.0).abs() < 0.1);
+
+        // 10 km at 915 MHz loses about 111.7 dB
+        assert!((path_loss_db(10_000.0, 915_000_000) - 111.7).abs() < 0.1);
+        let budget = LinkBudget { tx_power_dbm: 20.0, frequency_hz: 915_000_000, antenna_gain_db: 2.0, losses_db: 3.0 };
+        let margin = budget.margin_db(&sf9, 10_000.0);
+        assert!((margin - 36.8).abs() < 0.1);
+        // Every 6 dB of margin given up doubles the range
+        let range = budget.max_range_m(&sf9, margin - 6.02);
+        assert!((range - 20_000.0).abs() < 10.0);
+    }
+}
@@ -0,0 +1,205 @@
+use heapless::{Deque, Vec};
+
+use super::Radio;
+use crate::protocol::mesh::MAX_FRAME_LEN;
+use crate::protocol::ping::LinkQuality;
+
+/// Frames a MockRadio can hold waiting to be received or collected
+pub const MOCK_QUEUE_LEN: usize = 32;
+/// Longest loss pattern a Script can hold
+pub const MAX_SCRIPT: usize = 32;
+
+type Frame = Vec<u8, MAX_FRAME_LEN>;
+
+/// What happens to one frame arriving at a MockRadio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fate {
+    Deliver,
+    Drop,
+    /// Deliver the frame twice
+    Duplicate,
+    /// Hold the frame back until this many later frames have been delivered
+    Delay(u8),
+}
+
+/// Sequence of fates applied to successive arriving frames
+///
+/// Frames past the end of a one-shot script are delivered; a repeating script starts over.
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    fates: Vec<Fate, MAX_SCRIPT>,
+    repeat: bool,
+    next: usize,
+}
+
+impl Script {
+    pub fn once(fates: &[Fate]) -> Self {
+        Self { fates: fates.iter().copied().take(MAX_SCRIPT).collect(), repeat: false, next: 0 }
+    }
+
+    pub fn repeating(fates: &[Fate]) -> Self {
+        Self { repeat: true, ..Self::once(fates) }
+    }
+
+    fn next_fate(&mut self) -> Fate {
+        if self.fates.is_empty() || (!self.repeat && self.next >= self.fates.len()) {
+            return Fate::Deliver;
+        }
+        let fate = self.fates[self.next % self.fates.len()];
+        self.next += 1;
+        fate
+    }
+}
+
+/// MockRadio is an in-memory Radio for deterministic tests of the layers above it
+///
+/// Transmitted frames are collected; frames are fed in with `inject` or `relay`, passing through the
+/// receive-side Script so loss, duplication and reordering happen exactly as scripted.
+#[derive(Debug, Clone, Default)]
+pub struct MockRadio {
+    pub script: Script,
+    /// Link quality reported for every received frame
+    pub quality: LinkQuality,
+    /// Reported PA temperature, set by tests
+    pub pa_temperature_c: Option<f32>,
+    /// Last power set through the Radio trait
+    pub tx_power_dbm: Option<i8>,
+    /// Reported channel activity, set by tests; `None` acts like a radio that cannot listen
+    pub busy: Option<bool>,
+    inbox: Deque<Frame, MOCK_QUEUE_LEN>,
+    held: Vec<(u8, Frame), MOCK_QUEUE_LEN>,
+    sent: Deque<Frame, MOCK_QUEUE_LEN>,
+}
+
+impl MockRadio {
+    pub fn new(script: Script) -> Self {
+        Self { script, ..Default::default() }
+    }
+
+    /// Frames transmitted and not yet collected
+    pub fn sent_len(&self) -> usize {
+        self.sent.len()
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.inbox.len()
+    }
+
+    /// Removes the oldest transmitted frame
+    pub fn take_sent(&mut self) -> Option<Vec<u8, MAX_FRAME_LEN>> {
+        self.sent.pop_front()
+    }
+
+    /// Offers a frame to this radio's receiver, subject to the script
+    pub fn inject(&mut self, frame: &[u8]) {
+        let Ok(frame) = Frame::from_slice(frame) else {
+            return;
+        };
+        match self.script.next_fate() {
+            Fate::Deliver => self.enqueue(frame),
+            Fate::Drop => {}
+            Fate::Duplicate => {
+                self.enqueue(frame.clone());
+                self.enqueue(frame);
+            }
+            Fate::Delay(0) => self.enqueue(frame),
+            Fate::Delay(n) => {
+                let _ = self.held.push((n, frame));
+            }
+        }
+    }
+
+    fn enqueue(&mut self, frame: Frame) {
+        let _ = self.inbox.push_back(frame);
+        // Every delivered frame brings held frames one step closer to release
+        let mut index = 0;
+        while index < self.held.len() {
+            self.held[index].0 -= 1;
+            if self.held[index].0 == 0 {
+                let (_, released) = self.held.remove(index);
+                let _ = self.inbox.push_back(released);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Moves everything `from` has transmitted into `to`'s receiver
+pub fn relay(from: &mut MockRadio, to: &mut MockRadio) {
+    while let Some(frame) = from.take_sent() {
+        to.inject(&frame);
+    }
+}
+
+impl Radio for MockRadio {
+    type Error = core::convert::Infallible;
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        if let Ok(frame) = Frame::from_slice(frame) {
+            if self.sent.is_full() {
+                self.sent.pop_front();
+            }
+            let _ = self.sent.push_back(frame);
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self, buf: &mut [u8]) -> Result<Option<(usize, LinkQuality)>, Self::Error> {
+        let Some(frame) = self.inbox.pop_front() else {
+            return Ok(None);
+        };
+        let len = frame.len().min(buf.len());
+        buf[..len].copy_from_slice(&frame[..len]);
+        Ok(Some((len, self.quality)))
+    }
+
+    fn loopback(&mut self, pattern: &[u8], buf: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        let len = pattern.len().min(buf.len());
+        buf[..len].copy_from_slice(&pattern[..len]);
+        Ok(Some(len))
+    }
+
+    fn channel_busy(&mut self) -> Result<Option<bool>, Self::Error> {
+        Ok(self.busy)
+    }
+
+    fn pa_temperature_c(&mut self) -> Result<Option<f32>, Self::Error> {
+        Ok(self.pa_temperature_c)
+    }
+
+    fn set_tx_power_dbm(&mut self, dbm: i8) -> Result<(), Self::Error> {
+        self.tx_power_dbm = Some(dbm);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(radio: &mut MockRadio) -> std::vec::Vec<u8> {
+        let mut buf = [0u8; 8];
+        let mut received = std::vec::Vec::new();
+        while let Ok(Some((_, _))) = radio.receive(&mut buf) {
+            received.push(buf[0]);
+        }
+        received
+    }
+
+    #[test]
+    fn test_scripted_loss_duplication_and_reordering() {
+        let script = Script::once(&[Fate::Drop, Fate::Duplicate, Fate::Delay(2), Fate::Deliver]);
+        let mut radio = MockRadio::new(script);
+        for n in 1..=5u8 {
+            radio.inject(&[n]);
+        }
+        assert_eq!(drain(&mut radio), [2, 2, 4, 5, 3]);
+
+        let mut radio = MockRadio::new(Script::repeating(&[Fate::Deliver, Fate::Drop]));
+        for n in 1..=4u8 {
+            radio.inject(&[n]);
+        }
+        assert_eq!(drain(&mut radio), [1, 3]);
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod duty_cycle;
+#[cfg(feature = "fec")]
+pub mod fec;
+pub mod link_budget;
+pub mod mock;
+pub mod region;
+#[cfg(feature = "std")]
+pub mod sim;
+#[cfg(feature = "sx127x")]
+pub mod sx127x;
+pub mod thermal;
+#[cfg(feature = "std")]
+pub mod udp;
+
+use crate::protocol::mesh::MAX_FRAME_LEN;
+use crate::protocol::ping::LinkQuality;
+
+/// Radio is the half-duplex packet transceiver underneath the mesh
+///
+/// The routing and acknowledgement layers only see whole frames, so any datagram transport works: LoRa or
+/// FSK transceivers, `udp::UdpRadio` and `sim::SimRadio` for runs without hardware and `mock::MockRadio` for
+/// tests. With the `async` feature, `asynch::AsyncRadio` is the same contract for async drivers.
+pub trait Radio {
+    type Error: core::fmt::Debug;
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), Self::Error>;
+    /// Copies a received frame into `buf`, returning its length and link quality if one is waiting
+    fn receive(&mut self, buf: &mut [u8]) -> Result<Option<(usize, LinkQuality)>, Self::Error>;
+
+    /// Largest frame the transport carries, the node never sends more than `MAX_FRAME_LEN` regardless
+    fn max_payload(&self) -> usize {
+        MAX_FRAME_LEN
+    }
+
+    /// Passes `pattern` through the transceiver's internal loopback into `buf`, `None` if unsupported
+    fn loopback(&mut self, pattern: &[u8], buf: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        let _ = (pattern, buf);
+        Ok(None)
+    }
+
+    /// Listens for another transmission on the channel, `None` if the transceiver cannot tell
+    fn channel_busy(&mut self) -> Result<Option<bool>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Power amplifier temperature in Celsius, `None` if the hardware has no sensor
+    fn pa_temperature_c(&mut self) -> Result<Option<f32>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Sets the transmit power, radios without adjustable power ignore it
+    fn set_tx_power_dbm(&mut self, dbm: i8) -> Result<(), Self::Error> {
+        let _ = dbm;
+        Ok(())
+    }
+}
+
+/// LoRa modulation parameters, used to compute on-air time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoraParams {
+    /// Spreading factor, 6..12
+    pub spreading_factor: u8,
+    pub bandwidth_hz: u32,
+    /// Coding rate denominator, 5..8 for 4/5..4/8
+    pub coding_rate: u8,
+    pub preamble_len: u16,
+    pub explicit_header: bool,
+    pub crc: bool,
+}
+
+impl Default for LoraParams {
+    fn default() -> Self {
+        Self { spreading_factor: 9, bandwidth_hz: 125_000, coding_rate: 5, preamble_len: 8, explicit_header: true, crc: true }
+    }
+}
+
+impl LoraParams {
+    /// Time on air of a frame with `payload_len` bytes, in microseconds (Semtech AN1200.13)
+    pub fn airtime_us(&self, payload_len: usize) -> u64 {
+        let sf = self.spreading_factor as i64;
+        let symbol_us = (1_000_000u64 << sf) / self.bandwidth_hz as u64;
+        let low_data_rate = sf >= 11 && self.bandwidth_hz <= 125_000;
+
+        let numerator = 8 * payload_len as i64 - 4 * sf + 28 + if self.crc { 16 } else { 0 }
+            - if self.explicit_header { 0 } else { 20 };
+        let denominator = 4 * (sf - if low_data_rate { 2 } else { 0 });
+        let blocks = if numerator > 0 { (numerator + denominator - 1) / denominator } else { 0 };
+        let payload_symbols = 8 + blocks as u64 * self.coding_rate as u64;
+
+        // Preamble is n + 4.25 symbols, kept in quarter symbols to stay in integer math
+        let preamble_quarter_symbols = (self.preamble_len as u64 + 4) * 4 + 1;
+        preamble_quarter_symbols * symbol_us / 4 + payload_symbols * symbol_us
+    }
+
+    pub fn airtime_ms(&self, payload_len: usize) -> u32 {
+        self.airtime_us(payload_len).div_ceil(1000) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_airtime_matches_semtech_calculator() {
+        // SF7/125k, CR 4/5, 8 symbol preamble, 10 byte payload: 41.22 ms
+        let sf7 = LoraParams { spreading_factor: 7, ..Default::default() };
+        assert_eq!(sf7.airtime_ms(10), 42);
+        // SF12/125k, low data rate optimization on, 10 bytes: 991.23 ms
+        let sf12 = LoraParams { spreading_factor: 12, ..Default::default() };
+        assert_eq!(sf12.airtime_ms(10), 992);
+    }
+}
@@ -0,0 +1,158 @@
+use super::duty_cycle::{DutyCycle, SubBand, EU868_BANDS};
+use crate::params::digest::Fnv;
+use crate::params::{ids, ParamStore, ParamValue};
+
+/// A channel in a regional plan with its transmit power limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channel {
+    pub frequency_hz: u32,
+    pub max_power_dbm: i8,
+}
+
+/// Channel plan and regulatory limits for one region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionPlan {
+    pub region: Region,
+    pub channels: &'static [Channel],
+    /// Maximum time on air per transmission on one channel, if limited
+    pub max_dwell_ms: Option<u32>,
+    /// Duty-cycle sub-bands to enforce, empty where no duty-cycle rule applies
+    pub duty_cycle_bands: &'static [SubBand],
+}
+
+/// Regional frequency plan, selected at runtime so one firmware binary can fly at different sites
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Region {
+    /// FCC part 15.247, 902-928 MHz
+    #[default]
+    Us915 = 0,
+    /// ETSI EN 300 220, 863-870 MHz
+    Eu868 = 1,
+    /// ETSI EN 300 220, 433.05-434.79 MHz
+    Ism433 = 2,
+}
+
+const US915_CHANNELS: [Channel; 8] = [
+    Channel { frequency_hz: 903_900_000, max_power_dbm: 30 },
+    Channel { frequency_hz: 904_100_000, max_power_dbm: 30 },
+    Channel { frequency_hz: 904_300_000, max_power_dbm: 30 },
+    Channel { frequency_hz: 904_500_000, max_power_dbm: 30 },
+    Channel { frequency_hz: 904_700_000, max_power_dbm: 30 },
+    Channel { frequency_hz: 904_900_000, max_power_dbm: 30 },
+    Channel { frequency_hz: 905_100_000, max_power_dbm: 30 },
+    Channel { frequency_hz: 905_300_000, max_power_dbm: 30 },
+];
+
+const EU868_CHANNELS: [Channel; 4] = [
+    Channel { frequency_hz: 868_100_000, max_power_dbm: 14 },
+    Channel { frequency_hz: 868_300_000, max_power_dbm: 14 },
+    Channel { frequency_hz: 868_500_000, max_power_dbm: 14 },
+    // g3 sub-band allows 500 mW ERP at 10% duty cycle
+    Channel { frequency_hz: 869_525_000, max_power_dbm: 27 },
+];
+
+const ISM433_CHANNELS: [Channel; 4] = [
+    Channel { frequency_hz: 433_175_000, max_power_dbm: 10 },
+    Channel { frequency_hz: 433_375_000, max_power_dbm: 10 },
+    Channel { frequency_hz: 433_575_000, max_power_dbm: 10 },
+    Channel { frequency_hz: 433_775_000, max_power_dbm: 10 },
+];
+
+const ISM433_BANDS: [SubBand; 1] = [SubBand { start_hz: 433_050_000, end_hz: 434_790_000, duty_permille: 100 }];
+
+const PLANS: [RegionPlan; 3] = [
+    RegionPlan { region: Region::Us915, channels: &US915_CHANNELS, max_dwell_ms: Some(400), duty_cycle_bands: &[] },
+    RegionPlan { region: Region::Eu868, channels: &EU868_CHANNELS, max_dwell_ms: None, duty_cycle_bands: &EU868_BANDS },
+    RegionPlan {
+        region: Region::Ism433,
+        channels: &ISM433_CHANNELS,
+        max_dwell_ms: None,
+        duty_cycle_bands: &ISM433_BANDS,
+    },
+];
+
+impl Region {
+    pub fn plan(self) -> &'static RegionPlan {
+        &PLANS[self as usize]
+    }
+
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Region::Us915),
+            1 => Some(Region::Eu868),
+            2 => Some(Region::Ism433),
+            _ => None,
+        }
+    }
+
+    /// Reads the configured region from the parameter store, defaulting to US 915 when unset
+    pub fn from_params(params: &ParamStore) -> Self {
+        match params.get(ids::REGION) {
+            Some(ParamValue::U32(value)) => Self::from_u32(value).unwrap_or_default(),
+            _ => Region::default(),
+        }
+    }
+
+    pub fn store(self, params: &mut ParamStore) -> Result<(), crate::params::ParamsFull> {
+        params.set(ids::REGION, ParamValue::U32(self as u32))
+    }
+}
+
+impl RegionPlan {
+    pub fn channel(&self, frequency_hz: u32) -> Option<&Channel> {
+        self.channels.iter().find(|c| c.frequency_hz == frequency_hz)
+    }
+
+    /// Clamps a requested power to the channel limit, `None` if the frequency is not in the plan
+    pub fn clamp_power(&self, frequency_hz: u32, requested_dbm: i8) -> Option<i8> {
+        self.channel(frequency_hz).map(|c| requested_dbm.min(c.max_power_dbm))
+    }
+
+    /// Returns true if a frame with this airtime respects the dwell-time rule
+    pub fn dwell_ok(&self, airtime_ms: u32) -> bool {
+        self.max_dwell_ms.is_none_or(|max| airtime_ms <= max)
+    }
+
+    /// Duty-cycle tracker for the region's sub-bands
+    pub fn duty_cycle(&self) -> DutyCycle {
+        DutyCycle::new(self.duty_cycle_bands)
+    }
+
+    /// Hash of the channels and limits, reported in `ConfigHash::channel_plan`
+    pub fn hash(&self) -> u32 {
+        let mut hash = Fnv::new();
+        hash.write(&[self.region as u8]);
+        for channel in self.channels {
+            hash.write(&channel.frequency_hz.to_le_bytes());
+            hash.write(&channel.max_power_dbm.to_le_bytes());
+        }
+        hash.write(&self.max_dwell_ms.unwrap_or(0).to_le_bytes());
+        hash.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_selected_from_params() {
+        let mut params = ParamStore::new();
+        assert_eq!(Region::from_params(&params), Region::Us915);
+        Region::Eu868.store(&mut params).unwrap();
+        let plan = Region::from_params(&params).plan();
+        assert_eq!(plan.region, Region::Eu868);
+        assert_eq!(plan.clamp_power(868_100_000, 20), Some(14));
+        assert_eq!(plan.clamp_power(869_525_000, 20), Some(20));
+        assert_eq!(plan.clamp_power(915_000_000, 20), None);
+    }
+
+    #[test]
+    fn test_dwell_and_duty_rules() {
+        assert!(!Region::Us915.plan().dwell_ok(500));
+        assert!(Region::Eu868.plan().dwell_ok(1_500));
+        let mut duty = Region::Ism433.plan().duty_cycle();
+        duty.reserve(433_175_000, 100, 0).unwrap();
+        assert_eq!(duty.delay_ms(433_375_000, 0), Ok(1_000));
+    }
+}
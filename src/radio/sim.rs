@@ -0,0 +1,256 @@
+//! In-process radio medium for exercising routing, acknowledgement and fragmentation without hardware
+//!
+//! Every attached radio shares one channel. Frames are dropped, delayed and duplicated per receiver from a
+//! seeded generator, so a run under a `MockClock` replays exactly and is safe to use in CI.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use super::Radio;
+use crate::clock::Clock;
+use crate::protocol::ping::LinkQuality;
+use crate::rng::{NodeRng, RandomSource};
+
+/// Impairments applied to every frame on every link
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimConfig {
+    /// Probability a copy is lost, 0.0..=1.0
+    pub loss: f32,
+    /// Probability a delivered copy arrives twice
+    pub duplication: f32,
+    pub latency_ms: u32,
+    /// Extra delay drawn uniformly from `0..=jitter_ms`, which also reorders frames
+    pub jitter_ms: u32,
+    /// Link quality reported for every received frame
+    pub quality: LinkQuality,
+    pub seed: u32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            loss: 0.0,
+            duplication: 0.0,
+            latency_ms: 0,
+            jitter_ms: 0,
+            quality: LinkQuality { rssi_dbm: -80, snr_db: 8.0 },
+            seed: 0,
+        }
+    }
+}
+
+/// Copies counted by the medium, one per receiver of each transmission
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimStats {
+    pub transmitted: u32,
+    pub delivered: u32,
+    pub dropped: u32,
+    pub duplicated: u32,
+}
+
+struct InFlight {
+    deliver_at_ms: u64,
+    order: u64,
+    frame: Vec<u8>,
+}
+
+struct Port {
+    uid: u8,
+    inbox: Vec<InFlight>,
+}
+
+struct Channel {
+    config: SimConfig,
+    rng: NodeRng,
+    ports: Vec<Port>,
+    /// Directed links that do not carry frames, as (from, to)
+    cut: Vec<(u8, u8)>,
+    stats: SimStats,
+    next_order: u64,
+}
+
+impl Channel {
+    fn chance(&mut self, probability: f32) -> bool {
+        (self.rng.below(1_000_000) as f32) < probability * 1_000_000.0
+    }
+
+    fn broadcast(&mut self, from: u8, frame: &[u8], now_ms: u64) {
+        self.stats.transmitted += 1;
+        for index in 0..self.ports.len() {
+            let to = self.ports[index].uid;
+            if to == from || self.cut.contains(&(from, to)) {
+                continue;
+            }
+            if self.chance(self.config.loss) {
+                self.stats.dropped += 1;
+                continue;
+            }
+            let copies = if self.chance(self.config.duplication) { 2 } else { 1 };
+            self.stats.duplicated += copies - 1;
+            for _ in 0..copies {
+                let delay = self.config.latency_ms + self.rng.below(self.config.jitter_ms.saturating_add(1));
+                self.next_order += 1;
+                self.stats.delivered += 1;
+                let deliver_at_ms = now_ms + delay as u64;
+                self.ports[index].inbox.push(InFlight { deliver_at_ms, order: self.next_order, frame: frame.to_vec() });
+            }
+        }
+    }
+
+    /// Removes the earliest frame for `uid` that has arrived by `now_ms`
+    fn take(&mut self, uid: u8, now_ms: u64) -> Option<Vec<u8>> {
+        let inbox = &mut self.ports.iter_mut().find(|port| port.uid == uid)?.inbox;
+        let (index, _) = inbox
+            .iter()
+            .enumerate()
+            .filter(|(_, in_flight)| in_flight.deliver_at_ms <= now_ms)
+            .min_by_key(|(_, in_flight)| (in_flight.deliver_at_ms, in_flight.order))?;
+        Some(inbox.remove(index).frame)
+    }
+}
+
+/// SimMedium is the shared channel, cheap to clone and read by every attached radio
+///
+/// Single-threaded like `MockClock`: drive all the nodes from one loop and advance the clock between polls.
+#[derive(Clone)]
+pub struct SimMedium<'a> {
+    clock: &'a dyn Clock,
+    channel: Rc<RefCell<Channel>>,
+}
+
+impl<'a> SimMedium<'a> {
+    pub fn new(clock: &'a dyn Clock, config: SimConfig) -> Self {
+        let channel = Channel {
+            config,
+            rng: NodeRng::seeded(0, config.seed),
+            ports: Vec::new(),
+            cut: Vec::new(),
+            stats: SimStats::default(),
+            next_order: 0,
+        };
+        Self { clock, channel: Rc::new(RefCell::new(channel)) }
+    }
+
+    /// Adds a radio for node `uid`, hearing and heard by every other radio until links are cut
+    pub fn attach(&self, uid: u8) -> SimRadio<'a> {
+        let mut channel = self.channel.borrow_mut();
+        if !channel.ports.iter().any(|port| port.uid == uid) {
+            channel.ports.push(Port { uid, inbox: Vec::new() });
+        }
+        SimRadio { uid, medium: self.clone() }
+    }
+
+    /// Stops frames between `a` and `b` in both directions, e.g. to force traffic through a relay
+    pub fn cut(&self, a: u8, b: u8) {
+        let mut channel = self.channel.borrow_mut();
+        for link in [(a, b), (b, a)] {
+            if !channel.cut.contains(&link) {
+                channel.cut.push(link);
+            }
+        }
+    }
+
+    pub fn restore(&self, a: u8, b: u8) {
+        self.channel.borrow_mut().cut.retain(|&link| link != (a, b) && link != (b, a));
+    }
+
+    /// Replaces the impairments for frames transmitted from now on
+    pub fn set_config(&self, config: SimConfig) {
+        self.channel.borrow_mut().config = config;
+    }
+
+    pub fn stats(&self) -> SimStats {
+        self.channel.borrow().stats
+    }
+}
+
+/// One node's view of a SimMedium
+#[derive(Clone)]
+pub struct SimRadio<'a> {
+    uid: u8,
+    medium: SimMedium<'a>,
+}
+
+impl Radio for SimRadio<'_> {
+    type Error = core::convert::Infallible;
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        let now_ms = self.medium.clock.now_ms();
+        self.medium.channel.borrow_mut().broadcast(self.uid, frame, now_ms);
+        Ok(())
+    }
+
+    fn receive(&mut self, buf: &mut [u8]) -> Result<Option<(usize, LinkQuality)>, Self::Error> {
+        let mut channel = self.medium.channel.borrow_mut();
+        let Some(frame) = channel.take(self.uid, self.medium.clock.now_ms()) else {
+            return Ok(None);
+        };
+        // Frames longer than the buffer are truncated the way a transceiver's FIFO would
+        let len = frame.len().min(buf.len());
+        buf[..len].copy_from_slice(&frame[..len]);
+        Ok(Some((len, channel.config.quality)))
+    }
+}
+
+#[cfg(all(test, feature = "mesh"))]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::node::runtime::{MeshNode, NodeConfig, NodeEvent};
+    use crate::persistence::MemoryStore;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::packet::Packet;
+
+    #[test]
+    fn test_loss_and_latency_on_a_link() {
+        let clock = MockClock::new(0);
+        let medium = SimMedium::new(&clock, SimConfig { loss: 0.25, latency_ms: 50, seed: 3, ..SimConfig::default() });
+        let (mut a, mut b) = (medium.attach(1), medium.attach(2));
+        let mut buf = [0u8; 4];
+        for round in 0..1_000u32 {
+            a.transmit(&round.to_le_bytes()).unwrap();
+        }
+        assert_eq!(b.receive(&mut buf).unwrap(), None);
+        clock.advance(50);
+        let mut received = 0;
+        while b.receive(&mut buf).unwrap().is_some() {
+            received += 1;
+        }
+        let stats = medium.stats();
+        assert_eq!((stats.transmitted, stats.delivered + stats.dropped, received), (1_000, 1_000, stats.delivered));
+        assert!((200..300).contains(&stats.dropped));
+    }
+
+    #[test]
+    fn test_reliable_send_through_relay_with_duplication_and_reordering() {
+        let clock = MockClock::new(0);
+        let config = SimConfig { duplication: 0.5, latency_ms: 40, jitter_ms: 60, seed: 7, ..SimConfig::default() };
+        let medium = SimMedium::new(&clock, config);
+        // The rocket and the ground station only hear each other through the relay
+        medium.cut(1, 3);
+        let mut nodes = [1, 2, 3].map(|uid| {
+            MeshNode::new(uid, medium.attach(uid), &clock, MemoryStore::<2, 16>::new(), NodeConfig::default()).unwrap()
+        });
+
+        let sequence = nodes[0].send(3, Packet::Event(FlightEvent::Launch), true).unwrap();
+        let (mut received, mut delivered) = (0, None);
+        for _ in 0..1_000 {
+            for (index, node) in nodes.iter_mut().enumerate() {
+                while let Some(event) = node.poll().unwrap() {
+                    match event {
+                        NodeEvent::Received { packet: Packet::Event(FlightEvent::Launch), .. } if index == 2 => {
+                            received += 1;
+                        }
+                        NodeEvent::Delivered { sequence, .. } if index == 0 => delivered = Some(sequence),
+                        _ => {}
+                    }
+                }
+            }
+            clock.advance(10);
+        }
+        // Every duplicated copy is suppressed before the application
+        assert_eq!((received, delivered), (1, Some(sequence)));
+        assert!(medium.stats().duplicated > 0);
+    }
+}
@@ -0,0 +1,413 @@
+//! Driver for Semtech SX1276/77/78/79 and HopeRF RFM95/96/98 transceivers in LoRa mode (requires the
+//! `sx127x` feature)
+//!
+//! The driver polls the IRQ flags over SPI, so only the SPI bus is wired; DIO0 can be left for wake-up.
+//! Before each transmission it runs channel activity detection and backs off while another LoRa
+//! transmitter is heard.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::{Operation, SpiDevice};
+
+use super::{LoraParams, Radio};
+use crate::protocol::mesh::MAX_FRAME_LEN;
+use crate::protocol::ping::LinkQuality;
+use crate::rng::NodeRng;
+
+const REG_FIFO: u8 = 0x00;
+const REG_OP_MODE: u8 = 0x01;
+const REG_FRF_MSB: u8 = 0x06;
+const REG_PA_CONFIG: u8 = 0x09;
+const REG_FIFO_ADDR_PTR: u8 = 0x0D;
+const REG_FIFO_TX_BASE_ADDR: u8 = 0x0E;
+const REG_FIFO_RX_BASE_ADDR: u8 = 0x0F;
+const REG_FIFO_RX_CURRENT_ADDR: u8 = 0x10;
+const REG_IRQ_FLAGS: u8 = 0x12;
+const REG_RX_NB_BYTES: u8 = 0x13;
+const REG_PKT_SNR_VALUE: u8 = 0x19;
+const REG_PKT_RSSI_VALUE: u8 = 0x1A;
+const REG_MODEM_CONFIG_1: u8 = 0x1D;
+const REG_MODEM_CONFIG_2: u8 = 0x1E;
+const REG_PREAMBLE_MSB: u8 = 0x20;
+const REG_PAYLOAD_LENGTH: u8 = 0x22;
+const REG_MODEM_CONFIG_3: u8 = 0x26;
+const REG_RSSI_WIDEBAND: u8 = 0x2C;
+const REG_DETECT_OPTIMIZE: u8 = 0x31;
+const REG_DETECTION_THRESHOLD: u8 = 0x37;
+const REG_SYNC_WORD: u8 = 0x39;
+const REG_VERSION: u8 = 0x42;
+const REG_PA_DAC: u8 = 0x4D;
+
+const LONG_RANGE_MODE: u8 = 0x80;
+const MODE_SLEEP: u8 = 0x00;
+const MODE_STANDBY: u8 = 0x01;
+const MODE_TX: u8 = 0x03;
+const MODE_RX_CONTINUOUS: u8 = 0x05;
+const MODE_CAD: u8 = 0x07;
+
+const IRQ_RX_DONE: u8 = 0x40;
+const IRQ_PAYLOAD_CRC_ERROR: u8 = 0x20;
+const IRQ_TX_DONE: u8 = 0x08;
+const IRQ_CAD_DONE: u8 = 0x04;
+const IRQ_CAD_DETECTED: u8 = 0x01;
+
+/// Silicon revision reported by every SX1276-family part
+const VERSION: u8 = 0x12;
+/// Crystal frequency of the reference design
+const FXOSC_HZ: u64 = 32_000_000;
+/// Bandwidths selectable in RegModemConfig1, in register order
+const BANDWIDTHS_HZ: [u32; 10] = [7_800, 10_400, 15_600, 20_800, 31_250, 41_700, 62_500, 125_000, 250_000, 500_000];
+/// CAD takes about two symbols, this bounds the wait at SF12/7.8 kHz
+const CAD_TIMEOUT_MS: u32 = 1_100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sx127xError<E> {
+    Spi(E),
+    /// The version register did not read back as an SX127x, usually a wiring fault
+    WrongVersion(u8),
+    /// TxDone or CadDone never came
+    Timeout,
+    /// The channel stayed busy through every listen-before-talk attempt
+    ChannelBusy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sx127xConfig {
+    pub frequency_hz: u32,
+    pub params: LoraParams,
+    /// Output power on the PA_BOOST pin, 2..=20 dBm, which every RFM95-style module uses
+    pub tx_power_dbm: i8,
+    /// 0x12 for private networks, 0x34 is LoRaWAN
+    pub sync_word: u8,
+    /// Channel activity checks before giving up on a transmission, 0 to transmit without listening
+    pub lbt_attempts: u8,
+    /// Longest random wait between busy channel checks
+    pub lbt_backoff_ms: u32,
+}
+
+impl Default for Sx127xConfig {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 915_000_000,
+            params: LoraParams::default(),
+            tx_power_dbm: 17,
+            sync_word: 0x12,
+            lbt_attempts: 5,
+            lbt_backoff_ms: 100,
+        }
+    }
+}
+
+/// Sx127xRadio is a Radio over an SX127x on an embedded-hal SPI device, left in continuous receive
+pub struct Sx127xRadio<SPI, D> {
+    spi: SPI,
+    delay: D,
+    config: Sx127xConfig,
+    rng: NodeRng,
+}
+
+impl<SPI: SpiDevice, D: DelayNs> Sx127xRadio<SPI, D> {
+    /// Checks the chip version, applies `config` and starts receiving
+    pub fn new(spi: SPI, delay: D, config: Sx127xConfig) -> Result<Self, Sx127xError<SPI::Error>> {
+        let mut radio = Self { spi, delay, config, rng: NodeRng::seeded(0, 0) };
+        let version = radio.read(REG_VERSION)?;
+        if version != VERSION {
+            return Err(Sx127xError::WrongVersion(version));
+        }
+        // The long-range bit only changes in sleep
+        radio.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_SLEEP)?;
+        radio.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_STANDBY)?;
+        radio.write(REG_FIFO_TX_BASE_ADDR, 0)?;
+        radio.write(REG_FIFO_RX_BASE_ADDR, 0)?;
+        radio.configure(config)?;
+        // Wideband RSSI noise is the chip's recommended source of random bits
+        let mut seed = 0u32;
+        for _ in 0..4 {
+            seed = seed << 8 | radio.read(REG_RSSI_WIDEBAND)? as u32;
+        }
+        radio.rng = NodeRng::seeded(0, seed);
+        radio.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_RX_CONTINUOUS)?;
+        Ok(radio)
+    }
+
+    pub fn config(&self) -> &Sx127xConfig {
+        &self.config
+    }
+
+    /// Applies new frequency, modulation and power, e.g. after a rate change command
+    pub fn configure(&mut self, config: Sx127xConfig) -> Result<(), Sx127xError<SPI::Error>> {
+        let LoraParams { spreading_factor, bandwidth_hz, coding_rate, preamble_len, explicit_header, crc } =
+            config.params;
+        let frf = ((config.frequency_hz as u64) << 19) / FXOSC_HZ;
+        self.write_burst(REG_FRF_MSB, &[(frf >> 16) as u8, (frf >> 8) as u8, frf as u8])?;
+
+        let bw = BANDWIDTHS_HZ.iter().position(|&hz| hz >= bandwidth_hz).unwrap_or(BANDWIDTHS_HZ.len() - 1) as u8;
+        let cr = coding_rate.clamp(5, 8) - 4;
+        self.write(REG_MODEM_CONFIG_1, bw << 4 | cr << 1 | !explicit_header as u8)?;
+        let sf = spreading_factor.clamp(6, 12);
+        self.write(REG_MODEM_CONFIG_2, sf << 4 | (crc as u8) << 2)?;
+        let low_data_rate = sf >= 11 && bandwidth_hz <= 125_000;
+        // Automatic gain control on, and low data rate optimization where symbols exceed 16 ms
+        self.write(REG_MODEM_CONFIG_3, (low_data_rate as u8) << 3 | 0x04)?;
+        self.write_burst(REG_PREAMBLE_MSB, &preamble_len.to_be_bytes())?;
+        let (optimize, threshold) = if sf == 6 { (0xC5, 0x0C) } else { (0xC3, 0x0A) };
+        self.write(REG_DETECT_OPTIMIZE, optimize)?;
+        self.write(REG_DETECTION_THRESHOLD, threshold)?;
+        self.write(REG_SYNC_WORD, config.sync_word)?;
+        self.config = config;
+        self.set_power(config.tx_power_dbm)
+    }
+
+    /// Runs channel activity detection, returning whether a LoRa preamble was heard
+    pub fn channel_busy(&mut self) -> Result<bool, Sx127xError<SPI::Error>> {
+        self.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_STANDBY)?;
+        self.write(REG_IRQ_FLAGS, 0xFF)?;
+        self.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_CAD)?;
+        let flags = self.wait_for(IRQ_CAD_DONE, CAD_TIMEOUT_MS)?;
+        self.write(REG_IRQ_FLAGS, 0xFF)?;
+        Ok(flags & IRQ_CAD_DETECTED != 0)
+    }
+
+    pub fn release(self) -> (SPI, D) {
+        (self.spi, self.delay)
+    }
+
+    fn set_power(&mut self, dbm: i8) -> Result<(), Sx127xError<SPI::Error>> {
+        let dbm = dbm.clamp(2, 20);
+        // Above 17 dBm the high-power DAC adds 3 dB on top of the highest PA setting
+        let (pa_dac, output) = if dbm > 17 { (0x87, 15) } else { (0x84, dbm - 2) };
+        self.write(REG_PA_DAC, pa_dac)?;
+        self.write(REG_PA_CONFIG, 0x80 | 0x70 | output as u8)?;
+        self.config.tx_power_dbm = dbm;
+        Ok(())
+    }
+
+    /// Polls the IRQ flags every millisecond until `flag` is set
+    fn wait_for(&mut self, flag: u8, timeout_ms: u32) -> Result<u8, Sx127xError<SPI::Error>> {
+        for _ in 0..=timeout_ms {
+            let flags = self.read(REG_IRQ_FLAGS)?;
+            if flags & flag != 0 {
+                return Ok(flags);
+            }
+            self.delay.delay_ms(1);
+        }
+        Err(Sx127xError::Timeout)
+    }
+
+    fn read(&mut self, register: u8) -> Result<u8, Sx127xError<SPI::Error>> {
+        let mut value = [0];
+        self.read_burst(register, &mut value)?;
+        Ok(value[0])
+    }
+
+    fn read_burst(&mut self, register: u8, buf: &mut [u8]) -> Result<(), Sx127xError<SPI::Error>> {
+        let mut operations = [Operation::Write(&[register & 0x7F]), Operation::Read(buf)];
+        self.spi.transaction(&mut operations).map_err(Sx127xError::Spi)
+    }
+
+    fn write(&mut self, register: u8, value: u8) -> Result<(), Sx127xError<SPI::Error>> {
+        self.write_burst(register, &[value])
+    }
+
+    fn write_burst(&mut self, register: u8, data: &[u8]) -> Result<(), Sx127xError<SPI::Error>> {
+        let mut operations = [Operation::Write(&[register | 0x80]), Operation::Write(data)];
+        self.spi.transaction(&mut operations).map_err(Sx127xError::Spi)
+    }
+}
+
+impl<SPI: SpiDevice, D: DelayNs> Radio for Sx127xRadio<SPI, D> {
+    type Error = Sx127xError<SPI::Error>;
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        let frame = &frame[..frame.len().min(MAX_FRAME_LEN)];
+        if self.config.lbt_attempts > 0 {
+            let mut attempts = 0;
+            while self.channel_busy()? {
+                attempts += 1;
+                if attempts >= self.config.lbt_attempts {
+                    self.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_RX_CONTINUOUS)?;
+                    return Err(Sx127xError::ChannelBusy);
+                }
+                let backoff_ms = self.rng.delay_ms(self.config.lbt_backoff_ms);
+                self.delay.delay_ms(backoff_ms);
+            }
+        }
+        self.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_STANDBY)?;
+        self.write(REG_FIFO_ADDR_PTR, 0)?;
+        self.write_burst(REG_FIFO, frame)?;
+        self.write(REG_PAYLOAD_LENGTH, frame.len() as u8)?;
+        self.write(REG_IRQ_FLAGS, 0xFF)?;
+        self.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_TX)?;
+        // Twice the computed airtime covers crystal tolerance and the PA ramp
+        let result = self.wait_for(IRQ_TX_DONE, self.config.params.airtime_ms(frame.len()) * 2 + 10);
+        self.write(REG_IRQ_FLAGS, 0xFF)?;
+        self.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_RX_CONTINUOUS)?;
+        result.map(|_| ())
+    }
+
+    fn receive(&mut self, buf: &mut [u8]) -> Result<Option<(usize, LinkQuality)>, Self::Error> {
+        let flags = self.read(REG_IRQ_FLAGS)?;
+        if flags & IRQ_RX_DONE == 0 {
+            return Ok(None);
+        }
+        self.write(REG_IRQ_FLAGS, 0xFF)?;
+        if flags & IRQ_PAYLOAD_CRC_ERROR != 0 {
+            return Ok(None);
+        }
+        let len = (self.read(REG_RX_NB_BYTES)? as usize).min(buf.len());
+        let start = self.read(REG_FIFO_RX_CURRENT_ADDR)?;
+        self.write(REG_FIFO_ADDR_PTR, start)?;
+        self.read_burst(REG_FIFO, &mut buf[..len])?;
+
+        let snr_db = self.read(REG_PKT_SNR_VALUE)? as i8 as f32 / 4.0;
+        // High-frequency port offset; below the noise floor the packet RSSI understates the signal by the SNR
+        let mut rssi_dbm = -157 + self.read(REG_PKT_RSSI_VALUE)? as i16;
+        if snr_db < 0.0 {
+            rssi_dbm += snr_db as i16;
+        }
+        Ok(Some((len, LinkQuality { rssi_dbm, snr_db })))
+    }
+
+    /// Runs CAD and returns to receive; set `lbt_attempts` to 0 when the mesh's `Csma` does the backoff
+    fn channel_busy(&mut self) -> Result<Option<bool>, Self::Error> {
+        let busy = Sx127xRadio::channel_busy(self)?;
+        self.write(REG_OP_MODE, LONG_RANGE_MODE | MODE_RX_CONTINUOUS)?;
+        Ok(Some(busy))
+    }
+
+    fn set_tx_power_dbm(&mut self, dbm: i8) -> Result<(), Self::Error> {
+        self.set_power(dbm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_hal::spi::ErrorType;
+
+    use super::*;
+
+    /// Register file of an SX1276 that completes TX and CAD instantly
+    struct FakeChip {
+        registers: [u8; 128],
+        fifo: [u8; 256],
+        /// CAD results to report, `true` for a busy channel
+        cad: std::vec::Vec<bool>,
+        transmitted: std::vec::Vec<std::vec::Vec<u8>>,
+    }
+
+    impl FakeChip {
+        fn new() -> Self {
+            let mut registers = [0; 128];
+            registers[REG_VERSION as usize] = VERSION;
+            Self { registers, fifo: [0; 256], cad: std::vec::Vec::new(), transmitted: std::vec::Vec::new() }
+        }
+
+        fn poke(&mut self, register: u8, value: u8) {
+            match register {
+                REG_FIFO => {
+                    let pointer = self.registers[REG_FIFO_ADDR_PTR as usize];
+                    self.fifo[pointer as usize] = value;
+                    self.registers[REG_FIFO_ADDR_PTR as usize] = pointer.wrapping_add(1);
+                }
+                // Writing ones clears the flags
+                REG_IRQ_FLAGS => self.registers[REG_IRQ_FLAGS as usize] &= !value,
+                REG_OP_MODE => {
+                    self.registers[REG_OP_MODE as usize] = value;
+                    match value & 0x07 {
+                        MODE_TX => {
+                            let len = self.registers[REG_PAYLOAD_LENGTH as usize] as usize;
+                            self.transmitted.push(self.fifo[..len].to_vec());
+                            self.registers[REG_IRQ_FLAGS as usize] |= IRQ_TX_DONE;
+                        }
+                        MODE_CAD => {
+                            let busy = !self.cad.is_empty() && self.cad.remove(0);
+                            self.registers[REG_IRQ_FLAGS as usize] |= IRQ_CAD_DONE | busy as u8;
+                        }
+                        _ => {}
+                    }
+                }
+                _ => self.registers[register as usize] = value,
+            }
+        }
+
+        fn peek(&mut self, register: u8) -> u8 {
+            if register != REG_FIFO {
+                return self.registers[register as usize];
+            }
+            let pointer = self.registers[REG_FIFO_ADDR_PTR as usize];
+            self.registers[REG_FIFO_ADDR_PTR as usize] = pointer.wrapping_add(1);
+            self.fifo[pointer as usize]
+        }
+
+        /// Puts a frame in the FIFO as if it had just been received
+        fn receive(&mut self, frame: &[u8], snr: i8, rssi: u8) {
+            self.fifo[0x40..0x40 + frame.len()].copy_from_slice(frame);
+            self.registers[REG_FIFO_RX_CURRENT_ADDR as usize] = 0x40;
+            self.registers[REG_RX_NB_BYTES as usize] = frame.len() as u8;
+            self.registers[REG_PKT_SNR_VALUE as usize] = snr as u8;
+            self.registers[REG_PKT_RSSI_VALUE as usize] = rssi;
+            self.registers[REG_IRQ_FLAGS as usize] |= IRQ_RX_DONE;
+        }
+    }
+
+    impl ErrorType for &mut FakeChip {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for &mut FakeChip {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+            let [Operation::Write(&[address]), data] = operations else {
+                panic!("expected an address byte followed by data");
+            };
+            // Bursts advance the register address, except in the FIFO which has its own pointer
+            let step = (address & 0x7F != REG_FIFO) as u8;
+            match data {
+                Operation::Write(bytes) => {
+                    for (offset, byte) in bytes.iter().enumerate() {
+                        self.poke((address & 0x7F) + step * offset as u8, *byte);
+                    }
+                }
+                Operation::Read(bytes) => {
+                    for (offset, byte) in bytes.iter_mut().enumerate() {
+                        *byte = self.peek(address + step * offset as u8);
+                    }
+                }
+                _ => panic!("unexpected operation"),
+            }
+            Ok(())
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _: u32) {}
+    }
+
+    #[test]
+    fn test_configure_transmit_and_receive() {
+        let mut chip = FakeChip::new();
+        let config = Sx127xConfig { frequency_hz: 915_000_000, tx_power_dbm: 20, ..Sx127xConfig::default() };
+        let mut radio = Sx127xRadio::new(&mut chip, NoDelay, config).unwrap();
+
+        // The channel is busy for two checks, then clear
+        radio.spi.cad = std::vec![true, true];
+        radio.transmit(b"hello").unwrap();
+        radio.spi.receive(b"pong", -8, 60);
+        let mut buf = [0u8; 16];
+        let (len, quality) = radio.receive(&mut buf).unwrap().unwrap();
+        assert_eq!((&buf[..len], quality.rssi_dbm, quality.snr_db), (&b"pong"[..], -99, -2.0));
+        assert_eq!(radio.receive(&mut buf).unwrap(), None);
+
+        radio.spi.cad = std::vec![true; 5];
+        assert_eq!(radio.transmit(b"again"), Err(Sx127xError::ChannelBusy));
+        let (chip, _) = radio.release();
+        assert_eq!(chip.transmitted, [b"hello".to_vec()]);
+        // 915 MHz, SF9/125 kHz 4/5 with CRC, PA_BOOST at +20 dBm
+        assert_eq!(chip.registers[REG_FRF_MSB as usize..][..3], [0xE4, 0xC0, 0x00]);
+        assert_eq!(chip.registers[REG_MODEM_CONFIG_1 as usize..][..2], [0x72, 0x94]);
+        assert_eq!((chip.registers[REG_PA_CONFIG as usize], chip.registers[REG_PA_DAC as usize]), (0xFF, 0x87));
+        assert_eq!(chip.registers[REG_OP_MODE as usize], LONG_RANGE_MODE | MODE_RX_CONTINUOUS);
+    }
+}
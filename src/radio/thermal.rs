@@ -0,0 +1,115 @@
+use super::Radio;
+use crate::protocol::thermal::RadioThermal;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DeratingConfig {
+    pub max_power_dbm: i8,
+    /// Power is never derated below this, so the link survives even on a hot pad
+    pub min_power_dbm: i8,
+    /// Amplifier temperature at which derating starts
+    pub derate_above_c: f32,
+    /// Power is reduced by `step_db` for every `step_c` above `derate_above_c`
+    pub step_c: f32,
+    pub step_db: i8,
+    /// Temperature must fall this far below a step before power is restored
+    pub hysteresis_c: f32,
+}
+
+impl Default for DeratingConfig {
+    fn default() -> Self {
+        // 1 W amplifier, rated to 85 C
+        Self { max_power_dbm: 30, min_power_dbm: 17, derate_above_c: 70.0, step_c: 5.0, step_db: 3, hysteresis_c: 3.0 }
+    }
+}
+
+/// ThermalMonitor reads the PA temperature and steps TX power down as it heats up
+#[derive(Debug, Clone, Copy)]
+pub struct ThermalMonitor {
+    uid: u8,
+    config: DeratingConfig,
+    /// Number of derating steps currently applied
+    steps: u8,
+    last_temperature_c: Option<f32>,
+}
+
+impl ThermalMonitor {
+    pub fn new(uid: u8, config: DeratingConfig) -> Self {
+        Self { uid, config, steps: 0, last_temperature_c: None }
+    }
+
+    pub fn tx_power_dbm(&self) -> i8 {
+        let derated = self.config.max_power_dbm as i16 - self.steps as i16 * self.config.step_db as i16;
+        derated.max(self.config.min_power_dbm as i16) as i8
+    }
+
+    /// Latest status for the periodic report, `None` until a temperature has been read
+    pub fn status(&self) -> Option<RadioThermal> {
+        let pa_temperature_c = self.last_temperature_c?;
+        Some(RadioThermal {
+            uid: self.uid,
+            pa_temperature_c,
+            tx_power_dbm: self.tx_power_dbm(),
+            derated: self.steps > 0,
+        })
+    }
+
+    /// Reads the PA temperature and applies any power change, returning a status to send when power changed
+    pub fn poll<R: Radio>(&mut self, radio: &mut R) -> Result<Option<RadioThermal>, R::Error> {
+        let Some(temperature_c) = radio.pa_temperature_c()? else {
+            return Ok(None);
+        };
+        self.last_temperature_c = Some(temperature_c);
+        let before = self.tx_power_dbm();
+        self.steps = self.target_steps(temperature_c);
+        let after = self.tx_power_dbm();
+        if after == before {
+            return Ok(None);
+        }
+        radio.set_tx_power_dbm(after)?;
+        #[cfg(feature = "tracing")]
+        tracing::warn!(temperature_c, tx_power_dbm = after, "radio power derating changed");
+        Ok(self.status())
+    }
+
+    fn target_steps(&self, temperature_c: f32) -> u8 {
+        let over = (temperature_c - self.config.derate_above_c) / self.config.step_c.max(0.1);
+        let heating = if over > 0.0 { libm::floorf(over) as u8 + 1 } else { 0 };
+        if heating >= self.steps {
+            return heating;
+        }
+        // Cooling: only drop a step once below its threshold by the hysteresis margin
+        let cooled = over + self.config.hysteresis_c / self.config.step_c.max(0.1);
+        let cooling = if cooled > 0.0 { libm::floorf(cooled) as u8 + 1 } else { 0 };
+        cooling.min(self.steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::mock::MockRadio;
+
+    #[test]
+    fn test_power_derates_when_hot_and_recovers_with_hysteresis() {
+        let mut radio = MockRadio::default();
+        let mut monitor = ThermalMonitor::new(2, DeratingConfig::default());
+        radio.pa_temperature_c = Some(45.0);
+        assert_eq!(monitor.poll(&mut radio).unwrap(), None);
+        assert_eq!(monitor.status().unwrap().tx_power_dbm, 30);
+
+        radio.pa_temperature_c = Some(76.0);
+        let event = monitor.poll(&mut radio).unwrap().unwrap();
+        assert_eq!((event.tx_power_dbm, event.derated), (24, true));
+        assert_eq!(radio.tx_power_dbm, Some(24));
+
+        // Inside the hysteresis band power stays derated
+        radio.pa_temperature_c = Some(73.0);
+        assert_eq!(monitor.poll(&mut radio).unwrap(), None);
+        radio.pa_temperature_c = Some(60.0);
+        let event = monitor.poll(&mut radio).unwrap().unwrap();
+        assert_eq!((event.tx_power_dbm, event.derated), (30, false));
+
+        radio.pa_temperature_c = Some(150.0);
+        assert_eq!(monitor.poll(&mut radio).unwrap().unwrap().tx_power_dbm, 17);
+    }
+}
@@ -0,0 +1,98 @@
+//! UDP transport standing in for the radio, to run the mesh across processes or machines without hardware
+//!
+//! Every transmitted frame goes to each peer as one datagram, like a broadcast on a shared channel.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::vec::Vec;
+
+use super::Radio;
+use crate::protocol::ping::LinkQuality;
+
+/// UdpRadio sends frames to a fixed set of peers and receives from any of them without blocking
+#[derive(Debug)]
+pub struct UdpRadio {
+    socket: UdpSocket,
+    peers: Vec<SocketAddr>,
+    /// Link quality reported for every received frame, UDP has no signal to measure
+    pub quality: LinkQuality,
+}
+
+impl UdpRadio {
+    /// Binds `local` and transmits to `peers`
+    pub fn bind(local: impl ToSocketAddrs, peers: &[SocketAddr]) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peers: peers.to_vec(), quality: LinkQuality { rssi_dbm: 0, snr_db: 0.0 } })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Adds a peer, e.g. one bound after this radio to an OS-assigned port
+    pub fn add_peer(&mut self, peer: SocketAddr) {
+        if !self.peers.contains(&peer) {
+            self.peers.push(peer);
+        }
+    }
+}
+
+impl Radio for UdpRadio {
+    type Error = io::Error;
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), Self::Error> {
+        for peer in &self.peers {
+            self.socket.send_to(frame, peer)?;
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self, buf: &mut [u8]) -> Result<Option<(usize, LinkQuality)>, Self::Error> {
+        loop {
+            match self.socket.recv_from(buf) {
+                // Datagrams from outside the mesh are ignored
+                Ok((_, from)) if !self.peers.contains(&from) => continue,
+                Ok((len, _)) => return Ok(Some((len, self.quality))),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mesh"))]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::node::runtime::{MeshNode, NodeConfig, NodeEvent};
+    use crate::persistence::MemoryStore;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::packet::Packet;
+
+    #[test]
+    fn test_reliable_send_over_udp() {
+        let clock = MockClock::new(0);
+        let mut a = UdpRadio::bind("127.0.0.1:0", &[]).unwrap();
+        let b = UdpRadio::bind("127.0.0.1:0", &[a.local_addr().unwrap()]).unwrap();
+        a.add_peer(b.local_addr().unwrap());
+        let mut a = MeshNode::new(1, a, &clock, MemoryStore::<2, 16>::new(), NodeConfig::default()).unwrap();
+        let mut b = MeshNode::new(2, b, &clock, MemoryStore::<2, 16>::new(), NodeConfig::default()).unwrap();
+
+        let sequence = a.send(2, Packet::Event(FlightEvent::Launch), true).unwrap();
+        let (mut received, mut delivered) = (None, None);
+        for _ in 0..1_000 {
+            if let Some(NodeEvent::Delivered { sequence, .. }) = a.poll().unwrap() {
+                delivered = Some(sequence);
+            }
+            if let Some(NodeEvent::Received { packet, .. }) = b.poll().unwrap() {
+                received = Some(packet);
+            }
+            if delivered.is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!((received, delivered), (Some(Packet::Event(FlightEvent::Launch)), Some(sequence)));
+    }
+}
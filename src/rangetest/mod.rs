@@ -0,0 +1,177 @@
+use core::fmt;
+
+use heapless::Vec;
+
+use crate::geo;
+use crate::protocol::ping::LinkQuality;
+use crate::protocol::rangetest::RangeBeacon;
+
+/// Maximum number of distance bins in a range test report
+pub const MAX_BINS: usize = 64;
+
+/// Mobile side: emits a beacon every `interval_ms`
+#[derive(Debug, Clone, Copy)]
+pub struct Beaconer {
+    uid: u8,
+    interval_ms: u64,
+    tx_power_dbm: i8,
+    sequence: u32,
+    next_at_ms: u64,
+}
+
+impl Beaconer {
+    pub fn new(uid: u8, interval_ms: u64, tx_power_dbm: i8) -> Self {
+        Self { uid, interval_ms, tx_power_dbm, sequence: 0, next_at_ms: 0 }
+    }
+
+    /// Returns a beacon to transmit if the interval has elapsed
+    pub fn poll(&mut self, now_ms: u64, latitude: f64, longitude: f64, altitude: f32) -> Option<RangeBeacon> {
+        if now_ms < self.next_at_ms {
+            return None;
+        }
+        self.next_at_ms = now_ms + self.interval_ms;
+        let beacon = RangeBeacon {
+            uid: self.uid,
+            sequence: self.sequence,
+            latitude,
+            longitude,
+            altitude,
+            tx_power_dbm: self.tx_power_dbm,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+        Some(beacon)
+    }
+}
+
+/// Link statistics for beacons sent from one distance band
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RangeBin {
+    /// Lower edge of the band in meters
+    pub distance_m: u32,
+    pub received: u32,
+    pub lost: u32,
+    rssi_sum: i64,
+    snr_sum: f64,
+}
+
+impl RangeBin {
+    pub fn mean_rssi_dbm(&self) -> Option<f32> {
+        (self.received > 0).then(|| self.rssi_sum as f32 / self.received as f32)
+    }
+
+    pub fn mean_snr_db(&self) -> Option<f32> {
+        (self.received > 0).then(|| (self.snr_sum / self.received as f64) as f32)
+    }
+
+    pub fn loss_ratio(&self) -> f32 {
+        let total = self.received + self.lost;
+        if total == 0 {
+            0.0
+        } else {
+            self.lost as f32 / total as f32
+        }
+    }
+}
+
+/// Ground side: logs distance against RSSI, SNR and loss into distance bins
+#[derive(Debug, Clone)]
+pub struct RangeTestLog {
+    station_latitude: f64,
+    station_longitude: f64,
+    bin_m: u32,
+    last_sequence: Option<u32>,
+    bins: Vec<RangeBin, MAX_BINS>,
+}
+
+impl RangeTestLog {
+    pub fn new(station_latitude: f64, station_longitude: f64, bin_m: u32) -> Self {
+        Self { station_latitude, station_longitude, bin_m: bin_m.max(1), last_sequence: None, bins: Vec::new() }
+    }
+
+    /// Records a received beacon, counting any beacons missed since the previous one as lost
+    ///
+    /// Losses are attributed to the distance of the beacon that revealed them.
+    pub fn record(&mut self, beacon: &RangeBeacon, heard: LinkQuality) -> f64 {
+        let distance =
+            geo::distance_m(self.station_latitude, self.station_longitude, beacon.latitude, beacon.longitude);
+        let lost = match self.last_sequence {
+            Some(last) if beacon.sequence > last => beacon.sequence - last - 1,
+            _ => 0,
+        };
+        self.last_sequence = Some(beacon.sequence);
+
+        let lower = (distance as u32 / self.bin_m) * self.bin_m;
+        let index = match self.bins.iter().position(|b| b.distance_m == lower) {
+            Some(index) => index,
+            None => {
+                if self.bins.push(RangeBin { distance_m: lower, ..Default::default() }).is_err() {
+                    return distance;
+                }
+                self.bins.len() - 1
+            }
+        };
+        let bin = &mut self.bins[index];
+        bin.received += 1;
+        bin.lost += lost;
+        bin.rssi_sum += heard.rssi_dbm as i64;
+        bin.snr_sum += heard.snr_db as f64;
+        distance
+    }
+
+    /// Bins sorted by distance
+    pub fn report(&self) -> Vec<RangeBin, MAX_BINS> {
+        let mut bins = self.bins.clone();
+        bins.sort_unstable_by_key(|b| b.distance_m);
+        bins
+    }
+
+    /// Writes the report as CSV: distance_m,received,lost,loss_ratio,mean_rssi_dbm,mean_snr_db
+    pub fn write_csv<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        writeln!(out, "distance_m,received,lost,loss_ratio,mean_rssi_dbm,mean_snr_db")?;
+        for bin in self.report() {
+            writeln!(
+                out,
+                "{},{},{},{:.3},{:.1},{:.1}",
+                bin.distance_m,
+                bin.received,
+                bin.lost,
+                bin.loss_ratio(),
+                bin.mean_rssi_dbm().unwrap_or(f32::NAN),
+                bin.mean_snr_db().unwrap_or(f32::NAN)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beacons_binned_by_distance_with_losses() {
+        let mut beaconer = Beaconer::new(7, 1_000, 20);
+        let mut log = RangeTestLog::new(37.0, -80.0, 500);
+
+        // ~111 m per 0.001 degree of latitude
+        let b0 = beaconer.poll(0, 37.001, -80.0, 600.0).unwrap();
+        assert!(beaconer.poll(500, 37.001, -80.0, 600.0).is_none());
+        let _lost = beaconer.poll(1_000, 37.002, -80.0, 600.0).unwrap();
+        let b2 = beaconer.poll(2_000, 37.006, -80.0, 600.0).unwrap();
+
+        log.record(&b0, LinkQuality { rssi_dbm: -80, snr_db: 10.0 });
+        log.record(&b2, LinkQuality { rssi_dbm: -110, snr_db: -5.0 });
+
+        let report = log.report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].distance_m, 0);
+        assert_eq!(report[1].distance_m, 500);
+        assert_eq!(report[1].lost, 1);
+        assert_eq!(report[1].loss_ratio(), 0.5);
+        assert_eq!(report[1].mean_rssi_dbm(), Some(-110.0));
+
+        let mut csv = std::string::String::new();
+        log.write_csv(&mut csv).unwrap();
+        assert!(csv.contains("500,1,1,0.500,-110.0,-5.0"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Post-landing recovery report, repeated until the ground station confirms it has the vehicle's position
+
+use heapless::Deque;
+use libm::{acosf, sqrtf};
+
+use crate::flight::state::FlightPhase;
+use crate::protocol::recovery::RecoveryReport;
+use crate::protocol::GPS;
+
+/// Number of recent report sequence numbers an acknowledgement is matched against
+const OUTSTANDING: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeaconConfig {
+    /// How long the vehicle must stay Landed before the first report, so it is not sent mid-bounce
+    pub landed_hold_ms: u64,
+    pub interval_ms: u64,
+}
+
+impl Default for BeaconConfig {
+    fn default() -> Self {
+        Self { landed_hold_ms: 10_000, interval_ms: 15_000 }
+    }
+}
+
+/// RecoveryBeacon decides when a landed vehicle sends its RecoveryReport
+///
+/// Send each report reliably with `MeshNode::send` and pass its sequence number to `sent`. Every
+/// `NodeEvent::Delivered` goes to `on_delivered`; once one matches a report the beacon stops. A report
+/// whose retransmissions run out is simply followed by the next one.
+#[derive(Debug, Clone)]
+pub struct RecoveryBeacon {
+    config: BeaconConfig,
+    landed_since_ms: Option<u64>,
+    next_at_ms: u64,
+    outstanding: Deque<u16, OUTSTANDING>,
+    acknowledged: bool,
+}
+
+impl RecoveryBeacon {
+    pub fn new(config: BeaconConfig) -> Self {
+        Self { config, landed_since_ms: None, next_at_ms: 0, outstanding: Deque::new(), acknowledged: false }
+    }
+
+    /// Whether a report is due, called from the main loop with the current flight phase
+    pub fn poll(&mut self, phase: FlightPhase, now_ms: u64) -> bool {
+        if phase != FlightPhase::Landed {
+            self.landed_since_ms = None;
+            return false;
+        }
+        let since = *self.landed_since_ms.get_or_insert(now_ms);
+        !self.acknowledged && now_ms.saturating_sub(since) >= self.config.landed_hold_ms && now_ms >= self.next_at_ms
+    }
+
+    /// Records the sequence number the report went out with
+    pub fn sent(&mut self, sequence: u16, now_ms: u64) {
+        if self.outstanding.is_full() {
+            self.outstanding.pop_front();
+        }
+        let _ = self.outstanding.push_back(sequence);
+        self.next_at_ms = now_ms + self.config.interval_ms;
+    }
+
+    /// Returns true if the delivery acknowledged a report, which stops the beacon
+    pub fn on_delivered(&mut self, sequence: u16) -> bool {
+        if !self.outstanding.iter().any(|&sent| sent == sequence) {
+            return false;
+        }
+        self.outstanding.clear();
+        self.acknowledged = true;
+        true
+    }
+
+    pub fn acknowledged(&self) -> bool {
+        self.acknowledged
+    }
+}
+
+/// Builds the report from the last GPS fix and an accelerometer reading taken at rest
+///
+/// The accelerometer's z axis is taken to lie along the airframe, as the flight computers are mounted.
+pub fn report(uid: u8, gps: &GPS, battery_voltage: f32, max_altitude_m: f32, accel: [f32; 3]) -> RecoveryReport {
+    RecoveryReport {
+        uid,
+        latitude: gps.latitude,
+        longitude: gps.longitude,
+        battery_voltage,
+        max_altitude_m,
+        tilt_deg: tilt_deg(accel),
+    }
+}
+
+/// Angle between the z axis and the gravity vector measured at rest, 0 when the measurement is all zero
+pub fn tilt_deg(accel: [f32; 3]) -> f32 {
+    let [x, y, z] = accel;
+    let magnitude = sqrtf(x * x + y * y + z * z);
+    if magnitude == 0.0 {
+        return 0.0;
+    }
+    // The airframe may have come down nose or tail first, either way up is upright
+    acosf((z.abs() / magnitude).min(1.0)).to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_after_hold_until_acknowledged() {
+        let mut beacon = RecoveryBeacon::new(BeaconConfig { landed_hold_ms: 1_000, interval_ms: 5_000 });
+        assert!(!beacon.poll(FlightPhase::Descent, 0));
+        assert!(!beacon.poll(FlightPhase::Landed, 100));
+        assert!(beacon.poll(FlightPhase::Landed, 1_100));
+        beacon.sent(7, 1_100);
+        assert!(!beacon.poll(FlightPhase::Landed, 2_000));
+        assert!(beacon.poll(FlightPhase::Landed, 6_100));
+        beacon.sent(8, 6_100);
+
+        // Deliveries of other frames don't stop it, an ack for an earlier report does
+        assert!(!beacon.on_delivered(3));
+        assert!(beacon.on_delivered(7));
+        assert!(beacon.acknowledged());
+        assert!(!beacon.poll(FlightPhase::Landed, 20_000));
+
+        assert!(tilt_deg([0.0, 0.0, -9.8]) < 0.1);
+        assert!((tilt_deg([9.8, 0.0, 0.0]) - 90.0).abs() < 0.1);
+        assert!((tilt_deg([0.0, 6.93, 6.93]) - 45.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,123 @@
+//! Deployment devices behind one interface, so pyro, burn-wire and CO2 recovery report the same telemetry
+
+pub mod beacon;
+
+use crate::protocol::recovery::{DeviceFault, DeviceState, DeviceStatus, Mechanism, RecoveryStatus, MAX_DEVICES};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeploymentError {
+    /// Fire was requested before the device was armed
+    NotArmed,
+    Fault(DeviceFault),
+}
+
+/// DeploymentDevice drives one recovery mechanism through safe, armed and fired
+///
+/// Drivers read the hardware in `status` rather than echoing the last command, so a wire that burnt
+/// through or a cartridge that leaked shows up in telemetry.
+pub trait DeploymentDevice {
+    fn mechanism(&self) -> Mechanism;
+    fn arm(&mut self) -> Result<(), DeploymentError>;
+    /// Returns the device to safe, refusing to fire until armed again
+    fn disarm(&mut self) -> Result<(), DeploymentError>;
+    fn fire(&mut self) -> Result<(), DeploymentError>;
+    /// Current state and, where the mechanism has a circuit, its continuity
+    fn status(&mut self) -> (DeviceState, Option<bool>);
+}
+
+/// Reads every device into a RecoveryStatus, each device's channel being its index in `devices`
+///
+/// Devices past `MAX_DEVICES` are left out.
+pub fn report(uid: u8, devices: &mut [&mut dyn DeploymentDevice]) -> RecoveryStatus {
+    let mut status = RecoveryStatus { uid, devices: heapless::Vec::new() };
+    for (channel, device) in devices.iter_mut().enumerate().take(MAX_DEVICES) {
+        let (state, continuity) = device.status();
+        let mechanism = device.mechanism();
+        let _ = status.devices.push(DeviceStatus { channel: channel as u8, mechanism, state, continuity });
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Burn wire whose continuity is lost once it has burnt through
+    struct BurnWire {
+        state: DeviceState,
+    }
+
+    impl DeploymentDevice for BurnWire {
+        fn mechanism(&self) -> Mechanism {
+            Mechanism::BurnWire
+        }
+
+        fn arm(&mut self) -> Result<(), DeploymentError> {
+            self.state = DeviceState::Armed;
+            Ok(())
+        }
+
+        fn disarm(&mut self) -> Result<(), DeploymentError> {
+            self.state = DeviceState::Safe;
+            Ok(())
+        }
+
+        fn fire(&mut self) -> Result<(), DeploymentError> {
+            if self.state != DeviceState::Armed {
+                return Err(DeploymentError::NotArmed);
+            }
+            self.state = DeviceState::Fired;
+            Ok(())
+        }
+
+        fn status(&mut self) -> (DeviceState, Option<bool>) {
+            (self.state, Some(self.state != DeviceState::Fired))
+        }
+    }
+
+    /// CO2 cartridge with a pressure switch and no circuit to test
+    struct Co2 {
+        pressurized: bool,
+    }
+
+    impl DeploymentDevice for Co2 {
+        fn mechanism(&self) -> Mechanism {
+            Mechanism::Co2
+        }
+
+        fn arm(&mut self) -> Result<(), DeploymentError> {
+            Err(DeploymentError::Fault(DeviceFault::LowPressure))
+        }
+
+        fn disarm(&mut self) -> Result<(), DeploymentError> {
+            Ok(())
+        }
+
+        fn fire(&mut self) -> Result<(), DeploymentError> {
+            Err(DeploymentError::NotArmed)
+        }
+
+        fn status(&mut self) -> (DeviceState, Option<bool>) {
+            let state = if self.pressurized { DeviceState::Safe } else { DeviceState::Fault(DeviceFault::LowPressure) };
+            (state, None)
+        }
+    }
+
+    #[test]
+    fn test_mixed_mechanisms_report_one_status() {
+        let mut drogue = BurnWire { state: DeviceState::Safe };
+        let mut main = Co2 { pressurized: false };
+        assert_eq!(drogue.fire(), Err(DeploymentError::NotArmed));
+        drogue.arm().unwrap();
+
+        let status = report(7, &mut [&mut drogue, &mut main]);
+        assert_eq!(status.devices[0].state, DeviceState::Armed);
+        assert_eq!((status.devices[1].mechanism, status.devices[1].continuity), (Mechanism::Co2, None));
+        assert_eq!(status.continuity(), 0b01);
+        assert!(status.has_fault());
+
+        drogue.fire().unwrap();
+        let status = report(7, &mut [&mut drogue]);
+        assert_eq!((status.devices[0].state, status.continuity()), (DeviceState::Fired, 0));
+    }
+}
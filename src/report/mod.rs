@@ -0,0 +1,214 @@
+//! Post-flight reports built from derived flight logs
+
+pub mod subtitles;
+pub mod thrust;
+
+use std::fmt::Write as _;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::flight::backfill::DerivedRecord;
+use crate::protocol::events::FlightEvent;
+
+/// One flight's derived log, as produced by `flight::backfill`
+#[derive(Debug, Clone, Copy)]
+pub struct FlightLog<'a> {
+    pub name: &'a str,
+    pub records: &'a [DerivedRecord],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompareConfig {
+    /// Width of each output sample
+    pub bin_ms: u64,
+    /// Window kept before and after launch
+    pub before_launch_ms: u64,
+    pub after_launch_ms: u64,
+    /// Telemetry period the node was configured for, used to compute loss
+    pub nominal_period_ms: u64,
+}
+
+impl Default for CompareConfig {
+    fn default() -> Self {
+        Self { bin_ms: 500, before_launch_ms: 5_000, after_launch_ms: 120_000, nominal_period_ms: 100 }
+    }
+}
+
+/// Series for one flight, indexed like `Comparison::t_ms`; `None` where the bin received nothing
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightSeries {
+    pub name: String,
+    pub altitude_m: Vec<Option<f32>>,
+    pub vertical_velocity_mps: Vec<Option<f32>>,
+    /// Fraction of expected frames missing in each bin
+    pub loss: Vec<f32>,
+}
+
+/// Overlaid series for several flights aligned on launch detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Bin start times relative to launch
+    pub t_ms: Vec<i64>,
+    pub flights: Vec<FlightSeries>,
+    /// Flights that were skipped because no launch was detected in them
+    pub unaligned: Vec<String>,
+}
+
+/// Aligns flights by their launch event and resamples them onto a common time base
+pub fn compare(flights: &[FlightLog], config: &CompareConfig) -> Comparison {
+    let bin = config.bin_ms.max(1) as i64;
+    let start = -(config.before_launch_ms as i64);
+    let bins = ((config.before_launch_ms + config.after_launch_ms) as i64 / bin) as usize;
+    let mut comparison = Comparison {
+        t_ms: (0..bins).map(|i| start + i as i64 * bin).collect(),
+        flights: Vec::new(),
+        unaligned: Vec::new(),
+    };
+
+    for flight in flights {
+        let launch = flight.records.iter().find(|r| r.event == Some(FlightEvent::Launch)).map(|r| r.at_ms as i64);
+        let Some(launch) = launch else {
+            comparison.unaligned.push(flight.name.into());
+            continue;
+        };
+        let mut sums = std::vec![(0.0f64, 0.0f64, 0u32); bins];
+        for record in flight.records {
+            let offset = record.at_ms as i64 - launch - start;
+            if offset < 0 || offset / bin >= bins as i64 {
+                continue;
+            }
+            let slot = &mut sums[(offset / bin) as usize];
+            slot.0 += record.altitude_m as f64;
+            slot.1 += record.vertical_velocity_mps as f64;
+            slot.2 += 1;
+        }
+        let expected = (bin as f32 / config.nominal_period_ms.max(1) as f32).max(1.0);
+        comparison.flights.push(FlightSeries {
+            name: flight.name.into(),
+            altitude_m: sums.iter().map(|&(alt, _, n)| (n > 0).then(|| (alt / n as f64) as f32)).collect(),
+            vertical_velocity_mps: sums.iter().map(|&(_, vel, n)| (n > 0).then(|| (vel / n as f64) as f32)).collect(),
+            loss: sums.iter().map(|&(_, _, n)| (1.0 - n as f32 / expected).max(0.0)).collect(),
+        });
+    }
+    comparison
+}
+
+impl Comparison {
+    /// Wide CSV with one row per bin and `<name>_altitude_m`, `<name>_velocity_mps`, `<name>_loss` columns
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("t_ms");
+        for flight in &self.flights {
+            let _ = write!(out, ",{0}_altitude_m,{0}_velocity_mps,{0}_loss", flight.name);
+        }
+        out.push('\n');
+        for (i, t) in self.t_ms.iter().enumerate() {
+            let _ = write!(out, "{t}");
+            for flight in &self.flights {
+                let _ = write!(
+                    out,
+                    ",{},{},{:.3}",
+                    optional(flight.altitude_m[i]),
+                    optional(flight.vertical_velocity_mps[i]),
+                    flight.loss[i]
+                );
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// JSON object with `t_ms` and a `flights` array of named series, missing samples as `null`
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"t_ms\":");
+        push_array(&mut out, self.t_ms.iter().map(|t| t.to_string()));
+        out.push_str(",\"flights\":[");
+        for (i, flight) in self.flights.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{{\"name\":\"{}\",\"altitude_m\":", escape(&flight.name));
+            push_array(&mut out, flight.altitude_m.iter().map(|v| json_number(*v)));
+            out.push_str(",\"vertical_velocity_mps\":");
+            push_array(&mut out, flight.vertical_velocity_mps.iter().map(|v| json_number(*v)));
+            out.push_str(",\"loss\":");
+            push_array(&mut out, flight.loss.iter().map(|v| json_number(Some(*v))));
+            out.push('}');
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+fn optional(value: Option<f32>) -> String {
+    value.map(|v| std::format!("{v:.2}")).unwrap_or_default()
+}
+
+fn json_number(value: Option<f32>) -> String {
+    match value {
+        Some(v) if v.is_finite() => std::format!("{v}"),
+        _ => "null".into(),
+    }
+}
+
+fn push_array(out: &mut String, items: impl Iterator<Item = String>) {
+    out.push('[');
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&item);
+    }
+    out.push(']');
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::state::FlightPhase;
+
+    fn flight(launch_ms: u64, climb: f32) -> Vec<DerivedRecord> {
+        (0..40)
+            .filter(|i| i % 4 != 3)
+            .map(|i| {
+                let at_ms = i * 250;
+                let t = (at_ms as f32 - launch_ms as f32) / 1000.0;
+                DerivedRecord {
+                    at_ms,
+                    altitude_m: (climb * t).max(0.0),
+                    vertical_velocity_mps: if t >= 0.0 { climb } else { 0.0 },
+                    dynamic_pressure_pa: 0.0,
+                    mach: 0.0,
+                    phase: FlightPhase::Boost,
+                    event: (at_ms == launch_ms).then_some(FlightEvent::Launch),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flights_aligned_on_launch() {
+        let early = flight(2_000, 100.0);
+        let late = flight(5_000, 120.0);
+        let flights = [
+            FlightLog { name: "2024", records: &early },
+            FlightLog { name: "2025", records: &late },
+            FlightLog { name: "scrub", records: &[] },
+        ];
+        let config = CompareConfig { bin_ms: 1_000, before_launch_ms: 1_000, after_launch_ms: 3_000, nominal_period_ms: 250 };
+        let comparison = compare(&flights, &config);
+
+        assert_eq!(comparison.t_ms, [-1_000, 0, 1_000, 2_000]);
+        assert_eq!(comparison.unaligned, ["scrub"]);
+        assert_eq!(comparison.flights[0].vertical_velocity_mps[1], Some(100.0));
+        assert_eq!(comparison.flights[1].vertical_velocity_mps[1], Some(120.0));
+        assert_eq!(comparison.flights[0].loss[1], 0.25);
+
+        let csv = comparison.to_csv();
+        assert!(csv.starts_with("t_ms,2024_altitude_m,2024_velocity_mps,2024_loss,2025_altitude_m"));
+        assert!(comparison.to_json().starts_with("{\"t_ms\":[-1000,0,1000,2000],\"flights\":[{\"name\":\"2024\""));
+    }
+}
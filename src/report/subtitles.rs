@@ -0,0 +1,144 @@
+//! Timed subtitle overlays (SRT and ASS) generated from a derived flight log
+
+use std::fmt::Write as _;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::flight::backfill::DerivedRecord;
+use crate::protocol::events::FlightEvent;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SubtitleConfig {
+    /// Log time at which the video's first frame was recorded
+    pub video_start_ms: u64,
+    /// Duration of each cue
+    pub cue_ms: u64,
+    /// How long a flight event stays on screen
+    pub event_hold_ms: u64,
+}
+
+impl Default for SubtitleConfig {
+    fn default() -> Self {
+        Self { video_start_ms: 0, cue_ms: 1_000, event_hold_ms: 3_000 }
+    }
+}
+
+/// One on-screen caption, times relative to the start of the video
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Builds one cue per `cue_ms` from the last record in each window, with recent events appended
+pub fn cues(records: &[DerivedRecord], config: &SubtitleConfig) -> Vec<Cue> {
+    let cue_ms = config.cue_ms.max(1);
+    let mut cues: Vec<Cue> = Vec::new();
+    let mut event: Option<(FlightEvent, u64)> = None;
+    for record in records {
+        let Some(video_ms) = record.at_ms.checked_sub(config.video_start_ms) else {
+            continue;
+        };
+        if let Some(e) = record.event {
+            event = Some((e, video_ms));
+        }
+        let start_ms = video_ms / cue_ms * cue_ms;
+        let mut text = std::format!("ALT {:.0} m  VEL {:.0} m/s", record.altitude_m, record.vertical_velocity_mps);
+        if let Some((e, _)) = event.filter(|(_, at)| start_ms < at + config.event_hold_ms) {
+            let _ = write!(text, "\n{}", describe(&e));
+        }
+        match cues.last_mut() {
+            Some(last) if last.start_ms == start_ms => last.text = text,
+            _ => cues.push(Cue { start_ms, end_ms: start_ms + cue_ms, text }),
+        }
+    }
+    cues
+}
+
+fn describe(event: &FlightEvent) -> String {
+    match event {
+        FlightEvent::Launch => "LIFTOFF".into(),
+        FlightEvent::Burnout => "BURNOUT".into(),
+        FlightEvent::Apogee { altitude_m } => std::format!("APOGEE {altitude_m:.0} m"),
+        FlightEvent::DrogueDeployed => "DROGUE".into(),
+        FlightEvent::MainDeployed => "MAIN".into(),
+        FlightEvent::Landed => "LANDED".into(),
+    }
+}
+
+/// SubRip: numbered cues with `HH:MM:SS,mmm --> HH:MM:SS,mmm` timings
+pub fn to_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let (start, end) = (timestamp(cue.start_ms, ','), timestamp(cue.end_ms, ','));
+        let _ = write!(out, "{}\n{start} --> {end}\n{}\n\n", i + 1, cue.text);
+    }
+    out
+}
+
+/// Advanced SubStation Alpha with a bottom-left telemetry style
+pub fn to_ass(cues: &[Cue]) -> String {
+    let mut out = String::from(
+        "[Script Info]\nScriptType: v4.00+\nPlayResX: 1920\nPlayResY: 1080\n\n\
+         [V4+ Styles]\nFormat: Name, Fontname, Fontsize, PrimaryColour, OutlineColour, BorderStyle, Outline, Alignment, MarginL, MarginV\n\
+         Style: Telemetry,Consolas,48,&H00FFFFFF,&H00000000,1,3,1,40,40\n\n\
+         [Events]\nFormat: Layer, Start, End, Style, Text\n",
+    );
+    for cue in cues {
+        let _ = writeln!(
+            out,
+            "Dialogue: 0,{},{},Telemetry,{}",
+            ass_timestamp(cue.start_ms),
+            ass_timestamp(cue.end_ms),
+            cue.text.replace('\n', "\\N")
+        );
+    }
+    out
+}
+
+fn timestamp(ms: u64, separator: char) -> String {
+    let (hours, minutes, seconds) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1_000 % 60);
+    std::format!("{hours:02}:{minutes:02}:{seconds:02}{separator}{:03}", ms % 1_000)
+}
+
+/// ASS uses `H:MM:SS.cc` with centiseconds
+fn ass_timestamp(ms: u64) -> String {
+    std::format!("{}:{:02}:{:02}.{:02}", ms / 3_600_000, ms / 60_000 % 60, ms / 1_000 % 60, ms % 1_000 / 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::state::FlightPhase;
+
+    fn record(at_ms: u64, altitude_m: f32, event: Option<FlightEvent>) -> DerivedRecord {
+        DerivedRecord {
+            at_ms,
+            altitude_m,
+            vertical_velocity_mps: 50.0,
+            dynamic_pressure_pa: 0.0,
+            mach: 0.0,
+            phase: FlightPhase::Boost,
+            event,
+        }
+    }
+
+    #[test]
+    fn test_cues_aligned_to_video_start() {
+        let records = [
+            record(9_000, 0.0, None),
+            record(10_200, 0.0, Some(FlightEvent::Launch)),
+            record(10_900, 40.0, None),
+            record(14_500, 300.0, None),
+        ];
+        let cues = cues(&records, &SubtitleConfig { video_start_ms: 10_000, ..Default::default() });
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].text, "ALT 40 m  VEL 50 m/s\nLIFTOFF");
+        assert_eq!(cues[1].start_ms, 4_000);
+        assert!(!cues[1].text.contains("LIFTOFF"));
+
+        assert!(to_srt(&cues).starts_with("1\n00:00:00,000 --> 00:00:01,000\nALT 40 m"));
+        assert!(to_ass(&cues).contains("Dialogue: 0,0:00:04.00,0:00:05.00,Telemetry,ALT 300 m"));
+    }
+}
@@ -0,0 +1,255 @@
+//! Thrust curve reconstruction from logged acceleration and the vehicle's mass properties
+
+use std::fmt::Write as _;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::flight::backfill::{DerivedRecord, RawRecord};
+use crate::protocol::vehicle::VehicleConfig;
+
+/// Mass and drag properties of the vehicle as flown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassProperties {
+    /// Liftoff mass without propellant
+    pub dry_mass_kg: f32,
+    pub propellant_mass_kg: f32,
+    pub reference_area_m2: f32,
+    pub drag_coefficient: f32,
+}
+
+impl MassProperties {
+    /// Mass properties from the VehicleConfig at the head of the flight log
+    pub fn from_vehicle(vehicle: &VehicleConfig, drag_coefficient: f32) -> Self {
+        Self {
+            dry_mass_kg: vehicle.dry_mass_kg,
+            propellant_mass_kg: vehicle.propellant_mass_kg,
+            reference_area_m2: vehicle.reference_area_m2,
+            drag_coefficient,
+        }
+    }
+}
+
+/// Motor metadata written to the .eng and .rse headers
+#[derive(Debug, Clone, Copy)]
+pub struct MotorInfo<'a> {
+    pub designation: &'a str,
+    pub manufacturer: &'a str,
+    pub diameter_mm: f32,
+    pub length_mm: f32,
+    /// Loaded motor mass including the casing
+    pub total_mass_kg: f32,
+}
+
+/// Axial specific force and dynamic pressure at one instant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThrustSample {
+    pub at_ms: u64,
+    /// Accelerometer reading along the vehicle's up axis, m/s^2
+    pub accel_mps2: f32,
+    pub dynamic_pressure_pa: f32,
+}
+
+/// Pairs raw records with their derived records by timestamp, skipping frames without an accelerometer
+pub fn samples(raw: &[RawRecord], derived: &[DerivedRecord]) -> Vec<ThrustSample> {
+    let mut derived = derived.iter().peekable();
+    raw.iter()
+        .filter_map(|record| {
+            while derived.next_if(|d| d.at_ms < record.at_ms).is_some() {}
+            let d = derived.peek().filter(|d| d.at_ms == record.at_ms)?;
+            let accel = record.data.ism330dhcx.map(|imu| imu.accel_z).or(record.data.lsm6dso32.map(|imu| imu.accel_z))?;
+            Some(ThrustSample {
+                at_ms: record.at_ms,
+                accel_mps2: accel as f32,
+                dynamic_pressure_pa: d.dynamic_pressure_pa,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThrustConfig {
+    /// Specific force that marks ignition on the pad
+    pub ignition_accel_mps2: f32,
+}
+
+impl Default for ThrustConfig {
+    fn default() -> Self {
+        Self { ignition_accel_mps2: 30.0 }
+    }
+}
+
+/// Reconstructed thrust against time since ignition
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ThrustCurve {
+    /// (seconds since ignition, newtons), ending with zero thrust at burnout
+    pub points: Vec<(f32, f32)>,
+    pub total_impulse_ns: f32,
+    pub propellant_mass_kg: f32,
+}
+
+impl ThrustCurve {
+    pub fn burn_time_s(&self) -> f32 {
+        self.points.last().map_or(0.0, |p| p.0)
+    }
+
+    pub fn peak_n(&self) -> f32 {
+        self.points.iter().map(|p| p.1).fold(0.0, f32::max)
+    }
+
+    pub fn average_n(&self) -> f32 {
+        let burn = self.burn_time_s();
+        if burn > 0.0 {
+            self.total_impulse_ns / burn
+        } else {
+            0.0
+        }
+    }
+
+    /// RASP .eng format, as read by OpenRocket and RockSim
+    pub fn to_eng(&self, motor: &MotorInfo) -> String {
+        let mut out = std::format!("; Reconstructed from flight data, {:.1} Ns\n", self.total_impulse_ns);
+        let _ = writeln!(
+            out,
+            "{} {:.0} {:.0} 0 {:.4} {:.4} {}",
+            motor.designation,
+            motor.diameter_mm,
+            motor.length_mm,
+            self.propellant_mass_kg(motor),
+            motor.total_mass_kg,
+            motor.manufacturer
+        );
+        for (t, thrust) in self.points.iter().skip_while(|p| p.0 == 0.0) {
+            let _ = writeln!(out, "{t:.3} {thrust:.2}");
+        }
+        out.push_str(";\n");
+        out
+    }
+
+    /// RockSim .rse XML engine database entry
+    pub fn to_rse(&self, motor: &MotorInfo) -> String {
+        let mut out = String::from("<engine-database>\n<engine-list>\n");
+        let _ = writeln!(
+            out,
+            "<engine code=\"{}\" mfg=\"{}\" dia=\"{:.1}\" len=\"{:.1}\" initWt=\"{:.1}\" propWt=\"{:.1}\" \
+             Itot=\"{:.2}\" avgThrust=\"{:.2}\" peakThrust=\"{:.2}\" burn-time=\"{:.3}\" delays=\"0\">",
+            motor.designation,
+            motor.manufacturer,
+            motor.diameter_mm,
+            motor.length_mm,
+            motor.total_mass_kg * 1000.0,
+            self.propellant_mass_kg(motor) * 1000.0,
+            self.total_impulse_ns,
+            self.average_n(),
+            self.peak_n(),
+            self.burn_time_s()
+        );
+        out.push_str("<data>\n");
+        for (t, thrust) in &self.points {
+            let _ = writeln!(out, "<eng-data t=\"{t:.3}\" f=\"{thrust:.2}\"/>");
+        }
+        out.push_str("</data>\n</engine>\n</engine-list>\n</engine-database>\n");
+        out
+    }
+
+    /// Propellant can't outweigh the loaded motor, whatever the airframe config says
+    fn propellant_mass_kg(&self, motor: &MotorInfo) -> f32 {
+        self.propellant_mass_kg.min(motor.total_mass_kg)
+    }
+}
+
+/// Reconstructs thrust as `m * a + D` over the burn, where `D = q * Cd * A`
+///
+/// The burn runs from the first sample above the ignition threshold until the accelerometer reads
+/// negative (only drag remains). Mass is first assumed to fall linearly over the burn, then refined so
+/// propellant is consumed in proportion to the impulse delivered.
+pub fn reconstruct(samples: &[ThrustSample], mass: &MassProperties, config: &ThrustConfig) -> ThrustCurve {
+    let Some(start) = samples.iter().position(|s| s.accel_mps2 > config.ignition_accel_mps2) else {
+        return ThrustCurve::default();
+    };
+    let end = samples[start..].iter().position(|s| s.accel_mps2 <= 0.0).map_or(samples.len(), |i| start + i);
+    let burn = &samples[start..end];
+    let t0 = burn[0].at_ms;
+    let seconds = |s: &ThrustSample| (s.at_ms - t0) as f32 / 1000.0;
+    let burn_s = burn.last().map_or(0.0, seconds).max(f32::EPSILON);
+
+    let linear: Vec<f32> = burn.iter().map(|s| 1.0 - seconds(s) / burn_s).collect();
+    let first = thrust(burn, mass, &linear);
+    let (cumulative, total) = impulse(burn, &first);
+    let remaining: Vec<f32> = cumulative.iter().map(|i| 1.0 - i / total.max(f32::EPSILON)).collect();
+    let refined = thrust(burn, mass, &remaining);
+
+    let mut points: Vec<(f32, f32)> = burn.iter().map(seconds).zip(refined.iter().copied()).collect();
+    let burnout = samples.get(end).map_or(burn_s, seconds);
+    points.push((burnout, 0.0));
+    let (_, total_impulse_ns) = impulse_of(&points);
+    ThrustCurve { points, total_impulse_ns, propellant_mass_kg: mass.propellant_mass_kg }
+}
+
+fn thrust(burn: &[ThrustSample], mass: &MassProperties, propellant_left: &[f32]) -> Vec<f32> {
+    burn.iter()
+        .zip(propellant_left)
+        .map(|(s, left)| {
+            let m = mass.dry_mass_kg + mass.propellant_mass_kg * left.clamp(0.0, 1.0);
+            let drag = s.dynamic_pressure_pa * mass.drag_coefficient * mass.reference_area_m2;
+            (m * s.accel_mps2 + drag).max(0.0)
+        })
+        .collect()
+}
+
+/// Cumulative impulse at each sample and the total, by the trapezoid rule
+fn impulse(burn: &[ThrustSample], thrust: &[f32]) -> (Vec<f32>, f32) {
+    let points: Vec<(f32, f32)> = burn.iter().map(|s| s.at_ms as f32 / 1000.0).zip(thrust.iter().copied()).collect();
+    impulse_of(&points)
+}
+
+fn impulse_of(points: &[(f32, f32)]) -> (Vec<f32>, f32) {
+    let mut total = 0.0;
+    let mut cumulative = Vec::with_capacity(points.len());
+    cumulative.push(0.0);
+    for pair in points.windows(2) {
+        total += 0.5 * (pair[0].1 + pair[1].1) * (pair[1].0 - pair[0].0);
+        cumulative.push(total);
+    }
+    (cumulative, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_thrust_motor_recovered() {
+        let mass = MassProperties {
+            dry_mass_kg: 10.0,
+            propellant_mass_kg: 2.0,
+            reference_area_m2: 0.008,
+            drag_coefficient: 0.5,
+        };
+        // 1000 N for 2 s with propellant burning off linearly, then coasting
+        let samples: Vec<ThrustSample> = (0..40)
+            .map(|i| {
+                let t = i as f32 * 0.1;
+                let q = 2_000.0 * t;
+                let drag = q * 0.5 * 0.008;
+                let (thrust, m) = if t < 2.0 { (1_000.0, 12.0 - t) } else { (0.0, 10.0) };
+                ThrustSample { at_ms: i * 100, accel_mps2: (thrust - drag) / m, dynamic_pressure_pa: q }
+            })
+            .collect();
+        let curve = reconstruct(&samples, &mass, &ThrustConfig::default());
+        assert!((curve.points[5].1 - 1_000.0).abs() < 20.0);
+        assert!((curve.burn_time_s() - 2.0).abs() < 0.01);
+        assert!((curve.total_impulse_ns - 2_000.0).abs() < 100.0);
+
+        let motor = MotorInfo {
+            designation: "K1000",
+            manufacturer: "RVT",
+            diameter_mm: 54.0,
+            length_mm: 400.0,
+            total_mass_kg: 3.5,
+        };
+        let eng = curve.to_eng(&motor);
+        assert!(eng.lines().nth(1).unwrap().starts_with("K1000 54 400 0 2.0000 3.5000 RVT"));
+        assert!(eng.ends_with("2.000 0.00\n;\n"));
+        assert!(curve.to_rse(&motor).contains("<engine code=\"K1000\""));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::persistence::{keys, Persistence};
+use crate::protocol::selftest::{Failure, Outcome, SelfTestReport, Subsystem, TestResult};
+use crate::protocol::AllSensorData;
+use crate::radio::Radio;
+
+/// Raw ADXL375 reading at full scale
+const SATURATED: u16 = i16::MAX as u16;
+
+/// Pattern written to storage and sent through the radio loopback
+const PATTERN: [u8; 8] = [0x55, 0xAA, 0x00, 0xFF, 0x12, 0x34, 0x56, 0x78];
+
+/// Probe gives the self-test access to a sensor driver's identification register
+pub trait Probe {
+    type Error;
+
+    fn who_am_i(&mut self) -> Result<u8, Self::Error>;
+}
+
+/// SelfTest runs the power-on hardware checks and collects their results into a report
+///
+/// A subsystem checked more than once keeps its worst outcome.
+#[derive(Debug, Clone, Default)]
+pub struct SelfTest {
+    report: SelfTestReport,
+}
+
+impl SelfTest {
+    pub fn new(uid: u8) -> Self {
+        Self { report: SelfTestReport { uid, results: heapless::Vec::new() } }
+    }
+
+    /// Reads the sensor's WHO_AM_I register and compares it with the expected identifier
+    pub fn sensor<P: Probe>(&mut self, subsystem: Subsystem, probe: &mut P, expected_id: u8) -> Outcome {
+        let outcome = match probe.who_am_i() {
+            Ok(id) if id == expected_id => Outcome::Pass,
+            Ok(_) => Outcome::Fail(Failure::WrongId),
+            Err(_) => Outcome::Fail(Failure::NoResponse),
+        };
+        self.record(subsystem, outcome)
+    }
+
+    /// Checks that every reading in a sample taken on the pad is physically plausible
+    pub fn plausibility(&mut self, sample: &AllSensorData) {
+        let imu = |x: f64, y: f64, z: f64| near_one_g(libm::sqrt(x * x + y * y + z * z));
+        let pressure = 30_000.0..=110_000.0;
+        let temperature = -40.0..=85.0;
+        let checks = [
+            (Subsystem::Ism330dhcx, sample.ism330dhcx.map(|s| imu(s.accel_x, s.accel_y, s.accel_z))),
+            (Subsystem::Lsm6dso32, sample.lsm6dso32.map(|s| imu(s.accel_x, s.accel_y, s.accel_z))),
+            (Subsystem::Ism330dhcx2, sample.ism330dhcx2.map(|s| imu(s.accel_x, s.accel_y, s.accel_z))),
+            (
+                Subsystem::Bmp390,
+                sample.bmp390.map(|s| pressure.contains(&s.pressure) && temperature.contains(&s.temperature)),
+            ),
+            (
+                Subsystem::Gps,
+                sample.gps.map(|s| (-90.0..=90.0).contains(&s.latitude) && (-180.0..=180.0).contains(&s.longitude)),
+            ),
+            (
+                Subsystem::Adxl375,
+                // Saturated axes on the pad mean a stuck or shorted sensor
+                sample.adxl375.map(|s| [s.accel_x, s.accel_y, s.accel_z].iter().all(|a| a.unsigned_abs() < SATURATED)),
+            ),
+        ];
+        for (subsystem, plausible) in checks {
+            let outcome = match plausible {
+                Some(true) => Outcome::Pass,
+                Some(false) => Outcome::Fail(Failure::Implausible),
+                None => Outcome::Fail(Failure::NoResponse),
+            };
+            self.record(subsystem, outcome);
+        }
+    }
+
+    /// Sends a known pattern through the transceiver's loopback and checks it comes back intact
+    pub fn radio<R: Radio>(&mut self, radio: &mut R) -> Outcome {
+        let mut buf = [0; PATTERN.len()];
+        let outcome = match radio.loopback(&PATTERN, &mut buf) {
+            Ok(Some(len)) if buf[..len] == PATTERN => Outcome::Pass,
+            Ok(Some(_)) => Outcome::Fail(Failure::Mismatch),
+            Ok(None) => Outcome::Skipped,
+            Err(_) => Outcome::Fail(Failure::Io),
+        };
+        self.record(Subsystem::Radio, outcome)
+    }
+
+    /// Writes, reads back and removes a scratch blob
+    pub fn storage<P: Persistence>(&mut self, store: &mut P) -> Outcome {
+        let mut buf = [0; PATTERN.len()];
+        let outcome = match store.write(keys::SELF_TEST, &PATTERN).and_then(|_| store.read(keys::SELF_TEST, &mut buf)) {
+            Ok(Some(len)) if buf[..len] == PATTERN => Outcome::Pass,
+            Ok(_) => Outcome::Fail(Failure::Mismatch),
+            Err(_) => Outcome::Fail(Failure::Io),
+        };
+        let _ = store.remove(keys::SELF_TEST);
+        self.record(Subsystem::Storage, outcome)
+    }
+
+    pub fn finish(self) -> SelfTestReport {
+        self.report
+    }
+
+    fn record(&mut self, subsystem: Subsystem, outcome: Outcome) -> Outcome {
+        match self.report.results.iter_mut().find(|r| r.subsystem == subsystem) {
+            Some(existing) => {
+                if rank(outcome) > rank(existing.outcome) {
+                    existing.outcome = outcome;
+                }
+                existing.outcome
+            }
+            None => {
+                // Capacity covers every Subsystem variant, so this cannot overflow
+                let _ = self.report.results.push(TestResult { subsystem, outcome });
+                outcome
+            }
+        }
+    }
+}
+
+/// Accelerometer at rest should read 1 g within a generous tolerance, in m/s^2
+fn near_one_g(magnitude: f64) -> bool {
+    (7.0..=12.5).contains(&magnitude)
+}
+
+fn rank(outcome: Outcome) -> u8 {
+    match outcome {
+        Outcome::Skipped => 0,
+        Outcome::Pass => 1,
+        Outcome::Fail(_) => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryStore;
+    use crate::protocol::BMP390;
+    use crate::radio::mock::MockRadio;
+
+    struct Register(Option<u8>);
+
+    impl Probe for Register {
+        type Error = ();
+
+        fn who_am_i(&mut self) -> Result<u8, ()> {
+            self.0.ok_or(())
+        }
+    }
+
+    #[test]
+    fn test_report_collects_worst_outcome_per_subsystem() {
+        let mut test = SelfTest::new(4);
+        assert_eq!(test.sensor(Subsystem::Ism330dhcx, &mut Register(Some(0x6B)), 0x6B), Outcome::Pass);
+        assert_eq!(test.sensor(Subsystem::Lsm6dso32, &mut Register(Some(0x00)), 0x6C), Outcome::Fail(Failure::WrongId));
+        test.sensor(Subsystem::Bmp390, &mut Register(Some(0x60)), 0x60);
+        test.plausibility(&AllSensorData {
+            ism330dhcx: None,
+            lsm6dso32: None,
+            bmp390: Some(BMP390 { pressure: 5.0, temperature: 20.0, altitude: 0.0 }),
+            gps: None,
+            adxl375: None,
+            ism330dhcx2: None,
+        });
+        assert_eq!(test.radio(&mut MockRadio::default()), Outcome::Pass);
+        assert_eq!(test.storage(&mut MemoryStore::<2, 16>::new()), Outcome::Pass);
+
+        let report = test.finish();
+        assert_eq!(report.result(Subsystem::Bmp390), Some(Outcome::Fail(Failure::Implausible)));
+        assert_eq!(report.result(Subsystem::Ism330dhcx), Some(Outcome::Fail(Failure::NoResponse)));
+        assert_eq!(report.result(Subsystem::Storage), Some(Outcome::Pass));
+        assert_eq!(report.failures(), 6);
+    }
+}
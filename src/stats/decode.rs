@@ -0,0 +1,174 @@
+use core::fmt;
+
+use heapless::FnvIndexMap;
+
+use crate::protocol::packet::PacketKind;
+use crate::protocol::Error;
+
+/// Maximum number of senders tracked individually, later senders are counted as unattributed
+pub const MAX_SENDERS: usize = 16;
+const FAILURES: usize = 5;
+
+/// Why a received frame could not be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeFailure {
+    /// Rejected by the radio's CRC check or the frame's own, see `protocol::checksum`
+    Crc = 0,
+    /// Frame ended before the packet was complete
+    Truncated = 1,
+    /// Packet type this build doesn't know, usually a sender running newer firmware
+    UnknownType = 2,
+    /// Any other invalid content
+    Malformed = 3,
+    /// A signed packet kind whose HMAC trailer is missing or wrong, see `crypto::trailer`
+    Unauthenticated = 4,
+}
+
+impl From<Error> for DecodeFailure {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::BadCrc => DecodeFailure::Crc,
+            Error::Truncated => DecodeFailure::Truncated,
+            Error::UnknownDiscriminant(_) => DecodeFailure::UnknownType,
+            Error::Unauthenticated => DecodeFailure::Unauthenticated,
+            _ => DecodeFailure::Malformed,
+        }
+    }
+}
+
+/// Decode counters for one sender
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SenderStats {
+    /// Frames decoded, indexed by `PacketKind as usize`
+    pub decoded: [u32; PacketKind::COUNT],
+    /// Failures, indexed by `DecodeFailure as usize`
+    pub failures: [u32; FAILURES],
+}
+
+impl Default for SenderStats {
+    fn default() -> Self {
+        Self { decoded: [0; PacketKind::COUNT], failures: [0; FAILURES] }
+    }
+}
+
+impl SenderStats {
+    pub fn decoded(&self, kind: PacketKind) -> u32 {
+        self.decoded[kind as usize]
+    }
+
+    pub fn failures(&self, failure: DecodeFailure) -> u32 {
+        self.failures[failure as usize]
+    }
+}
+
+/// DecodeStats counts decoded packets by type and decode failures by cause, per sender
+///
+/// A failed frame is attributed to the first byte of its message, which is the header's source UID; CRC
+/// failures and empty frames cannot be attributed.
+#[derive(Debug, Clone, Default)]
+pub struct DecodeStats {
+    senders: FnvIndexMap<u8, SenderStats, MAX_SENDERS>,
+    unattributed: SenderStats,
+}
+
+impl DecodeStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_decoded(&mut self, source_uid: u8, kind: PacketKind) {
+        let counter = &mut self.entry(Some(source_uid)).decoded[kind as usize];
+        *counter = counter.saturating_add(1);
+    }
+
+    pub fn record_failure(&mut self, source_uid: Option<u8>, failure: DecodeFailure) {
+        let counter = &mut self.entry(source_uid).failures[failure as usize];
+        *counter = counter.saturating_add(1);
+    }
+
+    pub fn sender(&self, uid: u8) -> Option<&SenderStats> {
+        self.senders.get(&uid)
+    }
+
+    pub fn unattributed(&self) -> &SenderStats {
+        &self.unattributed
+    }
+
+    fn entry(&mut self, source_uid: Option<u8>) -> &mut SenderStats {
+        let Some(uid) = source_uid else {
+            return &mut self.unattributed;
+        };
+        if !self.senders.contains_key(&uid) && self.senders.insert(uid, SenderStats::default()).is_err() {
+            return &mut self.unattributed;
+        }
+        self.senders.get_mut(&uid).expect("sender was just inserted")
+    }
+}
+
+/// Table with one row per sender, for the ground station display
+impl fmt::Display for DecodeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>6} {:>8} {:>6} {:>9} {:>8} {:>9} {:>6}",
+            "sender", "decoded", "crc", "truncated", "unknown", "malformed", "unauth"
+        )?;
+        let rows = self.senders.iter().map(|(uid, stats)| (Some(*uid), stats));
+        for (uid, stats) in rows.chain(core::iter::once((None, &self.unattributed))) {
+            let decoded: u32 = stats.decoded.iter().sum();
+            if uid.is_none() && decoded == 0 && stats.failures.iter().all(|n| *n == 0) {
+                continue;
+            }
+            match uid {
+                Some(uid) => write!(f, "{uid:>6}")?,
+                None => write!(f, "{:>6}", "?")?,
+            }
+            let [crc, truncated, unknown, malformed, unauthenticated] = stats.failures;
+            writeln!(f, " {decoded:>8} {crc:>6} {truncated:>9} {unknown:>8} {malformed:>9} {unauthenticated:>6}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::events::FlightEvent;
+    use crate::protocol::mesh::{MeshFrame, MeshHeader};
+    use crate::protocol::packet::Packet;
+
+    #[test]
+    fn test_failures_classified_per_sender() {
+        let header = MeshHeader {
+            source_uid: 5,
+            destination_uid: 0,
+            sequence: 1,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted: false,
+            backup: false,
+            route: None,
+        };
+        let mut buf = [0u8; 64];
+        let frame = postcard::to_slice(&MeshFrame { header, packet: Packet::Event(FlightEvent::Landed) }, &mut buf)
+            .unwrap()
+            .to_vec();
+        let mut unknown = frame.clone();
+        unknown[8] = 0x7F;
+
+        let mut stats = DecodeStats::new();
+        for bytes in [&frame[..4], &unknown[..], &[5, 0, 0xFF, 0xFF, 0xFF, 0xFF][..]] {
+            let error = MeshFrame::decode(bytes).unwrap_err();
+            stats.record_failure(bytes.first().copied(), error.into());
+        }
+        stats.record_failure(None, DecodeFailure::Crc);
+        stats.record_decoded(5, PacketKind::Event);
+
+        let sender = stats.sender(5).unwrap();
+        assert_eq!(sender.failures, [0, 1, 1, 1, 0]);
+        assert_eq!(sender.decoded(PacketKind::Event), 1);
+        assert_eq!(stats.unattributed().failures(DecodeFailure::Crc), 1);
+        let table = std::format!("{stats}");
+        assert!(table.contains("     5        1      0         1        1         1      0"));
+    }
+}
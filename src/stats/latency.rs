@@ -0,0 +1,77 @@
+use super::Histogram;
+use crate::protocol::latency::LatencyProbe;
+
+/// Milliseconds in a GPS week, where time of week wraps
+const WEEK_MS: u32 = 604_800_000;
+
+/// A leg of the path from sensor read to ground display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Sensor read to radio TX on the node
+    SensorToTx,
+    /// Radio TX on the node to radio RX on the ground
+    TxToRx,
+    /// Radio RX to delivery to the sinks on the ground
+    RxToSink,
+    /// Sensor read to sink delivery
+    EndToEnd,
+}
+
+/// Per-stage latency histograms built from LatencyProbes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    sensor_to_tx: Histogram,
+    tx_to_rx: Histogram,
+    rx_to_sink: Histogram,
+    end_to_end: Histogram,
+}
+
+impl LatencyStats {
+    /// Records one probe with the ground's RX and sink delivery times, all in GPS time of week
+    pub fn record(&mut self, probe: &LatencyProbe, rx_itow: u32, sink_itow: u32) {
+        self.sensor_to_tx.record(elapsed(probe.sensor_read_itow, probe.tx_itow));
+        self.tx_to_rx.record(elapsed(probe.tx_itow, rx_itow));
+        self.rx_to_sink.record(elapsed(rx_itow, sink_itow));
+        self.end_to_end.record(elapsed(probe.sensor_read_itow, sink_itow));
+    }
+
+    pub fn stage(&self, stage: Stage) -> &Histogram {
+        match stage {
+            Stage::SensorToTx => &self.sensor_to_tx,
+            Stage::TxToRx => &self.tx_to_rx,
+            Stage::RxToSink => &self.rx_to_sink,
+            Stage::EndToEnd => &self.end_to_end,
+        }
+    }
+}
+
+/// Milliseconds from `from` to `to`, handling the weekly rollover
+///
+/// Clock skew can make a short leg appear negative, such legs count as zero.
+fn elapsed(from: u32, to: u32) -> u32 {
+    let diff = (to % WEEK_MS + WEEK_MS - from % WEEK_MS) % WEEK_MS;
+    if diff > WEEK_MS / 2 {
+        0
+    } else {
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stages_across_week_rollover() {
+        let mut stats = LatencyStats::default();
+        let probe = LatencyProbe { uid: 3, sensor_read_itow: WEEK_MS - 100, tx_itow: WEEK_MS - 40 };
+        stats.record(&probe, 150, 180);
+
+        assert_eq!(stats.stage(Stage::SensorToTx).max(), 60);
+        assert_eq!(stats.stage(Stage::TxToRx).max(), 190);
+        assert_eq!(stats.stage(Stage::RxToSink).max(), 30);
+        assert_eq!(stats.stage(Stage::EndToEnd).max(), 280);
+        assert_eq!(stats.stage(Stage::EndToEnd).percentile(50.0), Some(280));
+        assert_eq!(elapsed(1_000, 990), 0);
+    }
+}
@@ -0,0 +1,136 @@
+use heapless::FnvIndexMap;
+
+use super::decode::MAX_SENDERS;
+use crate::protocol::mesh::MeshHeader;
+
+/// Frames further behind the newest sequence number than this are treated as a counter reset, not reordering
+pub const MAX_REORDER_DEPTH: u16 = 1_024;
+
+/// Sequence-number accounting for one sender
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LossStats {
+    pub received: u32,
+    /// Sequence numbers skipped and not yet seen, a late frame is taken back off this count
+    pub lost: u32,
+    /// Frames that arrived after a frame with a higher sequence number
+    pub out_of_order: u32,
+    /// Largest distance in sequence numbers a late frame arrived behind the newest one
+    pub max_reorder_depth: u16,
+    /// Times the count was restarted, after a reboot or an implausible jump backwards
+    pub resyncs: u32,
+}
+
+impl LossStats {
+    /// Fraction of the sender's frames that never arrived, 0.0..=1.0
+    pub fn loss_ratio(&self) -> f32 {
+        let expected = self.received as u64 + self.lost as u64;
+        if expected == 0 {
+            return 0.0;
+        }
+        self.lost as f32 / expected as f32
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sender {
+    newest: u16,
+    /// Whether the last frame carried the `rebooted` flag
+    rebooted: bool,
+    stats: LossStats,
+}
+
+/// LossTracker finds gaps and reordering in each sender's `MeshHeader::sequence`
+///
+/// Feed it frames after duplicate suppression. A node numbers every frame it originates from one counter,
+/// so unicasts to other nodes that this receiver never hears count as lost too. The counter jumps ahead by
+/// up to a reserve block across a restart, so the first frame flagged `rebooted` restarts the count instead.
+#[derive(Debug, Clone, Default)]
+pub struct LossTracker {
+    senders: FnvIndexMap<u8, Sender, MAX_SENDERS>,
+}
+
+impl LossTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one received frame, senders beyond `MAX_SENDERS` are not tracked
+    pub fn record(&mut self, header: &MeshHeader) {
+        let Some(sender) = self.senders.get_mut(&header.source_uid) else {
+            let stats = LossStats { received: 1, ..LossStats::default() };
+            let sender = Sender { newest: header.sequence, rebooted: header.rebooted, stats };
+            let _ = self.senders.insert(header.source_uid, sender);
+            return;
+        };
+        let restarted = header.rebooted && !sender.rebooted;
+        sender.rebooted = header.rebooted;
+        let stats = &mut sender.stats;
+        let ahead = header.sequence.wrapping_sub(sender.newest);
+        let behind = sender.newest.wrapping_sub(header.sequence);
+        if ahead == 0 {
+            return;
+        }
+        stats.received = stats.received.saturating_add(1);
+        if restarted {
+            stats.resyncs = stats.resyncs.saturating_add(1);
+            sender.newest = header.sequence;
+        } else if ahead < u16::MAX / 2 {
+            stats.lost = stats.lost.saturating_add(ahead as u32 - 1);
+            sender.newest = header.sequence;
+        } else if behind <= MAX_REORDER_DEPTH {
+            stats.out_of_order = stats.out_of_order.saturating_add(1);
+            stats.max_reorder_depth = stats.max_reorder_depth.max(behind);
+            stats.lost = stats.lost.saturating_sub(1);
+        } else {
+            stats.resyncs = stats.resyncs.saturating_add(1);
+            sender.newest = header.sequence;
+        }
+    }
+
+    pub fn sender(&self, uid: u8) -> Option<&LossStats> {
+        self.senders.get(&uid).map(|sender| &sender.stats)
+    }
+
+    /// Every tracked sender with its stats
+    pub fn senders(&self) -> impl Iterator<Item = (u8, &LossStats)> {
+        self.senders.iter().map(|(uid, sender)| (*uid, &sender.stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(source_uid: u8, sequence: u16, rebooted: bool) -> MeshHeader {
+        MeshHeader {
+            source_uid,
+            destination_uid: 0,
+            sequence,
+            hops_left: 0,
+            ack_requested: false,
+            rebooted,
+            backup: false,
+            route: None,
+        }
+    }
+
+    #[test]
+    fn test_gaps_reordering_and_reboot() {
+        let mut tracker = LossTracker::new();
+        // 65_534, 1 and 2 are skipped across the wraparound, then 2 arrives late
+        for sequence in [65_533, 65_535, 0, 3, 4, 2] {
+            tracker.record(&header(7, sequence, false));
+        }
+        tracker.record(&header(8, 10, false));
+        let stats = *tracker.sender(7).unwrap();
+        assert_eq!((stats.received, stats.lost, stats.out_of_order, stats.max_reorder_depth), (6, 2, 1, 2));
+        assert!((stats.loss_ratio() - 0.25).abs() < 1e-6);
+
+        // The restarted node resumes a block ahead without that counting as loss, later gaps still count
+        tracker.record(&header(7, 40, true));
+        tracker.record(&header(7, 42, true));
+        let stats = *tracker.sender(7).unwrap();
+        assert_eq!((stats.received, stats.lost, stats.resyncs), (8, 3, 1));
+        assert_eq!(tracker.senders().count(), 2);
+    }
+}
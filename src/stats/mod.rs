@@ -0,0 +1,75 @@
+pub mod decode;
+pub mod latency;
+pub mod loss;
+
+/// Upper bucket edges in milliseconds used by latency histograms
+pub const LATENCY_EDGES_MS: [u32; 16] =
+    [10, 20, 50, 100, 150, 200, 300, 400, 500, 750, 1_000, 1_500, 2_000, 3_000, 5_000, 10_000];
+
+/// Fixed-bucket histogram, cheap enough to keep on a node and precise enough for percentiles
+///
+/// Percentiles are reported as the upper edge of the bucket they fall in; samples above the last edge
+/// land in an overflow bucket reported as the largest sample seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Histogram {
+    counts: [u32; LATENCY_EDGES_MS.len() + 1],
+    count: u32,
+    max: u32,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self { counts: [0; LATENCY_EDGES_MS.len() + 1], count: 0, max: 0 }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, value_ms: u32) {
+        let bucket = LATENCY_EDGES_MS.iter().position(|&edge| value_ms <= edge).unwrap_or(LATENCY_EDGES_MS.len());
+        self.counts[bucket] = self.counts[bucket].saturating_add(1);
+        self.count = self.count.saturating_add(1);
+        self.max = self.max.max(value_ms);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Value below which `percentile` percent of samples fall, `None` if empty
+    pub fn percentile(&self, percentile: f32) -> Option<u32> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = libm::ceilf(self.count as f32 * percentile.clamp(0.0, 100.0) / 100.0).max(1.0) as u32;
+        let mut seen = 0;
+        for (bucket, &n) in self.counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Some(LATENCY_EDGES_MS.get(bucket).map_or(self.max, |&edge| edge.min(self.max)));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.percentile(50.0), None);
+        for ms in [5, 15, 40, 90, 180, 190, 250, 600, 900, 12_000] {
+            histogram.record(ms);
+        }
+        assert_eq!(histogram.percentile(50.0), Some(200));
+        assert_eq!(histogram.percentile(90.0), Some(1_000));
+        assert_eq!(histogram.percentile(100.0), Some(12_000));
+        assert_eq!(histogram.percentile(0.0), Some(10));
+    }
+}
@@ -0,0 +1,230 @@
+use libm::{atan2, cos};
+
+use crate::flight::GRAVITY;
+use crate::geo;
+use crate::protocol::tracker::{TargetSource, TrackerStatus};
+
+/// A geodetic position, altitude in meters above mean sea level
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Position {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+/// Azimuth (clockwise from north), elevation and slant range from `from` to `to`
+pub fn look_angles(from: &Position, to: &Position) -> (f64, f64, f64) {
+    let ground = geo::distance_m(from.latitude, from.longitude, to.latitude, to.longitude);
+    let azimuth = geo::bearing_deg(from.latitude, from.longitude, to.latitude, to.longitude);
+    let dh = to.altitude - from.altitude;
+    let elevation = atan2(dh, ground).to_degrees();
+    let range = libm::sqrt(ground * ground + dh * dh);
+    (azimuth, elevation, range)
+}
+
+/// Constant-velocity extrapolation of the target between GPS fixes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeadReckoning {
+    last: Option<(Position, u64)>,
+    /// North, east and up velocity in m/s
+    velocity: (f64, f64, f64),
+}
+
+impl DeadReckoning {
+    pub fn update(&mut self, fix: Position, now_ms: u64) {
+        if let Some((prev, prev_ms)) = self.last {
+            let dt = now_ms.saturating_sub(prev_ms) as f64 / 1000.0;
+            if dt > 0.0 {
+                let north = (fix.latitude - prev.latitude).to_radians() * geo::EARTH_RADIUS_M;
+                let east = (fix.longitude - prev.longitude).to_radians()
+                    * geo::EARTH_RADIUS_M
+                    * cos(fix.latitude.to_radians());
+                self.velocity = (north / dt, east / dt, (fix.altitude - prev.altitude) / dt);
+            }
+        }
+        self.last = Some((fix, now_ms));
+    }
+
+    /// Age of the last fix in milliseconds
+    pub fn age_ms(&self, now_ms: u64) -> Option<u64> {
+        self.last.map(|(_, at)| now_ms.saturating_sub(at))
+    }
+
+    /// Drag-free apogee from the last fix and velocity, `None` once the target stops climbing
+    ///
+    /// Ignoring drag overestimates both height and drift, so ranges derived from it err on the safe side.
+    pub fn predicted_apogee(&self) -> Option<Position> {
+        let (fix, _) = self.last?;
+        let (vn, ve, vu) = self.velocity;
+        if vu <= 0.0 {
+            return None;
+        }
+        let to_apogee_s = vu / GRAVITY as f64;
+        let lat_rad = fix.latitude.to_radians();
+        Some(Position {
+            latitude: fix.latitude + (vn * to_apogee_s / geo::EARTH_RADIUS_M).to_degrees(),
+            longitude: fix.longitude + (ve * to_apogee_s / (geo::EARTH_RADIUS_M * cos(lat_rad))).to_degrees(),
+            altitude: fix.altitude + vu * vu / (2.0 * GRAVITY as f64),
+        })
+    }
+
+    pub fn estimate(&self, now_ms: u64) -> Option<Position> {
+        let (fix, at) = self.last?;
+        let dt = now_ms.saturating_sub(at) as f64 / 1000.0;
+        let (vn, ve, vu) = self.velocity;
+        let lat_rad = fix.latitude.to_radians();
+        Some(Position {
+            latitude: fix.latitude + (vn * dt / geo::EARTH_RADIUS_M).to_degrees(),
+            longitude: fix.longitude + (ve * dt / (geo::EARTH_RADIUS_M * cos(lat_rad))).to_degrees(),
+            altitude: fix.altitude + vu * dt,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TrackerConfig {
+    /// Proportional gain from pointing error (deg) to rate command (deg/s)
+    pub gain: f32,
+    pub max_azimuth_rate: f32,
+    pub max_elevation_rate: f32,
+    /// GPS fixes older than this are replaced by the dead-reckoned estimate
+    pub fix_timeout_ms: u64,
+    /// Stop extrapolating after this long without a fix
+    pub max_dead_reckoning_ms: u64,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            gain: 2.0,
+            max_azimuth_rate: 30.0,
+            max_elevation_rate: 20.0,
+            fix_timeout_ms: 1_500,
+            max_dead_reckoning_ms: 10_000,
+        }
+    }
+}
+
+/// Rate command for the azimuth and elevation axes in deg/s
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RateCommand {
+    pub azimuth: f32,
+    pub elevation: f32,
+}
+
+/// Closed-loop antenna tracker: encoder feedback in, slew-limited rate commands out
+#[derive(Debug, Clone, Copy)]
+pub struct Tracker {
+    pub config: TrackerConfig,
+    station: Position,
+    target: DeadReckoning,
+    last_fix_ms: Option<u64>,
+    status: TrackerStatus,
+}
+
+impl Tracker {
+    pub fn new(station: Position, config: TrackerConfig) -> Self {
+        Self { config, station, target: DeadReckoning::default(), last_fix_ms: None, status: TrackerStatus::default() }
+    }
+
+    pub fn on_fix(&mut self, fix: Position, now_ms: u64) {
+        self.target.update(fix, now_ms);
+        self.last_fix_ms = Some(now_ms);
+    }
+
+    pub fn status(&self) -> TrackerStatus {
+        self.status
+    }
+
+    /// Runs one control step from the current encoder readings
+    pub fn update(&mut self, azimuth_deg: f32, elevation_deg: f32, now_ms: u64) -> RateCommand {
+        self.status.azimuth_deg = azimuth_deg;
+        self.status.elevation_deg = elevation_deg;
+
+        let age = self.target.age_ms(now_ms);
+        self.status.fix_age_ms = age.unwrap_or(0).min(u32::MAX as u64) as u32;
+        let (source, target) = match age {
+            Some(age) if age <= self.config.fix_timeout_ms => {
+                (TargetSource::Gps, self.target.estimate(self.last_fix_ms.unwrap_or(now_ms)))
+            }
+            Some(age) if age <= self.config.max_dead_reckoning_ms => {
+                (TargetSource::DeadReckoned, self.target.estimate(now_ms))
+            }
+            _ => (TargetSource::None, None),
+        };
+        self.status.source = source;
+
+        let Some(target) = target else {
+            self.status.slew_limited = false;
+            return RateCommand::default();
+        };
+        let (az, el, range) = look_angles(&self.station, &target);
+        self.status.target_azimuth_deg = az as f32;
+        self.status.target_elevation_deg = el as f32;
+        self.status.range_m = range as f32;
+
+        let az_error = wrap_degrees(az as f32 - azimuth_deg);
+        let el_error = el as f32 - elevation_deg;
+        let (azimuth, az_limited) = clamp_rate(az_error * self.config.gain, self.config.max_azimuth_rate);
+        let (elevation, el_limited) = clamp_rate(el_error * self.config.gain, self.config.max_elevation_rate);
+        self.status.slew_limited = az_limited || el_limited;
+        RateCommand { azimuth, elevation }
+    }
+}
+
+/// Wraps an angle difference into -180..180 so the azimuth axis takes the short way round
+fn wrap_degrees(angle: f32) -> f32 {
+    let wrapped = (angle + 180.0) % 360.0;
+    if wrapped < 0.0 {
+        wrapped + 180.0
+    } else {
+        wrapped - 180.0
+    }
+}
+
+fn clamp_rate(rate: f32, max: f32) -> (f32, bool) {
+    if rate > max {
+        (max, true)
+    } else if rate < -max {
+        (-max, true)
+    } else {
+        (rate, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATION: Position = Position { latitude: 37.0, longitude: -80.0, altitude: 600.0 };
+
+    #[test]
+    fn test_wrap_takes_short_way() {
+        assert_eq!(wrap_degrees(350.0), -10.0);
+        assert_eq!(wrap_degrees(-350.0), 10.0);
+        assert_eq!(wrap_degrees(45.0), 45.0);
+    }
+
+    #[test]
+    fn test_slew_limit_and_dead_reckoning() {
+        let mut tracker = Tracker::new(STATION, TrackerConfig::default());
+        assert_eq!(tracker.update(0.0, 0.0, 0), RateCommand::default());
+        assert_eq!(tracker.status().source, TargetSource::None);
+
+        // Rocket climbing straight up 1 km east of the station at 100 m/s
+        tracker.on_fix(Position { latitude: 37.0, longitude: -79.9888, altitude: 1_600.0 }, 0);
+        tracker.on_fix(Position { latitude: 37.0, longitude: -79.9888, altitude: 1_700.0 }, 1_000);
+
+        let cmd = tracker.update(0.0, 0.0, 1_000);
+        assert_eq!(cmd.azimuth, 30.0);
+        assert!(tracker.status().slew_limited);
+        let el_gps = tracker.status().target_elevation_deg;
+
+        tracker.update(90.0, el_gps, 4_000);
+        assert_eq!(tracker.status().source, TargetSource::DeadReckoned);
+        assert!(tracker.status().target_elevation_deg > el_gps);
+
+        tracker.update(90.0, el_gps, 20_000);
+        assert_eq!(tracker.status().source, TargetSource::None);
+    }
+}
@@ -0,0 +1,320 @@
+//! Pre-flight 3-DOF trajectory simulation under the current weather, to compare against live telemetry
+//!
+//! The vehicle is a point mass pushed by its thrust curve, slowed by drag with a constant Cd and carried by
+//! the wind. It rides the rail, then weathercocks into the relative wind; after apogee it drifts down at
+//! the drogue and main descent rates. Winds come from the WindsAloft profile where there is one and from the
+//! pad station otherwise, and the atmosphere is the ISA lapse rate from the pad's temperature and pressure.
+
+use heapless::Vec;
+use libm::{cosf, powf, sinf, sqrtf};
+
+use crate::flight::GRAVITY;
+use crate::protocol::gse::{PadWeather, WindsAloft};
+use crate::protocol::prediction::FlightPrediction;
+use crate::protocol::vehicle::VehicleConfig;
+
+/// Ascent samples kept for comparison with telemetry
+pub const MAX_POINTS: usize = 128;
+/// Time between ascent samples
+pub const SAMPLE_S: f32 = 0.5;
+
+const ASCENT_STEP_S: f32 = 0.01;
+const DESCENT_STEP_S: f32 = 0.1;
+const MAX_FLIGHT_S: f32 = 3_600.0;
+/// Specific gas constant of dry air, J/(kg K)
+const R_AIR: f32 = 287.05;
+/// ISA temperature lapse rate in K/m
+const LAPSE_RATE: f32 = 0.0065;
+/// Wind speed factors and direction offsets in degrees flown to size the landing zone
+const DISPERSIONS: [(f32, f32); 4] = [(0.75, 0.0), (1.25, 0.0), (1.0, -20.0), (1.0, 20.0)];
+
+/// Everything about the vehicle and the pad that the weather doesn't decide
+#[derive(Debug, Clone, Copy)]
+pub struct LaunchSetup<'a> {
+    pub vehicle: &'a VehicleConfig,
+    /// Thrust curve as (seconds since ignition, newtons), as in a .eng file, ending at burnout
+    pub thrust_curve: &'a [(f32, f32)],
+    /// Drag coefficient for the whole flight, `VehicleConfig::cd_table` is not resolved here
+    pub drag_coefficient: f32,
+    pub rail_length_m: f32,
+    /// Rail angle from vertical in degrees
+    pub rail_tilt_deg: f32,
+    /// Direction the rail leans toward, degrees clockwise from true north
+    pub rail_azimuth_deg: f32,
+    pub drogue_descent_mps: f32,
+    pub main_descent_mps: f32,
+    /// Height above the pad at which the main opens
+    pub main_deploy_m: f32,
+}
+
+/// Simulated altitude at one time during the ascent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryPoint {
+    /// Seconds since liftoff
+    pub t_s: f32,
+    pub altitude_m: f32,
+    pub vertical_velocity_mps: f32,
+}
+
+/// A simulated flight, with the ascent sampled every `SAMPLE_S`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory {
+    pub uid: u8,
+    pub ascent: Vec<TrajectoryPoint, MAX_POINTS>,
+    pub apogee_m: f32,
+    pub apogee_s: f32,
+    /// Meters (east, north) of the pad
+    pub apogee_offset_m: (f32, f32),
+    pub landing_offset_m: (f32, f32),
+    /// Distance from the nominal landing point covering the wind dispersions
+    pub landing_radius_m: f32,
+    pub flight_time_s: f32,
+}
+
+impl Trajectory {
+    /// The prediction to broadcast to the LCO's displays
+    pub fn prediction(&self) -> FlightPrediction {
+        FlightPrediction {
+            uid: self.uid,
+            apogee_m: self.apogee_m,
+            time_to_apogee_s: self.apogee_s,
+            apogee_east_m: self.apogee_offset_m.0,
+            apogee_north_m: self.apogee_offset_m.1,
+            landing_east_m: self.landing_offset_m.0,
+            landing_north_m: self.landing_offset_m.1,
+            landing_radius_m: self.landing_radius_m,
+            flight_time_s: self.flight_time_s,
+        }
+    }
+
+    /// Predicted altitude `t_s` after liftoff, interpolated between samples, `None` past the ascent
+    pub fn altitude_at(&self, t_s: f32) -> Option<f32> {
+        let next = self.ascent.iter().position(|point| point.t_s >= t_s)?;
+        let high = self.ascent[next];
+        let Some(low) = next.checked_sub(1).map(|index| self.ascent[index]) else {
+            return Some(high.altitude_m);
+        };
+        let t = (t_s - low.t_s) / (high.t_s - low.t_s);
+        Some(low.altitude_m + (high.altitude_m - low.altitude_m) * t)
+    }
+
+    /// Live altitude minus the predicted altitude at the same time since liftoff
+    pub fn deviation_m(&self, t_s: f32, observed_altitude_m: f32) -> Option<f32> {
+        self.altitude_at(t_s).map(|predicted| observed_altitude_m - predicted)
+    }
+}
+
+/// Simulates the flight under `surface` conditions, with `aloft` winds above the pad if known
+pub fn predict(setup: &LaunchSetup, surface: &PadWeather, aloft: Option<&WindsAloft>) -> Trajectory {
+    let atmosphere = Atmosphere::new(surface);
+    let wind = |altitude_m: f32, (scale, turn_deg): (f32, f32)| {
+        let (east, north) = aloft.and_then(|aloft| aloft.wind_at(altitude_m)).unwrap_or(surface.wind_vector_mps());
+        let (sin, cos) = (sinf(turn_deg.to_radians()), cosf(turn_deg.to_radians()));
+        (scale * (east * cos + north * sin), scale * (north * cos - east * sin))
+    };
+    let mut nominal = fly(setup, &atmosphere, &|altitude_m| wind(altitude_m, (1.0, 0.0)));
+    nominal.landing_radius_m = DISPERSIONS
+        .iter()
+        .map(|&dispersion| fly(setup, &atmosphere, &|altitude_m| wind(altitude_m, dispersion)).landing_offset_m)
+        .map(|(east, north)| distance(east - nominal.landing_offset_m.0, north - nominal.landing_offset_m.1))
+        .fold(0.0, f32::max);
+    nominal
+}
+
+/// Air density from the ISA lapse rate anchored at the pad
+struct Atmosphere {
+    kelvin: f32,
+    pascals: f32,
+}
+
+impl Atmosphere {
+    fn new(surface: &PadWeather) -> Self {
+        Self { kelvin: surface.temperature_c + 273.15, pascals: surface.pressure_hpa * 100.0 }
+    }
+
+    fn density(&self, altitude_m: f32) -> f32 {
+        let kelvin = (self.kelvin - LAPSE_RATE * altitude_m.max(0.0)).max(1.0);
+        let pascals = self.pascals * powf(kelvin / self.kelvin, GRAVITY / (R_AIR * LAPSE_RATE));
+        pascals / (R_AIR * kelvin)
+    }
+}
+
+fn fly(setup: &LaunchSetup, atmosphere: &Atmosphere, wind: &dyn Fn(f32) -> (f32, f32)) -> Trajectory {
+    let vehicle = setup.vehicle;
+    let total_impulse = impulse(setup.thrust_curve);
+    let burnout_s = setup.thrust_curve.last().map_or(0.0, |&(t, _)| t);
+    let (tilt, azimuth) = (setup.rail_tilt_deg.to_radians(), setup.rail_azimuth_deg.to_radians());
+    let rail = [sinf(tilt) * sinf(azimuth), sinf(tilt) * cosf(azimuth), cosf(tilt)];
+
+    let mut trajectory = Trajectory {
+        uid: vehicle.uid,
+        ascent: Vec::new(),
+        apogee_m: 0.0,
+        apogee_s: 0.0,
+        apogee_offset_m: (0.0, 0.0),
+        landing_offset_m: (0.0, 0.0),
+        landing_radius_m: 0.0,
+        flight_time_s: 0.0,
+    };
+    let (mut position, mut velocity) = ([0.0f32; 3], [0.0f32; 3]);
+    let (mut t, mut delivered, mut next_sample) = (0.0, 0.0, 0.0);
+    while t < MAX_FLIGHT_S {
+        if t >= next_sample {
+            let point = TrajectoryPoint { t_s: t, altitude_m: position[2], vertical_velocity_mps: velocity[2] };
+            let _ = trajectory.ascent.push(point);
+            next_sample += SAMPLE_S;
+        }
+        let thrust = thrust_at(setup.thrust_curve, t);
+        let burnt = if total_impulse > 0.0 { (delivered / total_impulse).min(1.0) } else { 1.0 };
+        let mass = (vehicle.dry_mass_kg + vehicle.propellant_mass_kg * (1.0 - burnt)).max(0.001);
+        let (wind_east, wind_north) = wind(position[2]);
+        let air = [velocity[0] - wind_east, velocity[1] - wind_north, velocity[2]];
+        let airspeed = norm(air);
+        let drag = 0.5 * atmosphere.density(position[2]) * airspeed * airspeed
+            * setup.drag_coefficient
+            * vehicle.reference_area_m2;
+        let on_rail = dot(position, rail) < setup.rail_length_m;
+        let heading = if on_rail || airspeed < 1e-3 { rail } else { air.map(|v| v / airspeed) };
+
+        let mut accel = [0.0; 3];
+        for axis in 0..3 {
+            let drag_axis = if airspeed > 0.0 { air[axis] / airspeed } else { 0.0 };
+            accel[axis] = (heading[axis] * thrust - drag_axis * drag) / mass;
+        }
+        accel[2] -= GRAVITY;
+        if on_rail {
+            // The rail takes every force across it, and the pad holds the vehicle until thrust beats weight
+            let mut along = dot(accel, rail);
+            if dot(velocity, rail) <= 0.0 {
+                along = along.max(0.0);
+            }
+            accel = rail.map(|r| r * along);
+        }
+        for axis in 0..3 {
+            velocity[axis] += accel[axis] * ASCENT_STEP_S;
+            position[axis] += velocity[axis] * ASCENT_STEP_S;
+        }
+        delivered += thrust * ASCENT_STEP_S;
+        t += ASCENT_STEP_S;
+        if t > burnout_s && velocity[2] <= 0.0 {
+            break;
+        }
+    }
+    trajectory.apogee_m = position[2].max(0.0);
+    trajectory.apogee_s = t;
+    trajectory.apogee_offset_m = (position[0], position[1]);
+
+    while position[2] > 0.0 && t < MAX_FLIGHT_S {
+        let rate = if position[2] > setup.main_deploy_m { setup.drogue_descent_mps } else { setup.main_descent_mps };
+        let (wind_east, wind_north) = wind(position[2]);
+        position[0] += wind_east * DESCENT_STEP_S;
+        position[1] += wind_north * DESCENT_STEP_S;
+        position[2] -= rate.max(0.1) * DESCENT_STEP_S;
+        t += DESCENT_STEP_S;
+    }
+    trajectory.landing_offset_m = (position[0], position[1]);
+    trajectory.flight_time_s = t;
+    trajectory
+}
+
+/// Thrust at `t_s` after ignition, linear between curve points and zero outside the curve
+fn thrust_at(curve: &[(f32, f32)], t_s: f32) -> f32 {
+    let mut previous = (0.0, 0.0);
+    for &(t, thrust) in curve {
+        if t_s <= t {
+            let span = t - previous.0;
+            return if span > 0.0 { previous.1 + (thrust - previous.1) * (t_s - previous.0) / span } else { thrust };
+        }
+        previous = (t, thrust);
+    }
+    0.0
+}
+
+fn impulse(curve: &[(f32, f32)]) -> f32 {
+    let mut previous = (0.0, 0.0);
+    let mut total = 0.0;
+    for &(t, thrust) in curve {
+        total += (t - previous.0) * (thrust + previous.1) / 2.0;
+        previous = (t, thrust);
+    }
+    total
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn norm(v: [f32; 3]) -> f32 {
+    sqrtf(dot(v, v))
+}
+
+fn distance(east: f32, north: f32) -> f32 {
+    sqrtf(east * east + north * north)
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::String;
+
+    use super::*;
+
+    fn vehicle(propellant_mass_kg: f32) -> VehicleConfig {
+        VehicleConfig {
+            uid: 2,
+            airframe: String::new(),
+            dry_mass_kg: 10.0,
+            propellant_mass_kg,
+            reference_area_m2: 0.008,
+            cd_table: 0,
+            motor: String::new(),
+        }
+    }
+
+    fn weather(wind_speed_mps: f32) -> PadWeather {
+        PadWeather {
+            uid: 9,
+            temperature_c: 15.0,
+            pressure_hpa: 1_013.25,
+            humidity_pct: 40,
+            wind_speed_mps,
+            wind_gust_mps: wind_speed_mps,
+            wind_direction_deg: 270,
+        }
+    }
+
+    #[test]
+    fn test_apogee_and_wind_drift() {
+        // A 400 N step for 3 s with no drag and no propellant mass has a closed-form apogee
+        let curve = [(0.0, 400.0), (3.0, 400.0)];
+        let vehicle = vehicle(0.0);
+        let setup = LaunchSetup {
+            vehicle: &vehicle,
+            thrust_curve: &curve,
+            drag_coefficient: 0.0,
+            rail_length_m: 5.0,
+            rail_tilt_deg: 0.0,
+            rail_azimuth_deg: 0.0,
+            drogue_descent_mps: 30.0,
+            main_descent_mps: 6.0,
+            main_deploy_m: 300.0,
+        };
+        let calm = predict(&setup, &weather(0.0), None);
+        let accel = 40.0 - GRAVITY;
+        let expected = accel * 4.5 + (accel * 3.0) * (accel * 3.0) / (2.0 * GRAVITY);
+        assert!((calm.apogee_m - expected).abs() < expected * 0.01);
+        assert!(calm.landing_offset_m.0.abs() < 0.1 && calm.landing_radius_m < 0.1);
+        let midway = calm.altitude_at(2.0).unwrap();
+        assert!((midway - accel * 2.0).abs() < 1.0);
+        assert!((calm.deviation_m(2.0, midway + 15.0).unwrap() - 15.0).abs() < 1e-3);
+
+        // With drag, a westerly drifts the landing east while weathercocking takes the apogee upwind
+        let vehicle = LaunchSetup { drag_coefficient: 0.5, ..setup };
+        let windy = predict(&vehicle, &weather(6.0), None);
+        assert!(windy.apogee_m < calm.apogee_m);
+        assert!(windy.apogee_offset_m.0 < 0.0);
+        // About a minute under canopy at 6 m/s
+        assert!(windy.landing_offset_m.0 > windy.apogee_offset_m.0 + 300.0);
+        assert!(windy.landing_radius_m > 10.0);
+        assert_eq!(windy.prediction().landing_east_m, windy.landing_offset_m.0);
+    }
+}
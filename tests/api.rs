@@ -0,0 +1,52 @@
+//! Pins the prelude's public surface and wire layout
+//!
+//! A failure here means a change would break downstream firmware: either bump the major version or
+//! restore the previous shape. `cargo semver-checks` covers the rest of the signature surface.
+
+use Mesh::prelude::*;
+
+#[test]
+fn test_struct_fields_are_stable() {
+    let health = Health {
+        uid: 1,
+        uptime_ms: 2,
+        battery_voltage: 7.4,
+        arming: ArmingState::Safe,
+        rebooted: false,
+        degradation: DegradationLevel::Normal,
+    };
+    let info = NodeInfo { uid: 1, device_type: DeviceType::Top, role: Role::Lco, firmware_version: 3, mtu: 255 };
+    let command = SignedCommand { sender_uid: 1, target_uid: 2, sequence: 3, command: Command::Safe, tag: [0; 8] };
+    let sensors = AllSensorData {
+        ism330dhcx: None,
+        lsm6dso32: None,
+        bmp390: Some(BMP390 { pressure: 1.0, temperature: 2.0, altitude: 3.0 }),
+        gps: None,
+        adxl375: Some(ADXL375 { accel_x: 1, accel_y: 2, accel_z: 3 }),
+        ism330dhcx2: None,
+    };
+    let packets = [Packet::Health(health), Packet::NodeInfo(info), Packet::Command(command), Packet::Sensors(sensors)];
+    let kinds = packets.map(|packet| packet.kind());
+    assert_eq!(kinds, [PacketKind::Health, PacketKind::NodeInfo, PacketKind::Command, PacketKind::Sensors]);
+}
+
+#[test]
+fn test_wire_layout_is_stable() {
+    // Field order and variant indices are part of the wire format
+    let packet = Packet::Health(Health {
+        uid: 7,
+        uptime_ms: 1_000,
+        battery_voltage: 0.0,
+        arming: ArmingState::ArmedPad,
+        rebooted: true,
+        degradation: DegradationLevel::Beacon,
+    });
+    let mut raw = [0u8; 32];
+    assert_eq!(postcard::to_slice(&packet, &mut raw).unwrap(), [1, 7, 0xE8, 0x07, 0, 0, 0, 0, 1, 1, 3]);
+
+    let mut buf = [0u8; 32];
+    let frame = encode_frame(&packet, &mut buf).unwrap();
+
+    let decoded: Packet = decode_frame(frame).unwrap();
+    assert_eq!(decoded.kind(), PacketKind::Health);
+}
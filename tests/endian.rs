@@ -0,0 +1,61 @@
+//! Byte-order fixtures for the on-air formats
+//!
+//! Every fixture was captured on a little-endian host. Run this suite on a big-endian target, such as
+//! `cross test --target powerpc-unknown-linux-gnu --test endian`, to check that no code path depends on the
+//! host's byte order.
+
+use Mesh::protocol::checksum;
+use Mesh::protocol::codec;
+use Mesh::protocol::{AllSensorData, Error, BMP390};
+
+/// BMP390 reading of 95 000 Pa, 21.5 °C and 540 m in a codec frame
+const FRAME: [u8; 27] = [
+    0x7E, 0x02, 0x01, // start, version, type
+    0x14, 0x00, // length 20, little endian
+    0x00, 0x00, 0x01, // ism330dhcx and lsm6dso32 absent, bmp390 present
+    0x00, 0x8C, 0xB9, 0x47, // 95 000.0 f32 LE
+    0x00, 0x00, 0xAC, 0x41, // 21.5 f32 LE
+    0x00, 0x00, 0x07, 0x44, // 540.0 f32 LE
+    0x00, 0x00, 0x00, // gps, adxl375 and ism330dhcx2 absent
+    0x00, 0x00, // reserved
+    0xA8, 0x7A, // CRC-16/CCITT-FALSE, little endian
+];
+
+fn sensors() -> AllSensorData {
+    AllSensorData {
+        ism330dhcx: None,
+        lsm6dso32: None,
+        bmp390: Some(BMP390 { pressure: 95_000.0, temperature: 21.5, altitude: 540.0 }),
+        gps: None,
+        adxl375: None,
+        ism330dhcx2: None,
+    }
+}
+
+#[test]
+fn test_fixture_round_trips_on_any_host() {
+    let mut buf = [0u8; 64];
+    assert_eq!(codec::encode(&sensors(), &mut buf).unwrap(), FRAME);
+    assert_eq!(codec::decode::<AllSensorData>(&FRAME).unwrap(), (sensors(), FRAME.len()));
+    assert_eq!(checksum::crc16(b"123456789"), 0x29B1);
+}
+
+#[test]
+fn test_byte_swapped_fixture_is_rejected() {
+    // What a writer using its native big-endian order for the length and CRC would send
+    let mut swapped = FRAME;
+    swapped.swap(3, 4);
+    swapped.swap(25, 26);
+    assert_eq!(codec::decode::<AllSensorData>(&swapped), Err(Error::Truncated));
+
+    // Big-endian floats under a valid CRC decode, but to different values, so the fixture catches them
+    let mut floats = FRAME;
+    for field in floats[8..20].chunks_exact_mut(4) {
+        field.reverse();
+    }
+    assert_eq!(codec::decode::<AllSensorData>(&floats), Err(Error::BadCrc));
+    let crc = checksum::crc16(&floats[1..25]);
+    floats[25..].copy_from_slice(&crc.to_le_bytes());
+    let (decoded, _) = codec::decode::<AllSensorData>(&floats).unwrap();
+    assert_ne!(decoded, sensors());
+}
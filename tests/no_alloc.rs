@@ -0,0 +1,183 @@
+//! Runs the flight path under an allocator that counts every call, which must stay at zero
+//!
+//! The library never links `alloc`, but a dependency or a `std`-only path could still slip one in. This
+//! target is `#![no_std]`, so its own code can't allocate either; `std` is only linked for the counting
+//! allocator and `main`. A flight build that passes here runs with no global allocator at all.
+
+#![no_std]
+
+extern crate std;
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use embedded_storage::{ReadStorage, Storage};
+use Mesh::clock::MockClock;
+use Mesh::node::fragmentation::{self, Reassembler, MAX_MESSAGE_LEN};
+use Mesh::persistence::journal::Journal;
+use Mesh::prelude::*;
+use Mesh::protocol::ax25::{Ax25Address, UiFrame};
+use Mesh::protocol::mesh::MAX_FRAME_LEN;
+use Mesh::protocol::mic_e::MicEReport;
+use Mesh::protocol::{checksum, codec, kiss, AprsCompressedPositionReport};
+use Mesh::radio::mock::{relay, MockRadio, Script};
+
+struct Counting;
+
+static ARMED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if ARMED.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn main() {
+    check("encode", encode);
+    check("fragment", fragment);
+    check("log", log);
+    check("node", node);
+}
+
+fn check(name: &str, run: fn()) {
+    ALLOCATIONS.store(0, Ordering::SeqCst);
+    ARMED.store(true, Ordering::SeqCst);
+    run();
+    ARMED.store(false, Ordering::SeqCst);
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+    assert_eq!(allocations, 0, "{name} allocated {allocations} times");
+    std::println!("{name} ... ok");
+}
+
+fn sensors() -> AllSensorData {
+    AllSensorData {
+        ism330dhcx: None,
+        lsm6dso32: None,
+        bmp390: Some(BMP390 { pressure: 95_000.0, temperature: 21.5, altitude: 540.0 }),
+        gps: None,
+        adxl375: Some(ADXL375 { accel_x: 1, accel_y: 2, accel_z: 3 }),
+        ism330dhcx2: None,
+    }
+}
+
+fn header(sequence: u16) -> MeshHeader {
+    MeshHeader {
+        source_uid: 1,
+        destination_uid: BROADCAST_UID,
+        sequence,
+        hops_left: 3,
+        ack_requested: false,
+        rebooted: false,
+        backup: false,
+        route: None,
+    }
+}
+
+fn encode() {
+    let mut buf = [0u8; MAX_FRAME_LEN];
+    let len = MeshFrame { header: header(1), packet: Packet::Sensors(sensors()) }.encode_into(&mut buf).unwrap();
+    let message = checksum::verify_and_strip(&buf[..len]).unwrap();
+    let frame: MeshFrame = postcard::from_bytes(message).unwrap();
+    assert_eq!(frame.packet, Packet::Sensors(sensors()));
+
+    let len = codec::encode(&sensors(), &mut buf).unwrap().len();
+    assert_eq!(codec::decode::<AllSensorData>(&buf[..len]).unwrap().0, sensors());
+
+    let report = AprsCompressedPositionReport {
+        symbol_table: '\\',
+        symbol_code: 'O',
+        ..AprsCompressedPositionReport::encode(37.2296, -80.4139, 1_200.0, None, 0.0)
+    };
+    let mut info = [0u8; 64];
+    let len = report.write_info(&mut info).unwrap();
+    assert!(AprsCompressedPositionReport::decode(&info[..len]).is_ok());
+    let (destination, len) = MicEReport::new(37.2296, -80.4139, Some(1_200.0), 90, 40).write_info(&mut info).unwrap();
+    assert!(MicEReport::decode(&destination, &info[..len]).is_ok());
+
+    let frame = UiFrame::new(destination, Ax25Address::new("N0CALL", 9).unwrap(), &[], &info[..len]).unwrap();
+    let mut kiss = [0u8; kiss::MAX_KISS_FRAME_LEN];
+    assert!(kiss::encode_ui(0, &frame, &mut kiss).is_ok());
+}
+
+fn fragment() {
+    let mut message = [0u8; 600];
+    message.iter_mut().enumerate().for_each(|(i, byte)| *byte = i as u8);
+    let mut reassembler = Reassembler::new(5_000);
+    let (mut buf, mut out, mut received) = ([0u8; 96], [0u8; MAX_MESSAGE_LEN], None);
+    for (sequence, fragment) in fragmentation::split(7, &message, buf.len() as u16).unwrap().enumerate() {
+        let frame = MeshFrame { header: header(sequence as u16), packet: Packet::Fragment(fragment) };
+        let len = frame.encode_into(&mut buf).unwrap();
+        let Ok(MeshFrame { packet: Packet::Fragment(fragment), .. }) =
+            postcard::from_bytes(checksum::verify_and_strip(&buf[..len]).unwrap())
+        else {
+            panic!("fragment did not decode");
+        };
+        received = reassembler.on_fragment_into(1, &fragment, 0, &mut out).unwrap();
+    }
+    assert_eq!(received, Some(message.len()));
+    assert_eq!(out[..message.len()], message);
+}
+
+/// Flight recorder storage in RAM
+struct Ram([u8; 512]);
+
+impl ReadStorage for Ram {
+    type Error = ();
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+        bytes.copy_from_slice(&self.0[offset as usize..][..bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Storage for Ram {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+        self.0[offset as usize..][..bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+fn log() {
+    let (mut journal, _) = Journal::recover(Ram([0xFF; 512]), 0, 512).unwrap();
+    let mut scratch = [0u8; 64];
+    for _ in 0..3 {
+        journal.append_value(&sensors(), &mut scratch).unwrap();
+    }
+    let (mut cursor, mut records) = (0, 0);
+    while let Some(logged) = journal.read_value::<AllSensorData>(&mut cursor, &mut scratch).unwrap() {
+        assert_eq!(logged, sensors());
+        records += 1;
+    }
+    assert_eq!(records, 3);
+}
+
+fn node() {
+    let clock = MockClock::new(0);
+    let new = |uid| {
+        let store = MemoryStore::<2, 16>::new();
+        MeshNode::new(uid, MockRadio::new(Script::default()), &clock, store, NodeConfig::default()).unwrap()
+    };
+    let (mut rocket, mut ground) = (new(1), new(2));
+    let sequence = rocket.send(2, Packet::Sensors(sensors()), true).unwrap();
+    rocket.poll().unwrap();
+    relay(rocket.radio_mut(), ground.radio_mut());
+    assert!(matches!(ground.poll().unwrap(), Some(NodeEvent::Received { .. })));
+    ground.poll().unwrap();
+    relay(ground.radio_mut(), rocket.radio_mut());
+    assert_eq!(rocket.poll().unwrap(), Some(NodeEvent::Delivered { destination_uid: 2, sequence }));
+}